@@ -32,7 +32,7 @@ pub trait Keyspace {
     }
 }
 
-#[derive(PartialEq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
 pub struct KeyspaceId(u32);
 
 impl From<u32> for KeyspaceId {
@@ -41,6 +41,12 @@ impl From<u32> for KeyspaceId {
     }
 }
 
+impl From<KeyspaceId> for u32 {
+    fn from(id: KeyspaceId) -> Self {
+        id.0
+    }
+}
+
 impl Keyspace for ApiV1 {
     fn make_kv_pair(p: (Vec<u8>, Vec<u8>)) -> Result<Self::KvPair> {
         Ok(p)
@@ -0,0 +1,188 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::{collections::VecDeque, sync::Mutex};
+
+use tikv_util::time::{Instant, UnixSecs};
+
+/// How many events `DebugEventLog` keeps in memory (and therefore persists)
+/// per flush cycle. Once full, the oldest event is evicted (and counted as
+/// dropped) to make room for each new one instead of growing the buffer, so
+/// a noisy task can't balloon memory or the size of the debug log written
+/// alongside its backup data.
+const MAX_EVENTS: usize = 256;
+
+/// How many events `DebugEventLog` accepts per [`RATE_LIMIT_WINDOW`], across
+/// all kinds. This is deliberately coarser than [`MAX_EVENTS`]: it exists to
+/// smooth out a burst (e.g. every region in a task failing to observe at
+/// once) rather than to cap total memory, which `MAX_EVENTS` already does.
+const RATE_LIMIT: usize = 32;
+
+#[cfg(not(test))]
+const RATE_LIMIT_WINDOW: std::time::Duration = std::time::Duration::from_secs(1);
+// Tests need to cross several windows without actually waiting a second per
+// window, hence the much shorter window here.
+#[cfg(test)]
+const RATE_LIMIT_WINDOW: std::time::Duration = std::time::Duration::from_millis(20);
+
+struct Event {
+    at: UnixSecs,
+    kind: &'static str,
+    message: String,
+}
+
+struct Inner {
+    events: VecDeque<Event>,
+    dropped: usize,
+    window_start: Instant,
+    window_count: usize,
+}
+
+impl Default for Inner {
+    fn default() -> Self {
+        Self {
+            events: VecDeque::default(),
+            dropped: 0,
+            window_start: Instant::now_coarse(),
+            window_count: 0,
+        }
+    }
+}
+
+/// A small, rate-limited log of things a backup task did or hit: subscription
+/// changes, flush results, and errors (with whatever context chain
+/// [`crate::errors::Error::context`] attached to them). It is meant to be
+/// drained and written to the task's external storage alongside its backup
+/// data every flush, so troubleshooting a PiTR task doesn't require digging
+/// through weeks-old store logs to find out what happened to it.
+///
+/// Like the rest of log backup's in-memory bookkeeping, this holds a bounded
+/// number of events and is rate-limited: a task wedged in a tight error loop
+/// should degrade to "log that we're dropping events" instead of growing
+/// without bound or flooding external storage with writes.
+#[derive(Default)]
+pub struct DebugEventLog {
+    inner: Mutex<Inner>,
+}
+
+impl DebugEventLog {
+    /// Record that `kind` happened, with a human-readable `message`
+    /// (typically the `Display` of a [`crate::errors::Error`], which already
+    /// renders its full context chain).
+    pub fn record(&self, kind: &'static str, message: impl std::fmt::Display) {
+        let mut inner = self.inner.lock().unwrap();
+
+        let now = Instant::now_coarse();
+        if now.saturating_duration_since(inner.window_start) >= RATE_LIMIT_WINDOW {
+            inner.window_start = now;
+            inner.window_count = 0;
+        }
+        if inner.window_count >= RATE_LIMIT {
+            inner.dropped += 1;
+            return;
+        }
+        inner.window_count += 1;
+
+        if inner.events.len() >= MAX_EVENTS {
+            inner.events.pop_front();
+            inner.dropped += 1;
+        }
+        inner.events.push_back(Event {
+            at: UnixSecs::now(),
+            kind,
+            message: message.to_string(),
+        });
+    }
+
+    /// Drain every event recorded so far and render them as a simple
+    /// line-based text log, one event per line, suitable for writing
+    /// directly to external storage. Returns `None` if nothing (beyond
+    /// perhaps some dropped-event bookkeeping) was recorded, so callers don't
+    /// bother writing an empty file every flush.
+    pub fn take_rendered(&self) -> Option<String> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.events.is_empty() && inner.dropped == 0 {
+            return None;
+        }
+
+        let mut out = String::new();
+        for event in inner.events.drain(..) {
+            out.push_str(&format!(
+                "{}\t{}\t{}\n",
+                event.at.into_inner(),
+                event.kind,
+                event.message.replace('\n', "\\n")
+            ));
+        }
+        if inner.dropped > 0 {
+            out.push_str(&format!(
+                "{}\tthrottled\tdropped {} event(s) due to the rate limit or buffer being full\n",
+                UnixSecs::now().into_inner(),
+                inner.dropped
+            ));
+            inner.dropped = 0;
+        }
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_take_rendered_empty() {
+        let log = DebugEventLog::default();
+        assert!(log.take_rendered().is_none());
+    }
+
+    #[test]
+    fn test_record_and_take() {
+        let log = DebugEventLog::default();
+        log.record("flush", "ok");
+        log.record("error", format_args!("boom: {}", "oh no"));
+
+        let rendered = log.take_rendered().unwrap();
+        assert!(rendered.contains("flush"));
+        assert!(rendered.contains("boom: oh no"));
+
+        // Draining clears the log.
+        assert!(log.take_rendered().is_none());
+    }
+
+    #[test]
+    fn test_buffer_cap_drops_oldest() {
+        let log = DebugEventLog::default();
+        let total = MAX_EVENTS * 2;
+        for i in 0..total {
+            log.record("flush", format_args!("{}", i));
+            if (i + 1) % RATE_LIMIT == 0 {
+                // Cross into a fresh rate-limit window so this test is
+                // exercising the buffer cap, not the rate limit.
+                std::thread::sleep(RATE_LIMIT_WINDOW * 2);
+            }
+        }
+
+        let rendered = log.take_rendered().unwrap();
+        assert!(rendered.contains("dropped"));
+        // The oldest events should have been evicted, so this early index
+        // shouldn't be present while a late one should.
+        assert!(!rendered.contains(&format!("\t{}\n", 0)));
+        assert!(rendered.contains(&format!("\t{}\n", total - 1)));
+    }
+
+    #[test]
+    fn test_rate_limit_drops_bursts() {
+        let log = DebugEventLog::default();
+        for i in 0..(RATE_LIMIT * 2) {
+            log.record("flush", format_args!("{}", i));
+        }
+
+        let rendered = log.take_rendered().unwrap();
+        // Everything fit within the buffer, so the drops must have come
+        // from the rate limit rather than the buffer cap: exactly the
+        // second half of the burst.
+        assert!(rendered.contains(&format!("dropped {} event", RATE_LIMIT)));
+        assert!(rendered.contains(&format!("\t{}\n", RATE_LIMIT - 1)));
+        assert!(!rendered.contains(&format!("\t{}\n", RATE_LIMIT * 2 - 1)));
+    }
+}
@@ -16,6 +16,7 @@ use error_code::ErrorCodeExt;
 use futures::{stream::AbortHandle, FutureExt, TryFutureExt};
 use kvproto::{
     brpb::{StreamBackupError, StreamBackupTaskInfo},
+    kvrpcpb::ApiVersion,
     metapb::{Region, RegionEpoch},
 };
 use pd_client::PdClient;
@@ -25,7 +26,10 @@ use raftstore::{
     router::CdcHandle,
 };
 use resolved_ts::{resolve_by_raft, LeadershipResolver};
-use tikv::config::{BackupStreamConfig, ResolvedTsConfig};
+use tikv::{
+    config::{BackupStreamConfig, ResolvedTsConfig},
+    storage::txn::initial_scan_cache::InitialScanCache,
+};
 use tikv_util::{
     box_err,
     config::ReadableDuration,
@@ -60,7 +64,7 @@ use crate::{
     metadata::{store::MetaStore, MetadataClient, MetadataEvent, StreamTask},
     metrics::{self, TaskStatus},
     observer::BackupStreamObserver,
-    router::{self, ApplyEvents, Router, TaskSelector},
+    router::{self, ApplyEvents, Router, TaskSelector, TaskSelectorRef},
     subscription_manager::{RegionSubscriptionManager, ResolvedRegions},
     subscription_track::{Ref, RefMut, ResolveResult, SubscriptionTracer},
     try_send,
@@ -71,6 +75,10 @@ const SLOW_EVENT_THRESHOLD: f64 = 120.0;
 /// CHECKPOINT_SAFEPOINT_TTL_IF_ERROR specifies the safe point TTL(24 hour) if
 /// task has fatal error.
 const CHECKPOINT_SAFEPOINT_TTL_IF_ERROR: u64 = 24;
+/// How often this store cross-checks its locally subscribed tasks against
+/// the task/pause state recorded in the metadata store. See
+/// [`Endpoint::on_watchdog_tick`].
+const WATCHDOG_TICK_INTERVAL: Duration = Duration::from_secs(30);
 
 pub struct Endpoint<S, R, E: KvEngine, PDC> {
     // Note: those fields are more like a shared context between components.
@@ -95,6 +103,9 @@ pub struct Endpoint<S, R, E: KvEngine, PDC> {
     // however probably it would be useful in the future.
     config: BackupStreamConfig,
     pub checkpoint_mgr: CheckpointManager,
+    /// The cluster's API version, used to tell which tasks' ranges are
+    /// keyspace-scoped. See [`crate::keyspace`].
+    api_version: ApiVersion,
 
     // Runtime status:
     /// The handle to abort last save storage safe point.
@@ -124,6 +135,7 @@ where
         concurrency_manager: ConcurrencyManager,
         resolver: BackupStreamResolver<RT, E>,
         data_key_manager: Option<Arc<DataKeyManager>>,
+        api_version: ApiVersion,
     ) -> Self {
         crate::metrics::STREAM_ENABLED.inc();
         let pool = create_tokio_runtime((config.num_threads / 2).max(1), "backup-stream")
@@ -160,6 +172,11 @@ where
         let subs = SubscriptionTracer::default();
 
         let initial_scan_semaphore = Arc::new(Semaphore::new(config.initial_scan_concurrency));
+        // Bounds how many completed initial-scan results may be held for reuse by a
+        // second consumer of the same region+start_ts at once; entries also expire
+        // quickly on their own, so this only needs to be large enough to survive a
+        // burst of subscriptions starting together.
+        let initial_scan_cache = Arc::new(InitialScanCache::new(1024));
         let (region_operator, op_loop) = RegionSubscriptionManager::start(
             InitialDataLoader::new(
                 range_router.clone(),
@@ -172,6 +189,7 @@ where
                 // `InitialScan` trait -- we cannot do that.
                 Arc::new(Mutex::new(router)),
                 Arc::clone(&initial_scan_semaphore),
+                initial_scan_cache,
             ),
             accessor.clone(),
             meta_client.clone(),
@@ -200,8 +218,10 @@ where
             config,
             checkpoint_mgr,
             abort_last_storage_save: None,
+            api_version,
         };
         ep.pool.spawn(root!(ep.min_ts_worker()));
+        ep.pool.spawn(root!(ep.watchdog_worker()));
         ep
     }
 }
@@ -713,6 +733,10 @@ where
                     "task" => ?task,
                     "ranges_count" => ranges.inner.len(),
                 );
+                let keyspaces = crate::keyspace::keyspaces_of_ranges(
+                    self.api_version,
+                    ranges.inner.iter().map(|(start_key, _)| start_key.as_slice()),
+                );
                 let ranges = ranges
                     .inner
                     .into_iter()
@@ -721,7 +745,12 @@ where
                     })
                     .collect::<Vec<_>>();
                 range_router
-                    .register_task(task.clone(), ranges.clone(), self.config.file_size_limit.0)
+                    .register_task(
+                        task.clone(),
+                        ranges.clone(),
+                        self.config.file_size_limit.0,
+                        keyspaces.clone(),
+                    )
                     .await?;
 
                 for (start_key, end_key) in ranges {
@@ -731,6 +760,7 @@ where
                 info!(
                     "finish register backup stream ranges";
                     "task" => ?task,
+                    "keyspaces" => ?keyspaces,
                 );
                 Result::Ok(())
             };
@@ -794,6 +824,85 @@ where
         );
     }
 
+    /// Cross-checks this store's locally subscribed tasks against the
+    /// task/pause state recorded in the metadata store (PD / etcd), and
+    /// reconciles any drift found.
+    ///
+    /// A task may drift out of sync with the metadata store if this store
+    /// missed a `PauseTask`/`ResumeTask`/`RemoveTask` watch event (e.g. due
+    /// to a watch stream restart racing with the event). That leaves the
+    /// store's local subscription stale even though the regions involved
+    /// are otherwise healthy, which looks like (and can cause) the global
+    /// checkpoint getting stuck.
+    ///
+    /// Note: re-electing *which node* owns the job of driving the global
+    /// checkpoint forward is out of scope here -- this store has no
+    /// authority over that, and no API for it exists in this crate. That
+    /// role belongs to the external advancer (see the doc comment on
+    /// [`CheckpointManager`]), which already re-discovers the current
+    /// checkpoint from PD/etcd on every round, so it is not vulnerable to
+    /// this store's local staleness. This watchdog only deals with what
+    /// this store can directly observe and fix: whether its own task
+    /// subscriptions match what the metadata store says they should be.
+    pub fn on_watchdog_tick(&self) {
+        let tasks = match self.pool.block_on(self.meta_client.get_tasks()) {
+            Ok(tasks) => tasks.inner,
+            Err(err) => {
+                warn!("backup stream watchdog failed to load tasks from metadata store"; "err" => %err);
+                return;
+            }
+        };
+        let locally_running = self
+            .pool
+            .block_on(self.range_router.select_task(TaskSelectorRef::All));
+
+        let mut missed_pause = 0;
+        let mut missed_resume = 0;
+        for task in &tasks {
+            let name = task.info.get_name();
+            let running_locally = locally_running.iter().any(|t| t == name);
+            if task.is_paused && running_locally {
+                missed_pause += 1;
+                warn!(
+                    "backup stream watchdog: task is paused in the metadata store but still \
+                     subscribed locally, likely a missed pause notification; reconciling by \
+                     pausing it locally";
+                    "task" => name,
+                );
+                self.on_pause(name);
+            } else if !task.is_paused && !running_locally {
+                missed_resume += 1;
+                warn!(
+                    "backup stream watchdog: task is active in the metadata store but not \
+                     subscribed locally, likely a missed resume notification or a lost \
+                     subscription; reconciling by resuming it locally";
+                    "task" => name,
+                );
+                self.on_resume(name.to_owned());
+            }
+        }
+        let orphaned: Vec<_> = locally_running
+            .iter()
+            .filter(|name| !tasks.iter().any(|t| t.info.get_name() == name.as_str()))
+            .collect();
+        for name in &orphaned {
+            warn!(
+                "backup stream watchdog: task is subscribed locally but no longer exists in \
+                 the metadata store, likely a missed remove notification; reconciling by \
+                 unregistering it locally";
+                "task" => %name,
+            );
+            self.on_unregister(name.as_str());
+        }
+        info!(
+            "backup stream watchdog finished a diagnosis round";
+            "tasks_checked" => tasks.len(),
+            "missed_pause" => missed_pause,
+            "missed_resume" => missed_resume,
+            "orphaned" => orphaned.len(),
+        );
+    }
+
     /// unload a task from memory: this would stop observe the changes required
     /// by the task temporarily.
     fn unload_task(&self, task: &str) -> Option<StreamBackupTaskInfo> {
@@ -1035,6 +1144,7 @@ where
             Task::ExecFlush(task, min_ts) => self.on_exec_flush(task, min_ts),
             Task::RegionCheckpointsOp(s) => self.handle_region_checkpoints_op(s),
             Task::UpdateGlobalCheckpoint(task) => self.on_update_global_checkpoint(task),
+            Task::WatchdogTick => self.on_watchdog_tick(),
         }
     }
 
@@ -1052,6 +1162,16 @@ where
         }
     }
 
+    fn watchdog_worker(&self) -> future![()] {
+        let sched = self.scheduler.clone();
+        async move {
+            loop {
+                tokio::time::sleep(WATCHDOG_TICK_INTERVAL).await;
+                try_send!(sched, Task::WatchdogTick);
+            }
+        }
+    }
+
     pub fn handle_region_checkpoints_op(&mut self, op: RegionCheckpointOperation) {
         match op {
             RegionCheckpointOperation::Resolved {
@@ -1267,6 +1387,11 @@ pub enum Task {
     RegionCheckpointsOp(RegionCheckpointOperation),
     /// update global-checkpoint-ts to storage.
     UpdateGlobalCheckpoint(String),
+    /// Periodically cross-check this store's locally subscribed tasks
+    /// against the task/pause state recorded in the metadata store (PD /
+    /// etcd), and reconcile any drift found. See
+    /// [`Endpoint::on_watchdog_tick`].
+    WatchdogTick,
 }
 
 #[derive(Debug)]
@@ -1386,6 +1511,7 @@ impl fmt::Debug for Task {
             Self::UpdateGlobalCheckpoint(task) => {
                 f.debug_tuple("UpdateGlobalCheckpoint").field(task).finish()
             }
+            Self::WatchdogTick => f.debug_tuple("WatchdogTick").finish(),
         }
     }
 }
@@ -1424,6 +1550,7 @@ impl Task {
             Task::ExecFlush(..) => "flush_with_min_ts",
             Task::RegionCheckpointsOp(..) => "get_checkpoints",
             Task::UpdateGlobalCheckpoint(..) => "update_global_checkpoint",
+            Task::WatchdogTick => "watchdog_tick",
         }
     }
 }
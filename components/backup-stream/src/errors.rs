@@ -28,6 +28,10 @@ pub enum Error {
     MalformedMetadata(String),
     #[error("Out of quota for region {region_id}")]
     OutOfQuota { region_id: u64 },
+    #[error(
+        "local temp storage is full ({used} / {capacity} bytes used); waiting for pending data to be flushed, or enlarge `log-backup.temp-file-disk-quota`"
+    )]
+    TempFileStorageFull { used: u64, capacity: u64 },
 
     #[error("gRPC meet error {0}")]
     Grpc(#[from] GrpcError),
@@ -73,6 +77,7 @@ impl ErrorCodeExt for Error {
             Error::RaftStore(_) => RAFTSTORE,
             Error::ObserveCanceled(..) => OBSERVE_CANCELED,
             Error::OutOfQuota { .. } => OUT_OF_QUOTA,
+            Error::TempFileStorageFull { .. } => TEMP_FILE_STORAGE_FULL,
             Error::Grpc(_) => GRPC,
             Error::Encryption(_) => ENCRYPTION,
         }
@@ -12,7 +12,7 @@ use raftstore::{
 use tikv::storage::{
     kv::StatisticsSummary,
     mvcc::{DeltaScanner, ScannerBuilder},
-    txn::{TxnEntry, TxnEntryScanner},
+    txn::{initial_scan_cache::InitialScanCache, TxnEntry, TxnEntryScanner},
     Snapshot, Statistics,
 };
 use tikv_util::{
@@ -135,57 +135,70 @@ impl<S: Snapshot> EventLoader<S> {
         result: &mut ApplyEvents,
         resolver: &mut TwoPhaseResolver,
     ) -> Result<()> {
-        for entry in self.entry_batch.drain(..) {
-            match entry {
-                TxnEntry::Prewrite {
-                    default: (key, value),
-                    lock: (lock_at, lock_value),
-                    ..
-                } => {
-                    if !key.is_empty() {
-                        result.push(ApplyEvent {
-                            key,
-                            value,
-                            cf: CF_DEFAULT,
-                            cmd_type: CmdType::Put,
-                        });
-                    }
-                    let lock = Lock::parse(&lock_value).map_err(|err| {
-                        annotate!(
-                            err,
-                            "BUG?: failed to parse ts from lock; key = {}",
-                            utils::redact(&lock_at)
-                        )
-                    })?;
-                    debug!("meet lock during initial scanning."; "key" => %utils::redact(&lock_at), "ts" => %lock.ts);
-                    if utils::should_track_lock(&lock) {
-                        resolver
-                            .track_phase_one_lock(lock.ts, lock_at)
-                            .map_err(|_| Error::OutOfQuota {
-                                region_id: self.region.id,
-                            })?;
-                    }
+        emit_entries(self.entry_batch.drain(..), self.region.id, result, resolver)
+    }
+}
+
+/// Converts scanned [`TxnEntry`]s into [`ApplyEvents`], tracking the locks
+/// met along the way at the same time.
+///
+/// Extracted out of [`EventLoader::emit_entries_to`] so that entries reused
+/// from the [`InitialScanCache`] (which didn't come from an `EventLoader` at
+/// all) can be converted the same way.
+fn emit_entries(
+    entries: impl IntoIterator<Item = TxnEntry>,
+    region_id: u64,
+    result: &mut ApplyEvents,
+    resolver: &mut TwoPhaseResolver,
+) -> Result<()> {
+    for entry in entries {
+        match entry {
+            TxnEntry::Prewrite {
+                default: (key, value),
+                lock: (lock_at, lock_value),
+                ..
+            } => {
+                if !key.is_empty() {
+                    result.push(ApplyEvent {
+                        key,
+                        value,
+                        cf: CF_DEFAULT,
+                        cmd_type: CmdType::Put,
+                    });
+                }
+                let lock = Lock::parse(&lock_value).map_err(|err| {
+                    annotate!(
+                        err,
+                        "BUG?: failed to parse ts from lock; key = {}",
+                        utils::redact(&lock_at)
+                    )
+                })?;
+                debug!("meet lock during initial scanning."; "key" => %utils::redact(&lock_at), "ts" => %lock.ts);
+                if utils::should_track_lock(&lock) {
+                    resolver
+                        .track_phase_one_lock(lock.ts, lock_at)
+                        .map_err(|_| Error::OutOfQuota { region_id })?;
                 }
-                TxnEntry::Commit { default, write, .. } => {
+            }
+            TxnEntry::Commit { default, write, .. } => {
+                result.push(ApplyEvent {
+                    key: write.0,
+                    value: write.1,
+                    cf: CF_WRITE,
+                    cmd_type: CmdType::Put,
+                });
+                if !default.0.is_empty() {
                     result.push(ApplyEvent {
-                        key: write.0,
-                        value: write.1,
-                        cf: CF_WRITE,
+                        key: default.0,
+                        value: default.1,
+                        cf: CF_DEFAULT,
                         cmd_type: CmdType::Put,
                     });
-                    if !default.0.is_empty() {
-                        result.push(ApplyEvent {
-                            key: default.0,
-                            value: default.1,
-                            cf: CF_DEFAULT,
-                            cmd_type: CmdType::Put,
-                        });
-                    }
                 }
             }
         }
-        Ok(())
     }
+    Ok(())
 }
 
 /// The context for loading incremental data between range.
@@ -209,6 +222,12 @@ pub struct InitialDataLoader<E: KvEngine, H> {
 
     cdc_handle: H,
 
+    /// A short-lived cache shared with other initial-scan consumers (see its
+    /// doc comment). Scans that hit it can skip reading from the snapshot
+    /// entirely; scans that miss it save their result here for the next
+    /// consumer.
+    initial_scan_cache: Arc<InitialScanCache>,
+
     _engine: PhantomData<E>,
 }
 
@@ -225,6 +244,7 @@ where
         limiter: Limiter,
         cdc_handle: H,
         concurrency_limit: Arc<Semaphore>,
+        initial_scan_cache: Arc<InitialScanCache>,
     ) -> Self {
         Self {
             sink,
@@ -235,6 +255,7 @@ where
             cdc_handle,
             concurrency_limit,
             limit: limiter,
+            initial_scan_cache,
         }
     }
 
@@ -396,8 +417,9 @@ where
         handle: &ObserveHandle,
         mut event_loader: EventLoader<impl Snapshot>,
         join_handles: &mut Vec<tokio::task::JoinHandle<()>>,
-    ) -> Result<Statistics> {
+    ) -> Result<(Statistics, Vec<TxnEntry>)> {
         let mut stats = StatisticsSummary::default();
+        let mut scanned_entries = Vec::new();
         let start = Instant::now();
         loop {
             fail::fail_point!("scan_and_async_send", |msg| Err(Error::Other(box_err!(
@@ -410,6 +432,7 @@ where
             let (res, disk_read) =
                 utils::with_record_read_throughput(|| event_loader.fill_entries(&mut allocated));
             let res = res?;
+            scanned_entries.extend(event_loader.entry_batch.iter().cloned());
             self.with_resolver(region, handle, |r| {
                 event_loader.emit_entries_to(&mut events, r)
             })?;
@@ -432,7 +455,7 @@ where
             }));
             if !res.more {
                 metrics::INITIAL_SCAN_DURATION.observe(start.saturating_elapsed_secs());
-                return Ok(stats.stat);
+                return Ok((stats.stat, scanned_entries));
             }
             if res.out_of_memory {
                 futures::future::try_join_all(join_handles.drain(..))
@@ -447,6 +470,43 @@ where
         }
     }
 
+    /// Replays a cached initial-scan result, as if it had just been freshly
+    /// scanned, without touching the snapshot at all.
+    async fn send_cached_entries(
+        &self,
+        region: &Region,
+        handle: &ObserveHandle,
+        entries: Arc<Vec<TxnEntry>>,
+        join_handles: &mut Vec<tokio::task::JoinHandle<()>>,
+    ) -> Result<Statistics> {
+        let start = Instant::now();
+        let mut events = ApplyEvents::with_capacity(entries.len(), region.id);
+        self.with_resolver(region, handle, |r| {
+            emit_entries(entries.iter().cloned(), region.get_id(), &mut events, r)
+        })?;
+
+        let region_id = region.get_id();
+        let sink = self.sink.clone();
+        let event_size = events.size();
+        let sched = self.scheduler.clone();
+        let mut allocated = OwnedAllocated::new(Arc::clone(&self.quota));
+        // Best-effort: unlike a fresh scan, there's no batching here to slow down if
+        // the quota is exceeded, so a failure to account for it just means the quota
+        // temporarily under-counts this event set.
+        let _ = allocated.alloc(event_size);
+        debug!("sending cached initial-scan events to router"; "size" => %event_size, "region" => %region_id);
+        metrics::INCREMENTAL_SCAN_SIZE.observe(event_size as f64);
+        metrics::HEAP_MEMORY.add(event_size as _);
+        join_handles.push(tokio::spawn(async move {
+            utils::handle_on_event_result(&sched, sink.on_events(events).await);
+            metrics::HEAP_MEMORY.sub(event_size as _);
+            drop(allocated);
+            debug!("apply cached initial-scan events done"; "size" => %event_size, "region" => %region_id);
+        }));
+        metrics::INITIAL_SCAN_DURATION.observe(start.saturating_elapsed_secs());
+        Ok(Statistics::default())
+    }
+
     #[instrument(skip_all)]
     pub async fn do_initial_scan(
         &self,
@@ -457,16 +517,26 @@ where
         snap: impl Snapshot,
     ) -> Result<Statistics> {
         let mut join_handles = Vec::with_capacity(8);
+        let region_id = region.get_id();
 
         let permit = frame!(self.concurrency_limit.acquire())
             .await
             .expect("BUG: semaphore closed");
 
-        // It is ok to sink more data than needed. So scan to +inf TS for convenance.
-        let event_loader = EventLoader::load_from(snap, start_ts, TimeStamp::max(), region)?;
-        let stats = self
-            .scan_and_async_send(region, &handle, event_loader, &mut join_handles)
-            .await?;
+        let stats = if let Some(cached) = self.initial_scan_cache.get(region_id, start_ts) {
+            debug!("reusing cached initial scan result"; "region_id" => region_id, "start_ts" => %start_ts);
+            self.send_cached_entries(region, &handle, cached, &mut join_handles)
+                .await?
+        } else {
+            // It is ok to sink more data than needed. So scan to +inf TS for convenance.
+            let event_loader = EventLoader::load_from(snap, start_ts, TimeStamp::max(), region)?;
+            let (stats, scanned_entries) = self
+                .scan_and_async_send(region, &handle, event_loader, &mut join_handles)
+                .await?;
+            self.initial_scan_cache
+                .insert(region_id, start_ts, Arc::new(scanned_entries));
+            stats
+        };
         drop(permit);
 
         frame!(futures::future::try_join_all(join_handles))
@@ -0,0 +1,76 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Keyspace-awareness for API v2 keyspace-scoped log backup tasks.
+//!
+//! A task's ranges are already keyspace-prefixed raw keys under API v2 (see
+//! [`api_version::keyspace`]), so [`crate::router::Router`]'s existing
+//! range-based filtering already keeps one task's events from leaking into
+//! another's, without any change here. What's missing is a way to tell
+//! *which* keyspaces a task covers, for checkpoint/manifest reporting and
+//! for catching a task whose ranges accidentally straddle more keyspaces
+//! than its owner intended.
+//!
+//! `StreamBackupTaskInfo` has no field for a client to declare that intent
+//! directly, so it's derived from the task's own ranges instead. Tagging
+//! the per-keyspace breakdown into the written manifest
+//! ([`kvproto::brpb::DataFileInfo`]) itself would need a `kvproto` change.
+
+use api_version::{keyspace::KeyspaceId, ApiV2, Keyspace};
+use kvproto::kvrpcpb::ApiVersion;
+
+/// The distinct keyspace ids touched by `ranges`' start keys, deduplicated
+/// and sorted. Empty under API v1/v1ttl, which have no keyspace concept. A
+/// range whose start key doesn't parse as a valid API v2 key (e.g. an empty
+/// start key standing in for "from the beginning") contributes no id.
+pub fn keyspaces_of_ranges<'a>(
+    api_version: ApiVersion,
+    ranges: impl Iterator<Item = &'a [u8]>,
+) -> Vec<KeyspaceId> {
+    if api_version != ApiVersion::V2 {
+        return vec![];
+    }
+    let mut ids: Vec<KeyspaceId> = ranges
+        .filter_map(|start_key| ApiV2::parse_keyspace(start_key).ok())
+        .filter_map(|(id, _)| id)
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use api_version::api_v2::TXN_KEY_PREFIX;
+
+    use super::*;
+
+    fn v2_key(keyspace: u32, user_key: &[u8]) -> Vec<u8> {
+        let mut key = vec![TXN_KEY_PREFIX];
+        key.extend_from_slice(&keyspace.to_be_bytes()[1..]);
+        key.extend_from_slice(user_key);
+        key
+    }
+
+    #[test]
+    fn test_keyspaces_of_ranges() {
+        let ranges = vec![v2_key(1, b"a"), v2_key(2, b"a"), v2_key(1, b"b")];
+        let ids = keyspaces_of_ranges(ApiVersion::V2, ranges.iter().map(|k| k.as_slice()));
+        assert_eq!(ids, vec![KeyspaceId::from(1), KeyspaceId::from(2)]);
+    }
+
+    #[test]
+    fn test_keyspaces_of_ranges_v1_is_empty() {
+        let ranges = vec![v2_key(1, b"a")];
+        let ids = keyspaces_of_ranges(ApiVersion::V1, ranges.iter().map(|k| k.as_slice()));
+        assert!(ids.is_empty());
+    }
+
+    #[test]
+    fn test_keyspaces_of_ranges_ignores_unparsable() {
+        // An empty start key (meaning "from the very beginning") isn't a
+        // valid API v2 key, and shouldn't be counted as any keyspace.
+        let ranges: Vec<Vec<u8>> = vec![vec![]];
+        let ids = keyspaces_of_ranges(ApiVersion::V2, ranges.iter().map(|k| k.as_slice()));
+        assert!(ids.is_empty());
+    }
+}
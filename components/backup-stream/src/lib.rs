@@ -6,14 +6,17 @@
 
 mod checkpoint_manager;
 pub mod config;
+mod debug_event;
 mod endpoint;
 pub mod errors;
 mod event_loader;
+mod keyspace;
 pub mod metadata;
 pub mod metrics;
 pub mod observer;
 pub mod router;
 mod service;
+pub mod sink;
 mod subscription_manager;
 mod subscription_track;
 mod tempfiles;
@@ -111,12 +111,7 @@ impl<E: KvEngine> CmdObserver<E> for BackupStreamObserver {
             return;
         }
 
-        // TODO may be we should filter cmd batch here, to reduce the cost of clone.
-        let cmd_batches: Vec<_> = cmd_batches
-            .iter()
-            .filter(|cb| !cb.is_empty() && cb.level == ObserveLevel::All)
-            .cloned()
-            .collect();
+        let cmd_batches = Self::filter_cmd_batches(ObserveLevel::All, cmd_batches);
         if cmd_batches.is_empty() {
             return;
         }
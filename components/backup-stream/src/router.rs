@@ -13,6 +13,7 @@ use std::{
     time::Duration,
 };
 
+use api_version::keyspace::KeyspaceId;
 use encryption::DataKeyManager;
 use engine_traits::{CfName, CF_DEFAULT, CF_LOCK, CF_WRITE};
 use external_storage::{create_storage, BackendConfig, ExternalStorage, UnpinReader};
@@ -29,19 +30,20 @@ use protobuf::Message;
 use raftstore::coprocessor::CmdBatch;
 use slog_global::debug;
 use tidb_query_datatype::codec::table::decode_table_id;
-use tikv::config::BackupStreamConfig;
+use tikv::config::{BackupStreamConfig, KafkaSinkConfig};
 use tikv_util::{
     box_err,
     codec::stream_event::EventEncoder,
     config::ReadableSize,
     error, info,
+    memory::MemoryQuota,
     time::{Instant, Limiter},
     warn,
     worker::Scheduler,
     Either, HandyRwLock,
 };
 use tokio::{
-    io::AsyncWriteExt,
+    io::{AsyncReadExt, AsyncWriteExt},
     sync::{Mutex, RwLock},
 };
 use tokio_util::compat::TokioAsyncReadCompatExt;
@@ -52,10 +54,12 @@ use txn_types::{Key, Lock, TimeStamp, WriteRef};
 use super::errors::Result;
 use crate::{
     annotate,
+    debug_event::DebugEventLog,
     endpoint::Task,
     errors::{ContextualResultExt, Error},
     metadata::StreamTask,
     metrics::{HANDLE_KV_HISTOGRAM, SKIP_KV_COUNTER},
+    sink::{KafkaSink, LoggingKafkaProducer, Sink},
     subscription_track::TwoPhaseResolver,
     tempfiles::{self, TempFilePool},
     try_send,
@@ -64,6 +68,12 @@ use crate::{
 
 const FLUSH_FAILURE_BECOME_FATAL_THRESHOLD: usize = 30;
 
+/// Once the shared local temp storage usage reaches this ratio of its quota,
+/// `tick` will eagerly flush whichever task is currently holding the most
+/// temp-file bytes, to spill some of it out before admission control starts
+/// rejecting new events.
+const EARLY_FLUSH_DISK_USAGE_RATIO: f64 = 0.9;
+
 #[derive(Clone)]
 pub enum TaskSelector {
     ByName(String),
@@ -325,8 +335,10 @@ pub struct Config {
     pub prefix: PathBuf,
     pub temp_file_size_limit: u64,
     pub temp_file_memory_quota: u64,
+    pub temp_file_disk_quota: u64,
     pub max_flush_interval: Duration,
     pub data_key_manager: Option<Arc<DataKeyManager>>,
+    pub kafka_sink: KafkaSinkConfig,
 }
 
 impl From<tikv::config::BackupStreamConfig> for Config {
@@ -334,13 +346,16 @@ impl From<tikv::config::BackupStreamConfig> for Config {
         let prefix = PathBuf::from(value.temp_path);
         let temp_file_size_limit = value.file_size_limit.0;
         let temp_file_memory_quota = value.temp_file_memory_quota.0;
+        let temp_file_disk_quota = value.temp_file_disk_quota.0;
         let max_flush_interval = value.max_flush_interval.0;
         Self {
             prefix,
             temp_file_size_limit,
             temp_file_memory_quota,
+            temp_file_disk_quota,
             max_flush_interval,
             data_key_manager: None,
+            kafka_sink: value.kafka_sink,
         }
     }
 }
@@ -352,6 +367,16 @@ impl Router {
     }
 }
 
+/// Interpret a configured disk quota, where `0` means "unlimited", into the
+/// capacity `MemoryQuota` expects.
+fn disk_quota_capacity(configured: u64) -> usize {
+    if configured == 0 {
+        usize::MAX
+    } else {
+        configured as usize
+    }
+}
+
 impl std::ops::Deref for Router {
     type Target = RouterInner;
 
@@ -385,9 +410,19 @@ pub struct RouterInner {
     /// The size limit of temporary file per task.
     temp_file_size_limit: AtomicU64,
     temp_file_memory_quota: AtomicU64,
+    /// The quota of bytes that may be swapped out to the local temp
+    /// directory, shared by every task's [`TempFilePool`]. Once it is
+    /// exhausted, new events are rejected with [`Error::TempFileStorageFull`]
+    /// instead of being admitted and later failing the flush with an opaque
+    /// I/O error.
+    disk_quota: Arc<MemoryQuota>,
     /// The max duration the local data can be pending.
     max_flush_interval: SyncRwLock<Duration>,
     data_key_manager: Option<Arc<DataKeyManager>>,
+    /// Tasks allowed to publish their flushed data files to a Kafka sink
+    /// instead of the task's external storage. See
+    /// [`RouterInner::sink_for_task`].
+    kafka_sink: SyncRwLock<KafkaSinkConfig>,
 }
 
 impl std::fmt::Debug for RouterInner {
@@ -409,8 +444,12 @@ impl RouterInner {
             scheduler,
             temp_file_size_limit: AtomicU64::new(config.temp_file_size_limit),
             temp_file_memory_quota: AtomicU64::new(config.temp_file_memory_quota),
+            disk_quota: Arc::new(MemoryQuota::new(disk_quota_capacity(
+                config.temp_file_disk_quota,
+            ))),
             max_flush_interval: SyncRwLock::new(config.max_flush_interval),
             data_key_manager: config.data_key_manager,
+            kafka_sink: SyncRwLock::new(config.kafka_sink),
         }
     }
 
@@ -420,6 +459,9 @@ impl RouterInner {
             .store(config.file_size_limit.0, Ordering::SeqCst);
         self.temp_file_memory_quota
             .store(config.temp_file_memory_quota.0, Ordering::SeqCst);
+        self.disk_quota
+            .set_capacity(disk_quota_capacity(config.temp_file_disk_quota.0));
+        *self.kafka_sink.write().unwrap() = config.kafka_sink.clone();
         let tasks = self.tasks.blocking_lock();
         for task in tasks.values() {
             task.temp_file_pool
@@ -477,13 +519,22 @@ impl RouterInner {
         task: StreamTask,
         ranges: Vec<(Vec<u8>, Vec<u8>)>,
         merged_file_size_limit: u64,
+        keyspaces: Vec<KeyspaceId>,
     ) -> Result<()> {
         let task_name = task.info.get_name().to_owned();
 
         // register task info
         let cfg = self.tempfile_config_for_task(&task);
-        let stream_task =
-            StreamTaskInfo::new(task, ranges.clone(), merged_file_size_limit, cfg).await?;
+        let sink = self.sink_for_task(&task_name);
+        let stream_task = StreamTaskInfo::new(
+            task,
+            ranges.clone(),
+            merged_file_size_limit,
+            cfg,
+            sink,
+            keyspaces,
+        )
+        .await?;
         frame!(self.tasks.lock())
             .await
             .insert(task_name.clone(), Arc::new(stream_task));
@@ -495,9 +546,10 @@ impl RouterInner {
     }
 
     fn tempfile_config_for_task(&self, task: &StreamTask) -> tempfiles::Config {
-        // Note: the scope of this config is per-task. That means, when there are
-        // multi tasks, we may need to share the pool over tasks, or at least share the
-        // quota between tasks -- but not for now. We don't support that.
+        // Note: the pool itself is still scoped per-task, each with its own
+        // in-memory cache and swap-out directory. `disk_quota` is the exception:
+        // it is shared across every task's pool so the node-wide local temp
+        // storage usage can be tracked and capped together.
         tempfiles::Config {
             // Note: will it be more effective to directly sharing the same atomic value?
             cache_size: AtomicUsize::new(
@@ -508,9 +560,56 @@ impl RouterInner {
             minimal_swap_out_file_size: ReadableSize::mb(1).0 as _,
             write_buffer_size: ReadableSize::kb(4).0 as _,
             encryption: self.data_key_manager.clone(),
+            disk_quota: self.disk_quota.clone(),
         }
     }
 
+    /// The quota tracking how many bytes have been swapped out to the local
+    /// temp directory, shared across every task registered to this router.
+    pub fn disk_quota(&self) -> &Arc<MemoryQuota> {
+        &self.disk_quota
+    }
+
+    /// Resolve the [`Sink`] that `task_name`'s flushed data files should be
+    /// published to, if any. Only `log-backup.kafka-sink.tasks` opts a task
+    /// into this; everything else continues to flush to the task's
+    /// configured external storage.
+    ///
+    /// Note: there is no real Kafka client wired up yet (see
+    /// [`crate::sink::LoggingKafkaProducer`]), so this currently only
+    /// exercises the per-region batching, not an actual publish to a
+    /// broker. This is why actually constructing a [`KafkaSink`] is gated
+    /// behind the `kafka-sink-experimental` feature, which is off by
+    /// default: without it, `log-backup.kafka-sink.tasks` is accepted by
+    /// config validation but has no effect, rather than silently dropping
+    /// every flushed data file.
+    #[cfg(feature = "kafka-sink-experimental")]
+    fn sink_for_task(&self, task_name: &str) -> Option<Arc<dyn Sink>> {
+        let cfg = self.kafka_sink.rl();
+        if cfg.tasks.iter().any(|t| t == task_name) {
+            Some(Arc::new(KafkaSink::new(
+                LoggingKafkaProducer::new(),
+                cfg.topic.clone(),
+            )))
+        } else {
+            None
+        }
+    }
+
+    #[cfg(not(feature = "kafka-sink-experimental"))]
+    fn sink_for_task(&self, task_name: &str) -> Option<Arc<dyn Sink>> {
+        let cfg = self.kafka_sink.rl();
+        if cfg.tasks.iter().any(|t| t == task_name) {
+            warn!(
+                "log-backup.kafka-sink.tasks names this task, but this build was not compiled \
+                 with the `kafka-sink-experimental` feature, so its flushed data files will \
+                 continue to go to external storage only";
+                "task" => task_name,
+            );
+        }
+        None
+    }
+
     pub async fn unregister_task(&self, task_name: &str) -> Option<StreamBackupTaskInfo> {
         frame!(self.tasks.lock()).await.remove(task_name).map(|t| {
             info!(
@@ -573,6 +672,15 @@ impl RouterInner {
     #[instrument(skip_all, fields(task))]
     async fn on_event(&self, task: String, events: ApplyEvents) -> Result<()> {
         let task_info = self.get_task_info(&task).await?;
+        // Admission control: reject new events up front once the shared local
+        // temp storage is full, instead of letting the write fail deep inside
+        // the temp file pool with an opaque I/O error.
+        if self.disk_quota.in_use() >= self.disk_quota.capacity() {
+            return Err(Error::TempFileStorageFull {
+                used: self.disk_quota.in_use() as u64,
+                capacity: self.disk_quota.capacity() as u64,
+            });
+        }
         task_info.on_events(events).await?;
         let file_size_limit = self.temp_file_size_limit.load(Ordering::SeqCst);
 
@@ -664,8 +772,22 @@ impl RouterInner {
     #[instrument(skip_all)]
     pub async fn tick(&self) {
         let max_flush_interval = self.max_flush_interval.rl().to_owned();
+        let tasks = self.tasks.lock().await;
+        let disk_pressure = self.disk_quota.used_ratio() >= EARLY_FLUSH_DISK_USAGE_RATIO;
+        // Under disk pressure, pick whichever non-flushing task is currently
+        // holding the most temp-file bytes: flushing it evicts the largest
+        // chunk of local temp storage for the least number of flushes.
+        let early_flush_victim = disk_pressure
+            .then(|| {
+                tasks
+                    .iter()
+                    .filter(|(_, t)| !t.is_flushing())
+                    .max_by_key(|(_, t)| t.total_size())
+                    .map(|(name, _)| name.clone())
+            })
+            .flatten();
 
-        for (name, task_info) in self.tasks.lock().await.iter() {
+        for (name, task_info) in tasks.iter() {
             if let Err(e) = self
                 .scheduler
                 .schedule(Task::UpdateGlobalCheckpoint(name.to_string()))
@@ -675,12 +797,15 @@ impl RouterInner {
 
             // if stream task need flush this time, schedule Task::Flush, or update time
             // justly.
-            if task_info.should_flush(&max_flush_interval)
+            let need_early_flush = early_flush_victim.as_deref() == Some(name.as_str());
+            if (task_info.should_flush(&max_flush_interval) || need_early_flush)
                 && task_info.set_flushing_status_cas(false, true).is_ok()
             {
                 info!(
                     "backup stream trigger flush task by tick";
                     "task" => ?task_info,
+                    "early_flush_for_disk_pressure" => need_early_flush,
+                    "disk_used_ratio" => self.disk_quota.used_ratio(),
                 );
 
                 if let Err(e) = self.scheduler.schedule(Task::Flush(name.clone())) {
@@ -693,6 +818,10 @@ impl RouterInner {
 }
 
 /// The handle of a temporary file.
+///
+/// [`StreamTaskInfo::move_to_flushing_files`] sorts a batch of keys by
+/// `(table_id, region_id)`, which groups files belonging to the same table
+/// together before they are merged.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 struct TempFileKey {
     table_id: i64,
@@ -858,6 +987,17 @@ pub struct StreamTaskInfo {
     merged_file_size_limit: u64,
     /// The pool for holding the temporary files.
     temp_file_pool: Arc<TempFilePool>,
+    /// When set, flushed data files (not meta files or the global
+    /// checkpoint) are published to this sink instead of `storage`.
+    sink: Option<Arc<dyn Sink>>,
+    /// A rate-limited log of subscription changes, flush results, and
+    /// errors this task has hit, flushed to `storage` alongside the task's
+    /// backup data. See [`DebugEventLog`].
+    debug_events: DebugEventLog,
+    /// The keyspaces `ranges` covers, under API v2. Empty for API v1/v1ttl
+    /// tasks, which have no keyspace concept. See [`crate::keyspace`] for how
+    /// this is derived and why it doesn't need a `kvproto` change.
+    keyspaces: Vec<KeyspaceId>,
 }
 
 impl Drop for StreamTaskInfo {
@@ -900,6 +1040,8 @@ impl StreamTaskInfo {
         ranges: Vec<(Vec<u8>, Vec<u8>)>,
         merged_file_size_limit: u64,
         temp_pool_cfg: tempfiles::Config,
+        sink: Option<Arc<dyn Sink>>,
+        keyspaces: Vec<KeyspaceId>,
     ) -> Result<Self> {
         let temp_dir = &temp_pool_cfg.swap_files;
         tokio::fs::create_dir_all(temp_dir).await?;
@@ -923,9 +1065,18 @@ impl StreamTaskInfo {
             global_checkpoint_ts: AtomicU64::new(start_ts),
             merged_file_size_limit,
             temp_file_pool: Arc::new(TempFilePool::new(temp_pool_cfg)?),
+            sink,
+            debug_events: DebugEventLog::default(),
+            keyspaces,
         })
     }
 
+    /// The keyspaces this task's ranges cover. See the `keyspaces` field doc
+    /// for details.
+    pub fn keyspaces(&self) -> &[KeyspaceId] {
+        &self.keyspaces
+    }
+
     #[instrument(skip(self, events), fields(event_len = events.len()))]
     async fn on_events_of_key(&self, key: TempFileKey, events: ApplyEvents) -> Result<()> {
         fail::fail_point!("before_generate_temp_file");
@@ -982,6 +1133,42 @@ impl StreamTaskInfo {
         unsafe { *(self.last_flush_time.load(Ordering::SeqCst) as *const Instant) }
     }
 
+    /// Record a debug event (a subscription change, a flush result, an
+    /// error...) for this task. See [`DebugEventLog`].
+    pub(crate) fn record_debug_event(&self, kind: &'static str, message: impl Display) {
+        self.debug_events.record(kind, message);
+    }
+
+    /// Write whatever `debug_events` has buffered to this task's external
+    /// storage, alongside its backup data, so it survives past this
+    /// process's lifetime. Best-effort: a failure here is logged but does
+    /// not fail the flush that triggered it, since losing a few debug lines
+    /// is far preferable to losing backup data over it.
+    #[instrument(skip_all)]
+    async fn persist_debug_events(&self) {
+        let Some(rendered) = self.debug_events.take_rendered() else {
+            return;
+        };
+        let path = format!(
+            "v1/backupmeta/debug/{}-{}.log",
+            TimeStamp::physical_now(),
+            uuid::Uuid::new_v4()
+        );
+        let len = rendered.len();
+        if let Err(err) = self
+            .storage
+            .write(
+                &path,
+                UnpinReader(Box::new(Cursor::new(rendered.into_bytes()))),
+                len as _,
+            )
+            .await
+        {
+            warn!("backup stream failed to persist debug event log";
+                "task" => %self.task.info.get_name(), "path" => %path, "err" => ?err);
+        }
+    }
+
     pub fn total_size(&self) -> u64 {
         self.total_size.load(Ordering::SeqCst) as _
     }
@@ -1052,6 +1239,7 @@ impl StreamTaskInfo {
         let mut w = frame!(self.files.write()).await;
         let mut fw = frame!(self.flushing_files.write()).await;
         let mut fw_meta = frame!(self.flushing_meta_files.write()).await;
+        let mut drained = Vec::with_capacity(w.len());
         for (k, v) in w.drain() {
             // we should generate file metadata(calculate sha256) when moving file.
             // because sha256 calculation is a unsafe move operation.
@@ -1059,6 +1247,15 @@ impl StreamTaskInfo {
             // TODO refactor move_to_flushing_files and generate_metadata
             let mut v = v.into_inner();
             let file_meta = v.generate_metadata(&k)?;
+            drained.push((k, v, file_meta));
+        }
+        // `files` is a sharded hash map, so the drain order is arbitrary.
+        // Sort by (table_id, region_id) first so that `merge_log`'s
+        // size-limited batching groups files from the same table/region
+        // range into the same merged object, instead of interleaving
+        // unrelated tables into one blob.
+        drained.sort_by_key(|(k, ..)| (k.table_id, k.region_id));
+        for (k, v, file_meta) in drained {
             if file_meta.is_meta {
                 fw_meta.push((k, v, file_meta));
             } else {
@@ -1173,6 +1370,44 @@ impl StreamTaskInfo {
         Ok(())
     }
 
+    /// Publish one batch of data files to `sink`, grouped by region so a
+    /// sink that partitions by region (e.g. [`crate::sink::KafkaSink`]) only
+    /// ever sees one region's batches per partition, in flush order.
+    ///
+    /// Unlike [`Self::merge_and_flush_log_files_to`], nothing is pushed into
+    /// `metadata`: the files published here never land in the task's
+    /// external storage, so there is no blob for the meta file to
+    /// reference.
+    #[instrument(skip_all)]
+    async fn publish_batch_to_sink(
+        sink: &Arc<dyn Sink>,
+        files: &mut [(TempFileKey, DataFile, DataFileInfo)],
+        shared_pool: Arc<TempFilePool>,
+    ) -> Result<()> {
+        let mut by_region: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (i, (key, ..)) in files.iter().enumerate() {
+            by_region.entry(key.region_id).or_default().push(i);
+        }
+        for (region_id, indices) in by_region {
+            let mut content = Vec::new();
+            for i in indices {
+                let (_, data_file, _) = &files[i];
+                let mut file = shared_pool
+                    .open_raw_for_read(data_file.inner.path())
+                    .context(format_args!(
+                        "failed to open read file {:?}",
+                        data_file.inner.path()
+                    ))?;
+                file.read_to_end(&mut content).await?;
+            }
+            let key = format!("v1/kafka/{:08}-{}.log", region_id, uuid::Uuid::new_v4());
+            sink.publish(region_id, &key, content)
+                .await
+                .context(format_args!("publishing {:?} to sink", key))?;
+        }
+        Ok(())
+    }
+
     #[instrument(skip_all)]
     pub async fn flush_log(&self, metadata: &mut MetadataInfo) -> Result<()> {
         let storage = self.storage.clone();
@@ -1195,18 +1430,34 @@ impl StreamTaskInfo {
         let mut batch_size = 0;
         // file[batch_begin_index, i) is a batch
         let mut batch_begin_index = 0;
+        // Meta files always go through the external storage, regardless of
+        // whether a sink is configured for this task: only data files are
+        // eligible to be redirected.
+        let sink = if is_meta { None } else { self.sink.clone() };
         // TODO: upload the merged file concurrently,
         // then collect merged_file_infos and push them into `metadata`.
         for i in 0..files.len() {
             if batch_size >= self.merged_file_size_limit {
-                Self::merge_and_flush_log_files_to(
-                    storage.clone(),
-                    &mut files[batch_begin_index..i],
-                    metadata,
-                    is_meta,
-                    self.temp_file_pool.clone(),
-                )
-                .await?;
+                match &sink {
+                    Some(sink) => {
+                        Self::publish_batch_to_sink(
+                            sink,
+                            &mut files[batch_begin_index..i],
+                            self.temp_file_pool.clone(),
+                        )
+                        .await?
+                    }
+                    None => {
+                        Self::merge_and_flush_log_files_to(
+                            storage.clone(),
+                            &mut files[batch_begin_index..i],
+                            metadata,
+                            is_meta,
+                            self.temp_file_pool.clone(),
+                        )
+                        .await?
+                    }
+                }
 
                 batch_begin_index = i;
                 batch_size = 0;
@@ -1215,14 +1466,26 @@ impl StreamTaskInfo {
             batch_size += files[i].2.length;
         }
         if batch_begin_index < files.len() {
-            Self::merge_and_flush_log_files_to(
-                storage.clone(),
-                &mut files[batch_begin_index..],
-                metadata,
-                is_meta,
-                self.temp_file_pool.clone(),
-            )
-            .await?;
+            match &sink {
+                Some(sink) => {
+                    Self::publish_batch_to_sink(
+                        sink,
+                        &mut files[batch_begin_index..],
+                        self.temp_file_pool.clone(),
+                    )
+                    .await?
+                }
+                None => {
+                    Self::merge_and_flush_log_files_to(
+                        storage.clone(),
+                        &mut files[batch_begin_index..],
+                        metadata,
+                        is_meta,
+                        self.temp_file_pool.clone(),
+                    )
+                    .await?
+                }
+            }
         }
 
         Ok(())
@@ -1247,6 +1510,25 @@ impl StreamTaskInfo {
         Ok(())
     }
 
+    /// Best-effort reclaim of data files that were written to storage but
+    /// whose meta file never got published, so nothing will ever reference
+    /// them.
+    ///
+    /// This only covers the failure happening within the same `do_flush`
+    /// call that wrote the files (e.g. the meta write itself fails). If the
+    /// process dies between a successful `flush_log` and a `flush_meta`
+    /// that never gets to run, the files are left behind: `ExternalStorage`
+    /// has no listing API, so there is no way yet to discover them
+    /// out-of-band and reconcile them against the published meta files.
+    async fn gc_unpublished_data_files(&self, paths: &[String]) {
+        for path in paths {
+            if let Err(e) = self.storage.delete(path).await {
+                warn!("backup stream failed to gc unpublished data file";
+                    "path" => %path, "err" => ?e);
+            }
+        }
+    }
+
     /// get the total count of adjacent error.
     pub fn flush_failure_count(&self) -> usize {
         self.flush_fail_count.load(Ordering::SeqCst)
@@ -1298,8 +1580,21 @@ impl StreamTaskInfo {
                 .iter()
                 .map(|d| (d.length, d.data_files_info.len()))
                 .collect::<Vec<_>>();
-            // flush meta file to storage.
-            self.flush_meta(metadata_info).await?;
+            // Until the meta file below is written, the data files just flushed above
+            // are unpublished: nothing references them yet, so a reader recovering
+            // this task can't tell them apart from garbage. Remember their paths so
+            // that if publishing the meta file fails, we can reclaim them instead of
+            // leaking them in external storage forever.
+            let unpublished_data_files = metadata_info
+                .file_groups
+                .iter()
+                .map(|d| d.path.clone())
+                .collect::<Vec<_>>();
+            // flush meta file to storage, atomically publishing the data files above.
+            if let Err(e) = self.flush_meta(metadata_info).await {
+                self.gc_unpublished_data_files(&unpublished_data_files).await;
+                return Err(e);
+            }
             crate::metrics::FLUSH_DURATION
                 .with_label_values(&["save_files"])
                 .observe(sw.lap().as_secs_f64());
@@ -1322,6 +1617,16 @@ impl StreamTaskInfo {
         }
         .await;
 
+        match &result {
+            Ok(rts) => self
+                .debug_events
+                .record("flush", format_args!("flush succeeded, resolved_ts={:?}", rts)),
+            Err(err) => self
+                .debug_events
+                .record("flush", format_args!("flush failed: {}", err)),
+        }
+        self.persist_debug_events().await;
+
         if result.is_err() {
             self.flush_fail_count.fetch_add(1, Ordering::SeqCst);
         } else {
@@ -1626,6 +1931,7 @@ mod tests {
             minimal_swap_out_file_size: 0,
             write_buffer_size: 0,
             encryption: None,
+            disk_quota: Arc::new(MemoryQuota::new(usize::MAX)),
         }
     }
 
@@ -1722,8 +2028,10 @@ mod tests {
                 prefix: PathBuf::new(),
                 temp_file_size_limit: 1024,
                 temp_file_memory_quota: 1024 * 2,
+                temp_file_disk_quota: 0,
                 max_flush_interval: Duration::from_secs(300),
                 data_key_manager: None,
+                kafka_sink: KafkaSinkConfig::default(),
             },
         );
         // -----t1.start-----t1.end-----t2.start-----t2.end------
@@ -1793,6 +2101,7 @@ mod tests {
                     utils::wrap_key(make_table_key(table_id + 1, b"")),
                 )],
                 0x100000,
+                vec![],
             )
             .await
             .expect("failed to register task")
@@ -1833,8 +2142,10 @@ mod tests {
                 prefix: tmp.clone(),
                 temp_file_size_limit: 32,
                 temp_file_memory_quota: 32 * 2,
+                temp_file_disk_quota: 0,
                 max_flush_interval: Duration::from_secs(300),
                 data_key_manager: None,
+                kafka_sink: KafkaSinkConfig::default(),
             },
         );
         let (stream_task, storage_path) = task("dummy".to_owned()).await.unwrap();
@@ -1962,6 +2273,8 @@ mod tests {
             vec![(vec![], vec![])],
             merged_file_size_limit,
             make_tempfiles_cfg(tmp_dir.path()),
+            None,
+            vec![],
         )
         .await
         .unwrap();
@@ -1994,6 +2307,47 @@ mod tests {
         assert_eq!(log_count, 2);
     }
 
+    #[tokio::test]
+    async fn test_move_to_flushing_files_groups_by_table_id() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let backend = external_storage::make_local_backend(tmp_dir.path());
+        let mut task_info = StreamBackupTaskInfo::default();
+        task_info.set_storage(backend);
+        let stream_task = StreamTask {
+            info: task_info,
+            is_paused: false,
+        };
+        let task = StreamTaskInfo::new(
+            stream_task,
+            vec![(vec![], vec![])],
+            0x10000,
+            make_tempfiles_cfg(tmp_dir.path()),
+            None,
+            vec![],
+        )
+        .await
+        .unwrap();
+
+        // Feed table ids out of order, so the underlying sharded map can't
+        // just happen to drain them sorted.
+        for table_id in [5, 1, 3, 2, 4] {
+            let kv_events = mock_build_large_kv_events(table_id, table_id as u64, 1);
+            task.on_events(kv_events).await.unwrap();
+        }
+
+        task.move_to_flushing_files().await.unwrap();
+        let table_ids: Vec<_> = task
+            .flushing_files
+            .read()
+            .await
+            .iter()
+            .map(|(k, ..)| k.table_id)
+            .collect();
+        let mut sorted = table_ids.clone();
+        sorted.sort();
+        assert_eq!(table_ids, sorted);
+    }
+
     struct ErrorStorage<Inner> {
         inner: Inner,
         error_on_write: Box<dyn Fn() -> io::Result<()> + Send + Sync>,
@@ -2083,8 +2437,10 @@ mod tests {
                 prefix: tmp.clone(),
                 temp_file_size_limit: 1,
                 temp_file_memory_quota: 2,
+                temp_file_disk_quota: 0,
                 max_flush_interval: Duration::from_secs(300),
                 data_key_manager: None,
+                kafka_sink: KafkaSinkConfig::default(),
             },
         ));
         let (task, _path) = task("error_prone".to_owned()).await?;
@@ -2122,8 +2478,10 @@ mod tests {
                 prefix: tmp.clone(),
                 temp_file_size_limit: 32,
                 temp_file_memory_quota: 32 * 2,
+                temp_file_disk_quota: 0,
                 max_flush_interval: Duration::from_secs(300),
                 data_key_manager: None,
+                kafka_sink: KafkaSinkConfig::default(),
             },
         );
         let mut stream_task = StreamBackupTaskInfo::default();
@@ -2138,6 +2496,7 @@ mod tests {
                 },
                 vec![],
                 0x100000,
+                vec![],
             )
             .await
             .unwrap();
@@ -2158,8 +2517,10 @@ mod tests {
                 prefix: tmp.clone(),
                 temp_file_size_limit: 1,
                 temp_file_memory_quota: 2,
+                temp_file_disk_quota: 0,
                 max_flush_interval: Duration::from_secs(300),
                 data_key_manager: None,
+                kafka_sink: KafkaSinkConfig::default(),
             },
         ));
         let (task, _path) = task("cleanup_test".to_owned()).await?;
@@ -2215,8 +2576,10 @@ mod tests {
                 prefix: tmp.clone(),
                 temp_file_size_limit: 1,
                 temp_file_memory_quota: 2,
+                temp_file_disk_quota: 0,
                 max_flush_interval: Duration::from_secs(300),
                 data_key_manager: None,
+                kafka_sink: KafkaSinkConfig::default(),
             },
         ));
         let (task, _path) = task("flush_failure".to_owned()).await?;
@@ -2352,6 +2715,8 @@ mod tests {
             vec![(vec![], vec![])],
             0x100000,
             make_tempfiles_cfg(tmp_dir.path()),
+            None,
+            vec![],
         )
         .await
         .unwrap();
@@ -2477,8 +2842,10 @@ mod tests {
                 prefix: PathBuf::new(),
                 temp_file_size_limit: 1,
                 temp_file_memory_quota: 2,
+                temp_file_disk_quota: 0,
                 max_flush_interval: cfg.max_flush_interval.0,
                 data_key_manager: None,
+                kafka_sink: KafkaSinkConfig::default(),
             },
         ));
 
@@ -2534,8 +2901,10 @@ mod tests {
                 // disable auto flush.
                 temp_file_size_limit: 1000,
                 temp_file_memory_quota: 2,
+                temp_file_disk_quota: 0,
                 max_flush_interval: Duration::from_secs(300),
                 data_key_manager: None,
+                kafka_sink: KafkaSinkConfig::default(),
             },
         ));
 
@@ -0,0 +1,137 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! A pluggable destination for a log backup task's flushed data, as an
+//! alternative to writing merged files to the task's configured
+//! `external_storage::ExternalStorage` backend (S3/GCS/local/...).
+//!
+//! Unlike the external-storage path, which merges every region's events in
+//! a flush batch into a single blob before writing it out, a [`Sink`]
+//! publishes one message per region, so a downstream consumer that cares
+//! about per-region ordering (e.g. a Kafka consumer group partitioned by
+//! region) can rely on messages for the same region arriving in flush
+//! order.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tikv_util::{info, warn};
+
+use crate::errors::Result;
+
+/// A destination that a log backup task's flushed, region-partitioned data
+/// can be published to, instead of the task's `ExternalStorage` backend.
+#[async_trait]
+pub trait Sink: Send + Sync {
+    /// Publish one flushed batch belonging to `region_id`. `key` mirrors
+    /// the file name that would have been used on the `ExternalStorage`
+    /// path, and `content` is the already-compressed/encrypted bytes of
+    /// every temporary file merged for that region in this flush.
+    async fn publish(&self, region_id: u64, key: &str, content: Vec<u8>) -> Result<()>;
+}
+
+/// Configuration needed to reach a Kafka-compatible broker. See
+/// `tikv::config::BackupStreamConfig::kafka_sink` for how a task opts into
+/// this sink.
+#[derive(Clone, Debug, Default)]
+pub struct KafkaSinkConfig {
+    pub brokers: Vec<String>,
+    pub topic: String,
+}
+
+/// Publishes messages to a Kafka-compatible broker. The concrete transport
+/// is pluggable via [`KafkaProducer`] so the batching/partitioning logic in
+/// [`KafkaSink`] can be exercised without a running broker.
+#[async_trait]
+pub trait KafkaProducer: Send + Sync {
+    /// Send one message, partitioned/ordered by `key` (the region id,
+    /// big-endian encoded, per Kafka's usual "same key -> same partition"
+    /// convention).
+    async fn send(&self, topic: &str, key: &[u8], payload: Vec<u8>) -> Result<()>;
+}
+
+/// A [`Sink`] that republishes each region's flushed batch as one Kafka
+/// message, keyed by region id so a single Kafka partition only ever sees
+/// one region's batches, preserving their flush order.
+pub struct KafkaSink<P> {
+    producer: P,
+    topic: String,
+}
+
+impl<P: KafkaProducer> KafkaSink<P> {
+    pub fn new(producer: P, topic: String) -> Self {
+        Self { producer, topic }
+    }
+}
+
+#[async_trait]
+impl<P: KafkaProducer> Sink for KafkaSink<P> {
+    async fn publish(&self, region_id: u64, key: &str, content: Vec<u8>) -> Result<()> {
+        self.producer
+            .send(&self.topic, &region_id.to_be_bytes(), content)
+            .await
+            .map_err(|err| err.context(format_args!("publishing {} to kafka", key)))
+    }
+}
+
+/// A [`KafkaProducer`] that doesn't actually talk to a broker: it exists so
+/// the plumbing in [`KafkaSink`] can be wired up and tested without adding
+/// a Kafka client library as a dependency of this crate. A real
+/// implementation (e.g. backed by `rdkafka`) should replace this once such
+/// a dependency is available.
+pub struct LoggingKafkaProducer {
+    sent: Arc<std::sync::Mutex<Vec<(String, Vec<u8>, Vec<u8>)>>>,
+}
+
+impl LoggingKafkaProducer {
+    pub fn new() -> Self {
+        Self {
+            sent: Arc::default(),
+        }
+    }
+
+    pub fn sent(&self) -> Vec<(String, Vec<u8>, Vec<u8>)> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+impl Default for LoggingKafkaProducer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl KafkaProducer for LoggingKafkaProducer {
+    async fn send(&self, topic: &str, key: &[u8], payload: Vec<u8>) -> Result<()> {
+        warn!(
+            "no Kafka client is wired up, dropping message that would have been published";
+            "topic" => topic,
+            "key" => ?key,
+            "payload_len" => payload.len(),
+        );
+        self.sent.lock().unwrap().push((topic.to_owned(), key.to_owned(), payload));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_kafka_sink_keys_by_region() {
+        let producer = LoggingKafkaProducer::new();
+        let sink = KafkaSink::new(producer, "log-backup".to_owned());
+
+        sink.publish(1, "region-1.log", b"a".to_vec()).await.unwrap();
+        sink.publish(2, "region-2.log", b"b".to_vec()).await.unwrap();
+        sink.publish(1, "region-1.log.2", b"c".to_vec()).await.unwrap();
+
+        let sent = sink.producer.sent();
+        assert_eq!(sent.len(), 3);
+        assert_eq!(sent[0].1, 1u64.to_be_bytes().to_vec());
+        assert_eq!(sent[1].1, 2u64.to_be_bytes().to_vec());
+        assert_eq!(sent[2].1, 1u64.to_be_bytes().to_vec());
+        info!("sent messages"; "sent" => ?sent);
+    }
+}
@@ -1,6 +1,6 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{collections::HashMap, fmt::Display, sync::Arc, time::Duration};
 
 use engine_traits::KvEngine;
 use futures::FutureExt;
@@ -505,6 +505,12 @@ where
             Some(err) => {
                 self.subs
                     .set_pending_if(&region, |sub, _| sub.handle.id == handle.id);
+                self.record_debug_event(
+                    &region,
+                    "observe",
+                    format_args!("observing region {} failed: {}", region.id, err),
+                )
+                .await;
                 if !should_retry(&err) {
                     self.failure_count.remove(&region.id);
                     // The pending record will be cleaned up by `Stop` command.
@@ -837,6 +843,20 @@ where
         self.range_router
             .find_task_by_range(&r.start_key, &r.end_key)
     }
+
+    /// Best-effort: record a subscription-change debug event on whichever
+    /// task owns `region`, so it ends up in that task's persisted debug log
+    /// (see [`crate::debug_event::DebugEventLog`]). A region with no owning
+    /// task (e.g. it was just removed) is silently skipped, since there's
+    /// nowhere meaningful to put the event.
+    async fn record_debug_event(&self, region: &Region, kind: &'static str, message: impl Display) {
+        let Some(task_name) = self.find_task_by_region(region) else {
+            return;
+        };
+        if let Ok(task) = self.range_router.get_task_info(&task_name).await {
+            task.record_debug_event(kind, message);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1074,6 +1094,7 @@ mod test {
                 task_wrapped,
                 vec![(vec![], vec![0xff, 0xff])],
                 1024 * 1024,
+                vec![],
             ))
             .unwrap();
             let subs_mgr = RegionSubscriptionManager {
@@ -31,7 +31,7 @@ use std::{
 use encryption::{DataKeyManager, DecrypterReader, EncrypterWriter, Iv};
 use futures::{AsyncWriteExt, TryFutureExt};
 use kvproto::{brpb::CompressionType, encryptionpb::EncryptionMethod};
-use tikv_util::warn;
+use tikv_util::{memory::MemoryQuota, warn};
 use tokio::{
     fs::File as OsFile,
     io::{AsyncRead, AsyncWrite},
@@ -67,6 +67,12 @@ pub struct Config {
     /// The encryption applied to swapped out files.
     /// The in-memory content will be plaintext always.
     pub encryption: Option<Arc<DataKeyManager>>,
+    /// The quota of bytes that may be swapped out to `swap_files`.
+    /// Unlike `cache_size`, which bounds the in-memory buffer of a single
+    /// pool, this may be shared by multiple pools (say, one per task) so the
+    /// whole node's local temp storage usage can be tracked and capped
+    /// together.
+    pub disk_quota: Arc<MemoryQuota>,
 }
 
 impl std::fmt::Debug for Config {
@@ -84,6 +90,7 @@ impl std::fmt::Debug for Config {
                 "encryption",
                 &self.encryption.as_ref().map(|enc| enc.encryption_method()),
             )
+            .field("disk_quota_capacity", &self.disk_quota.capacity())
             .finish()
     }
 }
@@ -147,6 +154,11 @@ struct FileCore {
     external_file: Option<SwappedOut>,
     /// self.mem[0..written] has been written to out file.
     written: usize,
+    /// The total bytes this file has ever swapped out to disk. Unlike
+    /// `written`, this never gets reset, so it can be used to give back
+    /// exactly what was taken from `the_pool.cfg.disk_quota` once the file is
+    /// dropped.
+    disk_bytes: usize,
 
     // Some metadata of the file.
     the_pool: Arc<TempFilePool>,
@@ -486,6 +498,12 @@ impl FileCore {
             }
             TEMP_FILE_SWAP_OUT_BYTES.inc_by(n as _);
             self.written += n;
+            self.disk_bytes += n;
+            // This is merely accounting: the bytes are already physically on
+            // disk, we cannot undo that. Callers wishing to avoid ever
+            // reaching this point should check `the_pool.config().disk_quota`
+            // before admitting more data, not here.
+            self.the_pool.cfg.disk_quota.alloc_force(n);
         }
     }
 
@@ -533,6 +551,7 @@ impl FileCore {
             in_mem: v,
             external_file: None,
             written: 0,
+            disk_bytes: 0,
             the_pool: pool,
             rel_path,
         }
@@ -593,6 +612,7 @@ impl Drop for FileCore {
             .current
             .fetch_sub(self.in_mem.capacity(), Ordering::SeqCst);
         TEMP_FILE_MEMORY_USAGE.set(self.the_pool.current.load(Ordering::Acquire) as _);
+        self.the_pool.cfg.disk_quota.free(self.disk_bytes);
         if self.external_file.is_some() {
             if let Err(err) = self.the_pool.delete_relative(&self.rel_path) {
                 warn!("failed to remove the file."; "file" => %self.rel_path.display(), "err" => %err);
@@ -795,6 +815,7 @@ mod test {
     use kvproto::{brpb::CompressionType, encryptionpb::EncryptionMethod};
     use tempfile::{tempdir, TempDir};
     use test_util::new_test_key_manager;
+    use tikv_util::memory::MemoryQuota;
     use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
     use walkdir::WalkDir;
 
@@ -832,6 +853,7 @@ mod test {
             minimal_swap_out_file_size: 8192,
             write_buffer_size: 4096,
             encryption: None,
+            disk_quota: Arc::new(MemoryQuota::new(usize::MAX)),
         };
         m(&mut cfg);
         TestPool {
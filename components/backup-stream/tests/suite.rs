@@ -406,6 +406,7 @@ impl Suite {
             cm,
             BackupStreamResolver::V1(resolver),
             sim.encryption.clone(),
+            ApiVersion::V1,
         );
         worker.start(endpoint);
     }
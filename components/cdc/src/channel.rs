@@ -2,13 +2,15 @@
 
 use std::{fmt, sync::Arc, time::Duration};
 
+use collections::HashMap;
 use futures::{
     channel::mpsc::{
         channel as bounded, unbounded, Receiver, SendError as FuturesSendError, Sender,
         TrySendError, UnboundedReceiver, UnboundedSender,
     },
+    compat::Future01CompatExt,
     executor::block_on,
-    stream, SinkExt, Stream, StreamExt,
+    stream, FutureExt, SinkExt, Stream, StreamExt,
 };
 use grpcio::WriteFlags;
 use kvproto::cdcpb::{ChangeDataEvent, Event, ResolvedTs};
@@ -18,11 +20,22 @@ use tikv_util::{
     impl_display_as_debug,
     memory::{MemoryQuota, MemoryQuotaExceeded},
     time::Instant,
+    timer::GLOBAL_TIMER_HANDLE,
     warn,
 };
 
 use crate::metrics::*;
 
+/// Identifies a single (region, downstream) event stream. Used only to
+/// verify in-order delivery; see `unbounded_send_seq` and `Drain::drain`.
+pub type EventSeqKey = (u64, u64);
+
+/// An event together with the bookkeeping `channel` needs to actually
+/// deliver it: when it was queued, how many bytes it was charged for, and
+/// (optionally) the sequence number it was stamped with for order
+/// verification.
+type ChannelEvent = (Instant, CdcEvent, usize, Option<(EventSeqKey, u64)>);
+
 /// The maximum bytes of events can be batched into one `CdcEvent::Event`, 32KB.
 pub const CDC_EVENT_MAX_BYTES: usize = 32 * 1024;
 
@@ -187,7 +200,17 @@ impl EventBatcher {
     }
 }
 
-pub fn channel(buffer: usize, memory_quota: Arc<MemoryQuota>) -> (Sink, Drain) {
+/// Creates a `Sink`/`Drain` pair for a single CDC connection.
+///
+/// `memory_quota` is the store-wide sink quota shared by every connection.
+/// `conn_memory_quota` is scoped to just this connection, so that one
+/// changefeed consuming its own sub-quota cannot starve the others sharing
+/// `memory_quota`.
+pub fn channel(
+    buffer: usize,
+    memory_quota: Arc<MemoryQuota>,
+    conn_memory_quota: Arc<MemoryQuota>,
+) -> (Sink, Drain) {
     let (unbounded_sender, unbounded_receiver) = unbounded();
     let (bounded_sender, bounded_receiver) = bounded(buffer);
     (
@@ -195,11 +218,16 @@ pub fn channel(buffer: usize, memory_quota: Arc<MemoryQuota>) -> (Sink, Drain) {
             unbounded_sender,
             bounded_sender,
             memory_quota: memory_quota.clone(),
+            conn_memory_quota: conn_memory_quota.clone(),
         },
         Drain {
             unbounded_receiver,
             bounded_receiver,
             memory_quota,
+            conn_memory_quota,
+            batch_wait_duration: Duration::ZERO,
+            #[cfg(debug_assertions)]
+            last_seq: HashMap::default(),
         },
     )
 }
@@ -209,6 +237,9 @@ pub enum SendError {
     Full,
     Disconnected,
     Congested,
+    /// Like `Congested`, but the connection's own sub-quota was exhausted
+    /// rather than the store-wide sink quota.
+    ConnCongested,
 }
 
 impl std::error::Error for SendError {}
@@ -235,7 +266,7 @@ macro_rules! impl_from_future_send_error {
 
 impl_from_future_send_error! {
     FuturesSendError,
-    TrySendError<(Instant, CdcEvent, usize)>,
+    TrySendError<ChannelEvent>,
 }
 
 impl From<MemoryQuotaExceeded> for SendError {
@@ -246,24 +277,77 @@ impl From<MemoryQuotaExceeded> for SendError {
 
 #[derive(Clone)]
 pub struct Sink {
-    unbounded_sender: UnboundedSender<(Instant, CdcEvent, usize)>,
-    bounded_sender: Sender<(Instant, CdcEvent, usize)>,
+    unbounded_sender: UnboundedSender<ChannelEvent>,
+    bounded_sender: Sender<ChannelEvent>,
     memory_quota: Arc<MemoryQuota>,
+    conn_memory_quota: Arc<MemoryQuota>,
 }
 
 impl Sink {
+    // Allocates `bytes` from the connection's sub-quota first, then the
+    // store-wide quota, so callers can tell which one is congested. On
+    // failure, whatever was already allocated is rolled back.
+    fn alloc_quota(&self, bytes: usize) -> Result<(), SendError> {
+        if bytes == 0 {
+            return Ok(());
+        }
+        self.conn_memory_quota
+            .alloc(bytes)
+            .map_err(|_| SendError::ConnCongested)?;
+        if let Err(e) = self.memory_quota.alloc(bytes) {
+            self.conn_memory_quota.free(bytes);
+            return Err(SendError::from(e));
+        }
+        Ok(())
+    }
+
+    fn free_quota(&self, bytes: usize) {
+        self.memory_quota.free(bytes);
+        self.conn_memory_quota.free(bytes);
+    }
+
+    /// Whether this connection's own sub-quota is already fully spent, i.e.
+    /// the next `unbounded_send` would fail with `SendError::ConnCongested`.
+    /// Lets `Endpoint::on_register` reject a new incremental scan up front
+    /// instead of admitting it and only discovering the connection can't
+    /// take any more events once the scan starts sinking rows.
+    pub fn is_congested(&self) -> bool {
+        self.conn_memory_quota.in_use() >= self.conn_memory_quota.capacity()
+    }
+
     pub fn unbounded_send(&self, event: CdcEvent, force: bool) -> Result<(), SendError> {
+        self.send_inner(event, force, None)
+    }
+
+    /// Like `unbounded_send`, but additionally stamps the event with a
+    /// sequence number scoped to `key`. `Drain` asserts, in debug builds,
+    /// that sequence numbers for the same `key` always arrive in increasing
+    /// order, catching reordering bugs before they ever reach the wire.
+    pub fn unbounded_send_seq(
+        &self,
+        event: CdcEvent,
+        force: bool,
+        key: EventSeqKey,
+        seq: u64,
+    ) -> Result<(), SendError> {
+        self.send_inner(event, force, Some((key, seq)))
+    }
+
+    fn send_inner(
+        &self,
+        event: CdcEvent,
+        force: bool,
+        seq: Option<(EventSeqKey, u64)>,
+    ) -> Result<(), SendError> {
         // Try it's best to send error events.
         let bytes = if !force { event.size() as usize } else { 0 };
-        if bytes != 0 {
-            self.memory_quota.alloc(bytes)?;
-        }
+        self.alloc_quota(bytes)?;
         let now = Instant::now_coarse();
-        match self.unbounded_sender.unbounded_send((now, event, bytes)) {
+        match self.unbounded_sender.unbounded_send((now, event, bytes, seq)) {
             Ok(_) => Ok(()),
             Err(e) => {
                 // Free quota if send fails.
-                self.memory_quota.free(bytes);
+                self.free_quota(bytes);
                 Err(SendError::from(e))
             }
         }
@@ -276,20 +360,20 @@ impl Sink {
             let bytes = event.size();
             total_bytes += bytes;
         }
-        self.memory_quota.alloc(total_bytes as _)?;
+        self.alloc_quota(total_bytes as _)?;
 
         let now = Instant::now_coarse();
         for event in events {
             let bytes = event.size() as usize;
-            if let Err(e) = self.bounded_sender.feed((now, event, bytes)).await {
+            if let Err(e) = self.bounded_sender.feed((now, event, bytes, None)).await {
                 // Free quota if send fails.
-                self.memory_quota.free(total_bytes as _);
+                self.free_quota(total_bytes as _);
                 return Err(SendError::from(e));
             }
         }
         if let Err(e) = self.bounded_sender.flush().await {
             // Free quota if send fails.
-            self.memory_quota.free(total_bytes as _);
+            self.free_quota(total_bytes as _);
             return Err(SendError::from(e));
         }
         Ok(())
@@ -297,15 +381,35 @@ impl Sink {
 }
 
 pub struct Drain {
-    unbounded_receiver: UnboundedReceiver<(Instant, CdcEvent, usize)>,
-    bounded_receiver: Receiver<(Instant, CdcEvent, usize)>,
+    unbounded_receiver: UnboundedReceiver<ChannelEvent>,
+    bounded_receiver: Receiver<ChannelEvent>,
     memory_quota: Arc<MemoryQuota>,
+    conn_memory_quota: Arc<MemoryQuota>,
+    // How long `forward` is willing to wait, after the first event of a
+    // batch arrives, for more events to coalesce with it before flushing
+    // to the gRPC sink. Zero (the default) means never wait: only grab
+    // whatever is already queued, same as before this field existed.
+    batch_wait_duration: Duration,
+    // Only tracked in debug builds: the last sequence number seen per
+    // `EventSeqKey`, used to assert in-order delivery. See
+    // `Sink::unbounded_send_seq`.
+    #[cfg(debug_assertions)]
+    last_seq: HashMap<EventSeqKey, u64>,
 }
 
 impl<'a> Drain {
+    /// Sets the max latency `forward` may add to accumulate a bigger batch
+    /// of events before flushing them to the gRPC sink. Only takes effect
+    /// for batches started after this call.
+    pub fn set_batch_wait_duration(&mut self, dur: Duration) {
+        self.batch_wait_duration = dur;
+    }
+
     pub fn drain(&'a mut self) -> impl Stream<Item = (CdcEvent, usize)> + 'a {
+        #[cfg(debug_assertions)]
+        let last_seq = &mut self.last_seq;
         stream::select(&mut self.bounded_receiver, &mut self.unbounded_receiver).map(
-            |(start, mut event, size)| {
+            move |(start, mut event, size, seq)| {
                 CDC_EVENTS_PENDING_DURATION.observe(start.saturating_elapsed_secs() * 1000.0);
                 if let CdcEvent::Barrier(ref mut barrier) = event {
                     if let Some(barrier) = barrier.take() {
@@ -313,6 +417,20 @@ impl<'a> Drain {
                         barrier(());
                     }
                 }
+                #[cfg(debug_assertions)]
+                if let Some((key, seq)) = seq {
+                    let prev = last_seq.entry(key).or_insert(0);
+                    debug_assert!(
+                        seq > *prev,
+                        "cdc events delivered out of order for {:?}: seq {} after {}",
+                        key,
+                        seq,
+                        *prev
+                    );
+                    *prev = seq;
+                }
+                #[cfg(not(debug_assertions))]
+                let _ = seq;
                 (event, size)
             },
         )
@@ -328,8 +446,54 @@ impl<'a> Drain {
             CDC_GRPC_ACCUMULATE_MESSAGE_BYTES.with_label_values(&["resolved_ts"]);
 
         let memory_quota = self.memory_quota.clone();
-        let mut chunks = self.drain().ready_chunks(CDC_EVENT_MAX_COUNT);
-        while let Some(events) = chunks.next().await {
+        let conn_memory_quota = self.conn_memory_quota.clone();
+        let batch_wait_duration = self.batch_wait_duration;
+        let mut drain = self.drain();
+        loop {
+            let first = match drain.next().await {
+                Some(e) => e,
+                None => return Ok(()),
+            };
+            let mut events = vec![first];
+            let mut closed = false;
+            if batch_wait_duration.is_zero() {
+                // No deliberate wait: opportunistically grab whatever is
+                // already queued, equivalent to the `ready_chunks` behavior
+                // this loop used to delegate to.
+                while events.len() < CDC_EVENT_MAX_COUNT {
+                    match drain.next().now_or_never() {
+                        Some(Some(e)) => events.push(e),
+                        Some(None) => {
+                            closed = true;
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+            } else {
+                // Wait up to `batch_wait_duration`, counted from the first
+                // event of this batch, for more events to coalesce with it.
+                // This trades a small, bounded amount of latency for fewer,
+                // larger gRPC messages under bursty small-event workloads.
+                let timeout = GLOBAL_TIMER_HANDLE
+                    .delay(std::time::Instant::now() + batch_wait_duration)
+                    .compat()
+                    .fuse();
+                futures::pin_mut!(timeout);
+                while events.len() < CDC_EVENT_MAX_COUNT {
+                    futures::select! {
+                        e = drain.next().fuse() => match e {
+                            Some(e) => events.push(e),
+                            None => {
+                                closed = true;
+                                break;
+                            }
+                        },
+                        _ = timeout => break,
+                    }
+                }
+            }
+
             let mut bytes = 0;
             let mut batcher = EventBatcher::with_capacity(CDC_RESP_MAX_BATCH_COUNT);
             events.into_iter().for_each(|(e, size)| {
@@ -341,6 +505,7 @@ impl<'a> Drain {
             let resps_len = resps.len();
             // Events are about to be sent, free pending events memory counter.
             memory_quota.free(bytes as _);
+            conn_memory_quota.free(bytes as _);
             for (i, e) in resps.into_iter().enumerate() {
                 // Buffer messages and flush them at once.
                 let write_flags = WriteFlags::default().buffer_hint(i + 1 != resps_len);
@@ -349,8 +514,11 @@ impl<'a> Drain {
             sink.flush().await?;
             total_event_bytes.inc_by(event_bytes as u64);
             total_resolved_ts_bytes.inc_by(resolved_ts_bytes as u64);
+
+            if closed {
+                return Ok(());
+            }
         }
-        Ok(())
     }
 }
 
@@ -361,12 +529,14 @@ impl Drop for Drain {
         let start = Instant::now();
         let mut drain = Box::pin(async {
             let memory_quota = self.memory_quota.clone();
+            let conn_memory_quota = self.conn_memory_quota.clone();
             let mut total_bytes = 0;
             let mut drain = self.drain();
             while let Some((_, bytes)) = drain.next().await {
                 total_bytes += bytes;
             }
             memory_quota.free(total_bytes);
+            conn_memory_quota.free(total_bytes);
         });
         block_on(&mut drain);
         let takes = start.saturating_elapsed();
@@ -398,7 +568,8 @@ mod tests {
     type Send = Box<dyn FnMut(CdcEvent) -> Result<(), SendError>>;
     fn new_test_channel(buffer: usize, capacity: usize, force_send: bool) -> (Send, Drain) {
         let memory_quota = Arc::new(MemoryQuota::new(capacity));
-        let (mut tx, rx) = channel(buffer, memory_quota);
+        let conn_memory_quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let (mut tx, rx) = channel(buffer, memory_quota, conn_memory_quota);
         let mut flag = true;
         let send = move |event| {
             flag = !flag;
@@ -468,6 +639,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_batch_wait_duration() {
+        let memory_quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let conn_memory_quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let (tx, mut drain) = channel(CDC_EVENT_MAX_COUNT * 2, memory_quota, conn_memory_quota);
+        drain.set_batch_wait_duration(Duration::from_millis(200));
+        tx.unbounded_send(CdcEvent::Event(Default::default()), false)
+            .unwrap();
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.spawn(async move {
+            // The second event arrives well inside the batch wait window, so
+            // it should be coalesced into the same `ChangeDataEvent` as the
+            // first instead of being flushed on its own.
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            tx.unbounded_send(CdcEvent::Event(Default::default()), false)
+                .unwrap();
+        });
+
+        let (mut sink, mut rx) = unbounded();
+        runtime.spawn(async move {
+            drain.forward(&mut sink).await.unwrap();
+        });
+        let (batch, _) = recv_timeout(&mut rx, Duration::from_millis(500))
+            .unwrap()
+            .unwrap();
+        assert_eq!(batch.events.len(), 2);
+    }
+
     #[test]
     fn test_congest() {
         let mut e = kvproto::cdcpb::Event::default();
@@ -546,7 +746,8 @@ mod tests {
         let max_pending_bytes = 1024;
         let buffer = max_pending_bytes / event.size();
         let memory_quota = Arc::new(MemoryQuota::new(max_pending_bytes as _));
-        let (tx, _rx) = channel(buffer as _, memory_quota);
+        let conn_memory_quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let (tx, _rx) = channel(buffer as _, memory_quota, conn_memory_quota);
         for _ in 0..buffer {
             tx.unbounded_send(CdcEvent::Event(e.clone()), false)
                 .unwrap();
@@ -624,6 +825,68 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_conn_congest() {
+        let mut e = kvproto::cdcpb::Event::default();
+        e.region_id = 1;
+        let event = CdcEvent::Event(e.clone());
+        assert!(event.size() != 0);
+        // 1KB per-connection quota, much smaller than the store-wide quota.
+        let conn_pending_bytes = 1024;
+        let buffer = conn_pending_bytes / event.size();
+        let memory_quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let conn_memory_quota = Arc::new(MemoryQuota::new(conn_pending_bytes as _));
+        let (tx, _rx) = channel(buffer as _, memory_quota.clone(), conn_memory_quota);
+        for _ in 0..buffer {
+            tx.unbounded_send(CdcEvent::Event(e.clone()), false)
+                .unwrap();
+        }
+        // The connection's own sub-quota is exhausted, but the store-wide quota
+        // still has plenty of room.
+        assert_matches!(
+            tx.unbounded_send(CdcEvent::Event(e), false).unwrap_err(),
+            SendError::ConnCongested
+        );
+        assert_eq!(memory_quota.in_use(), buffer as usize * event.size() as usize);
+    }
+
+    #[test]
+    fn test_seq_order_ok() {
+        let memory_quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let conn_memory_quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let (tx, mut rx) = channel(10, memory_quota, conn_memory_quota);
+        let key = (1, 1);
+        for seq in 1..=3 {
+            tx.unbounded_send_seq(CdcEvent::Event(Default::default()), false, key, seq)
+                .unwrap();
+        }
+        block_on(async {
+            let mut drain = rx.drain();
+            for _ in 0..3 {
+                drain.next().await.unwrap();
+            }
+        });
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "delivered out of order")]
+    fn test_seq_order_violation() {
+        let memory_quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let conn_memory_quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let (tx, mut rx) = channel(10, memory_quota, conn_memory_quota);
+        let key = (1, 1);
+        tx.unbounded_send_seq(CdcEvent::Event(Default::default()), false, key, 2)
+            .unwrap();
+        tx.unbounded_send_seq(CdcEvent::Event(Default::default()), false, key, 1)
+            .unwrap();
+        block_on(async {
+            let mut drain = rx.drain();
+            drain.next().await;
+            drain.next().await;
+        });
+    }
+
     #[test]
     fn test_event_batcher() {
         let check_events = |result: Vec<ChangeDataEvent>, expected: Vec<Vec<CdcEvent>>| {
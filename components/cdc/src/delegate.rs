@@ -7,7 +7,7 @@ use std::{
     result::Result as StdResult,
     string::String,
     sync::{
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
     time::Duration,
@@ -24,9 +24,12 @@ use kvproto::{
     kvrpcpb::ExtraOp as TxnExtraOp,
     metapb::{Region, RegionEpoch},
     raft_cmdpb::{
-        AdminCmdType, AdminRequest, AdminResponse, CmdType, DeleteRequest, PutRequest, Request,
+        AdminCmdType, AdminRequest, AdminResponse, CmdType, DeleteRangeRequest, DeleteRequest,
+        PutRequest, Request,
     },
 };
+use pd_client::BucketMeta;
+use protobuf::Message as _;
 use raftstore::{
     coprocessor::{Cmd, CmdBatch, ObserveHandle},
     store::util::compare_region_epoch,
@@ -39,21 +42,64 @@ use tikv_util::{
     time::Instant,
     warn,
 };
-use txn_types::{Key, Lock, LockType, TimeStamp, WriteBatchFlags, WriteRef, WriteType};
+use tokio::runtime::Handle as ScanPoolHandle;
+use txn_types::{Key, Lock, LockType, TimeStamp, Value, WriteBatchFlags, WriteRef, WriteType};
 
 use crate::{
     channel::{CdcEvent, SendError, Sink, CDC_EVENT_MAX_BYTES},
     endpoint::Advance,
+    external_storage_sink::ExternalStorageSink,
     initializer::KvEntry,
     metrics::*,
-    old_value::{OldValueCache, OldValueCallback},
+    old_value::{OldValueBudget, OldValueCache, OldValueLookup, OldValueResolver, OldValueTask},
     service::{Conn, ConnId, FeatureGate, RequestId},
-    txn_source::TxnSource,
+    txn_source::{TxnSource, TxnSourceFilter},
     Error, Result,
 };
 
 static DOWNSTREAM_ID_ALLOC: AtomicUsize = AtomicUsize::new(0);
 
+/// A changefeed's lifecycle, as seen from a single region's `Delegate`.
+/// Logged via [`Delegate::trace_lifecycle`] at every transition, tagged
+/// with `region_id` and the `ObserveId` that uniquely identifies this
+/// subscription, so a log aggregator can reconstruct one changefeed's
+/// whole lifecycle across `grep`-able events without cross-referencing
+/// anything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DelegateLifecycleStage {
+    /// `Delegate::new` -- observing the region, but no incremental scan has
+    /// started yet.
+    Created,
+    /// `Delegate::init_lock_tracker` -- an incremental scan is preparing the
+    /// region's lock tracker; change events are buffered, not yet sinkable.
+    Scanning,
+    /// `Delegate::finish_scan_locks` succeeded -- the region's locks are
+    /// known and steady-state `on_batch` delivery can proceed.
+    Normal,
+    /// `Delegate::stop` -- an unrecoverable error tore the delegate down;
+    /// downstreams are being broadcast the error and deregistered.
+    Stopping,
+    /// `Delegate::drop` -- the delegate itself is being freed.
+    Removed,
+}
+
+impl DelegateLifecycleStage {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DelegateLifecycleStage::Created => "created",
+            DelegateLifecycleStage::Scanning => "scanning",
+            DelegateLifecycleStage::Normal => "normal",
+            DelegateLifecycleStage::Stopping => "stopping",
+            DelegateLifecycleStage::Removed => "removed",
+        }
+    }
+}
+
+/// Fixed stand-in for an old-value lookup's engine IO cost, charged against
+/// an `OldValueBudget` when the lookup is scheduled -- its real cost isn't
+/// known until `resolve_old_values` actually runs it.
+const OLD_VALUE_LOOKUP_BYTES_ESTIMATE: usize = 512;
+
 /// A unique identifier of a Downstream.
 #[derive(Clone, Copy, Debug, PartialEq, Hash)]
 pub struct DownstreamId(usize);
@@ -62,6 +108,10 @@ impl DownstreamId {
     pub fn new() -> DownstreamId {
         DownstreamId(DOWNSTREAM_ID_ALLOC.fetch_add(1, Ordering::SeqCst))
     }
+
+    pub fn as_u64(&self) -> u64 {
+        self.0 as u64
+    }
 }
 
 impl Default for DownstreamId {
@@ -80,6 +130,12 @@ pub enum DownstreamState {
     /// Incremental scan is finished so that resolved timestamps are acceptable
     /// now.
     Normal,
+    /// Temporarily stopped receiving change events and resolved timestamps,
+    /// at the client's request, without losing its `ObserveId` or
+    /// incremental-scan progress. Unlike `Stopped`, a `Paused` downstream is
+    /// not torn down: `Task::ResumeDownstream` puts it back to `Normal` and
+    /// it picks up from there, with no rescan.
+    Paused,
     Stopped,
 }
 
@@ -89,6 +145,23 @@ impl Default for DownstreamState {
     }
 }
 
+/// Whether a failure to sink an event to a downstream is transient, i.e. the
+/// downstream's channel is merely backed up rather than gone for good.
+///
+/// Transient failures don't need the whole delegate (and thus every other
+/// downstream observing the region) to be torn down: the offending
+/// downstream is dropped on its own, while the delegate and its `ObserveId`
+/// stay alive so that the same downstream can resume in place, without a
+/// full rescan, once the client reconnects with the same `RequestId`.
+fn is_retryable_sink_error(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::Sink(SendError::Full)
+            | Error::Sink(SendError::Congested)
+            | Error::Sink(SendError::ConnCongested)
+    )
+}
+
 /// Should only be called when it's uninitialized or stopped. Return false if
 /// it's stopped.
 pub(crate) fn on_init_downstream(s: &AtomicCell<DownstreamState>) -> bool {
@@ -106,10 +179,27 @@ pub(crate) fn post_init_downstream(s: &AtomicCell<DownstreamState>) -> bool {
         .is_ok()
 }
 
+/// Pauses a downstream in response to `Task::PauseDownstream`. Only valid
+/// from `Normal`; returns `false` (no state change) otherwise, e.g. if the
+/// downstream is still initializing or was already paused.
+pub(crate) fn pause_downstream(s: &AtomicCell<DownstreamState>) -> bool {
+    s.compare_exchange(DownstreamState::Normal, DownstreamState::Paused)
+        .is_ok()
+}
+
+/// Resumes a downstream paused by `pause_downstream`, in response to
+/// `Task::ResumeDownstream`. Only valid from `Paused`.
+pub(crate) fn resume_downstream(s: &AtomicCell<DownstreamState>) -> bool {
+    s.compare_exchange(DownstreamState::Paused, DownstreamState::Normal)
+        .is_ok()
+}
+
 impl DownstreamState {
     pub fn ready_for_change_events(&self) -> bool {
         match *self {
-            DownstreamState::Uninitialized | DownstreamState::Stopped => false,
+            DownstreamState::Uninitialized
+            | DownstreamState::Stopped
+            | DownstreamState::Paused => false,
             DownstreamState::Initializing | DownstreamState::Normal => true,
         }
     }
@@ -120,7 +210,41 @@ impl DownstreamState {
 
             DownstreamState::Uninitialized
             | DownstreamState::Stopped
-            | DownstreamState::Initializing => false,
+            | DownstreamState::Initializing
+            | DownstreamState::Paused => false,
+        }
+    }
+}
+
+/// Per-downstream filter over the kind of row events a downstream receives.
+///
+/// Lets a downstream that only cares about a subset of changes (e.g. a TTL
+/// auditor that only needs deletes) skip the bandwidth and sink quota of
+/// events it would just discard. All fields default to `false`, i.e.
+/// nothing is filtered, preserving today's behavior.
+///
+/// TODO: `ChangeDataRequest` has no fields to let a client request a
+/// non-default filter yet, so today this can only be set by calling
+/// `Downstream::set_event_filter` directly (e.g. from tests); wiring it up
+/// to the request needs a `kvproto` change.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EventRowFilter {
+    pub skip_puts: bool,
+    pub skip_deletes: bool,
+    pub skip_old_value: bool,
+}
+
+impl EventRowFilter {
+    fn retain(&self, row: &EventRow) -> bool {
+        !matches!(
+            (row.get_op_type(), self.skip_puts, self.skip_deletes),
+            (EventRowOpType::Put, true, _) | (EventRowOpType::Delete, _, true)
+        )
+    }
+
+    fn strip_old_value(&self, row: &mut EventRow) {
+        if self.skip_old_value {
+            row.old_value = Vec::new();
         }
     }
 }
@@ -137,16 +261,53 @@ pub struct Downstream {
     pub conn_id: ConnId,
 
     pub kv_api: ChangeDataRequestKvApi,
-    pub filter_loop: bool,
+    pub txn_source_filter: TxnSourceFilter,
     pub observed_range: ObservedRange,
+    pub event_filter: EventRowFilter,
+    /// If set, this downstream only wants a consistent point-in-time scan at
+    /// `checkpoint_ts`, not ongoing delta events: once its `Initializer`
+    /// finishes the incremental scan and sinks the completion event, it's
+    /// deregistered instead of being left subscribed for future `on_batch`
+    /// calls. Lets a client pull a one-off region snapshot over the CDC
+    /// protocol without holding a long-lived delegate afterwards.
+    pub snapshot_only: bool,
+    /// The resource group this downstream's incremental scan should be
+    /// charged against, so low-priority changefeeds don't starve
+    /// foreground reads; see [`crate::initializer::Initializer::resource_group_name`].
+    /// Defaults to the empty string, which `ResourceGroupManager` treats as
+    /// the default resource group.
+    pub resource_group_name: String,
 
     sink: Option<Sink>,
+    /// An alternate sink that archives events to an external storage
+    /// backend instead of streaming them over `sink`'s gRPC connection; see
+    /// `crate::external_storage_sink`. Checked before `sink` in
+    /// `sink_event`, since a downstream flagged this way was never given a
+    /// gRPC `Sink` to begin with.
+    external_storage_sink: Option<ExternalStorageSink>,
     state: Arc<AtomicCell<DownstreamState>>,
 
+    // Monotonically increasing sequence number stamped on every event sent
+    // through `sink_event`, scoped to this downstream. `Sink`/`Drain` use it
+    // to assert in-order delivery in debug builds.
+    seq: AtomicU64,
+
     // Fields to handle ResolvedTs advancing. If `lock_heap` is none it means
     // the downstream hasn't finished the incremental scanning.
     lock_heap: Option<BTreeMap<TimeStamp, isize>>,
     advanced_to: TimeStamp,
+
+    // When this `Downstream` was created, and whether its first delta event has
+    // been sent yet. Used to trace registration -> first delta event latency;
+    // see `sink_event`.
+    created: Instant,
+    first_event_sent: AtomicBool,
+
+    // Running totals of rows/bytes sunk to this downstream since the last
+    // `sample_and_reset_delivery_stats` call. See that method, and
+    // `Delegate::on_min_ts`, for how these feed `FeatureGate::REGION_STATS_EVENTS`.
+    delivered_rows: AtomicU64,
+    delivered_bytes: AtomicU64,
 }
 
 impl fmt::Debug for Downstream {
@@ -170,7 +331,7 @@ impl Downstream {
         req_id: RequestId,
         conn_id: ConnId,
         kv_api: ChangeDataRequestKvApi,
-        filter_loop: bool,
+        txn_source_filter: TxnSourceFilter,
         observed_range: ObservedRange,
     ) -> Downstream {
         Downstream {
@@ -180,36 +341,89 @@ impl Downstream {
             req_id,
             conn_id,
             kv_api,
-            filter_loop,
+            txn_source_filter,
 
             observed_range,
+            event_filter: EventRowFilter::default(),
+            snapshot_only: false,
+            resource_group_name: String::new(),
 
             sink: None,
+            external_storage_sink: None,
             state: Arc::new(AtomicCell::new(DownstreamState::default())),
 
+            seq: AtomicU64::new(0),
+
             lock_heap: None,
             advanced_to: TimeStamp::zero(),
+
+            created: Instant::now_coarse(),
+            first_event_sent: AtomicBool::new(false),
+
+            delivered_rows: AtomicU64::new(0),
+            delivered_bytes: AtomicU64::new(0),
         }
     }
 
     /// Sink events to the downstream.
     pub fn sink_event(&self, mut event: Event, force: bool) -> Result<()> {
         event.set_request_id(self.req_id.0);
+        if let Some(external_storage_sink) = &self.external_storage_sink {
+            external_storage_sink.sink_event(event);
+            return Ok(());
+        }
         if self.sink.is_none() {
             info!("cdc drop event, no sink";
                 "conn_id" => ?self.conn_id, "downstream_id" => ?self.id, "req_id" => ?self.req_id);
             return Err(Error::Sink(SendError::Disconnected));
         }
+        // Tally rows/bytes before `event` is moved into the channel, so
+        // `sample_and_reset_delivery_stats` can report how much this downstream
+        // has actually been sent, regardless of whether anyone ever delivers a
+        // stats event for it over the wire.
+        let delivered_rows = match &event.event {
+            Some(Event_oneof_event::Entries(entries)) => entries.entries.len() as u64,
+            _ => 0,
+        };
+        let delivered_bytes = event.compute_size() as u64;
         let sink = self.sink.as_ref().unwrap();
-        match sink.unbounded_send(CdcEvent::Event(event), force) {
-            Ok(_) => Ok(()),
+        let region_id = event.region_id;
+        let key = (event.region_id, self.id.as_u64());
+        let seq = self.seq.fetch_add(1, Ordering::SeqCst);
+        match sink.unbounded_send_seq(CdcEvent::Event(event), force, key, seq) {
+            Ok(_) => {
+                self.delivered_rows.fetch_add(delivered_rows, Ordering::Relaxed);
+                self.delivered_bytes.fetch_add(delivered_bytes, Ordering::Relaxed);
+                // Only the first delta event's latency is interesting: once the
+                // downstream is caught up, subsequent events are just steady-state
+                // throughput. Guard with `compare_exchange` so this fires exactly once
+                // per downstream even though `sink_event` is called concurrently from
+                // many regions' apply threads.
+                if self
+                    .first_event_sent
+                    .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+                {
+                    tracing::info!(
+                        region_id,
+                        conn_id = ?self.conn_id,
+                        downstream_id = ?self.id,
+                        req_id = ?self.req_id,
+                        latency = ?self.created.saturating_elapsed(),
+                        "cdc first delta event delivered to downstream",
+                    );
+                }
+                Ok(())
+            }
             Err(SendError::Disconnected) => {
                 debug!("cdc send event failed, disconnected";
                     "conn_id" => ?self.conn_id, "downstream_id" => ?self.id, "req_id" => ?self.req_id);
                 Err(Error::Sink(SendError::Disconnected))
             }
             // TODO handle errors.
-            Err(e @ SendError::Full) | Err(e @ SendError::Congested) => {
+            Err(e @ SendError::Full)
+            | Err(e @ SendError::Congested)
+            | Err(e @ SendError::ConnCongested) => {
                 info!("cdc send event failed, full";
                     "conn_id" => ?self.conn_id, "downstream_id" => ?self.id, "req_id" => ?self.req_id);
                 Err(Error::Sink(e))
@@ -242,9 +456,32 @@ impl Downstream {
         self.sink = Some(sink);
     }
 
+    /// Routes this downstream's events to an external storage backend (see
+    /// `crate::external_storage_sink`) instead of the gRPC connection.
+    /// Nothing parses this out of `ChangeDataRequest` today -- see that
+    /// module's doc comment -- so callers have to opt a downstream in
+    /// directly.
+    pub fn set_external_storage_sink(&mut self, sink: ExternalStorageSink) {
+        self.external_storage_sink = Some(sink);
+    }
+
+    pub fn set_event_filter(&mut self, event_filter: EventRowFilter) {
+        self.event_filter = event_filter;
+    }
+
     pub fn get_state(&self) -> Arc<AtomicCell<DownstreamState>> {
         self.state.clone()
     }
+
+    /// Reads and clears the rows/bytes delivered to this downstream since the
+    /// last call. Used by `Delegate::on_min_ts` to build `RegionStatsSample`s
+    /// for downstreams that opted into `FeatureGate::REGION_STATS_EVENTS`.
+    pub fn sample_and_reset_delivery_stats(&self) -> (u64, u64) {
+        (
+            self.delivered_rows.swap(0, Ordering::SeqCst),
+            self.delivered_bytes.swap(0, Ordering::SeqCst),
+        )
+    }
 }
 
 // In `PendingLock`,  `key` is encoded.
@@ -316,6 +553,24 @@ impl MiniLock {
     }
 }
 
+/// A sample of what one downstream has been sent for one region, taken by
+/// `Delegate::on_min_ts` for downstreams that opted into
+/// `FeatureGate::REGION_STATS_EVENTS`.
+///
+/// NOTE: there's no `cdcpb::Event` variant to carry this to the client yet,
+/// so for now `Endpoint::on_min_ts` only logs these; see its caller for
+/// details. Delivering them over the wire needs a new `Event_oneof_event`
+/// variant, which can't be added without a `kvproto` change.
+#[derive(Debug)]
+pub(crate) struct RegionStatsSample {
+    pub(crate) conn_id: ConnId,
+    pub(crate) req_id: RequestId,
+    pub(crate) region_id: u64,
+    pub(crate) rows: u64,
+    pub(crate) bytes: u64,
+    pub(crate) resolved_ts: TimeStamp,
+}
+
 /// A CDC delegate of a raftstore region peer.
 ///
 /// It converts raft commands into CDC events and broadcast to downstreams.
@@ -330,12 +585,35 @@ pub struct Delegate {
     txn_extra_op: Arc<AtomicCell<TxnExtraOp>>,
     failed: bool,
 
+    // The most recently reported bucket boundaries for this region, if any. Kept around so
+    // `resolved_ts_per_bucket` can break the region-wide resolved ts down by bucket; huge
+    // regions with a single resolved ts force every downstream to wait on the slowest key in
+    // the whole region, even though only one bucket may actually be lagging.
+    region_buckets: Option<Arc<BucketMeta>>,
+
+    // The raft log index of the last committed entry seen by `on_batch`. After a leader
+    // transfer, raftstore can replay and redeliver entries this delegate already processed
+    // (the registration is resumed on the same `Delegate`/`ObserveId`, see the `Occupied`
+    // branch in `Endpoint::on_register`), so `on_batch` uses this to skip anything at or
+    // below the watermark instead of sinking it to downstreams twice. Zero, a raft log index
+    // that's never actually assigned to a real entry, means nothing has been seen yet.
+    last_applied_index: u64,
+
+    // Events and bytes `sink_data` has applied to this region since the last
+    // `flush_throughput` call. `Endpoint::on_timeout` drains these into
+    // `CDC_REGION_THROUGHPUT_*_HISTOGRAM` every `METRICS_FLUSH_INTERVAL`;
+    // `throughput` reads the running total in between, for
+    // `Validate::RegionThroughput`.
+    throughput_events: u64,
+    throughput_bytes: u64,
+
     created: Instant,
     last_lag_warn: Instant,
 }
 
 impl Drop for Delegate {
     fn drop(&mut self) {
+        self.trace_lifecycle(DelegateLifecycleStage::Removed);
         match &self.lock_tracker {
             LockTracker::Pending => {}
             LockTracker::Preparing(locks) => {
@@ -359,6 +637,15 @@ impl Drop for Delegate {
 }
 
 impl Delegate {
+    pub(crate) fn trace_lifecycle(&self, stage: DelegateLifecycleStage) {
+        tracing::info!(
+            region_id = self.region_id,
+            observe_id = ?self.handle.id,
+            stage = stage.as_str(),
+            "cdc delegate lifecycle transition",
+        );
+    }
+
     fn push_lock(&mut self, key: Key, start_ts: MiniLock) -> Result<isize> {
         let bytes = key.approximate_heap_size();
         let mut lock_count_modify = 0;
@@ -405,6 +692,7 @@ impl Delegate {
     pub(crate) fn init_lock_tracker(&mut self) -> bool {
         if matches!(self.lock_tracker, LockTracker::Pending) {
             self.lock_tracker = LockTracker::Preparing(vec![]);
+            self.trace_lifecycle(DelegateLifecycleStage::Scanning);
             return true;
         }
         false
@@ -468,6 +756,7 @@ impl Delegate {
 
         info!("cdc region is ready"; "region_id" => self.region_id);
         self.finish_prepare_lock_tracker(region, locks)?;
+        self.trace_lifecycle(DelegateLifecycleStage::Normal);
 
         let region = match &self.lock_tracker {
             LockTracker::Prepared { region, .. } => region,
@@ -492,7 +781,7 @@ impl Delegate {
         memory_quota: Arc<MemoryQuota>,
         txn_extra_op: Arc<AtomicCell<TxnExtraOp>>,
     ) -> Delegate {
-        Delegate {
+        let delegate = Delegate {
             region_id,
             handle: ObserveHandle::new(),
             memory_quota,
@@ -502,9 +791,22 @@ impl Delegate {
             txn_extra_op,
             failed: false,
 
+            region_buckets: None,
+            last_applied_index: 0,
+            throughput_events: 0,
+            throughput_bytes: 0,
+
             created: Instant::now_coarse(),
             last_lag_warn: Instant::now_coarse(),
-        }
+        };
+        delegate.trace_lifecycle(DelegateLifecycleStage::Created);
+        delegate
+    }
+
+    /// Record the region's latest bucket boundaries, as reported by raftstore through
+    /// `RegionChangeEvent::UpdateBuckets`. Used by [`Delegate::resolved_ts_per_bucket`].
+    pub fn on_region_buckets_updated(&mut self, buckets: Arc<BucketMeta>) {
+        self.region_buckets = Some(buckets);
     }
 
     /// Let downstream subscribe the delegate.
@@ -564,9 +866,21 @@ impl Delegate {
     /// This means the region has met an unrecoverable error for CDC.
     /// It broadcasts errors to all downstream and stops.
     pub fn stop(&mut self, err: Error) {
+        self.trace_lifecycle(DelegateLifecycleStage::Stopping);
         self.mark_failed();
         self.stop_observing();
+        self.broadcast_error(err);
+    }
 
+    /// Broadcasts `err` to every downstream currently attached to this
+    /// delegate, without touching `failed`/observing state.
+    ///
+    /// Split out of [`Self::stop`] so that callers which need to rate-limit
+    /// error delivery to downstreams (e.g. `Endpoint::on_deregister` during a
+    /// flapping region) can still unconditionally run the state-transition
+    /// half of `stop` while choosing whether this particular error is worth
+    /// broadcasting.
+    pub fn broadcast_error(&self, err: Error) {
         info!("cdc met region error";
             "region_id" => self.region_id, "error" => ?err);
         let region_id = self.region_id;
@@ -602,14 +916,20 @@ impl Delegate {
         self.txn_extra_op.as_ref()
     }
 
-    /// Try advance and broadcast resolved ts.
+    /// Try advance and broadcast resolved ts. Returns the worst (largest)
+    /// lag behind `current_ts` seen across this region's downstreams this
+    /// tick, or [`Duration::ZERO`] if there's nothing to report yet (e.g.
+    /// still scanning locks) -- callers shouldn't read that as "not
+    /// lagging". Used by `Endpoint::on_min_ts` to decide whether this region
+    /// has been blocking resolved-ts advancement long enough to quarantine.
     pub(crate) fn on_min_ts(
         &mut self,
         min_ts: TimeStamp,
         current_ts: TimeStamp,
         connections: &HashMap<ConnId, Conn>,
+        unacked_bytes_limit: usize,
         advance: &mut Advance,
-    ) {
+    ) -> Duration {
         let locks = match &self.lock_tracker {
             LockTracker::Prepared { locks, .. } => locks,
             _ => {
@@ -626,7 +946,7 @@ impl Delegate {
                     );
                     self.last_lag_warn = now;
                 }
-                return;
+                return Duration::ZERO;
             }
         };
 
@@ -658,6 +978,7 @@ impl Delegate {
         };
 
         let mut slow_downstreams = Vec::new();
+        let mut max_lag = Duration::ZERO;
         for d in &mut self.downstreams {
             let advanced_to = match handle_downstream(d) {
                 Some(ts) => ts,
@@ -678,10 +999,43 @@ impl Delegate {
                 }
             };
 
-            let lag = current_ts
-                .physical()
-                .saturating_sub(d.advanced_to.physical());
-            if Duration::from_millis(lag) > WARN_LAG_THRESHOLD {
+            // Always sample, not just under `REGION_STATS_EVENTS`: the
+            // unacked-bytes backpressure check below needs every
+            // connection's sent-bytes tally kept current, regardless of
+            // whether the connection also wants region stats events.
+            let (rows, bytes) = d.sample_and_reset_delivery_stats();
+            let conn = connections.get(&d.conn_id).unwrap();
+            conn.record_sent_bytes(bytes as usize);
+
+            if unacked_bytes_limit > 0 && conn.unacked_bytes() > unacked_bytes_limit {
+                if pause_downstream(&d.state) {
+                    info!("cdc downstream paused: unacked bytes limit exceeded";
+                        "region_id" => self.region_id, "conn_id" => ?d.conn_id,
+                        "req_id" => ?d.req_id, "unacked_bytes" => conn.unacked_bytes(),
+                        "limit" => unacked_bytes_limit);
+                }
+            }
+
+            if features.contains(FeatureGate::REGION_STATS_EVENTS) {
+                advance.region_stats.push(RegionStatsSample {
+                    conn_id: d.conn_id,
+                    req_id: d.req_id,
+                    region_id: self.region_id,
+                    rows,
+                    bytes,
+                    resolved_ts: advanced_to,
+                });
+            }
+
+            let lag = Duration::from_millis(
+                current_ts
+                    .physical()
+                    .saturating_sub(d.advanced_to.physical()),
+            );
+            if lag > max_lag {
+                max_lag = lag;
+            }
+            if lag > WARN_LAG_THRESHOLD {
                 slow_downstreams.push(d.id);
             }
         }
@@ -697,14 +1051,69 @@ impl Delegate {
                 self.last_lag_warn = now;
             }
         }
+
+        max_lag
+    }
+
+    /// Notifies every downstream currently attached to this delegate that
+    /// resolved-ts advancement has been paused for this region (see
+    /// `Endpoint::on_min_ts`'s quarantine handling), without touching
+    /// `failed` or observing state -- the delegate keeps tracking locks
+    /// normally so it can resume the moment the quarantine is lifted by
+    /// `Task::ReleaseQuarantine`.
+    pub(crate) fn notify_quarantined(&self, reason: &str) {
+        for downstream in &self.downstreams {
+            if let Err(err) = downstream.sink_server_is_busy(self.region_id, reason.to_owned()) {
+                warn!("cdc send quarantine notice failed";
+                    "region_id" => self.region_id, "error" => ?err,
+                    "downstream_id" => ?downstream.id, "conn_id" => ?downstream.conn_id);
+            }
+        }
+    }
+
+    /// Break the region-wide resolved ts down by bucket, using the same "smallest in-range
+    /// lock" technique as [`Delegate::on_min_ts`], but ranging over each bucket's key span
+    /// instead of a downstream's `observed_range`.
+    ///
+    /// Returns `None` if no bucket boundaries have been reported for this region yet, or the
+    /// lock tracker isn't ready. Otherwise returns one `(bucket_start_key, resolved_ts)` pair
+    /// per bucket, where `bucket_start_key` is the encoded start key of that bucket.
+    ///
+    /// NOTE: TiKV has no way to advertise this to downstream CDC clients yet: doing so needs a
+    /// new field on `kvproto`'s `cdcpb::ResolvedTs` (or the registration response), and that
+    /// dependency isn't available to change here. This only makes the computation available
+    /// internally (e.g. for `Task::Validate(Validate::Region(..))` callers) so that wiring up
+    /// the wire format later is a small, mechanical follow-up.
+    pub fn resolved_ts_per_bucket(&self, min_ts: TimeStamp) -> Option<Vec<(Vec<u8>, TimeStamp)>> {
+        let buckets = self.region_buckets.as_ref()?;
+        let locks = match &self.lock_tracker {
+            LockTracker::Prepared { locks, .. } => locks,
+            _ => return None,
+        };
+
+        let mut resolved = Vec::with_capacity(buckets.keys.len().saturating_sub(1));
+        for (start, end) in buckets.keys.iter().zip(buckets.keys.iter().skip(1)) {
+            let start_key = Key::from_encoded_slice(start);
+            let end_key = Key::from_encoded_slice(end);
+            let min_lock = locks
+                .range((Bound::Included(&start_key), Bound::Excluded(&end_key)))
+                .map(|(_, lock)| lock.ts)
+                .min()
+                .unwrap_or(min_ts);
+            resolved.push((start.clone(), std::cmp::min(min_lock, min_ts)));
+        }
+        Some(resolved)
     }
 
     pub fn on_batch(
         &mut self,
         batch: CmdBatch,
-        old_value_cb: &OldValueCallback,
+        old_value_resolver: &OldValueResolver,
+        scan_pool: &ScanPoolHandle,
         old_value_cache: &mut OldValueCache,
+        old_value_budget: &mut OldValueBudget,
         statistics: &mut Statistics,
+        retryable_failures: &mut Vec<(ConnId, RequestId, DownstreamId, Error)>,
     ) -> Result<()> {
         // Stale CmdBatch, drop it silently.
         if batch.cdc_id != self.handle.id {
@@ -721,15 +1130,29 @@ impl Delegate {
                 let err_header = response.mut_header().take_error();
                 return Err(Error::request(err_header));
             }
+            if index <= self.last_applied_index {
+                // Already delivered this entry from an earlier `on_batch` call on this
+                // same delegate; see `last_applied_index`'s doc comment.
+                CDC_DUPLICATE_CMD_COUNTER.inc();
+                debug!("cdc skip replayed command";
+                    "region_id" => self.region_id,
+                    "index" => index,
+                    "last_applied_index" => self.last_applied_index);
+                continue;
+            }
+            self.last_applied_index = index;
             if !request.has_admin_request() {
                 let flags = WriteBatchFlags::from_bits_truncate(request.get_header().get_flags());
                 self.sink_data(
                     index,
                     request.requests.into(),
                     flags,
-                    old_value_cb,
+                    old_value_resolver,
+                    scan_pool,
                     old_value_cache,
+                    old_value_budget,
                     statistics,
+                    retryable_failures,
                 )?;
             } else {
                 self.sink_admin(request.take_admin_request(), response.take_admin_response())?;
@@ -742,8 +1165,9 @@ impl Delegate {
         region_id: u64,
         request_id: RequestId,
         entries: Vec<Option<KvEntry>>,
-        filter_loop: bool,
+        txn_source_filter: TxnSourceFilter,
         observed_range: &ObservedRange,
+        max_row_size: usize,
     ) -> Result<Vec<CdcEvent>> {
         let entries_len = entries.len();
         let mut rows = vec![Vec::with_capacity(entries_len)];
@@ -806,11 +1230,14 @@ impl Delegate {
                     row_size = 0;
                 }
             }
-            if TxnSource::is_lossy_ddl_reorg_source_set(row.txn_source)
-                || filter_loop && TxnSource::is_cdc_write_source_set(row.txn_source)
-            {
+            if txn_source_filter.filter(row.txn_source) {
                 continue;
             }
+            let row_size = if row_size > max_row_size {
+                truncate_oversized_row_value(region_id, &mut row, max_row_size)
+            } else {
+                row_size
+            };
             if current_rows_size + row_size >= CDC_EVENT_MAX_BYTES {
                 rows.push(Vec::with_capacity(entries_len));
                 current_rows_size = 0;
@@ -843,16 +1270,41 @@ impl Delegate {
         index: u64,
         requests: Vec<Request>,
         flags: WriteBatchFlags,
-        old_value_cb: &OldValueCallback,
+        old_value_resolver: &OldValueResolver,
+        scan_pool: &ScanPoolHandle,
         old_value_cache: &mut OldValueCache,
+        old_value_budget: &mut OldValueBudget,
         statistics: &mut Statistics,
+        retryable_failures: &mut Vec<(ConnId, RequestId, DownstreamId, Error)>,
     ) -> Result<()> {
         debug_assert_eq!(self.txn_extra_op.load(), TxnExtraOp::ReadOldValue);
 
+        // Rows whose old value couldn't be answered from `old_value_cache`
+        // alone. They are resolved together, concurrently, on the scan
+        // worker pool once every request in this batch has been decoded,
+        // instead of one engine read at a time on the endpoint thread.
+        let mut pending = Vec::new();
         let mut read_old_value = |row: &mut EventRow, read_old_ts| -> Result<()> {
             let key = Key::from_raw(&row.key).append_ts(row.start_ts.into());
-            let old_value = old_value_cb(key, read_old_ts, old_value_cache, statistics)?;
-            row.old_value = old_value.unwrap_or_default();
+            match old_value_cache.check(&key, read_old_ts) {
+                OldValueLookup::Resolved(value) => row.old_value = value.unwrap_or_default(),
+                OldValueLookup::Pending(task) => {
+                    // An engine read's actual cost isn't known until it's
+                    // done, so the budget is checked (and charged) against
+                    // an estimate instead. `EventRow` has no field to mark
+                    // a value as "unavailable because the budget ran out"
+                    // -- the same kind of wire gap `Error::is_retryable`'s
+                    // doc comment notes elsewhere -- so falling back just
+                    // means leaving `row.old_value` empty, indistinguishable
+                    // on the wire from a resolved `None`.
+                    if old_value_budget.has_capacity(OLD_VALUE_LOOKUP_BYTES_ESTIMATE) {
+                        old_value_budget.charge(OLD_VALUE_LOOKUP_BYTES_ESTIMATE);
+                        pending.push((Key::from_raw(&row.key), task));
+                    } else {
+                        CDC_OLD_VALUE_BUDGET_EXHAUSTED.inc();
+                    }
+                }
+            }
             Ok(())
         };
 
@@ -864,19 +1316,62 @@ impl Delegate {
                     self.sink_put(req.take_put(), &mut rows_builder, &mut read_old_value)?
                 }
                 CmdType::Delete => self.sink_delete(req.take_delete(), &mut rows_builder)?,
+                CmdType::DeleteRange => self.sink_delete_range(req.take_delete_range()),
                 _ => debug!("cdc skip other command";
                     "region_id" => self.region_id,
                     "command" => ?req),
             };
         }
+        drop(read_old_value);
+
+        if !pending.is_empty() {
+            for (key, old_value) in
+                resolve_old_values(pending, old_value_resolver, scan_pool, statistics)?
+            {
+                // The row may have been dropped by `decode_write`/`decode_lock` (e.g. a
+                // rollback record), in which case there's nothing left to backfill.
+                if let Some(row) = rows_builder.txns_by_key.get_mut(&key) {
+                    row.v.old_value = old_value.unwrap_or_default();
+                }
+            }
+        }
 
         let (raws, txns) = rows_builder.finish_build();
-        self.sink_downstream_raw(raws, index)?;
-        self.sink_downstream_tidb(txns)?;
+        self.throughput_events += (raws.len() + txns.len()) as u64;
+        self.throughput_bytes += raws.iter().map(EventRow::compute_size).sum::<u32>() as u64
+            + txns
+                .iter()
+                .map(|(row, _)| row.compute_size())
+                .sum::<u32>() as u64;
+        self.sink_downstream_raw(raws, index, retryable_failures)?;
+        self.sink_downstream_tidb(txns, retryable_failures)?;
         Ok(())
     }
 
-    fn sink_downstream_raw(&mut self, entries: Vec<EventRow>, index: u64) -> Result<()> {
+    /// Current accumulated event count and byte count this region has
+    /// applied since the last `flush_throughput` call. Doesn't reset the
+    /// counters -- backs `Validate::RegionThroughput`.
+    pub fn throughput(&self) -> (u64, u64) {
+        (self.throughput_events, self.throughput_bytes)
+    }
+
+    /// Drains the accumulated throughput counters. `Endpoint::on_timeout`
+    /// calls this every `METRICS_FLUSH_INTERVAL` (1s) and folds the result
+    /// into `CDC_REGION_THROUGHPUT_*_HISTOGRAM`, so the drained counts are
+    /// already a rate.
+    pub fn flush_throughput(&mut self) -> (u64, u64) {
+        (
+            std::mem::take(&mut self.throughput_events),
+            std::mem::take(&mut self.throughput_bytes),
+        )
+    }
+
+    fn sink_downstream_raw(
+        &mut self,
+        entries: Vec<EventRow>,
+        index: u64,
+        retryable_failures: &mut Vec<(ConnId, RequestId, DownstreamId, Error)>,
+    ) -> Result<()> {
         let mut downstreams = Vec::with_capacity(self.downstreams.len());
         for d in &mut self.downstreams {
             if d.kv_api == ChangeDataRequestKvApi::RawKv && d.state.load().ready_for_change_events()
@@ -889,14 +1384,29 @@ impl Delegate {
         }
 
         for downstream in downstreams {
-            let filtered_entries: Vec<_> = entries
+            let mut out_of_range = 0u64;
+            let mut filtered_entries: Vec<_> = entries
                 .iter()
-                .filter(|x| downstream.observed_range.contains_raw_key(&x.key))
+                .filter(|x| {
+                    if !downstream.observed_range.contains_raw_key(&x.key) {
+                        out_of_range += 1;
+                        return false;
+                    }
+                    downstream.event_filter.retain(x)
+                })
                 .cloned()
                 .collect();
+            if out_of_range > 0 {
+                CDC_SINK_DROPPED_OUT_OF_RANGE_ENTRIES
+                    .with_label_values(&["raw"])
+                    .inc_by(out_of_range);
+            }
             if filtered_entries.is_empty() {
                 continue;
             }
+            for row in &mut filtered_entries {
+                downstream.event_filter.strip_old_value(row);
+            }
             let event = Event {
                 region_id: self.region_id,
                 index,
@@ -907,12 +1417,27 @@ impl Delegate {
                 })),
                 ..Default::default()
             };
-            downstream.sink_event(event, false)?;
+            if let Err(e) = downstream.sink_event(event, false) {
+                if is_retryable_sink_error(&e) {
+                    retryable_failures.push((
+                        downstream.conn_id,
+                        downstream.req_id,
+                        downstream.id,
+                        e,
+                    ));
+                    continue;
+                }
+                return Err(e);
+            }
         }
         Ok(())
     }
 
-    fn sink_downstream_tidb(&mut self, mut entries: Vec<(EventRow, isize)>) -> Result<()> {
+    fn sink_downstream_tidb(
+        &mut self,
+        mut entries: Vec<(EventRow, isize)>,
+        retryable_failures: &mut Vec<(ConnId, RequestId, DownstreamId, Error)>,
+    ) -> Result<()> {
         let mut downstreams = Vec::with_capacity(self.downstreams.len());
         for d in &mut self.downstreams {
             if d.kv_api == ChangeDataRequestKvApi::TiDb && d.state.load().ready_for_change_events()
@@ -929,6 +1454,7 @@ impl Delegate {
 
         for downstream in downstreams {
             let mut filtered_entries = Vec::with_capacity(entries.len());
+            let mut out_of_range = 0u64;
             for (entry, lock_count_modify) in &entries {
                 if *lock_count_modify != 0 && downstream.lock_heap.is_some() {
                     let lock_heap = downstream.lock_heap.as_mut().unwrap();
@@ -950,14 +1476,24 @@ impl Delegate {
                     }
                 }
 
-                if !downstream.observed_range.contains_raw_key(&entry.key)
-                    || downstream.filter_loop
-                        && TxnSource::is_cdc_write_source_set(entry.txn_source)
+                if !downstream.observed_range.contains_raw_key(&entry.key) {
+                    out_of_range += 1;
+                    continue;
+                }
+                if downstream.txn_source_filter.filter(entry.txn_source)
+                    || !downstream.event_filter.retain(entry)
                 {
                     continue;
                 }
 
-                filtered_entries.push(entry.clone());
+                let mut entry = entry.clone();
+                downstream.event_filter.strip_old_value(&mut entry);
+                filtered_entries.push(entry);
+            }
+            if out_of_range > 0 {
+                CDC_SINK_DROPPED_OUT_OF_RANGE_ENTRIES
+                    .with_label_values(&["txn"])
+                    .inc_by(out_of_range);
             }
             if filtered_entries.is_empty() {
                 continue;
@@ -971,7 +1507,18 @@ impl Delegate {
                 })),
                 ..Default::default()
             };
-            downstream.sink_event(event, false)?;
+            if let Err(e) = downstream.sink_event(event, false) {
+                if is_retryable_sink_error(&e) {
+                    retryable_failures.push((
+                        downstream.conn_id,
+                        downstream.req_id,
+                        downstream.id,
+                        e,
+                    ));
+                    continue;
+                }
+                return Err(e);
+            }
         }
         Ok(())
     }
@@ -1067,6 +1614,41 @@ impl Delegate {
         Ok(())
     }
 
+    /// A `DeleteRange` raft command never reaches a TxnKV downstream: it's
+    /// only ever raised for a raw key range (e.g. RawKV's `delete_range` API,
+    /// or TTL compaction-filter cleanup), and TxnKV writes are always
+    /// point Puts/Deletes against the `write`/`lock`/`default` cfs.
+    ///
+    /// RawKV CDC consumers, however, do need to know about it to mirror the
+    /// range deletion -- but there's no way to deliver that today: unlike
+    /// `EventRow`'s single `key` field, `cdcpb::Event`'s oneof has no
+    /// variant carrying `[start_key, end_key)` (only single-key `EventRow`s
+    /// via `Entries`, plus `Admin`/`Error`/`ResolvedTs`), and synthesizing
+    /// two point `Delete` rows at the boundaries would misrepresent what
+    /// actually happened. Delivering this needs a `kvproto` change (e.g. a
+    /// new `Event_oneof_event::DeleteRange` variant); until then this is
+    /// counted and logged so operators can see how often a RawKV CDC
+    /// consumer is missing a range deletion it needs to account for.
+    fn sink_delete_range(&mut self, delete_range: DeleteRangeRequest) {
+        if ApiV2::parse_key_mode(delete_range.get_start_key()) != KeyMode::Raw {
+            return;
+        }
+        if !self
+            .downstreams
+            .iter()
+            .any(|d| d.kv_api == ChangeDataRequestKvApi::RawKv)
+        {
+            return;
+        }
+        CDC_SINK_UNDELIVERABLE_DELETE_RANGE
+            .with_label_values(&["rawkv"])
+            .inc();
+        warn!("cdc cannot propagate a raw delete_range to downstreams, no wire event exists yet";
+            "region_id" => self.region_id,
+            "start_key" => log_wrappers::Value::key(delete_range.get_start_key()),
+            "end_key" => log_wrappers::Value::key(delete_range.get_end_key()));
+    }
+
     fn sink_admin(&mut self, request: AdminRequest, mut response: AdminResponse) -> Result<()> {
         let store_err = match request.get_cmd_type() {
             AdminCmdType::Split => RaftStoreError::EpochNotMatch(
@@ -1131,7 +1713,7 @@ impl Delegate {
         Ok(())
     }
 
-    fn stop_observing(&self) {
+    pub(crate) fn stop_observing(&self) {
         info!("cdc stop observing"; "region_id" => self.region_id, "failed" => self.failed);
         // Stop observe further events.
         self.handle.stop_observing();
@@ -1140,6 +1722,38 @@ impl Delegate {
     }
 }
 
+/// Resolve a batch of pending old-value lookups concurrently on `scan_pool`,
+/// instead of one engine read at a time on the calling (endpoint) thread.
+///
+/// This only parallelizes the lookups that belong to a single `CmdBatch`;
+/// the caller still waits for all of them before moving on to sink the
+/// batch, so ordering between `CmdBatch`es (and thus between downstream
+/// events) is unaffected.
+fn resolve_old_values(
+    pending: Vec<(Key, OldValueTask)>,
+    old_value_resolver: &OldValueResolver,
+    scan_pool: &ScanPoolHandle,
+    statistics: &mut Statistics,
+) -> Result<Vec<(Key, Option<Value>)>> {
+    let futures = pending.into_iter().map(|(key, task)| {
+        let resolver = old_value_resolver.clone();
+        scan_pool.spawn_blocking(move || {
+            let mut stats = Statistics::default();
+            let value = resolver(task, &mut stats);
+            (key, value, stats)
+        })
+    });
+    let joined = scan_pool.block_on(futures::future::join_all(futures));
+
+    let mut resolved = Vec::with_capacity(joined.len());
+    for join_result in joined {
+        let (key, value, stats) = join_result.map_err(|e| Error::Other(Box::new(e)))?;
+        statistics.add(&stats);
+        resolved.push((key, value?));
+    }
+    Ok(resolved)
+}
+
 #[derive(Default)]
 struct RowsBuilder {
     // map[Key]->(row, has_value, lock_count_modify)
@@ -1183,6 +1797,25 @@ fn set_event_row_type(row: &mut EventRow, ty: EventLogType) {
     row.r_type = ty;
 }
 
+/// Truncates `row`'s value so that `row.key.len() + row.value.len()` fits in
+/// `max_row_size`, and returns the resulting row size.
+///
+/// There's currently no way to mark a row as truncated on the wire, so a
+/// downstream that needs a byte-perfect value for a pathologically large row
+/// can't tell it received a partial one; delivering the rest losslessly
+/// would require downstream-negotiated chunked continuation events, which
+/// isn't implemented. Truncating is still preferable to the alternative of
+/// sending an oversized event that can silently break a client whose gRPC
+/// channel has a smaller max receive message size than this store's.
+fn truncate_oversized_row_value(region_id: u64, row: &mut EventRow, max_row_size: usize) -> usize {
+    let original_size = row.key.len() + row.value.len();
+    let value_limit = max_row_size.saturating_sub(row.key.len());
+    warn!("cdc truncating oversized row value";
+        "region_id" => region_id, "original_size" => original_size, "max_row_size" => max_row_size);
+    row.value.truncate(value_limit);
+    row.key.len() + row.value.len()
+}
+
 fn make_overlapped_rollback(key: Key, row: &mut EventRow) {
     // The current record's commit_ts is the rolled-back transaction's start_ts.
     row.start_ts = key.decode_ts().unwrap().into_inner();
@@ -1428,7 +2061,8 @@ mod tests {
         let region_epoch = region.get_region_epoch().clone();
 
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
-        let (sink, mut drain) = crate::channel::channel(1, quota.clone());
+        let (sink, mut drain) =
+            crate::channel::channel(1, quota.clone(), Arc::new(MemoryQuota::new(usize::MAX)));
         let rx = drain.drain();
         let request_id = RequestId(123);
         let mut downstream = Downstream::new(
@@ -1437,7 +2071,7 @@ mod tests {
             request_id,
             ConnId::new(),
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         downstream.set_sink(sink);
@@ -1562,7 +2196,7 @@ mod tests {
                 id,
                 ConnId::new(),
                 ChangeDataRequestKvApi::TiDb,
-                false,
+                crate::txn_source::TxnSourceFilter::from_filter_loop(false),
                 ObservedRange::default(),
             )
         };
@@ -1727,14 +2361,16 @@ mod tests {
             RequestId(1),
             ConnId::new(),
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             observed_range,
         );
         downstream.set_sink(sink);
         downstream.get_state().store(DownstreamState::Normal);
         delegate.add_downstream(downstream);
         let (_, entries) = rows_builder.finish_build();
-        delegate.sink_downstream_tidb(entries).unwrap();
+        delegate
+            .sink_downstream_tidb(entries, &mut Vec::new())
+            .unwrap();
 
         let (mut tx, mut rx) = futures::channel::mpsc::unbounded();
         let runtime = tokio::runtime::Runtime::new().unwrap();
@@ -1748,6 +2384,10 @@ mod tests {
     }
 
     fn test_downstream_txn_source_filter(txn_source: TxnSource, filter_loop: bool) {
+        test_downstream_txn_source_filter_with(txn_source, TxnSourceFilter::from_filter_loop(filter_loop));
+    }
+
+    fn test_downstream_txn_source_filter_with(txn_source: TxnSource, txn_source_filter: TxnSourceFilter) {
         // Create a new delegate that observes [a, f).
         let observed_range = ObservedRange::new(
             Key::from_raw(b"a").into_encoded(),
@@ -1794,14 +2434,16 @@ mod tests {
             RequestId(1),
             ConnId::new(),
             ChangeDataRequestKvApi::TiDb,
-            filter_loop,
+            txn_source_filter,
             observed_range,
         );
         downstream.set_sink(sink);
         downstream.get_state().store(DownstreamState::Normal);
         delegate.add_downstream(downstream);
         let (_, entries) = rows_builder.finish_build();
-        delegate.sink_downstream_tidb(entries).unwrap();
+        delegate
+            .sink_downstream_tidb(entries, &mut Vec::new())
+            .unwrap();
 
         let (mut tx, mut rx) = futures::channel::mpsc::unbounded();
         let runtime = tokio::runtime::Runtime::new().unwrap();
@@ -1814,6 +2456,95 @@ mod tests {
         assert_eq!(e.events[0].get_entries().get_entries().len(), 1, "{:?}", e);
     }
 
+    #[test]
+    fn test_downstream_event_type_filter() {
+        // Create a new delegate that observes [a, f).
+        let observed_range = ObservedRange::new(
+            Key::from_raw(b"a").into_encoded(),
+            Key::from_raw(b"f").into_encoded(),
+        )
+        .unwrap();
+        let memory_quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let txn_extra_op = Arc::new(AtomicCell::new(TxnExtraOp::Noop));
+        let mut delegate = Delegate::new(1, memory_quota, txn_extra_op);
+        assert!(delegate.handle.is_observing());
+        assert!(delegate.init_lock_tracker());
+
+        let mut rows_builder = RowsBuilder::default();
+        // `a` and `c` are puts, `b`, `d` and `e` are deletes.
+        for k in b'a'..=b'e' {
+            let mut put = PutRequest::default();
+            put.key = Key::from_raw(&[k]).into_encoded();
+            put.cf = "lock".to_owned();
+            let lock_type = if matches!(k, b'a' | b'c') {
+                LockType::Put
+            } else {
+                LockType::Delete
+            };
+            put.value = Lock::new(
+                lock_type,
+                put.key.clone(),
+                1.into(),
+                10,
+                Some(b"test".to_vec()),
+                TimeStamp::zero(),
+                0,
+                TimeStamp::zero(),
+                false,
+            )
+            .to_bytes();
+            delegate
+                .sink_txn_put(
+                    put,
+                    |row, _| {
+                        row.old_value = b"old".to_vec();
+                        Ok(())
+                    },
+                    &mut rows_builder,
+                )
+                .unwrap();
+        }
+        assert_eq!(rows_builder.txns_by_key.len(), 5);
+
+        let (sink, mut drain) = channel(1, Arc::new(MemoryQuota::new(1024)));
+        let mut downstream = Downstream::new(
+            "peer".to_owned(),
+            RegionEpoch::default(),
+            RequestId(1),
+            ConnId::new(),
+            ChangeDataRequestKvApi::TiDb,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
+            observed_range,
+        );
+        downstream.set_event_filter(EventRowFilter {
+            skip_deletes: true,
+            skip_old_value: true,
+            ..Default::default()
+        });
+        downstream.set_sink(sink);
+        downstream.get_state().store(DownstreamState::Normal);
+        delegate.add_downstream(downstream);
+        let (_, entries) = rows_builder.finish_build();
+        delegate
+            .sink_downstream_tidb(entries, &mut Vec::new())
+            .unwrap();
+
+        let (mut tx, mut rx) = futures::channel::mpsc::unbounded();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.spawn(async move {
+            drain.forward(&mut tx).await.unwrap();
+        });
+        let (e, _) = recv_timeout(&mut rx, std::time::Duration::from_secs(5))
+            .unwrap()
+            .unwrap();
+        let rows = e.events[0].get_entries().get_entries();
+        assert_eq!(rows.len(), 2, "{:?}", e);
+        for row in rows {
+            assert_eq!(row.get_op_type(), EventRowOpType::Put, "{:?}", row);
+            assert!(row.get_old_value().is_empty(), "{:?}", row);
+        }
+    }
+
     #[test]
     fn test_downstream_filter_cdc_write_entires() {
         let mut txn_source = TxnSource::default();
@@ -1843,6 +2574,85 @@ mod tests {
         test_downstream_txn_source_filter(txn_source, true);
     }
 
+    #[test]
+    fn test_downstream_filter_lightning_physical_import_entries() {
+        // 16 is `LIGHTNING_PHYSICAL_IMPORT_SOURCE` in txn_source.rs; a
+        // downstream that opted into filtering it should drop these
+        // entries even though `filter_loop` (CDC_WRITE_LOOP) is off.
+        let mut txn_source = TxnSource::default();
+        txn_source.set_cdc_write_source(16);
+        test_downstream_txn_source_filter_with(
+            txn_source,
+            TxnSourceFilter::LIGHTNING_PHYSICAL_IMPORT,
+        );
+
+        // A plain loopback write (source 1) is unaffected by that filter.
+        let mut txn_source = TxnSource::default();
+        txn_source.set_cdc_write_source(1);
+        let observed_range = ObservedRange::new(
+            Key::from_raw(b"a").into_encoded(),
+            Key::from_raw(b"f").into_encoded(),
+        )
+        .unwrap();
+        let memory_quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let txn_extra_op = Arc::new(AtomicCell::new(TxnExtraOp::Noop));
+        let mut delegate = Delegate::new(1, memory_quota, txn_extra_op);
+        delegate.init_lock_tracker();
+        let mut rows_builder = RowsBuilder::default();
+        for k in b'a'..=b'e' {
+            let mut put = PutRequest::default();
+            put.key = Key::from_raw(&[k]).into_encoded();
+            put.cf = "lock".to_owned();
+            let mut lock = Lock::new(
+                LockType::Put,
+                put.key.clone(),
+                1.into(),
+                10,
+                Some(b"test".to_vec()),
+                TimeStamp::zero(),
+                0,
+                TimeStamp::zero(),
+                false,
+            );
+            if k != b'a' {
+                lock = lock.set_txn_source(txn_source.into());
+            }
+            put.value = lock.to_bytes();
+            delegate
+                .sink_txn_put(put, |_, _| Ok(()), &mut rows_builder)
+                .unwrap();
+        }
+        let (sink, mut drain) = channel(1, Arc::new(MemoryQuota::new(1024)));
+        let mut downstream = Downstream::new(
+            "peer".to_owned(),
+            RegionEpoch::default(),
+            RequestId(1),
+            ConnId::new(),
+            ChangeDataRequestKvApi::TiDb,
+            TxnSourceFilter::LIGHTNING_PHYSICAL_IMPORT,
+            observed_range,
+        );
+        downstream.set_sink(sink);
+        downstream.get_state().store(DownstreamState::Normal);
+        delegate.add_downstream(downstream);
+        let (_, entries) = rows_builder.finish_build();
+        delegate
+            .sink_downstream_tidb(entries, &mut Vec::new())
+            .unwrap();
+        let (mut tx, mut rx) = futures::channel::mpsc::unbounded();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.spawn(async move {
+            drain.forward(&mut tx).await.unwrap();
+        });
+        let (e, _) = recv_timeout(&mut rx, std::time::Duration::from_secs(5))
+            .unwrap()
+            .unwrap();
+        // All 5 keys come through: LIGHTNING_PHYSICAL_IMPORT doesn't match
+        // a plain loopback write, and lossy DDL filtering isn't in play
+        // here either.
+        assert_eq!(e.events[0].get_entries().get_entries().len(), 5, "{:?}", e);
+    }
+
     #[test]
     fn test_decode_rawkv() {
         let cases = vec![
@@ -1941,4 +2751,88 @@ mod tests {
             .finish_prepare_lock_tracker(Default::default(), scaned_locks)
             .unwrap();
     }
+
+    #[test]
+    fn test_resolved_ts_per_bucket() {
+        let quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let mut delegate = Delegate::new(1, quota, Default::default());
+        assert!(delegate.init_lock_tracker());
+
+        let mut scaned_locks = BTreeMap::default();
+        scaned_locks.insert(Key::from_raw(b"key1"), MiniLock::from_ts(100));
+        scaned_locks.insert(Key::from_raw(b"key5"), MiniLock::from_ts(200));
+        delegate
+            .finish_prepare_lock_tracker(Default::default(), scaned_locks)
+            .unwrap();
+
+        // No bucket boundaries have been reported yet.
+        assert!(
+            delegate
+                .resolved_ts_per_bucket(TimeStamp::from(500))
+                .is_none()
+        );
+
+        let meta = Arc::new(BucketMeta {
+            keys: vec![
+                Key::from_raw(b"key0").into_encoded(),
+                Key::from_raw(b"key3").into_encoded(),
+                Key::from_raw(b"key9").into_encoded(),
+            ],
+            sizes: vec![0, 0],
+            ..Default::default()
+        });
+        delegate.on_region_buckets_updated(meta);
+
+        let resolved = delegate
+            .resolved_ts_per_bucket(TimeStamp::from(500))
+            .unwrap();
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved[0].1, TimeStamp::from(100));
+        assert_eq!(resolved[1].1, TimeStamp::from(200));
+    }
+
+    #[test]
+    fn test_downstream_delivery_stats() {
+        let quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let (sink, mut drain) =
+            crate::channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
+        let _rx = drain.drain();
+        let mut downstream = Downstream::new(
+            String::new(),
+            RegionEpoch::default(),
+            RequestId(1),
+            ConnId::new(),
+            ChangeDataRequestKvApi::TiDb,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
+            ObservedRange::default(),
+        );
+        downstream.set_sink(sink);
+
+        // No events sent yet.
+        assert_eq!(downstream.sample_and_reset_delivery_stats(), (0, 0));
+
+        let mut event = Event::default();
+        event.region_id = 1;
+        event.set_request_id(1);
+        event.event = Some(Event_oneof_event::Entries(EventEntries {
+            entries: vec![EventRow::default(), EventRow::default()].into(),
+            ..Default::default()
+        }));
+        let bytes = event.compute_size() as u64;
+        downstream.sink_event(event, false).unwrap();
+
+        let (rows, sent_bytes) = downstream.sample_and_reset_delivery_stats();
+        assert_eq!(rows, 2);
+        assert_eq!(sent_bytes, bytes);
+
+        // Sampling resets the counters.
+        assert_eq!(downstream.sample_and_reset_delivery_stats(), (0, 0));
+
+        // Events without entries (e.g. errors) don't count as rows.
+        let mut err_event = EventError::default();
+        err_event.mut_region_not_found().region_id = 1;
+        downstream.sink_error_event(1, err_event).unwrap();
+        let (rows, _) = downstream.sample_and_reset_delivery_stats();
+        assert_eq!(rows, 0);
+    }
 }
@@ -8,7 +8,7 @@ use std::{
         atomic::{AtomicBool, AtomicIsize, Ordering},
         Arc, Mutex as StdMutex,
     },
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 
 use causal_ts::{CausalTsProvider, CausalTsProviderImpl};
@@ -29,14 +29,15 @@ use kvproto::{
     metapb::Region,
 };
 use online_config::{ConfigChange, OnlineConfig};
-use pd_client::{Feature, PdClient};
+use pd_client::{BucketMeta, Feature, PdClient};
 use raftstore::{
     coprocessor::{CmdBatch, ObserveId},
     router::CdcHandle,
     store::fsm::store::StoreRegionMeta,
 };
 use resolved_ts::{resolve_by_raft, LeadershipResolver};
-use security::SecurityManager;
+use resource_control::{with_resource_limiter, ResourceGroupManager};
+use security::{match_peer_names, SecurityManager};
 use tikv::{
     config::{CdcConfig, ResolvedTsConfig},
     storage::{kv::LocalTablets, Statistics},
@@ -56,14 +57,20 @@ use tokio::{
     runtime::{Builder, Runtime},
     sync::Semaphore,
 };
+use tracing::instrument;
+use tracing_active_tree::root;
 use txn_types::{Key, TimeStamp, TxnExtra, TxnExtraScheduler};
 
 use crate::{
     channel::{CdcEvent, SendError},
-    delegate::{on_init_downstream, Delegate, Downstream, DownstreamId, DownstreamState, MiniLock},
+    delegate::{
+        on_init_downstream, pause_downstream, resume_downstream, Delegate, Downstream,
+        DownstreamId, DownstreamState, MiniLock, RegionStatsSample,
+    },
     initializer::Initializer,
     metrics::*,
-    old_value::{OldValueCache, OldValueCallback},
+    old_value::{OldValueBudget, OldValueCache, OldValueCacheStats, OldValueResolver},
+    registry::{CdcSubscriptionRegistry, DownstreamSubscription},
     service::{validate_kv_api, Conn, ConnId, FeatureGate, RequestId},
     CdcObserver, Error,
 };
@@ -71,6 +78,18 @@ use crate::{
 const FEATURE_RESOLVED_TS_STORE: Feature = Feature::require(5, 0, 0);
 const METRICS_FLUSH_INTERVAL: u64 = 1_000; // 1s
 
+/// Minimum gap between two region-error broadcasts for the same region.
+///
+/// A flapping region (e.g. one that keeps transferring its leader) can
+/// deregister and re-register many times a second, and every deregister
+/// would otherwise broadcast a fresh error event to all of its downstreams.
+/// Within this window, at most one broadcast is let through per region; the
+/// rest are dropped and counted in [`CDC_REGION_ERROR_EVENT_RATE_LIMITED`].
+/// Once the region stops flapping, the very next deregister after the
+/// window elapses is delivered normally, so downstreams are never left
+/// without an explanation for longer than this window.
+const REGION_ERROR_EVENT_RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+
 pub enum Deregister {
     Conn(ConnId),
     Request {
@@ -88,6 +107,13 @@ pub enum Deregister {
         region_id: u64,
         downstream_id: DownstreamId,
         err: Option<Error>,
+        /// Whether `err` (if any) is [`Error::is_retryable`]. Computed by
+        /// the caller rather than derived on the fly here, so it's captured
+        /// at the moment the error actually happened rather than whenever
+        /// this gets handled -- the two are usually the same call, but
+        /// keeping the classification with the error it describes is one
+        /// less thing for a future `err` variant to get wrong by omission.
+        retryable: bool,
     },
     Delegate {
         region_id: u64,
@@ -96,6 +122,25 @@ pub enum Deregister {
     },
 }
 
+/// A locally-computed hint for how a client might reconnect faster after a
+/// region's delegate is stopped due to a store-side condition (overload,
+/// draining, a config change, ...).
+///
+/// Note: `cdcpb::Error`, the error event sent to downstreams, has no fields
+/// to carry this yet, so today it's only observable through the log line
+/// emitted alongside it (see `Endpoint::on_deregister`). Actually handing it
+/// to TiCDC over the wire needs a `kvproto` change to add fields to the
+/// error event.
+#[derive(Debug, Default)]
+pub struct RegionFailoverHint {
+    /// A suggested minimum delay before the client retries subscribing to
+    /// this region.
+    pub retry_after: Duration,
+    /// Store ids of the region's other peers, as known locally from region
+    /// meta, that the client could try subscribing to instead.
+    pub alternative_stores: Vec<u64>,
+}
+
 impl_display_as_debug!(Deregister);
 
 impl fmt::Debug for Deregister {
@@ -130,6 +175,7 @@ impl fmt::Debug for Deregister {
                 ref region_id,
                 ref downstream_id,
                 ref err,
+                ref retryable,
             } => de
                 .field("deregister", &"downstream")
                 .field("conn_id", conn_id)
@@ -137,6 +183,7 @@ impl fmt::Debug for Deregister {
                 .field("region_id", region_id)
                 .field("downstream_id", downstream_id)
                 .field("err", err)
+                .field("retryable", retryable)
                 .finish(),
             Deregister::Delegate {
                 ref region_id,
@@ -157,6 +204,57 @@ type InitCallback = Box<dyn FnOnce() + Send>;
 pub enum Validate {
     Region(u64, Box<dyn FnOnce(Option<&Delegate>) + Send>),
     OldValueCache(Box<dyn FnOnce(&OldValueCache) + Send>),
+    /// Hands back a [`OldValueCacheStats`] snapshot -- hit ratio, cache-wide
+    /// counters, and the largest cached entries -- for the status-server
+    /// `/debug/old_value_cache` endpoint, without requiring the caller to
+    /// build its own closure over `OldValueCache`'s internals the way
+    /// `Validate::OldValueCache` does.
+    OldValueCacheStats(usize, Box<dyn FnOnce(OldValueCacheStats) + Send>),
+    /// Hands back the resolved-ts lag tracked for every live downstream, so
+    /// operators (and tests) can tell which (conn, request) is holding back
+    /// resolved ts instead of only seeing the store-wide minimum. Backs the
+    /// same data as `CDC_DOWNSTREAM_RESOLVED_TS_LAG`.
+    DownstreamLag(Box<dyn FnOnce(&HashMap<(ConnId, RequestId), TimeStamp>) + Send>),
+    /// Hands back a [`EndpointStats`] snapshot -- connections, per-conn
+    /// subscription counts, quota usage, scan backlog, and the store-wide
+    /// min resolved ts -- for the status-server `/debug/cdc_status`
+    /// endpoint, so operators can get a quick look without `grpcurl`.
+    EndpointStats(Box<dyn FnOnce(EndpointStats) + Send>),
+    /// Hands back the (events, bytes) this region has applied since the
+    /// last metrics flush -- the same numbers `on_timeout` folds into
+    /// `CDC_REGION_THROUGHPUT_*_HISTOGRAM` -- so an operator or test can
+    /// check one hot region directly instead of reading it back off the
+    /// histogram. `None` if the region isn't captured here.
+    RegionThroughput(u64, Box<dyn FnOnce(Option<(u64, u64)>) + Send>),
+    /// Hands back a [`HealthReport`] -- see its doc comment for why
+    /// `Task::Validate` is the only way to reach it today.
+    HealthReport(Box<dyn FnOnce(HealthReport) + Send>),
+}
+
+/// A snapshot of this store's CDC health along the four dimensions a
+/// changefeed router would want before assigning a new changefeed to it:
+/// how many regions it's already capturing, how far behind its resolved ts
+/// is, how much of its sink memory quota is in use, and how many scan
+/// tasks are backlogged.
+///
+/// `cdcpb::ChangeData` only has `event_feed`/`event_feed_v2` -- there's no
+/// RPC for a client (e.g. TiCDC) to pull this, and adding one needs a
+/// `kvproto` change, the same kind of gap
+/// [`crate::errors::Error::is_retryable`]'s doc comment describes for
+/// retry classification. [`Service::health_report`] is the internal entry
+/// point other TiKV code (e.g. a future status-server route) can already
+/// call; wiring an RPC up to it is future work outside this crate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HealthReport {
+    pub captured_regions: usize,
+    /// Milliseconds the store-wide min resolved ts is behind the latest PD
+    /// tso seen this tick; 0 if no region has reported yet this tick (see
+    /// `Endpoint::on_timeout`, which this mirrors and which also resets the
+    /// two `TimeStamp`s this is computed from every tick).
+    pub min_resolved_ts_lag_millis: u64,
+    pub sink_memory_quota_in_use: usize,
+    pub sink_memory_quota_capacity: usize,
+    pub scan_backlog: isize,
 }
 
 pub enum Task {
@@ -164,6 +262,18 @@ pub enum Task {
         request: ChangeDataRequest,
         downstream: Downstream,
     },
+    /// Bootstrap a downstream with a consistent point-in-time snapshot of
+    /// the observed range as of `snapshot_ts`, then keep streaming delta
+    /// events exactly as [`Task::Register`] would: this schedules the same
+    /// registration, just without requiring the caller to build a full
+    /// [`ChangeDataRequest`]. It exists so a downstream can do a one-time
+    /// full export through the CDC channel instead of bootstrapping with a
+    /// separate BR-based full backup before subscribing for deltas.
+    Snapshot {
+        region_id: u64,
+        snapshot_ts: TimeStamp,
+        downstream: Downstream,
+    },
     Deregister(Deregister),
     OpenConn {
         conn: Conn,
@@ -175,7 +285,7 @@ pub enum Task {
     },
     MultiBatch {
         multi: Vec<CmdBatch>,
-        old_value_cb: OldValueCallback,
+        old_value_resolver: OldValueResolver,
     },
     MinTs {
         regions: Vec<u64>,
@@ -209,6 +319,85 @@ pub enum Task {
     TxnExtra(TxnExtra),
     Validate(Validate),
     ChangeConfig(ConfigChange),
+    // Raftstore reported fresh bucket boundaries for a region we're observing. Stashed on the
+    // region's `Delegate` so `Delegate::resolved_ts_per_bucket` can use them; see its doc
+    // comment for why this doesn't (yet) reach downstream CDC clients.
+    RegionBuckets {
+        region_id: u64,
+        buckets: Arc<BucketMeta>,
+    },
+    /// Reports incremental-scan progress for `(region_id, conn_id,
+    /// request_id)`, so that if this downstream is torn down by a transient,
+    /// retryable sink error and resumes under the same identity (see the
+    /// comment on the `conn.subscribe` call in `on_register`), its next
+    /// `Initializer` can continue the scan from `resume_key` instead of
+    /// rescanning the observed range from scratch. `resume_key` of `None`
+    /// clears any previously stored progress, which happens once the scan
+    /// finishes (there's nothing left to resume) or the downstream is torn
+    /// down for good.
+    UpdateScanProgress {
+        region_id: u64,
+        conn_id: ConnId,
+        request_id: RequestId,
+        resume_key: Option<Key>,
+    },
+    /// Stops a downstream from receiving change events and resolved
+    /// timestamps without deregistering it: the delegate, its `ObserveId`
+    /// and the downstream's incremental-scan progress are all left alone, so
+    /// [`Task::ResumeDownstream`] can put it back to `Normal` with no
+    /// rescan. See [`crate::delegate::pause_downstream`].
+    ///
+    /// Note: `ChangeDataRequest` has no oneof variant for a client to ask
+    /// for this yet, so today it can only be scheduled internally (e.g. from
+    /// tests); wiring it up to a client-facing control message needs a
+    /// `kvproto` change, see `cdc::service::Service::handle_request`.
+    PauseDownstream {
+        conn_id: ConnId,
+        request_id: RequestId,
+        region_id: u64,
+    },
+    /// Reverses a [`Task::PauseDownstream`]; see its doc comment.
+    ResumeDownstream {
+        conn_id: ConnId,
+        request_id: RequestId,
+        region_id: u64,
+    },
+    /// Reports that a downstream has consumed `bytes` worth of events sent
+    /// to `conn_id`, shrinking that connection's unacked window (see
+    /// `Conn::ack_bytes`) and potentially un-pausing downstreams that
+    /// `CdcConfig::unacked_bytes_limit` backpressure had paused. See
+    /// `Delegate::on_min_ts` for where the window grows.
+    ///
+    /// Note: same gap as [`Task::PauseDownstream`] -- this can only be
+    /// scheduled internally today, pending a `kvproto` change to let a
+    /// client actually send acks.
+    Ack { conn_id: ConnId, bytes: usize },
+    /// Records the latest schema version this store has applied, so it can
+    /// be attached as a watermark alongside the resolved ts this store
+    /// advances to downstream. Meant to be scheduled whenever this TiKV
+    /// observes a DDL job reach a new schema version (e.g. from the raftstore
+    /// coprocessor's schema-change notification), not polled from `Endpoint`
+    /// itself.
+    ///
+    /// Note: `cdcpb::ResolvedTs` has no field to carry this yet, so
+    /// `Endpoint::schema_version` is tracked and logged alongside each
+    /// resolved-ts tick (see `Advance::emit_resolved_ts`) but not actually
+    /// put on the wire; doing that needs a `kvproto` change.
+    UpdateSchemaVersion { schema_version: u64 },
+    /// Lifts `region_id` out of quarantine (see `Endpoint::on_min_ts`'s
+    /// handling of `CdcConfig::resolved_ts_quarantine_timeout`), letting it
+    /// participate in resolved-ts advancement again. A no-op if the region
+    /// isn't currently quarantined. Nothing re-quarantines it until it's
+    /// been seen lagging for the full timeout again.
+    ReleaseQuarantine { region_id: u64 },
+    /// Begins a graceful shutdown: stops admitting new registrations (see
+    /// [`Endpoint::on_register`]), forces one more resolved-ts advance over
+    /// every currently captured region so any events already queued ahead
+    /// of this task on a downstream's sink are covered by a final resolved
+    /// ts, then runs `callback`. Meant to be scheduled from the store's
+    /// shutdown path before the gRPC server stops accepting connections, so
+    /// a rolling restart doesn't cut a changefeed off mid-batch.
+    Drain(InitCallback),
 }
 
 impl_display_as_debug!(Task);
@@ -228,6 +417,17 @@ impl fmt::Debug for Task {
                 .field("id", &downstream.id)
                 .field("conn_id", &downstream.conn_id)
                 .finish(),
+            Task::Snapshot {
+                ref region_id,
+                ref snapshot_ts,
+                ref downstream,
+            } => de
+                .field("type", &"snapshot")
+                .field("region_id", region_id)
+                .field("snapshot_ts", snapshot_ts)
+                .field("id", &downstream.id)
+                .field("conn_id", &downstream.conn_id)
+                .finish(),
             Task::Deregister(deregister) => de
                 .field("type", &"deregister")
                 .field("deregister", deregister)
@@ -286,11 +486,68 @@ impl fmt::Debug for Task {
             Task::Validate(validate) => match validate {
                 Validate::Region(region_id, _) => de.field("region_id", &region_id).finish(),
                 Validate::OldValueCache(_) => de.finish(),
+                Validate::OldValueCacheStats(..) => de.finish(),
+                Validate::DownstreamLag(_) => de.finish(),
+                Validate::EndpointStats(_) => de.finish(),
+                Validate::RegionThroughput(region_id, _) => {
+                    de.field("region_id", &region_id).finish()
+                }
+                Validate::HealthReport(_) => de.finish(),
             },
             Task::ChangeConfig(change) => de
                 .field("type", &"change_config")
                 .field("change", change)
                 .finish(),
+            Task::UpdateScanProgress {
+                region_id,
+                conn_id,
+                request_id,
+                resume_key,
+            } => de
+                .field("type", &"update_scan_progress")
+                .field("region_id", &region_id)
+                .field("conn_id", &conn_id)
+                .field("req_id", &request_id)
+                .field("has_resume_key", &resume_key.is_some())
+                .finish(),
+            Task::RegionBuckets { region_id, .. } => de
+                .field("type", &"region_buckets")
+                .field("region_id", &region_id)
+                .finish(),
+            Task::PauseDownstream {
+                conn_id,
+                request_id,
+                region_id,
+            } => de
+                .field("type", &"pause_downstream")
+                .field("conn_id", &conn_id)
+                .field("request_id", &request_id)
+                .field("region_id", &region_id)
+                .finish(),
+            Task::ResumeDownstream {
+                conn_id,
+                request_id,
+                region_id,
+            } => de
+                .field("type", &"resume_downstream")
+                .field("conn_id", &conn_id)
+                .field("request_id", &request_id)
+                .field("region_id", &region_id)
+                .finish(),
+            Task::Ack { conn_id, bytes } => de
+                .field("type", &"ack")
+                .field("conn_id", &conn_id)
+                .field("bytes", &bytes)
+                .finish(),
+            Task::UpdateSchemaVersion { schema_version } => de
+                .field("type", &"update_schema_version")
+                .field("schema_version", &schema_version)
+                .finish(),
+            Task::ReleaseQuarantine { region_id } => de
+                .field("type", &"release_quarantine")
+                .field("region_id", &region_id)
+                .finish(),
+            Task::Drain(_) => de.field("type", &"drain").finish(),
         }
     }
 }
@@ -369,6 +626,16 @@ pub(crate) struct Advance {
     pub(crate) blocked_on_scan: usize,
 
     pub(crate) blocked_on_locks: usize,
+
+    // Samples gathered from downstreams that opted into
+    // `FeatureGate::REGION_STATS_EVENTS`; see `log_region_stats`.
+    pub(crate) region_stats: Vec<RegionStatsSample>,
+
+    // The resolved ts delivered to each (conn, request) this tick, i.e. the
+    // minimum resolved ts among the regions it's watching. Filled in by
+    // `emit_resolved_ts`; `Endpoint::on_min_ts` folds it into its own
+    // longer-lived map to drive `CDC_DOWNSTREAM_RESOLVED_TS_LAG`.
+    pub(crate) downstream_resolved_ts: HashMap<(ConnId, RequestId), TimeStamp>,
 }
 
 impl Advance {
@@ -380,7 +647,9 @@ impl Advance {
                     debug!("cdc send event failed, disconnected";
                         "conn_id" => ?conn.get_id(), "downstream" => ?conn.get_peer());
                 }
-                Err(SendError::Full) | Err(SendError::Congested) => {
+                Err(SendError::Full)
+                | Err(SendError::Congested)
+                | Err(SendError::ConnCongested) => {
                     info!("cdc send event failed, full";
                         "conn_id" => ?conn.get_id(), "downstream" => ?conn.get_peer());
                 }
@@ -429,8 +698,16 @@ impl Advance {
         for (conn_id, req_id, mut region_ts_heap) in unioned {
             let conn = connections.get(&conn_id).unwrap();
             let mut batch_count = 8;
+            let mut is_first_batch = true;
             while !region_ts_heap.is_empty() {
                 let (ts, regions) = region_ts_heap.pop(batch_count);
+                if is_first_batch {
+                    // `pop` drains the min-heap in ascending order, so the
+                    // first batch's `ts` is this downstream's resolved ts:
+                    // the minimum across every region it's watching.
+                    self.downstream_resolved_ts.insert((conn_id, req_id), ts);
+                    is_first_batch = false;
+                }
                 if min_resolved.is_none() {
                     let rid = regions.iter().next().map_or(0, |x| *x);
                     min_resolved = Some((rid, ts));
@@ -445,6 +722,52 @@ impl Advance {
         }
         min_resolved.unwrap_or_default()
     }
+
+    /// Reports the per-(conn, region) delivery samples gathered this tick.
+    ///
+    /// There's no `cdcpb::Event` variant to actually deliver these to the
+    /// subscribing client yet, so for now opting into
+    /// `FeatureGate::REGION_STATS_EVENTS` only gets a downstream included in
+    /// these logs, not an extra event on its feed. Sending them over the wire
+    /// needs a new `Event_oneof_event` variant, which isn't something we can
+    /// add without a `kvproto` change.
+    fn log_region_stats(&mut self) {
+        for sample in std::mem::take(&mut self.region_stats) {
+            info!("cdc region stats";
+                "conn_id" => ?sample.conn_id,
+                "req_id" => ?sample.req_id.0,
+                "region_id" => sample.region_id,
+                "rows" => sample.rows,
+                "bytes" => sample.bytes,
+                "resolved_ts" => sample.resolved_ts.into_inner(),
+            );
+        }
+    }
+}
+
+/// A snapshot of one connection's state, as reported by
+/// [`Validate::EndpointStats`].
+#[derive(Debug)]
+pub struct ConnStats {
+    pub conn_id: ConnId,
+    pub peer: String,
+    pub subscription_count: usize,
+    pub unacked_bytes: usize,
+    pub scan_task_count: isize,
+}
+
+/// A lightweight snapshot of [`Endpoint`] state, for the status-server
+/// `/debug/cdc_status` endpoint. See [`Validate::EndpointStats`].
+#[derive(Debug)]
+pub struct EndpointStats {
+    pub connections: Vec<ConnStats>,
+    pub capture_region_count: usize,
+    pub max_capture_regions: usize,
+    pub sink_memory_quota_in_use: usize,
+    pub sink_memory_quota_capacity: usize,
+    pub scan_task_count: isize,
+    pub min_resolved_ts: TimeStamp,
+    pub min_resolved_ts_region_id: u64,
 }
 
 pub struct Endpoint<T, E, S> {
@@ -460,7 +783,7 @@ pub struct Endpoint<T, E, S> {
     pd_client: Arc<dyn PdClient>,
     timer: SteadyTimer,
     tso_worker: Runtime,
-    store_meta: Arc<StdMutex<S>>,
+    pub(crate) store_meta: Arc<StdMutex<S>>,
     /// The concurrency manager for transactions. It's needed for CDC to check
     /// locks when calculating resolved_ts.
     concurrency_manager: ConcurrencyManager,
@@ -478,18 +801,118 @@ pub struct Endpoint<T, E, S> {
     fetch_speed_limiter: Limiter,
     max_scan_batch_bytes: usize,
     max_scan_batch_size: usize,
+    max_row_size: usize,
     sink_memory_quota: Arc<MemoryQuota>,
+    /// Consulted in `on_register` to charge a downstream's incremental scan
+    /// against its resource group, so low-priority changefeeds don't starve
+    /// foreground reads. `None` when resource control is disabled cluster-wide.
+    resource_manager: Option<Arc<ResourceGroupManager>>,
 
     old_value_cache: OldValueCache,
 
     causal_ts_provider: Option<Arc<CausalTsProviderImpl>>,
 
+    // Lets the registered subscriptions outlive this particular `Endpoint`
+    // value; see `CdcSubscriptionRegistry`'s docs.
+    subscription_registry: CdcSubscriptionRegistry,
+
     // Metrics and logging.
     current_ts: TimeStamp,
     min_resolved_ts: TimeStamp,
     min_ts_region_id: u64,
     resolved_region_count: usize,
     unresolved_region_count: usize,
+
+    /// The last time a region error broadcast was actually sent to
+    /// downstreams, keyed by region ID. Used to rate-limit error event
+    /// storms from flapping regions; see
+    /// [`REGION_ERROR_EVENT_RATE_LIMIT_WINDOW`].
+    last_region_error_sent: HashMap<u64, Instant>,
+
+    /// When `Endpoint::on_register` last rejected a `(conn, request_id,
+    /// region)` triple, keyed by that triple. `request_id` alone isn't
+    /// enough: it's the client-supplied `request.request_id`, not a
+    /// store-wide unique id, so two unrelated connections can legitimately
+    /// reuse the same small id for the same region, and must not back off
+    /// each other's registrations. A re-registration of the same triple
+    /// within `config.register_backoff_interval` is rejected immediately,
+    /// with a backoff hint in the error event, before any of the usual admission
+    /// checks or -- critically -- the incremental scan that a register
+    /// which gets this far would otherwise kick off. Entries past the
+    /// backoff window are pruned in `on_timeout` so this doesn't grow
+    /// unbounded; empty, and never written to, while
+    /// `register_backoff_interval` is 0 (the default).
+    recent_register_failures: HashMap<(ConnId, RequestId, u64), Instant>,
+
+    /// Resolved ts last delivered to each downstream, i.e. the minimum
+    /// resolved ts among the regions it's watching. Updated on every
+    /// [`Endpoint::on_min_ts`] tick; see [`Advance::downstream_resolved_ts`]
+    /// and [`CDC_DOWNSTREAM_RESOLVED_TS_LAG`]. Entries are removed as their
+    /// (conn, request) is torn down so this can't grow unbounded.
+    downstream_resolved_ts: HashMap<(ConnId, RequestId), TimeStamp>,
+
+    /// The last key a region's incremental scan had fully sunk to the
+    /// downstream, keyed by the stable `(region_id, conn_id, request_id)`
+    /// identity rather than `DownstreamId` (which is regenerated on every
+    /// register). Consulted by [`Self::on_register`] so a downstream that
+    /// resumes after a transient deregister doesn't pay to rescan keys it
+    /// already received; see [`Task::UpdateScanProgress`]. Entries are
+    /// removed once a scan finishes or the (conn, request) is torn down for
+    /// good.
+    scan_progress: HashMap<(u64, ConnId, RequestId), Key>,
+
+    /// The latest schema version this store has applied, as last reported by
+    /// [`Task::UpdateSchemaVersion`]. Zero until the first report.
+    schema_version: u64,
+
+    /// Regions currently excluded from [`Self::on_min_ts`]'s resolved-ts
+    /// advancement because they blocked it for longer than
+    /// `CdcConfig::resolved_ts_quarantine_timeout`. Cleared by
+    /// [`Task::ReleaseQuarantine`].
+    quarantined_regions: HashSet<u64>,
+
+    /// When each region not yet in [`Self::quarantined_regions`] was first
+    /// seen lagging behind `current_ts` on a [`Self::on_min_ts`] tick, so
+    /// `resolved_ts_quarantine_timeout` is measured from a stable start
+    /// instead of resetting every tick. Cleared once a region catches up,
+    /// is quarantined, or stops being captured.
+    slow_region_since: HashMap<u64, Instant>,
+
+    /// Set by [`Task::Drain`] once a graceful shutdown has started. While
+    /// `true`, [`Self::on_register`] rejects every new registration instead
+    /// of admitting it, so a rolling restart can't hand a downstream a
+    /// changefeed that's about to be cut off mid-batch. Never reset back to
+    /// `false` -- a draining `Endpoint` is on its way out.
+    draining: bool,
+
+    /// Used by [`Self::revalidate_conn_certs`] to re-check already-open
+    /// connections' peer CN against [`security::SecurityConfig::cert_allowed_cn`]
+    /// whenever the serving certificate rotates -- the cert reload
+    /// [`security::SecurityManager::bind`] wires into grpc only takes effect
+    /// on new connections, not ones already established.
+    security_mgr: Arc<SecurityManager>,
+    /// Last observed certificate mtime, consulted by
+    /// [`security::SecurityConfig::is_modified`] in
+    /// [`Self::revalidate_conn_certs`] so that method is a no-op between
+    /// rotations instead of re-checking every connection every
+    /// [`METRICS_FLUSH_INTERVAL`].
+    last_cert_check: Option<SystemTime>,
+}
+
+/// Builds an `on_thread_start` hook that confines the calling thread to
+/// `cpus`, e.g. the CPUs of a single NUMA node, so a runtime's worker threads
+/// don't bounce across sockets. A `cpus` of `[]` is a no-op, leaving
+/// scheduling to the OS; only takes effect on Linux.
+fn pin_to_cpus(pool: &'static str, cpus: Vec<usize>) -> impl Fn() + Send + Sync + 'static {
+    move || {
+        if let Err(e) = tikv_util::sys::thread::set_current_thread_affinity(&cpus) {
+            warn!("cdc failed to set thread affinity";
+                "pool" => pool,
+                "cpus" => ?cpus,
+                "err" => ?e,
+            );
+        }
+    }
 }
 
 impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E, S> {
@@ -510,18 +933,23 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
         security_mgr: Arc<SecurityManager>,
         sink_memory_quota: Arc<MemoryQuota>,
         causal_ts_provider: Option<Arc<CausalTsProviderImpl>>,
+        subscription_registry: CdcSubscriptionRegistry,
+        resource_manager: Option<Arc<ResourceGroupManager>>,
     ) -> Endpoint<T, E, S> {
         let workers = Builder::new_multi_thread()
             .thread_name("cdcwkr")
             .worker_threads(config.incremental_scan_threads)
-            .with_sys_hooks()
+            .with_sys_and_custom_hooks(
+                pin_to_cpus("cdcwkr", config.incremental_scan_worker_cpus.clone()),
+                || {},
+            )
             .build()
             .unwrap();
         let tso_worker = Builder::new_multi_thread()
             .thread_name("tso")
             .worker_threads(config.tso_worker_threads)
             .enable_time()
-            .with_sys_hooks()
+            .with_sys_and_custom_hooks(pin_to_cpus("tso", config.tso_worker_cpus.clone()), || {})
             .build()
             .unwrap();
 
@@ -553,7 +981,7 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
             store_meta.lock().unwrap().store_id(),
             pd_client.clone(),
             env,
-            security_mgr,
+            security_mgr.clone(),
             region_read_progress,
             store_resolver_gc_interval,
         );
@@ -585,16 +1013,31 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
             fetch_speed_limiter,
             max_scan_batch_bytes,
             max_scan_batch_size,
+            max_row_size: config.max_row_size.0 as usize,
             sink_memory_quota,
+            resource_manager,
 
             old_value_cache,
             causal_ts_provider,
 
+            subscription_registry,
+
             current_ts: TimeStamp::zero(),
             min_resolved_ts: TimeStamp::max(),
             min_ts_region_id: 0,
             resolved_region_count: 0,
             unresolved_region_count: 0,
+
+            last_region_error_sent: HashMap::default(),
+            recent_register_failures: HashMap::default(),
+            downstream_resolved_ts: HashMap::default(),
+            scan_progress: HashMap::default(),
+            schema_version: 0,
+            quarantined_regions: HashSet::default(),
+            slow_region_since: HashMap::default(),
+            draining: false,
+            security_mgr,
+            last_cert_check: None,
         };
         ep.register_min_ts_event(leader_resolver, Instant::now());
         ep
@@ -635,6 +1078,37 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
                 Arc::new(Semaphore::new(self.config.incremental_scan_concurrency))
         }
 
+        if change.get("incremental_scan_threads").is_some() {
+            match Builder::new_multi_thread()
+                .thread_name("cdcwkr")
+                .worker_threads(self.config.incremental_scan_threads)
+                .with_sys_and_custom_hooks(
+                    pin_to_cpus("cdcwkr", self.config.incremental_scan_worker_cpus.clone()),
+                    || {},
+                )
+                .build()
+            {
+                Ok(new_workers) => {
+                    // Scan tasks already running hold their own `ScanPoolHandle` clone
+                    // of the old pool (fetched fresh from `self.workers` in
+                    // `Self::on_register` each time a scan is spawned), so swapping
+                    // `self.workers` here only changes where *new* scans land; it
+                    // doesn't interrupt scans already in flight. `shutdown_background`
+                    // lets the old pool's threads finish those in the background
+                    // instead of blocking this task on them.
+                    let old_workers = std::mem::replace(&mut self.workers, new_workers);
+                    old_workers.shutdown_background();
+                    info!("cdc resized incremental scan worker pool";
+                        "incremental_scan_threads" => self.config.incremental_scan_threads);
+                }
+                Err(e) => {
+                    warn!("cdc failed to resize incremental scan worker pool, keeping the old one";
+                        "incremental_scan_threads" => self.config.incremental_scan_threads,
+                        "error" => ?e);
+                }
+            }
+        }
+
         if change.get("sink_memory_quota").is_some() {
             self.sink_memory_quota
                 .set_capacity(self.config.sink_memory_quota.0 as usize);
@@ -659,12 +1133,59 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
 
             self.fetch_speed_limiter.set_speed_limit(new_speed_limit);
         }
+        if change.get("max_row_size").is_some() {
+            self.max_row_size = self.config.max_row_size.0 as usize;
+        }
     }
 
     pub fn set_max_scan_batch_size(&mut self, max_scan_batch_size: usize) {
         self.max_scan_batch_size = max_scan_batch_size;
     }
 
+    /// Builds the snapshot backing [`Validate::EndpointStats`].
+    fn stats(&self) -> EndpointStats {
+        let connections = self
+            .connections
+            .values()
+            .map(|conn| ConnStats {
+                conn_id: conn.get_id(),
+                peer: conn.get_peer().to_owned(),
+                subscription_count: conn.downstreams_count(),
+                unacked_bytes: conn.unacked_bytes(),
+                scan_task_count: conn.scan_task_count(),
+            })
+            .collect();
+        EndpointStats {
+            connections,
+            capture_region_count: self.capture_regions.len(),
+            max_capture_regions: self.config.max_capture_regions,
+            sink_memory_quota_in_use: self.sink_memory_quota.in_use(),
+            sink_memory_quota_capacity: self.sink_memory_quota.capacity(),
+            scan_task_count: self.scan_task_counter.load(Ordering::Relaxed),
+            min_resolved_ts: self.min_resolved_ts,
+            min_resolved_ts_region_id: self.min_ts_region_id,
+        }
+    }
+
+    /// Builds [`HealthReport`]. See its doc comment for why this isn't
+    /// (yet) reachable over the `ChangeData` RPC.
+    fn health_report(&self) -> HealthReport {
+        let min_resolved_ts_lag_millis = if self.min_resolved_ts == TimeStamp::max() {
+            0
+        } else {
+            self.current_ts
+                .physical()
+                .saturating_sub(self.min_resolved_ts.physical())
+        };
+        HealthReport {
+            captured_regions: self.capture_regions.len(),
+            min_resolved_ts_lag_millis,
+            sink_memory_quota_in_use: self.sink_memory_quota.in_use(),
+            sink_memory_quota_capacity: self.sink_memory_quota.capacity(),
+            scan_backlog: self.scan_task_counter.load(Ordering::Relaxed),
+        }
+    }
+
     fn deregister_downstream(
         &mut self,
         region_id: u64,
@@ -678,7 +1199,82 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
         if delegate.get_mut().unsubscribe(downstream_id, err) {
             let observe_id = delegate.get().handle.id;
             delegate.remove();
+            self.subscription_registry.remove_region(region_id);
             self.deregister_observe(region_id, observe_id);
+        } else {
+            self.subscription_registry
+                .remove_downstream(region_id, downstream_id);
+        }
+    }
+
+    /// Computes [`RegionFailoverHint`] for `region_id` from locally known
+    /// region meta and CDC config.
+    fn region_failover_hint(&self, region_id: u64) -> RegionFailoverHint {
+        let alternative_stores = {
+            let meta = self.store_meta.lock().unwrap();
+            let self_store_id = meta.store_id();
+            meta.reader(region_id)
+                .map(|reader| {
+                    reader
+                        .region
+                        .get_peers()
+                        .iter()
+                        .map(|p| p.get_store_id())
+                        .filter(|&store_id| store_id != self_store_id)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        RegionFailoverHint {
+            retry_after: self.config.min_ts_interval.0,
+            alternative_stores,
+        }
+    }
+
+    /// Returns whether a region-error broadcast for `region_id` is allowed
+    /// right now, and if so records that one was just sent.
+    ///
+    /// See [`REGION_ERROR_EVENT_RATE_LIMIT_WINDOW`].
+    fn should_broadcast_region_error(&mut self, region_id: u64) -> bool {
+        let now = Instant::now();
+        let allow = match self.last_region_error_sent.get(&region_id) {
+            Some(last) => {
+                now.saturating_duration_since(*last) >= REGION_ERROR_EVENT_RATE_LIMIT_WINDOW
+            }
+            None => true,
+        };
+        if allow {
+            self.last_region_error_sent.insert(region_id, now);
+        }
+        allow
+    }
+
+    /// Drops the resolved-ts lag tracked for `(conn_id, request_id)`, along
+    /// with its `CDC_DOWNSTREAM_RESOLVED_TS_LAG` series, so a torn-down
+    /// downstream doesn't linger forever in either.
+    fn forget_downstream_lag(&mut self, conn_id: ConnId, request_id: RequestId) {
+        self.downstream_resolved_ts.remove(&(conn_id, request_id));
+        let _ = CDC_DOWNSTREAM_RESOLVED_TS_LAG
+            .remove_label_values(&[&conn_id.id().to_string(), &request_id.0.to_string()]);
+    }
+
+    /// Drops any scan progress stored for `(conn_id, request_id)`, across all
+    /// regions. Called on the same teardowns as [`Self::forget_downstream_lag`]
+    /// (a whole connection or request going away for good): a region- or
+    /// delegate-level deregister leaves the entry in place on purpose, since
+    /// those are exactly the transient cases `Task::UpdateScanProgress` exists
+    /// to survive, and a stale leftover entry is harmless (it's only ever read
+    /// back by a resume under the same `(conn_id, request_id)`, and gets
+    /// cleaned up here once that identity is actually torn down).
+    fn forget_scan_progress(&mut self, conn_id: ConnId, request_id: RequestId) {
+        let keys: Vec<_> = self
+            .scan_progress
+            .keys()
+            .filter(|(_, c, r)| *c == conn_id && *r == request_id)
+            .cloned()
+            .collect();
+        for key in keys {
+            self.scan_progress.remove(&key);
         }
     }
 
@@ -701,6 +1297,16 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
                 conn.iter_downstreams(|_, region_id, downstream_id, _| {
                     self.deregister_downstream(region_id, downstream_id, None);
                 });
+                let request_ids: Vec<RequestId> = self
+                    .downstream_resolved_ts
+                    .keys()
+                    .filter(|(c, _)| *c == conn_id)
+                    .map(|(_, r)| *r)
+                    .collect();
+                for request_id in request_ids {
+                    self.forget_downstream_lag(conn_id, request_id);
+                    self.forget_scan_progress(conn_id, request_id);
+                }
             }
             Deregister::Request {
                 conn_id,
@@ -711,6 +1317,8 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
                     let err = Some(Error::Other("region not found".into()));
                     self.deregister_downstream(region_id, downstream, err);
                 }
+                self.forget_downstream_lag(conn_id, request_id);
+                self.forget_scan_progress(conn_id, request_id);
             }
             Deregister::Region {
                 conn_id,
@@ -729,7 +1337,13 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
                 region_id,
                 downstream_id,
                 err,
+                retryable,
             } => {
+                if err.is_some() {
+                    CDC_DEREGISTER_REASON
+                        .with_label_values(&[if retryable { "retryable" } else { "permanent" }])
+                        .inc();
+                }
                 let conn = match self.connections.get_mut(&conn_id) {
                     Some(conn) => conn,
                     None => return,
@@ -757,22 +1371,196 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
                         x.remove()
                     }
                 };
-                delegate.stop(err);
+                let hint = self.region_failover_hint(region_id);
+                info!("cdc region failover hint";
+                    "region_id" => region_id,
+                    "retry_after" => ?hint.retry_after,
+                    "alternative_stores" => ?hint.alternative_stores);
+                delegate.mark_failed();
+                delegate.stop_observing();
+                if self.should_broadcast_region_error(region_id) {
+                    delegate.broadcast_error(err);
+                } else {
+                    CDC_REGION_ERROR_EVENT_RATE_LIMITED.inc();
+                    debug!("cdc region error rate limited, dropping broadcast";
+                        "region_id" => region_id, "error" => ?err);
+                }
                 for downstream in delegate.downstreams() {
                     let request_id = downstream.req_id;
                     for conn in &mut self.connections.values_mut() {
                         conn.unsubscribe(request_id, region_id);
                     }
                 }
+                self.subscription_registry.remove_region(region_id);
                 self.deregister_observe(region_id, delegate.handle.id);
             }
         }
     }
 
+    fn on_region_buckets(&mut self, region_id: u64, buckets: Arc<BucketMeta>) {
+        if let Some(delegate) = self.capture_regions.get_mut(&region_id) {
+            delegate.on_region_buckets_updated(buckets);
+        }
+    }
+
+    /// Handles [`Task::UpdateScanProgress`]; see its doc comment.
+    fn on_update_scan_progress(
+        &mut self,
+        region_id: u64,
+        conn_id: ConnId,
+        request_id: RequestId,
+        resume_key: Option<Key>,
+    ) {
+        match resume_key {
+            Some(key) => {
+                self.scan_progress.insert((region_id, conn_id, request_id), key);
+            }
+            None => {
+                self.scan_progress.remove(&(region_id, conn_id, request_id));
+            }
+        }
+    }
+
+    /// Handles [`Task::PauseDownstream`]; see its doc comment.
+    fn on_pause_downstream(&mut self, conn_id: ConnId, request_id: RequestId, region_id: u64) {
+        let downstream = match self.find_downstream(conn_id, request_id, region_id) {
+            Some(downstream) => downstream,
+            None => return,
+        };
+        if pause_downstream(&downstream.get_state()) {
+            info!("cdc downstream paused";
+                "region_id" => region_id, "conn_id" => ?conn_id, "request_id" => ?request_id);
+        } else {
+            warn!("cdc downstream pause ignored, not in a pausable state";
+                "region_id" => region_id, "conn_id" => ?conn_id, "request_id" => ?request_id);
+        }
+    }
+
+    /// Handles [`Task::ResumeDownstream`]; see its doc comment.
+    fn on_resume_downstream(&mut self, conn_id: ConnId, request_id: RequestId, region_id: u64) {
+        let downstream = match self.find_downstream(conn_id, request_id, region_id) {
+            Some(downstream) => downstream,
+            None => return,
+        };
+        if resume_downstream(&downstream.get_state()) {
+            info!("cdc downstream resumed";
+                "region_id" => region_id, "conn_id" => ?conn_id, "request_id" => ?request_id);
+        } else {
+            warn!("cdc downstream resume ignored, not paused";
+                "region_id" => region_id, "conn_id" => ?conn_id, "request_id" => ?request_id);
+        }
+    }
+
+    /// Handles [`Task::Ack`]; see its doc comment.
+    fn on_ack(&mut self, conn_id: ConnId, bytes: usize) {
+        let conn = match self.connections.get(&conn_id) {
+            Some(conn) => conn,
+            None => return,
+        };
+        conn.ack_bytes(bytes);
+
+        // Un-pause any downstream that `Delegate::on_min_ts` paused for this
+        // connection, now that the ack brought its unacked window back
+        // under the limit. A limit of 0 means the backpressure is disabled,
+        // so it can't have paused anything.
+        let unacked_bytes_limit = self.config.unacked_bytes_limit.0 as usize;
+        if unacked_bytes_limit > 0 && conn.unacked_bytes() <= unacked_bytes_limit {
+            conn.iter_downstreams(|request_id, region_id, _downstream_id, state| {
+                if resume_downstream(state) {
+                    info!("cdc downstream resumed: unacked bytes back under limit";
+                        "region_id" => region_id, "conn_id" => ?conn_id,
+                        "request_id" => ?request_id, "unacked_bytes" => conn.unacked_bytes(),
+                        "limit" => unacked_bytes_limit);
+                }
+            });
+        }
+    }
+
+    /// Handles [`Task::UpdateSchemaVersion`]; see its doc comment.
+    fn on_update_schema_version(&mut self, schema_version: u64) {
+        if schema_version < self.schema_version {
+            warn!("cdc schema version went backwards, ignoring";
+                "current" => self.schema_version, "reported" => schema_version);
+            return;
+        }
+        self.schema_version = schema_version;
+    }
+
+    /// Handles [`Task::ReleaseQuarantine`]; see its doc comment.
+    fn on_release_quarantine(&mut self, region_id: u64) {
+        if self.quarantined_regions.remove(&region_id) {
+            info!("cdc region released from quarantine"; "region_id" => region_id);
+        }
+        self.slow_region_since.remove(&region_id);
+    }
+
+    /// Handles [`Task::Drain`]; see its doc comment.
+    fn on_drain(&mut self, callback: InitCallback) {
+        info!("cdc starts draining"; "capture_region_count" => self.capture_regions.len());
+        self.draining = true;
+        // Re-run the same resolved-ts advance `Self::on_min_ts` would do on
+        // its next tick, just triggered immediately instead of waiting for
+        // it: any events already queued ahead of this task on a
+        // downstream's sink were enqueued by tasks processed before this
+        // one, so by the time this runs they're already flushed, and this
+        // only needs to cover them with one final resolved ts. Reuses
+        // `self.current_ts` -- the last min ts this store already advanced
+        // to -- rather than fetching a fresh one from PD, since draining
+        // just needs to cover what's already been sent, not wait on a new
+        // round trip.
+        let regions: Vec<u64> = self.capture_regions.keys().copied().collect();
+        if !regions.is_empty() {
+            let current_ts = self.current_ts;
+            self.on_min_ts(regions, current_ts, current_ts);
+        }
+        callback();
+    }
+
+    /// Looks up the live [`Downstream`] registered under `(conn_id,
+    /// request_id, region_id)`, the same identity `Deregister::Downstream`
+    /// uses, going through `Conn::get_downstream` first so a stale
+    /// `downstream_id` from a torn-down connection can't be confused with a
+    /// live one.
+    fn find_downstream(
+        &self,
+        conn_id: ConnId,
+        request_id: RequestId,
+        region_id: u64,
+    ) -> Option<&Downstream> {
+        let downstream_id = self
+            .connections
+            .get(&conn_id)?
+            .get_downstream(request_id, region_id)?;
+        self.capture_regions
+            .get(&region_id)?
+            .downstream(downstream_id)
+    }
+
+    /// Handle [`Task::Snapshot`] by building the equivalent
+    /// [`ChangeDataRequest`] and handing it to [`Self::on_register`], so a
+    /// snapshot-bootstrapped downstream goes through exactly the same
+    /// incremental-scan-then-delta path as a normal registration.
+    pub fn on_snapshot(&mut self, region_id: u64, snapshot_ts: TimeStamp, downstream: Downstream) {
+        let mut request = ChangeDataRequest::default();
+        request.region_id = region_id;
+        request.request_id = downstream.req_id.0;
+        request.checkpoint_ts = snapshot_ts.into_inner();
+        request.set_region_epoch(downstream.region_epoch.clone());
+        request.kv_api = downstream.kv_api;
+        self.on_register(request, downstream);
+    }
+
+    #[instrument(skip_all, fields(
+        region_id = request.region_id,
+        conn_id = ?downstream.conn_id,
+        downstream_id = ?downstream.id,
+    ))]
     pub fn on_register(&mut self, mut request: ChangeDataRequest, mut downstream: Downstream) {
         let kv_api = request.get_kv_api();
         let api_version = self.api_version;
-        let filter_loop = downstream.filter_loop;
+        let txn_source_filter = downstream.txn_source_filter;
+        let snapshot_only = downstream.snapshot_only;
+        let resource_group_name = downstream.resource_group_name.clone();
 
         let region_id = request.region_id;
         let request_id = RequestId(request.request_id);
@@ -796,6 +1584,44 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
         };
         downstream.set_sink(conn.get_sink().clone());
 
+        let register_backoff_interval = self.config.register_backoff_interval.0;
+        if !register_backoff_interval.is_zero() {
+            if let Some(last_failure) = self
+                .recent_register_failures
+                .get(&(conn_id, request_id, region_id))
+            {
+                let elapsed = Instant::now().saturating_duration_since(*last_failure);
+                if elapsed < register_backoff_interval {
+                    let retry_after = register_backoff_interval - elapsed;
+                    debug!("cdc rejects registration, recently failed and still backing off";
+                        "region_id" => region_id,
+                        "conn_id" => ?conn_id,
+                        "req_id" => ?request_id,
+                        "retry_after" => ?retry_after);
+                    // Same gap as the admission checks below: no structured
+                    // field exists in kvproto to carry `retry_after`, so it
+                    // rides along in the free-text reason.
+                    let _ = downstream.sink_server_is_busy(
+                        region_id,
+                        format!(
+                            "registration recently failed, retry after {:?}",
+                            retry_after
+                        ),
+                    );
+                    return;
+                }
+            }
+        }
+
+        if self.draining {
+            debug!("cdc rejects registration, endpoint is draining";
+                "region_id" => region_id,
+                "conn_id" => ?conn_id,
+                "req_id" => ?request_id);
+            let _ = downstream.sink_server_is_busy(region_id, "cdc endpoint is draining".to_owned());
+            return;
+        }
+
         // Check if the cluster id matches if supported.
         if conn.features().contains(FeatureGate::VALIDATE_CLUSTER_ID) {
             let request_cluster_id = request.get_header().get_cluster_id();
@@ -822,10 +1648,53 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
             return;
         }
 
+        if conn.get_sink().is_congested() {
+            debug!("cdc rejects registration, connection memory quota exceeded";
+                "region_id" => region_id,
+                "conn_id" => ?conn_id,
+                "req_id" => ?request_id);
+            // Same reasoning as the incremental-scan-concurrency check below:
+            // admitting the scan would just queue more events behind a
+            // connection that's already unable to sink any, making things
+            // worse for every other downstream sharing it.
+            let _ = downstream
+                .sink_server_is_busy(region_id, "connection memory quota exceeded".to_owned());
+            return;
+        }
+
+        let conn_scan_task_count = conn.scan_task_count_handle();
+        let per_conn_limit = self.config.incremental_scan_concurrency_limit_per_conn as isize;
+        if per_conn_limit > 0 && conn_scan_task_count.load(Ordering::Relaxed) >= per_conn_limit {
+            debug!("cdc rejects registration, too many scan tasks on this connection";
+                "region_id" => region_id,
+                "conn_id" => ?conn_id,
+                "req_id" => ?request_id,
+                "scan_task_count" => conn_scan_task_count.load(Ordering::Relaxed),
+                "incremental_scan_concurrency_limit_per_conn" => per_conn_limit,
+            );
+            // Reject just this connection rather than falling through to the
+            // store-wide check below, which would also reject every other,
+            // well-behaved connection once the store-wide count is exhausted.
+            let _ = downstream.sink_server_is_busy(
+                region_id,
+                "too many pending incremental scans on this connection".to_owned(),
+            );
+            return;
+        }
+
         let scan_task_counter = self.scan_task_counter.clone();
         let scan_task_count = scan_task_counter.fetch_add(1, Ordering::Relaxed);
+        conn_scan_task_count.fetch_add(1, Ordering::Relaxed);
+        let conn_id_label = conn_id.id().to_string();
+        CDC_SCAN_TASKS_PER_CONN
+            .with_label_values(&[&conn_id_label])
+            .inc();
         let release_scan_task_counter = tikv_util::DeferContext::new(move || {
             scan_task_counter.fetch_sub(1, Ordering::Relaxed);
+            conn_scan_task_count.fetch_sub(1, Ordering::Relaxed);
+            CDC_SCAN_TASKS_PER_CONN
+                .with_label_values(&[&conn_id_label])
+                .dec();
         });
         if scan_task_count >= self.config.incremental_scan_concurrency_limit as isize {
             debug!("cdc rejects registration, too many scan tasks";
@@ -847,10 +1716,21 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
             None => {
                 error!("cdc register for a not found region"; "region_id" => region_id);
                 let _ = downstream.sink_region_not_found(region_id);
+                if !register_backoff_interval.is_zero() {
+                    self.recent_register_failures
+                        .insert((conn_id, request_id, region_id), Instant::now());
+                }
                 return;
             }
         };
 
+        // Note: this only rejects *concurrent* duplicate requests. If the
+        // downstream previously registered under this `(conn_id, request_id)`
+        // was already removed (e.g. it hit a transient, retryable sink error,
+        // see `is_retryable_sink_error`), `conn.subscribe` below finds nothing
+        // and this register is treated as a resume: the region's `Delegate`
+        // and its `ObserveId` are reused via the `Occupied` branch further
+        // down, so the client doesn't pay for a full rescan of the region.
         if conn
             .subscribe(request_id, region_id, downstream_id, downstream_state)
             .is_some()
@@ -865,6 +1745,42 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
                 "conn_id" => ?conn_id,
                 "req_id" => ?request_id,
                 "downstream_id" => ?downstream_id);
+            if !register_backoff_interval.is_zero() {
+                self.recent_register_failures
+                    .insert((conn_id, request_id, region_id), Instant::now());
+            }
+            return;
+        }
+
+        let capture_region_count = self.capture_regions.len();
+        let max_capture_regions = self.config.max_capture_regions;
+        if max_capture_regions > 0
+            && capture_region_count >= max_capture_regions
+            && !self.capture_regions.contains_key(&region_id)
+        {
+            debug!("cdc rejects registration, too many captured regions";
+                "region_id" => region_id,
+                "conn_id" => ?conn_id,
+                "req_id" => ?request_id,
+                "capture_region_count" => capture_region_count,
+                "max_capture_regions" => max_capture_regions,
+            );
+            // No structured "capacity exceeded" EventError variant exists in
+            // kvproto to carry the current/max counts on the wire, so this
+            // reuses the existing free-text server-busy notice (same as the
+            // scan-concurrency and connection-quota admission checks above);
+            // a real structured error needs a kvproto change.
+            let _ = downstream.sink_server_is_busy(
+                region_id,
+                format!(
+                    "too many captured regions on this store: {}/{}",
+                    capture_region_count, max_capture_regions
+                ),
+            );
+            if !register_backoff_interval.is_zero() {
+                self.recent_register_failures
+                    .insert((conn_id, request_id, region_id), Instant::now());
+            }
             return;
         }
 
@@ -900,6 +1816,10 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
             if is_new_delegate {
                 self.capture_regions.remove(&region_id);
             }
+            if !register_backoff_interval.is_zero() {
+                self.recent_register_failures
+                    .insert((conn_id, request_id, region_id), Instant::now());
+            }
             return;
         }
         if is_new_delegate {
@@ -915,6 +1835,26 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
             );
         };
 
+        self.subscription_registry.upsert_downstream(
+            region_id,
+            observe_id,
+            request.checkpoint_ts.into(),
+            DownstreamSubscription {
+                conn_id,
+                request_id,
+                downstream_id,
+            },
+        );
+
+        // If this is a resume under a previously-used `(conn_id, request_id)`
+        // (see the comment on `conn.subscribe` above), pick up where that
+        // scan left off instead of rescanning from the observed range's
+        // start; see `Initializer::resume_key` and `Task::UpdateScanProgress`.
+        let resume_key = self
+            .scan_progress
+            .get(&(region_id, conn_id, request_id))
+            .cloned();
+
         let mut init = Initializer {
             region_id,
             conn_id,
@@ -927,6 +1867,7 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
             observe_handle: delegate.handle.clone(),
             downstream_id,
             downstream_state,
+            resume_key,
 
             tablet: self.tablets.get(region_id).map(|t| t.into_owned()),
             sched,
@@ -937,18 +1878,49 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
             fetch_speed_limiter: self.fetch_speed_limiter.clone(),
             max_scan_batch_bytes: self.max_scan_batch_bytes,
             max_scan_batch_size: self.max_scan_batch_size,
+            max_row_size: self.max_row_size,
 
             ts_filter_ratio: self.config.incremental_scan_ts_filter_ratio,
             kv_api,
-            filter_loop,
+            txn_source_filter,
+            snapshot_only,
+            resource_group_name: resource_group_name.clone(),
+            fill_cache: self.config.incremental_scan_fill_cache,
         };
 
         let cdc_handle = self.cdc_handle.clone();
-        self.workers.spawn(async move {
+        let scheduler = self.scheduler.clone();
+        // Background scan task, same convention `backup::Endpoint` uses for its
+        // scans: if there's no user-defined resource group, or resource control
+        // is disabled, this is `None` and the scan runs unthrottled.
+        let resource_limiter = self.resource_manager.as_ref().and_then(|m| {
+            m.get_background_resource_limiter(&resource_group_name, "cdc_incremental_scan")
+        });
+        // Root a fresh trace here: this spawned task is where registration hands off
+        // to the (much slower) incremental scan, so it's the span that quantifies
+        // subscription startup time end to end.
+        self.workers.spawn(root!("cdc_register_scan"; async move {
             CDC_SCAN_TASKS.with_label_values(&["total"]).inc();
-            match init.initialize(cdc_handle).await {
+            match with_resource_limiter(init.initialize(cdc_handle), resource_limiter).await {
                 Ok(()) => {
                     CDC_SCAN_TASKS.with_label_values(&["finish"]).inc();
+                    if init.snapshot_only {
+                        // The completion event was already sunk by the scan
+                        // itself; deregistering with `err: None` just drops
+                        // this downstream without sending an error event on
+                        // top of it. See `Downstream::snapshot_only`.
+                        if let Err(e) = scheduler.schedule(Task::Deregister(Deregister::Downstream {
+                            conn_id: init.conn_id,
+                            request_id: init.request_id,
+                            region_id,
+                            downstream_id: init.downstream_id,
+                            err: None,
+                            retryable: false,
+                        })) {
+                            error!("cdc failed to deregister snapshot-only downstream";
+                                "region_id" => region_id, "error" => ?e);
+                        }
+                    }
                 }
                 Err(e) => {
                     CDC_SCAN_TASKS.with_label_values(&["abort"]).inc();
@@ -960,15 +1932,21 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
                 }
             }
             drop(release_scan_task_counter);
-        });
+        }; region_id));
     }
 
-    pub fn on_multi_batch(&mut self, multi: Vec<CmdBatch>, old_value_cb: OldValueCallback) {
+    pub fn on_multi_batch(&mut self, multi: Vec<CmdBatch>, old_value_resolver: OldValueResolver) {
         fail_point!("cdc_before_handle_multi_batch", |_| {});
         let mut statistics = Statistics::default();
+        let scan_pool_handle = self.workers.handle().clone();
+        // One budget for the whole task: every region's old-value lookups in
+        // this `MultiBatch` draw from the same pool, so a single noisy
+        // region can't duck the limit just by being processed first.
+        let mut old_value_budget = OldValueBudget::default();
         for batch in multi {
             let region_id = batch.region_id;
             let mut deregister = None;
+            let mut retryable_failures = Vec::new();
             if let Some(delegate) = self.capture_regions.get_mut(&region_id) {
                 if delegate.has_failed() {
                     // Skip the batch if the delegate has failed.
@@ -976,9 +1954,12 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
                 }
                 if let Err(e) = delegate.on_batch(
                     batch,
-                    &old_value_cb,
+                    &old_value_resolver,
+                    &scan_pool_handle,
                     &mut self.old_value_cache,
+                    &mut old_value_budget,
                     &mut statistics,
+                    &mut retryable_failures,
                 ) {
                     delegate.mark_failed();
                     // Delegate has error, deregister the delegate.
@@ -989,6 +1970,23 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
                     });
                 }
             }
+            // Downstreams that merely hit a transient, retryable sink error
+            // (e.g. the connection is congested) are dropped on their own,
+            // instead of failing the whole delegate: the region keeps being
+            // observed under the same ObserveId, so the client can resume
+            // the downstream in place -- by reconnecting with the same
+            // RequestId -- without a full rescan of the region.
+            for (conn_id, request_id, downstream_id, err) in retryable_failures {
+                let retryable = err.is_retryable();
+                self.on_deregister(Deregister::Downstream {
+                    conn_id,
+                    request_id,
+                    region_id,
+                    downstream_id,
+                    err: Some(err),
+                    retryable,
+                });
+            }
             if let Some(deregister) = deregister {
                 self.on_deregister(deregister);
             }
@@ -1014,6 +2012,7 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
                                 request_id: downstream.req_id,
                                 region_id,
                                 downstream_id: downstream.id,
+                                retryable: e.is_retryable(),
                                 err: Some(e),
                             });
                         }
@@ -1045,19 +2044,77 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta> Endpoint<T, E,
         self.current_ts = current_ts;
         self.min_resolved_ts = current_ts;
 
+        let unacked_bytes_limit = self.config.unacked_bytes_limit.0 as usize;
+        let quarantine_timeout = self.config.resolved_ts_quarantine_timeout.0;
         let mut advance = Advance::default();
         for region_id in regions {
-            if let Some(d) = self.capture_regions.get_mut(&region_id) {
-                d.on_min_ts(min_ts, current_ts, &self.connections, &mut advance);
+            if self.quarantined_regions.contains(&region_id) {
+                continue;
+            }
+
+            let lag = if let Some(d) = self.capture_regions.get_mut(&region_id) {
+                let lag = d.on_min_ts(
+                    min_ts,
+                    current_ts,
+                    &self.connections,
+                    unacked_bytes_limit,
+                    &mut advance,
+                );
+                self.subscription_registry.advance_checkpoint(region_id, min_ts);
+                Some(lag)
+            } else {
+                None
+            };
+
+            if quarantine_timeout.is_zero() {
+                continue;
+            }
+            match lag {
+                Some(lag) if !lag.is_zero() => {
+                    let since = *self
+                        .slow_region_since
+                        .entry(region_id)
+                        .or_insert_with(Instant::now);
+                    if since.saturating_elapsed() >= quarantine_timeout {
+                        self.slow_region_since.remove(&region_id);
+                        self.quarantined_regions.insert(region_id);
+                        if let Some(d) = self.capture_regions.get(&region_id) {
+                            d.notify_quarantined(
+                                "region has blocked resolved ts advancement for too long",
+                            );
+                        }
+                        warn!("cdc region quarantined for blocking resolved ts advancement";
+                            "region_id" => region_id, "lag" => ?lag,
+                            "timeout" => ?quarantine_timeout);
+                    }
+                }
+                _ => {
+                    self.slow_region_since.remove(&region_id);
+                }
             }
         }
 
         self.resolved_region_count = advance.scan_finished;
         self.unresolved_region_count = advance.blocked_on_scan;
+        advance.log_region_stats();
         let (rid, ts) = advance.emit_resolved_ts(&self.connections);
         if rid > 0 {
             self.min_resolved_ts = ts;
             self.min_ts_region_id = rid;
+            // `cdcpb::ResolvedTs` can't carry this watermark yet (see
+            // `Task::UpdateSchemaVersion`'s doc comment), so it's logged here
+            // instead of attached to the event actually sent above.
+            debug!("cdc resolved ts advanced";
+                "region_id" => rid, "resolved_ts" => ts.into_inner(),
+                "schema_version" => self.schema_version);
+        }
+
+        for ((conn_id, req_id), resolved_ts) in advance.downstream_resolved_ts {
+            let lag = current_ts.physical().saturating_sub(resolved_ts.physical());
+            CDC_DOWNSTREAM_RESOLVED_TS_LAG
+                .with_label_values(&[&conn_id.id().to_string(), &req_id.0.to_string()])
+                .set(lag as i64);
+            self.downstream_resolved_ts.insert((conn_id, req_id), resolved_ts);
         }
     }
 
@@ -1191,6 +2248,11 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta + Send> Runnable
                 request,
                 downstream,
             } => self.on_register(request, downstream),
+            Task::Snapshot {
+                region_id,
+                snapshot_ts,
+                downstream,
+            } => self.on_snapshot(region_id, snapshot_ts, downstream),
             Task::FinishScanLocks {
                 observe_id,
                 region,
@@ -1199,8 +2261,8 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta + Send> Runnable
             Task::Deregister(deregister) => self.on_deregister(deregister),
             Task::MultiBatch {
                 multi,
-                old_value_cb,
-            } => self.on_multi_batch(multi, old_value_cb),
+                old_value_resolver,
+            } => self.on_multi_batch(multi, old_value_resolver),
             Task::OpenConn { conn } => self.on_open_conn(conn),
             Task::SetConnVersion {
                 conn_id,
@@ -1223,6 +2285,13 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta + Send> Runnable
                 incremental_scan_barrier,
                 cb,
             } => {
+                let _span = tracing::info_span!(
+                    "cdc_init_downstream",
+                    region_id,
+                    observe_id = ?observe_id,
+                    downstream_id = ?downstream_id,
+                )
+                .entered();
                 match self.capture_regions.get_mut(&region_id) {
                     Some(delegate) if delegate.handle.id == observe_id => {
                         if delegate.init_lock_tracker() {
@@ -1253,6 +2322,9 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta + Send> Runnable
                 cb();
             }
             Task::TxnExtra(txn_extra) => {
+                for (k, reason) in txn_extra.rollback_reasons {
+                    debug!("cdc observed a rollback"; "key" => %k, "reason" => ?reason);
+                }
                 for (k, v) in txn_extra.old_values {
                     self.old_value_cache.insert(k, v);
                 }
@@ -1264,18 +2336,59 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta + Send> Runnable
                 Validate::OldValueCache(validate) => {
                     validate(&self.old_value_cache);
                 }
+                Validate::OldValueCacheStats(top_n, validate) => {
+                    validate(self.old_value_cache.stats(top_n));
+                }
+                Validate::DownstreamLag(validate) => {
+                    validate(&self.downstream_resolved_ts);
+                }
+                Validate::EndpointStats(validate) => {
+                    validate(self.stats());
+                }
+                Validate::RegionThroughput(region_id, validate) => {
+                    validate(self.capture_regions.get(&region_id).map(Delegate::throughput));
+                }
+                Validate::HealthReport(validate) => {
+                    validate(self.health_report());
+                }
             },
             Task::ChangeConfig(change) => self.on_change_cfg(change),
-        }
-    }
-}
-
-impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta + Send> RunnableWithTimer
+            Task::RegionBuckets { region_id, buckets } => {
+                self.on_region_buckets(region_id, buckets)
+            }
+            Task::UpdateScanProgress {
+                region_id,
+                conn_id,
+                request_id,
+                resume_key,
+            } => self.on_update_scan_progress(region_id, conn_id, request_id, resume_key),
+            Task::PauseDownstream {
+                conn_id,
+                request_id,
+                region_id,
+            } => self.on_pause_downstream(conn_id, request_id, region_id),
+            Task::ResumeDownstream {
+                conn_id,
+                request_id,
+                region_id,
+            } => self.on_resume_downstream(conn_id, request_id, region_id),
+            Task::Ack { conn_id, bytes } => self.on_ack(conn_id, bytes),
+            Task::UpdateSchemaVersion { schema_version } => {
+                self.on_update_schema_version(schema_version)
+            }
+            Task::ReleaseQuarantine { region_id } => self.on_release_quarantine(region_id),
+            Task::Drain(callback) => self.on_drain(callback),
+        }
+    }
+}
+
+impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta + Send> RunnableWithTimer
     for Endpoint<T, E, S>
 {
     fn on_timeout(&mut self) {
         CDC_ENDPOINT_PENDING_TASKS.set(self.scheduler.pending_tasks() as _);
         CDC_CAPTURED_REGION_COUNT.set(self.capture_regions.len() as i64);
+        CDC_CAPTURED_REGION_LIMIT.set(self.config.max_capture_regions as i64);
         CDC_REGION_RESOLVE_STATUS_GAUGE_VEC
             .with_label_values(&["unresolved"])
             .set(self.unresolved_region_count as _);
@@ -1304,6 +2417,111 @@ impl<T: 'static + CdcHandle<E>, E: KvEngine, S: StoreRegionMeta + Send> Runnable
 
         self.old_value_cache.flush_metrics();
         CDC_SINK_BYTES.set(self.sink_memory_quota.in_use() as i64);
+
+        for delegate in self.capture_regions.values_mut() {
+            let (events, bytes) = delegate.flush_throughput();
+            CDC_REGION_THROUGHPUT_EVENTS_HISTOGRAM.observe(events as f64);
+            CDC_REGION_THROUGHPUT_BYTES_HISTOGRAM.observe(bytes as f64);
+        }
+
+        // Regions that are no longer captured and haven't broadcast an error
+        // recently can't still be flapping; drop their rate-limiter entries
+        // so this map doesn't grow unbounded over the store's lifetime.
+        let capture_regions = &self.capture_regions;
+        self.last_region_error_sent.retain(|region_id, last| {
+            capture_regions.contains_key(region_id)
+                || Instant::now().saturating_duration_since(*last)
+                    < REGION_ERROR_EVENT_RATE_LIMIT_WINDOW * 2
+        });
+
+        // Entries past the backoff window can't affect any future
+        // registration, so they're pure garbage; drop them so this map
+        // doesn't grow unbounded over the store's lifetime.
+        let register_backoff_interval = self.config.register_backoff_interval.0;
+        self.recent_register_failures.retain(|_, last| {
+            Instant::now().saturating_duration_since(*last) < register_backoff_interval
+        });
+
+        // Regions no longer captured can't still be lagging; drop their
+        // quarantine-candidate entries so this map doesn't grow unbounded.
+        let capture_regions = &self.capture_regions;
+        self.slow_region_since
+            .retain(|region_id, _| capture_regions.contains_key(region_id));
+
+        self.revalidate_conn_certs();
+    }
+
+    /// Re-checks every open connection's peer CN against
+    /// [`security::SecurityConfig::cert_allowed_cn`] whenever the serving
+    /// certificate has rotated since the last tick. `SecurityManager::bind`'s
+    /// [`grpcio::ServerCredentialsFetcher`] already reloads the cert for new
+    /// connections, and its [`grpcio::ServerChecker`] already validates a
+    /// connection's CN once at accept time -- but neither ever revisits a
+    /// connection that's already streaming, so a CN that's since fallen out
+    /// of `cert_allowed_cn` (or a CA rotation that implicitly revokes it)
+    /// would otherwise go unnoticed until the client disconnects on its own.
+    fn revalidate_conn_certs(&mut self) {
+        let cert_allowed_cn = &self.security_mgr.get_config().cert_allowed_cn;
+        if cert_allowed_cn.is_empty() {
+            return;
+        }
+        match self
+            .security_mgr
+            .get_config()
+            .is_modified(&mut self.last_cert_check)
+        {
+            Ok(false) => return,
+            Ok(true) => {}
+            Err(e) => {
+                warn!("cdc failed to check certificate modification time"; "error" => ?e);
+                return;
+            }
+        }
+        let stale: Vec<ConnId> = self
+            .connections
+            .iter()
+            .filter(|(_, conn)| match conn.peer_cn() {
+                Some(cn) => !match_peer_names(cert_allowed_cn, cn),
+                None => false,
+            })
+            .map(|(conn_id, _)| *conn_id)
+            .collect();
+        for conn_id in stale {
+            self.close_conn_for_cert_rotation(conn_id);
+        }
+    }
+
+    /// Sinks a dedicated error event -- reusing `ErrorServerIsBusy`'s free-text
+    /// `reason`, the same way [`Self::on_register`] already does for e.g. "cdc
+    /// endpoint is draining"; `cdcpb::Error` has no variant of its own for
+    /// "certificate no longer trusted" -- to every downstream on `conn_id`,
+    /// then tears the connection down the normal way, instead of just
+    /// dropping it and leaving the client to read a bare stream reset.
+    fn close_conn_for_cert_rotation(&mut self, conn_id: ConnId) {
+        let conn = match self.connections.get(&conn_id) {
+            Some(conn) => conn,
+            None => return,
+        };
+        warn!("cdc closing connection whose certificate is no longer trusted";
+            "conn_id" => ?conn_id, "peer" => conn.get_peer());
+        let mut downstreams: Vec<(u64, DownstreamId)> = Vec::new();
+        conn.iter_downstreams(|_, region_id, downstream_id, _| {
+            downstreams.push((region_id, downstream_id));
+        });
+        for (region_id, downstream_id) in downstreams {
+            if let Some(downstream) = self
+                .capture_regions
+                .get(&region_id)
+                .and_then(|delegate| delegate.downstream(downstream_id))
+            {
+                let _ = downstream.sink_server_is_busy(
+                    region_id,
+                    "certificate no longer trusted, reconnect with a valid client certificate"
+                        .to_owned(),
+                );
+            }
+        }
+        self.on_deregister(Deregister::Conn(conn_id));
     }
 
     fn get_interval(&self) -> Duration {
@@ -1332,165 +2550,24 @@ impl TxnExtraScheduler for CdcTxnExtraScheduler {
 
 #[cfg(test)]
 mod tests {
-    use std::ops::{Deref, DerefMut};
-
-    use engine_rocks::RocksEngine;
     use futures::executor::block_on;
     use kvproto::{
         cdcpb::{ChangeDataRequestKvApi, Header},
         errorpb::Error as ErrorHeader,
     };
-    use raftstore::{
-        errors::{DiscardReason, Error as RaftStoreError},
-        router::{CdcRaftRouter, RaftStoreRouter},
-        store::{fsm::StoreMeta, msg::CasualMessage, PeerMsg, ReadDelegate},
-    };
-    use test_pd_client::TestPdClient;
-    use test_raftstore::MockRaftStoreRouter;
-    use tikv::{
-        server::DEFAULT_CLUSTER_ID,
-        storage::{kv::Engine, TestEngineBuilder},
-    };
-    use tikv_util::{
-        config::{ReadableDuration, ReadableSize},
-        worker::{dummy_scheduler, ReceiverWrapper},
-    };
+    use tikv_util::config::{ReadableDuration, ReadableSize};
 
     use super::*;
     use crate::{
         channel,
         delegate::{post_init_downstream, ObservedRange},
         recv_timeout,
+        test_support::{
+            mock_endpoint, mock_endpoint_with_ts_provider, set_conn_version_task,
+            TestEndpointSuite,
+        },
     };
 
-    fn set_conn_version_task(conn_id: ConnId, version: semver::Version) -> Task {
-        Task::SetConnVersion {
-            conn_id,
-            version,
-            explicit_features: vec![],
-        }
-    }
-
-    struct TestEndpointSuite {
-        // The order must ensure `endpoint` be dropped before other fields.
-        endpoint: Endpoint<CdcRaftRouter<MockRaftStoreRouter>, RocksEngine, StoreMeta>,
-        cdc_handle: CdcRaftRouter<MockRaftStoreRouter>,
-        task_rx: ReceiverWrapper<Task>,
-        raft_rxs: HashMap<u64, tikv_util::mpsc::Receiver<PeerMsg<RocksEngine>>>,
-        leader_resolver: Option<LeadershipResolver>,
-    }
-
-    impl TestEndpointSuite {
-        // It's important to matain raft receivers in `raft_rxs`, otherwise all cases
-        // need to drop `endpoint` and `rx` in order manually.
-        fn add_region(&mut self, region_id: u64, cap: usize) {
-            let rx = self.cdc_handle.add_region(region_id, cap);
-            self.raft_rxs.insert(region_id, rx);
-            self.add_local_reader(region_id);
-        }
-
-        fn add_local_reader(&self, region_id: u64) {
-            self.store_meta
-                .lock()
-                .unwrap()
-                .readers
-                .insert(region_id, ReadDelegate::mock(region_id));
-        }
-
-        fn fill_raft_rx(&self, region_id: u64) {
-            let router = &self.cdc_handle;
-            loop {
-                match router.send_casual_msg(region_id, CasualMessage::ClearRegionSize) {
-                    Ok(_) => continue,
-                    Err(RaftStoreError::Transport(DiscardReason::Full)) => break,
-                    _ => unreachable!(),
-                }
-            }
-        }
-
-        fn raft_rx(&self, region_id: u64) -> &tikv_util::mpsc::Receiver<PeerMsg<RocksEngine>> {
-            self.raft_rxs.get(&region_id).unwrap()
-        }
-    }
-
-    impl Deref for TestEndpointSuite {
-        type Target = Endpoint<CdcRaftRouter<MockRaftStoreRouter>, RocksEngine, StoreMeta>;
-        fn deref(&self) -> &Self::Target {
-            &self.endpoint
-        }
-    }
-
-    impl DerefMut for TestEndpointSuite {
-        fn deref_mut(&mut self) -> &mut Self::Target {
-            &mut self.endpoint
-        }
-    }
-
-    fn mock_endpoint(
-        cfg: &CdcConfig,
-        engine: Option<RocksEngine>,
-        api_version: ApiVersion,
-    ) -> TestEndpointSuite {
-        mock_endpoint_with_ts_provider(cfg, engine, api_version, None)
-    }
-
-    fn mock_endpoint_with_ts_provider(
-        cfg: &CdcConfig,
-        engine: Option<RocksEngine>,
-        api_version: ApiVersion,
-        causal_ts_provider: Option<Arc<CausalTsProviderImpl>>,
-    ) -> TestEndpointSuite {
-        let (task_sched, task_rx) = dummy_scheduler();
-        let cdc_handle = CdcRaftRouter(MockRaftStoreRouter::new());
-        let mut store_meta = StoreMeta::new(0);
-        store_meta.store_id = Some(1);
-        let region_read_progress = store_meta.region_read_progress.clone();
-        let pd_client = Arc::new(TestPdClient::new(0, true));
-        let env = Arc::new(Environment::new(1));
-        let security_mgr = Arc::new(SecurityManager::default());
-        let store_resolver_gc_interval = Duration::from_secs(60);
-        let leader_resolver = LeadershipResolver::new(
-            1,
-            pd_client.clone(),
-            env.clone(),
-            security_mgr.clone(),
-            region_read_progress,
-            store_resolver_gc_interval,
-        );
-        let ep = Endpoint::new(
-            DEFAULT_CLUSTER_ID,
-            cfg,
-            &ResolvedTsConfig::default(),
-            false,
-            api_version,
-            pd_client,
-            task_sched.clone(),
-            cdc_handle.clone(),
-            LocalTablets::Singleton(engine.unwrap_or_else(|| {
-                TestEngineBuilder::new()
-                    .build_without_cache()
-                    .unwrap()
-                    .kv_engine()
-                    .unwrap()
-            })),
-            CdcObserver::new(task_sched),
-            Arc::new(StdMutex::new(store_meta)),
-            ConcurrencyManager::new(1.into()),
-            env,
-            security_mgr,
-            Arc::new(MemoryQuota::new(usize::MAX)),
-            causal_ts_provider,
-        );
-
-        TestEndpointSuite {
-            endpoint: ep,
-            cdc_handle,
-            task_rx,
-            raft_rxs: HashMap::default(),
-            leader_resolver: Some(leader_resolver),
-        }
-    }
-
     #[test]
     fn test_api_version_check() {
         let mut cfg = CdcConfig::default();
@@ -1500,10 +2577,10 @@ mod tests {
         let mut suite = mock_endpoint(&cfg, None, ApiVersion::V1);
         suite.add_region(1, 100);
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
-        let (tx, mut rx) = channel::channel(1, quota);
+        let (tx, mut rx) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
         let mut rx = rx.drain();
 
-        let conn = Conn::new(tx, String::new());
+        let conn = Conn::new(tx, String::new(), None);
         let conn_id = conn.get_id();
         suite.run(Task::OpenConn { conn });
         suite.run(set_conn_version_task(
@@ -1525,7 +2602,7 @@ mod tests {
             RequestId(1),
             conn_id,
             ChangeDataRequestKvApi::RawKv,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         req.set_kv_api(ChangeDataRequestKvApi::RawKv);
@@ -1560,7 +2637,7 @@ mod tests {
             RequestId(2),
             conn_id,
             ChangeDataRequestKvApi::TxnKv,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         req.set_kv_api(ChangeDataRequestKvApi::TxnKv);
@@ -1596,7 +2673,7 @@ mod tests {
             RequestId(3),
             conn_id,
             ChangeDataRequestKvApi::TxnKv,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         req.set_kv_api(ChangeDataRequestKvApi::TxnKv);
@@ -1780,14 +2857,14 @@ mod tests {
     #[test]
     fn test_raftstore_is_busy() {
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
-        let (tx, _rx) = channel::channel(1, quota);
+        let (tx, _rx) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
         let mut suite = mock_endpoint(&CdcConfig::default(), None, ApiVersion::V1);
 
         // Fill the channel.
         suite.add_region(1 /* region id */, 1 /* cap */);
         suite.fill_raft_rx(1);
 
-        let conn = Conn::new(tx, String::new());
+        let conn = Conn::new(tx, String::new(), None);
         let conn_id = conn.get_id();
         suite.run(Task::OpenConn { conn });
         suite.run(set_conn_version_task(
@@ -1806,7 +2883,7 @@ mod tests {
             RequestId(0),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -1835,10 +2912,10 @@ mod tests {
         let mut suite = mock_endpoint(&cfg, None, ApiVersion::V1);
         suite.add_region(1, 100);
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
-        let (tx, mut rx) = channel::channel(1, quota);
+        let (tx, mut rx) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
         let mut rx = rx.drain();
 
-        let conn = Conn::new(tx, String::new());
+        let conn = Conn::new(tx, String::new(), None);
         let conn_id = conn.get_id();
         suite.run(Task::OpenConn { conn });
 
@@ -1858,7 +2935,7 @@ mod tests {
             RequestId(1),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -1879,7 +2956,7 @@ mod tests {
             RequestId(1),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -1923,7 +3000,7 @@ mod tests {
             RequestId(1),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.add_local_reader(100);
@@ -1955,7 +3032,7 @@ mod tests {
             RequestId(1),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -1976,6 +3053,292 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_snapshot_registers_like_register() {
+        let cfg = CdcConfig {
+            min_ts_interval: ReadableDuration(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        let mut suite = mock_endpoint(&cfg, None, ApiVersion::V1);
+        suite.add_region(1, 100);
+        let quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let (tx, _rx) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
+
+        let conn = Conn::new(tx, String::new(), None);
+        let conn_id = conn.get_id();
+        suite.run(Task::OpenConn { conn });
+
+        let region_epoch = ChangeDataRequest::default().get_region_epoch().clone();
+        let downstream = Downstream::new(
+            "".to_string(),
+            region_epoch,
+            RequestId(1),
+            conn_id,
+            ChangeDataRequestKvApi::TiDb,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
+            ObservedRange::default(),
+        );
+        let downstream_id = downstream.id;
+        suite.run(Task::Snapshot {
+            region_id: 1,
+            snapshot_ts: TimeStamp::new(100),
+            downstream,
+        });
+
+        // A snapshot bootstrap goes through the same registration path as
+        // `Task::Register`: the region gets a delegate and the downstream is
+        // tracked in the subscription registry, just as if it had registered
+        // normally with `checkpoint_ts = 100`.
+        assert_eq!(suite.endpoint.capture_regions.len(), 1);
+        let sub = suite.endpoint.subscription_registry.snapshot();
+        let region_sub = &sub[&1];
+        assert_eq!(region_sub.checkpoint_ts, TimeStamp::new(100));
+        assert!(
+            region_sub
+                .downstreams
+                .iter()
+                .any(|d| d.downstream_id == downstream_id)
+        );
+    }
+
+    #[test]
+    fn test_register_backoff() {
+        let cfg = CdcConfig {
+            min_ts_interval: ReadableDuration(Duration::from_secs(60)),
+            register_backoff_interval: ReadableDuration(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        // No region is added, so registering region 1 always fails with
+        // "region not found" -- used here purely to make `on_register`
+        // reject the first attempt without needing a real scan.
+        let mut suite = mock_endpoint(&cfg, None, ApiVersion::V1);
+
+        let quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let (tx, mut rx) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
+        let mut rx = rx.drain();
+
+        let conn = Conn::new(tx, String::new(), None);
+        let conn_id = conn.get_id();
+        suite.run(Task::OpenConn { conn });
+
+        let version = FeatureGate::batch_resolved_ts();
+        suite.run(set_conn_version_task(conn_id, version));
+
+        let mut req_header = Header::default();
+        req_header.set_cluster_id(0);
+        let mut req = ChangeDataRequest::default();
+        req.set_region_id(1);
+        req.set_request_id(1);
+        let region_epoch = req.get_region_epoch().clone();
+
+        let downstream = Downstream::new(
+            "".to_string(),
+            region_epoch.clone(),
+            RequestId(1),
+            conn_id,
+            ChangeDataRequestKvApi::TiDb,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
+            ObservedRange::default(),
+        );
+        suite.run(Task::Register {
+            request: req.clone(),
+            downstream,
+        });
+        let cdc_event = channel::recv_timeout(&mut rx, Duration::from_millis(500))
+            .unwrap()
+            .unwrap();
+        if let CdcEvent::Event(mut e) = cdc_event.0 {
+            let event = e.event.take().unwrap();
+            match event {
+                Event_oneof_event::Error(err) => assert!(err.has_region_not_found()),
+                other => panic!("unknown event {:?}", other),
+            }
+        } else {
+            panic!("unknown cdc event {:?}", cdc_event);
+        }
+
+        // Re-registering the same (request_id, region) right away is
+        // short-circuited by the backoff map instead of being evaluated
+        // (and failing with "region not found") all over again.
+        let downstream = Downstream::new(
+            "".to_string(),
+            region_epoch,
+            RequestId(1),
+            conn_id,
+            ChangeDataRequestKvApi::TiDb,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
+            ObservedRange::default(),
+        );
+        suite.run(Task::Register {
+            request: req,
+            downstream,
+        });
+        let cdc_event = channel::recv_timeout(&mut rx, Duration::from_millis(500))
+            .unwrap()
+            .unwrap();
+        if let CdcEvent::Event(mut e) = cdc_event.0 {
+            let event = e.event.take().unwrap();
+            match event {
+                Event_oneof_event::Error(err) => assert!(err.has_server_is_busy()),
+                other => panic!("unknown event {:?}", other),
+            }
+        } else {
+            panic!("unknown cdc event {:?}", cdc_event);
+        }
+    }
+
+    #[test]
+    fn test_register_backoff_is_per_connection() {
+        let cfg = CdcConfig {
+            min_ts_interval: ReadableDuration(Duration::from_secs(60)),
+            register_backoff_interval: ReadableDuration(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        // No region is added, so registering region 1 always fails with
+        // "region not found" -- used here purely to make `on_register`
+        // reject the first attempt without needing a real scan.
+        let mut suite = mock_endpoint(&cfg, None, ApiVersion::V1);
+
+        let quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let (tx_a, mut rx_a) = channel::channel(1, quota.clone(), Arc::new(MemoryQuota::new(usize::MAX)));
+        let mut rx_a = rx_a.drain();
+        let (tx_b, mut rx_b) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
+        let mut rx_b = rx_b.drain();
+
+        let conn_a = Conn::new(tx_a, String::new(), None);
+        let conn_a_id = conn_a.get_id();
+        suite.run(Task::OpenConn { conn: conn_a });
+        let conn_b = Conn::new(tx_b, String::new(), None);
+        let conn_b_id = conn_b.get_id();
+        suite.run(Task::OpenConn { conn: conn_b });
+
+        let version = FeatureGate::batch_resolved_ts();
+        suite.run(set_conn_version_task(conn_a_id, version));
+        suite.run(set_conn_version_task(conn_b_id, version));
+
+        let mut req_header = Header::default();
+        req_header.set_cluster_id(0);
+        let mut req = ChangeDataRequest::default();
+        req.set_region_id(1);
+        // Both connections reuse the same client-supplied request_id, which
+        // is the common case: `request_id` is a per-connection sequence
+        // number, not a store-wide unique id.
+        req.set_request_id(1);
+        let region_epoch = req.get_region_epoch().clone();
+
+        // Connection A's register fails and is recorded in the backoff map.
+        let downstream = Downstream::new(
+            "".to_string(),
+            region_epoch.clone(),
+            RequestId(1),
+            conn_a_id,
+            ChangeDataRequestKvApi::TiDb,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
+            ObservedRange::default(),
+        );
+        suite.run(Task::Register {
+            request: req.clone(),
+            downstream,
+        });
+        let cdc_event = channel::recv_timeout(&mut rx_a, Duration::from_millis(500))
+            .unwrap()
+            .unwrap();
+        if let CdcEvent::Event(mut e) = cdc_event.0 {
+            let event = e.event.take().unwrap();
+            match event {
+                Event_oneof_event::Error(err) => assert!(err.has_region_not_found()),
+                other => panic!("unknown event {:?}", other),
+            }
+        } else {
+            panic!("unknown cdc event {:?}", cdc_event);
+        }
+
+        // Connection B registering the same `(request_id, region)` must not
+        // be backed off by connection A's recent failure: it should be
+        // evaluated fresh and fail with "region not found" again, not
+        // "server is busy".
+        let downstream = Downstream::new(
+            "".to_string(),
+            region_epoch,
+            RequestId(1),
+            conn_b_id,
+            ChangeDataRequestKvApi::TiDb,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
+            ObservedRange::default(),
+        );
+        suite.run(Task::Register {
+            request: req,
+            downstream,
+        });
+        let cdc_event = channel::recv_timeout(&mut rx_b, Duration::from_millis(500))
+            .unwrap()
+            .unwrap();
+        if let CdcEvent::Event(mut e) = cdc_event.0 {
+            let event = e.event.take().unwrap();
+            match event {
+                Event_oneof_event::Error(err) => assert!(err.has_region_not_found()),
+                other => panic!("unknown event {:?}", other),
+            }
+        } else {
+            panic!("unknown cdc event {:?}", cdc_event);
+        }
+    }
+
+    #[test]
+    fn test_ack_resumes_paused_downstream() {
+        let cfg = CdcConfig {
+            min_ts_interval: ReadableDuration(Duration::from_secs(60)),
+            unacked_bytes_limit: ReadableSize::kb(1),
+            ..Default::default()
+        };
+        let mut suite = mock_endpoint(&cfg, None, ApiVersion::V1);
+
+        let quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let (tx, _rx) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
+
+        let mut conn = Conn::new(tx, String::new(), None);
+        let conn_id = conn.get_id();
+
+        let req = ChangeDataRequest::default();
+        let downstream = Downstream::new(
+            "".to_string(),
+            req.get_region_epoch().clone(),
+            RequestId(1),
+            conn_id,
+            ChangeDataRequestKvApi::TiDb,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
+            ObservedRange::default(),
+        );
+        let state = downstream.get_state();
+        // Only `Normal` downstreams can be paused.
+        state.store(DownstreamState::Normal);
+        conn.subscribe(RequestId(1), 1, downstream.id, state.clone());
+        suite.run(Task::OpenConn { conn });
+
+        // Simulate `Delegate::on_min_ts` pausing this downstream because the
+        // connection's unacked window crossed `unacked_bytes_limit`.
+        suite
+            .endpoint
+            .connections
+            .get(&conn_id)
+            .unwrap()
+            .record_sent_bytes(cfg.unacked_bytes_limit.0 as usize + 1);
+        assert!(pause_downstream(&state));
+
+        // Acking part of the window still leaves it over the limit: the
+        // downstream must stay paused.
+        suite.run(Task::Ack { conn_id, bytes: 1 });
+        assert_eq!(state.load(), DownstreamState::Paused);
+
+        // Acking the rest brings the window back under the limit: the
+        // downstream must be resumed.
+        suite.run(Task::Ack {
+            conn_id,
+            bytes: cfg.unacked_bytes_limit.0 as usize,
+        });
+        assert_eq!(state.load(), DownstreamState::Normal);
+    }
+
     #[test]
     fn test_too_many_scan_tasks() {
         let cfg = CdcConfig {
@@ -1998,10 +3361,10 @@ mod tests {
 
         suite.add_region(1, 100);
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
-        let (tx, mut rx) = channel::channel(1, quota);
+        let (tx, mut rx) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
         let mut rx = rx.drain();
 
-        let conn = Conn::new(tx, String::new());
+        let conn = Conn::new(tx, String::new(), None);
         let conn_id = conn.get_id();
         suite.run(Task::OpenConn { conn });
 
@@ -2021,7 +3384,7 @@ mod tests {
             RequestId(1),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -2038,7 +3401,7 @@ mod tests {
             RequestId(2),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -2101,11 +3464,11 @@ mod tests {
         suite.add_region(1, 100);
 
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
-        let (tx, mut rx) = channel::channel(1, quota);
+        let (tx, mut rx) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
         let mut rx = rx.drain();
         let mut region = Region::default();
         region.set_id(1);
-        let conn = Conn::new(tx, String::new());
+        let conn = Conn::new(tx, String::new(), None);
         let conn_id = conn.get_id();
         suite.run(Task::OpenConn { conn });
 
@@ -2124,7 +3487,7 @@ mod tests {
             RequestId(0),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         downstream.get_state().store(DownstreamState::Normal);
@@ -2162,7 +3525,7 @@ mod tests {
             RequestId(0),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         downstream.get_state().store(DownstreamState::Normal);
@@ -2197,11 +3560,11 @@ mod tests {
 
         // Register region 3 to another conn which is not support batch resolved ts.
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
-        let (tx, mut rx2) = channel::channel(1, quota);
+        let (tx, mut rx2) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
         let mut rx2 = rx2.drain();
         let mut region = Region::default();
         region.set_id(3);
-        let conn = Conn::new(tx, String::new());
+        let conn = Conn::new(tx, String::new(), None);
         let conn_id = conn.get_id();
         suite.run(Task::OpenConn { conn });
         suite.run(set_conn_version_task(
@@ -2217,7 +3580,7 @@ mod tests {
             RequestId(3),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         downstream.get_state().store(DownstreamState::Normal);
@@ -2274,10 +3637,10 @@ mod tests {
         let mut suite = mock_endpoint(&CdcConfig::default(), None, ApiVersion::V1);
         suite.add_region(1, 100);
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
-        let (tx, mut rx) = channel::channel(1, quota);
+        let (tx, mut rx) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
         let mut rx = rx.drain();
 
-        let conn = Conn::new(tx, String::new());
+        let conn = Conn::new(tx, String::new(), None);
         let conn_id = conn.get_id();
         suite.run(Task::OpenConn { conn });
         suite.run(set_conn_version_task(
@@ -2296,7 +3659,7 @@ mod tests {
             RequestId(0),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         let downstream_id = downstream.id;
@@ -2314,6 +3677,7 @@ mod tests {
             region_id: 1,
             downstream_id,
             err: Some(Error::request(err_header.clone())),
+            retryable: true,
         };
         suite.run(Task::Deregister(deregister));
         loop {
@@ -2339,7 +3703,7 @@ mod tests {
             RequestId(0),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         let new_downstream_id = downstream.id;
@@ -2355,6 +3719,7 @@ mod tests {
             region_id: 1,
             downstream_id,
             err: Some(Error::request(err_header.clone())),
+            retryable: true,
         };
         suite.run(Task::Deregister(deregister));
         channel::recv_timeout(&mut rx, Duration::from_millis(200)).unwrap_err();
@@ -2366,6 +3731,7 @@ mod tests {
             region_id: 1,
             downstream_id: new_downstream_id,
             err: Some(Error::request(err_header.clone())),
+            retryable: true,
         };
         suite.run(Task::Deregister(deregister));
         let cdc_event = channel::recv_timeout(&mut rx, Duration::from_millis(500))
@@ -2392,7 +3758,7 @@ mod tests {
             RequestId(0),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -2414,6 +3780,80 @@ mod tests {
         assert_eq!(suite.endpoint.capture_regions.len(), 1);
     }
 
+    #[test]
+    fn test_deregister_delegate_rate_limits_region_error() {
+        let mut suite = mock_endpoint(&CdcConfig::default(), None, ApiVersion::V1);
+        suite.add_region(1, 100);
+        let quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let (tx, mut rx) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
+        let mut rx = rx.drain();
+
+        let conn = Conn::new(tx, String::new(), None);
+        let conn_id = conn.get_id();
+        suite.run(Task::OpenConn { conn });
+        suite.run(set_conn_version_task(
+            conn_id,
+            semver::Version::new(0, 0, 0),
+        ));
+
+        let mut req = ChangeDataRequest::default();
+        req.set_region_id(1);
+        let region_epoch = req.get_region_epoch().clone();
+
+        let mut err_header = ErrorHeader::default();
+        err_header.set_not_leader(Default::default());
+
+        let register_region_1 = |suite: &mut TestEndpointSuite| {
+            let downstream = Downstream::new(
+                "".to_string(),
+                region_epoch.clone(),
+                RequestId(0),
+                conn_id,
+                ChangeDataRequestKvApi::TiDb,
+                crate::txn_source::TxnSourceFilter::from_filter_loop(false),
+                ObservedRange::default(),
+            );
+            suite.run(Task::Register {
+                request: req.clone(),
+                downstream,
+            });
+        };
+
+        // First flap: the region is captured, then errors out. The error is
+        // delivered to the downstream as usual.
+        register_region_1(&mut suite);
+        assert_eq!(suite.endpoint.capture_regions.len(), 1);
+        let observe_id = suite.endpoint.capture_regions[&1].handle.id;
+        suite.run(Task::Deregister(Deregister::Delegate {
+            region_id: 1,
+            observe_id,
+            err: Error::request(err_header.clone()),
+        }));
+        channel::recv_timeout(&mut rx, Duration::from_millis(500))
+            .unwrap()
+            .unwrap();
+        assert_eq!(suite.endpoint.capture_regions.len(), 0);
+        let rate_limited_before = CDC_REGION_ERROR_EVENT_RATE_LIMITED.get();
+
+        // The region immediately flaps again, well inside the rate limit
+        // window. The delegate is still deregistered, but the error is
+        // dropped instead of flooding the downstream.
+        register_region_1(&mut suite);
+        assert_eq!(suite.endpoint.capture_regions.len(), 1);
+        let observe_id = suite.endpoint.capture_regions[&1].handle.id;
+        suite.run(Task::Deregister(Deregister::Delegate {
+            region_id: 1,
+            observe_id,
+            err: Error::request(err_header),
+        }));
+        channel::recv_timeout(&mut rx, Duration::from_millis(200)).unwrap_err();
+        assert_eq!(suite.endpoint.capture_regions.len(), 0);
+        assert_eq!(
+            CDC_REGION_ERROR_EVENT_RATE_LIMITED.get(),
+            rate_limited_before + 1
+        );
+    }
+
     #[test]
     fn test_broadcast_resolved_ts() {
         let cfg = CdcConfig {
@@ -2427,9 +3867,10 @@ mod tests {
         let mut conn_rxs = vec![];
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
         for region_ids in [vec![1, 2], vec![3]] {
-            let (tx, rx) = channel::channel(1, quota.clone());
+            let (tx, rx) =
+                channel::channel(1, quota.clone(), Arc::new(MemoryQuota::new(usize::MAX)));
             conn_rxs.push(rx);
-            let conn = Conn::new(tx, String::new());
+            let conn = Conn::new(tx, String::new(), None);
             let conn_id = conn.get_id();
             suite.run(Task::OpenConn { conn });
             let version = FeatureGate::batch_resolved_ts();
@@ -2448,7 +3889,7 @@ mod tests {
                     RequestId(0),
                     conn_id,
                     ChangeDataRequestKvApi::TiDb,
-                    false,
+                    crate::txn_source::TxnSourceFilter::from_filter_loop(false),
                     ObservedRange::default(),
                 );
                 downstream.get_state().store(DownstreamState::Normal);
@@ -2544,8 +3985,9 @@ mod tests {
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
 
         // Open conn a
-        let (tx1, _rx1) = channel::channel(1, quota.clone());
-        let conn_a = Conn::new(tx1, String::new());
+        let (tx1, _rx1) =
+            channel::channel(1, quota.clone(), Arc::new(MemoryQuota::new(usize::MAX)));
+        let conn_a = Conn::new(tx1, String::new(), None);
         let conn_id_a = conn_a.get_id();
         suite.run(Task::OpenConn { conn: conn_a });
         suite.run(set_conn_version_task(
@@ -2554,9 +3996,9 @@ mod tests {
         ));
 
         // Open conn b
-        let (tx2, mut rx2) = channel::channel(1, quota);
+        let (tx2, mut rx2) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
         let mut rx2 = rx2.drain();
-        let conn_b = Conn::new(tx2, String::new());
+        let conn_b = Conn::new(tx2, String::new(), None);
         let conn_id_b = conn_b.get_id();
         suite.run(Task::OpenConn { conn: conn_b });
         suite.run(set_conn_version_task(
@@ -2577,7 +4019,7 @@ mod tests {
             RequestId(0),
             conn_id_a,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -2600,7 +4042,7 @@ mod tests {
             RequestId(0),
             conn_id_b,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -2702,10 +4144,10 @@ mod tests {
         };
         let mut suite = mock_endpoint(&cfg, None, ApiVersion::V1);
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
-        let (tx, mut rx) = channel::channel(1, quota);
+        let (tx, mut rx) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
         let mut rx = rx.drain();
 
-        let conn = Conn::new(tx, String::new());
+        let conn = Conn::new(tx, String::new(), None);
         let conn_id = conn.get_id();
         suite.run(Task::OpenConn { conn });
         // Enable batch resolved ts in the test.
@@ -2729,7 +4171,7 @@ mod tests {
                 RequestId(0),
                 conn_id,
                 ChangeDataRequestKvApi::TiDb,
-                false,
+                crate::txn_source::TxnSourceFilter::from_filter_loop(false),
                 ObservedRange::default(),
             );
             on_init_downstream(&downstream.get_state());
@@ -2799,10 +4241,10 @@ mod tests {
         let mut suite = mock_endpoint(&cfg, None, ApiVersion::V1);
         suite.add_region(1, 100);
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
-        let (tx, mut rx) = channel::channel(1, quota);
+        let (tx, mut rx) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
         let mut rx = rx.drain();
 
-        let conn = Conn::new(tx, String::new());
+        let conn = Conn::new(tx, String::new(), None);
         let conn_id = conn.get_id();
         suite.run(Task::OpenConn { conn });
 
@@ -2822,7 +4264,7 @@ mod tests {
             RequestId(1),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -2839,7 +4281,7 @@ mod tests {
             RequestId(2),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -2856,7 +4298,7 @@ mod tests {
             RequestId(2),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -2881,6 +4323,7 @@ mod tests {
             region_id: 1,
             downstream_id: DownstreamId::new(),
             err: None,
+            retryable: false,
         }));
         assert_eq!(suite.connections[&conn_id].downstreams_count(), 2);
 
@@ -2900,6 +4343,7 @@ mod tests {
             region_id: 1,
             downstream_id,
             err: Some(Error::Rocks("test error".to_owned())),
+            retryable: false,
         }));
         assert_eq!(suite.connections[&conn_id].downstreams_count(), 1);
         let cdc_event = channel::recv_timeout(&mut rx, Duration::from_millis(500))
@@ -2920,7 +4364,7 @@ mod tests {
             RequestId(1),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -2959,7 +4403,7 @@ mod tests {
                 RequestId(i),
                 conn_id,
                 ChangeDataRequestKvApi::TiDb,
-                false,
+                crate::txn_source::TxnSourceFilter::from_filter_loop(false),
                 ObservedRange::default(),
             );
             suite.run(Task::Register {
@@ -3004,7 +4448,7 @@ mod tests {
                 RequestId(1),
                 conn_id,
                 ChangeDataRequestKvApi::TiDb,
-                false,
+                crate::txn_source::TxnSourceFilter::from_filter_loop(false),
                 ObservedRange::default(),
             );
             suite.run(Task::Register {
@@ -3053,9 +4497,9 @@ mod tests {
         let mut suite = mock_endpoint(&cfg, None, ApiVersion::V1);
         suite.add_region(1, 100);
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
-        let (tx, _rx) = channel::channel(1, quota);
+        let (tx, _rx) = channel::channel(1, quota, Arc::new(MemoryQuota::new(usize::MAX)));
 
-        let conn = Conn::new(tx, String::new());
+        let conn = Conn::new(tx, String::new(), None);
         let conn_id = conn.get_id();
         suite.run(Task::OpenConn { conn });
 
@@ -3072,7 +4516,7 @@ mod tests {
             RequestId(1),
             conn_id,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
             ObservedRange::default(),
         );
         suite.run(Task::Register {
@@ -3081,4 +4525,70 @@ mod tests {
         });
         assert!(suite.connections.is_empty());
     }
+
+    // Builds `region_count` delegates, each with one `Normal` downstream
+    // subscribed on a single shared connection with batch-resolved-ts
+    // enabled, and inserts them straight into `capture_regions`/`connections`
+    // rather than going through `Task::Register`/`Task::OpenConn`'s full
+    // raftstore-router-backed path. That keeps the setup cheap enough to
+    // build at a 100k-region scale, while still exercising the same
+    // `Delegate::on_min_ts` and `Advance::emit_resolved_ts` fan-out that a
+    // real `Task::MinTs` batch drives every `min-ts-interval`.
+    //
+    // This only times the fan-out, via `test::Bencher`, the same way the
+    // other `#[bench]` cases in this workspace do; it doesn't track
+    // allocations, since this repo has no allocation-counting bench harness
+    // to plug in here.
+    fn build_min_ts_bench_suite(region_count: u64) -> (TestEndpointSuite, Vec<u64>) {
+        let cfg = CdcConfig::default();
+        let mut suite = mock_endpoint(&cfg, None, ApiVersion::V1);
+
+        let quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let (tx, _rx) = channel::channel(1, quota.clone(), Arc::new(MemoryQuota::new(usize::MAX)));
+        let conn = Conn::new(tx, String::new(), None);
+        let conn_id = conn.get_id();
+        suite.run(Task::OpenConn { conn });
+        suite.run(set_conn_version_task(conn_id, FeatureGate::batch_resolved_ts()));
+
+        let regions: Vec<u64> = (1..=region_count).collect();
+        for &region_id in &regions {
+            let mut region = Region::default();
+            region.set_id(region_id);
+            let region_epoch = region.get_region_epoch().clone();
+
+            let mut delegate = Delegate::new(region_id, quota.clone(), Default::default());
+            delegate.finish_scan_locks(region, Default::default()).unwrap();
+
+            let downstream = Downstream::new(
+                String::new(),
+                region_epoch,
+                RequestId(0),
+                conn_id,
+                ChangeDataRequestKvApi::TiDb,
+                crate::txn_source::TxnSourceFilter::from_filter_loop(false),
+                ObservedRange::default(),
+            );
+            downstream.get_state().store(DownstreamState::Normal);
+            delegate.subscribe(downstream).unwrap();
+
+            suite.capture_regions.insert(region_id, delegate);
+        }
+
+        (suite, regions)
+    }
+
+    #[bench]
+    fn bench_on_min_ts_100k_regions(b: &mut test::Bencher) {
+        let (mut suite, regions) = build_min_ts_bench_suite(100_000);
+
+        let mut ts = 1;
+        b.iter(|| {
+            ts += 1;
+            suite.run(Task::MinTs {
+                regions: regions.clone(),
+                min_ts: TimeStamp::from(ts),
+                current_ts: TimeStamp::from(ts),
+            });
+        });
+    }
 }
@@ -0,0 +1,124 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Shards CDC work across several [`crate::Endpoint`] runnables by
+//! region hash, so a store capturing tens of thousands of regions isn't
+//! bottlenecked on one endpoint thread.
+//!
+//! This only covers routing: picking which of N already-running
+//! `Scheduler<Task>` handles a given region's tasks go to, consistently,
+//! so the same region never gets processed by two different endpoints at
+//! once (each endpoint still needs connections/memory quota of its own,
+//! same as today's single-`Endpoint` setup -- sharing those across shards
+//! is future work for whatever spawns the pool). `components/server`
+//! still spawns a single `cdc` `LazyWorker`/`Endpoint` today; wiring N of
+//! them up behind this pool is a server-startup change outside this
+//! crate, not something `EndpointPool` itself can do.
+
+use tikv_util::worker::{ScheduleError, Scheduler};
+
+use crate::endpoint::Task;
+
+/// Routes CDC tasks to one of several shards by region hash.
+pub struct EndpointPool {
+    shards: Vec<Scheduler<Task>>,
+}
+
+impl EndpointPool {
+    /// `shards` must be non-empty; each entry is the `Scheduler<Task>` of an
+    /// already-running `Endpoint` (e.g. from its own `LazyWorker`).
+    pub fn new(shards: Vec<Scheduler<Task>>) -> EndpointPool {
+        assert!(!shards.is_empty(), "EndpointPool needs at least one shard");
+        EndpointPool { shards }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Which shard index `region_id` is routed to. A region always maps to
+    /// the same shard as long as `shard_count()` doesn't change, which is
+    /// what "consistent routing" means here -- this is a plain hash-mod,
+    /// not a consistent-hashing ring, so it gives no guarantee about which
+    /// regions move if the shard count ever does change.
+    pub fn shard_index(&self, region_id: u64) -> usize {
+        (region_hash(region_id) % self.shards.len() as u64) as usize
+    }
+
+    pub fn shard(&self, region_id: u64) -> &Scheduler<Task> {
+        &self.shards[self.shard_index(region_id)]
+    }
+
+    /// Look up a shard by the index `shard_index` already returned, e.g.
+    /// after grouping several regions' tasks by shard up front.
+    pub fn shard_by_index(&self, shard: usize) -> &Scheduler<Task> {
+        &self.shards[shard]
+    }
+
+    pub fn schedule(&self, region_id: u64, task: Task) -> Result<(), ScheduleError<Task>> {
+        self.shard(region_id).schedule(task)
+    }
+}
+
+/// A region's numeric ID is allocated sequentially by PD, so using it
+/// directly as a shard index would pile consecutive regions (often the
+/// result of one split) onto the same shard. Mixing the bits spreads them
+/// out instead, the same rationale `collections::HashMap`'s default hasher
+/// exists for.
+fn region_hash(region_id: u64) -> u64 {
+    let mut x = region_id;
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    x ^= x >> 33;
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use tikv_util::worker::LazyWorker;
+
+    use super::*;
+
+    struct NoopRunnable;
+    impl tikv_util::worker::Runnable for NoopRunnable {
+        type Task = Task;
+        fn run(&mut self, _task: Task) {}
+    }
+
+    fn new_pool(n: usize) -> (EndpointPool, Vec<LazyWorker<Task>>) {
+        let mut workers = Vec::new();
+        let mut shards = Vec::new();
+        for i in 0..n {
+            let mut worker = LazyWorker::new(format!("cdc-shard-{}", i));
+            shards.push(worker.scheduler());
+            worker.start(NoopRunnable);
+            workers.push(worker);
+        }
+        (EndpointPool::new(shards), workers)
+    }
+
+    #[test]
+    fn test_routing_is_consistent() {
+        let (pool, _workers) = new_pool(4);
+        for region_id in 0..1000 {
+            let first = pool.shard_index(region_id);
+            let second = pool.shard_index(region_id);
+            assert_eq!(first, second);
+            assert!(first < 4);
+        }
+    }
+
+    #[test]
+    fn test_consecutive_regions_spread_across_shards() {
+        let (pool, _workers) = new_pool(8);
+        let mut seen = std::collections::HashSet::new();
+        for region_id in 1..=64 {
+            seen.insert(pool.shard_index(region_id));
+        }
+        // With 8 shards and 64 consecutive region ids, every shard should
+        // get at least one -- if this ever fails, `region_hash` stopped
+        // spreading sequential ids out.
+        assert_eq!(seen.len(), 8);
+    }
+}
@@ -78,6 +78,34 @@ impl Error {
         )
     }
 
+    /// Whether a downstream that sees this error (via [`Self::into_error_event`])
+    /// can reasonably retry the same subscription and expect it to succeed --
+    /// e.g. a leader transfer in progress -- versus a permanent condition
+    /// that retrying won't fix, like an incompatible request.
+    ///
+    /// `cdcpb::Error` has no field to carry this classification over the
+    /// wire yet (the same gap `RegionFailoverHint`'s doc comment notes for
+    /// reconnect hints), so `Deregister::Downstream::retryable` only reaches
+    /// TiCDC indirectly today, through which `ErrorEvent` variant ends up
+    /// set -- `not_leader`/`epoch_not_match` already imply retryable by
+    /// convention. This is still useful locally, e.g. for
+    /// `CDC_DEREGISTER_REASON` to distinguish the two without string
+    /// matching on the error.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Sink(_) | Error::MemoryQuotaExceeded(_) => true,
+            Error::Kv(KvError(box EngineErrorInner::Request(e)))
+            | Error::Txn(TxnError(box TxnErrorInner::Engine(KvError(
+                box EngineErrorInner::Request(e),
+            ))))
+            | Error::Txn(TxnError(box TxnErrorInner::Mvcc(MvccError(box MvccErrorInner::Kv(
+                KvError(box EngineErrorInner::Request(e)),
+            )))))
+            | Error::Request(box e) => e.has_not_leader() || e.has_epoch_not_match(),
+            _ => false,
+        }
+    }
+
     pub fn extract_region_error(self) -> errorpb::Error {
         match self {
             Error::Kv(KvError(box EngineErrorInner::Request(e)))
@@ -0,0 +1,209 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! An alternate `Downstream` sink that archives change log events and
+//! resolved-ts advances directly to an [`external_storage::ExternalStorage`]
+//! backend (S3, GCS, ...) instead of streaming them over the `EventFeed`
+//! gRPC connection. Lets a changefeed be served without a TiCDC process
+//! polling the stream at all -- just a store that drops log files into a
+//! bucket on its own.
+//!
+//! `ChangeDataRequest` has no field to flag a subscription this way (the
+//! same kind of wire gap `Error::is_retryable`'s doc comment already notes
+//! for reconnect hints), so nothing today parses a `sink=external_storage`
+//! request out of the wire protocol. What's here is real and usable once
+//! something constructs a `Downstream` with
+//! [`Downstream::set_external_storage_sink`] directly, e.g. a test harness
+//! or an internal-only entry point.
+
+use std::sync::Arc;
+
+use external_storage::{ExternalStorage, UnpinReader};
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use kvproto::cdcpb::Event;
+use protobuf::Message as _;
+use tikv_util::{error, info};
+
+use crate::metrics::{CDC_EXTERNAL_STORAGE_WRITE_BYTES, CDC_EXTERNAL_STORAGE_WRITE_ERROR};
+
+/// Flush once a region's buffered, not-yet-written bytes cross this
+/// threshold, so a busy region doesn't grow its buffer unbounded while
+/// `ExternalStorageDrain::run` keeps receiving events for it.
+const FLUSH_BYTES_THRESHOLD: usize = 8 * 1024 * 1024;
+
+enum ExternalStorageItem {
+    Event(Event),
+    ResolvedTs { region_id: u64, ts: u64 },
+}
+
+/// The write half, held by a [`crate::delegate::Downstream`] in place of
+/// (or alongside) a gRPC [`crate::channel::Sink`].
+#[derive(Clone)]
+pub struct ExternalStorageSink {
+    sender: UnboundedSender<ExternalStorageItem>,
+}
+
+impl ExternalStorageSink {
+    pub fn sink_event(&self, event: Event) {
+        // Best-effort: if the drain side has already been dropped (e.g. the
+        // flush task was torn down on shutdown), there's nowhere to
+        // archive this to. Matches `Delegate::sink_event`'s drop-and-log
+        // behavior for a disconnected gRPC sink.
+        if self.sender.unbounded_send(ExternalStorageItem::Event(event)).is_err() {
+            info!("cdc external storage sink dropped event, drain gone");
+        }
+    }
+
+    pub fn sink_resolved_ts(&self, region_id: u64, ts: u64) {
+        if self
+            .sender
+            .unbounded_send(ExternalStorageItem::ResolvedTs { region_id, ts })
+            .is_err()
+        {
+            info!("cdc external storage sink dropped resolved-ts, drain gone");
+        }
+    }
+}
+
+/// The read half, driven by a background task spawned onto a tokio runtime
+/// (see `ExternalStorageDrain::run`). Batches events per region and flushes
+/// them to `storage` as length-prefixed protobuf records, the same encoding
+/// `channel::EventBatcher` would otherwise hand to the gRPC codec.
+pub struct ExternalStorageDrain {
+    receiver: UnboundedReceiver<ExternalStorageItem>,
+    storage: Arc<dyn ExternalStorage>,
+    buffers: collections::HashMap<u64, Vec<u8>>,
+}
+
+/// Creates a connected `(ExternalStorageSink, ExternalStorageDrain)` pair,
+/// analogous to `channel::channel`'s `(Sink, Drain)`.
+pub fn external_storage_channel(
+    storage: Arc<dyn ExternalStorage>,
+) -> (ExternalStorageSink, ExternalStorageDrain) {
+    let (sender, receiver) = unbounded();
+    (
+        ExternalStorageSink { sender },
+        ExternalStorageDrain {
+            receiver,
+            storage,
+            buffers: collections::HashMap::default(),
+        },
+    )
+}
+
+impl ExternalStorageDrain {
+    /// Encodes `event`/`ts` into this region's buffer, flushing it to
+    /// `self.storage` once it crosses `FLUSH_BYTES_THRESHOLD`. Drains
+    /// `self.receiver` until the paired `ExternalStorageSink` is dropped.
+    pub async fn run(mut self) {
+        use futures::StreamExt;
+        while let Some(item) = self.receiver.next().await {
+            let (region_id, encoded) = match item {
+                ExternalStorageItem::Event(event) => {
+                    (event.region_id, encode_record(&event.write_to_bytes().unwrap_or_default()))
+                }
+                ExternalStorageItem::ResolvedTs { region_id, ts } => {
+                    (region_id, encode_record(&ts.to_le_bytes()))
+                }
+            };
+            let buffer = self.buffers.entry(region_id).or_default();
+            buffer.extend_from_slice(&encoded);
+            if buffer.len() >= FLUSH_BYTES_THRESHOLD {
+                self.flush_region(region_id).await;
+            }
+        }
+        for region_id in self.buffers.keys().copied().collect::<Vec<_>>() {
+            self.flush_region(region_id).await;
+        }
+    }
+
+    async fn flush_region(&mut self, region_id: u64) {
+        let buffer = match self.buffers.get_mut(&region_id) {
+            Some(buffer) if !buffer.is_empty() => std::mem::take(buffer),
+            _ => return,
+        };
+        let len = buffer.len() as u64;
+        // Object name doesn't need to be globally unique beyond this store
+        // and region: each flush is append-only log content, never
+        // overwritten, so a collision would only happen if two flushes of
+        // the same region landed in the same nanosecond.
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let name = format!("cdc/{}/{}.log", region_id, nanos);
+        let reader = UnpinReader(Box::new(std::io::Cursor::new(buffer)));
+        match self.storage.write(&name, reader, len).await {
+            Ok(()) => CDC_EXTERNAL_STORAGE_WRITE_BYTES.inc_by(len),
+            Err(e) => {
+                error!("cdc external storage flush failed"; "region_id" => region_id, "error" => ?e);
+                CDC_EXTERNAL_STORAGE_WRITE_ERROR.inc();
+            }
+        }
+    }
+}
+
+/// A simple length-prefixed record: a u32 little-endian length followed by
+/// that many bytes. Lets a reader walk a flushed `.log` file back out into
+/// discrete events/resolved-ts advances without needing a delimiter that
+/// could collide with serialized protobuf bytes.
+fn encode_record(payload: &[u8]) -> Vec<u8> {
+    let mut record = Vec::with_capacity(4 + payload.len());
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(payload);
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use external_storage::LocalStorage;
+    use futures::executor::block_on;
+    use kvproto::cdcpb::Event;
+
+    use super::*;
+
+    #[test]
+    fn test_sink_and_flush_events() {
+        let dir = tempfile::Builder::new().tempdir().unwrap();
+        let storage: Arc<dyn ExternalStorage> = Arc::new(LocalStorage::new(dir.path()).unwrap());
+        let (sink, mut drain) = external_storage_channel(storage);
+
+        let mut event = Event::default();
+        event.region_id = 1;
+        sink.sink_event(event);
+        sink.sink_resolved_ts(1, 100);
+        drop(sink);
+
+        block_on(drain.run());
+
+        let mut found = false;
+        for entry in walkdir(dir.path()) {
+            if entry.ends_with(".log") {
+                found = true;
+            }
+        }
+        assert!(found, "expected a flushed .log file under {:?}", dir.path());
+    }
+
+    fn walkdir(root: &std::path::Path) -> Vec<String> {
+        let mut out = Vec::new();
+        for entry in walkdir_inner(root) {
+            out.push(entry);
+        }
+        out
+    }
+
+    fn walkdir_inner(dir: &std::path::Path) -> Vec<String> {
+        let mut out = Vec::new();
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    out.extend(walkdir_inner(&path));
+                } else {
+                    out.push(path.to_string_lossy().into_owned());
+                }
+            }
+        }
+        out
+    }
+}
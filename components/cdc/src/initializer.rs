@@ -49,6 +49,8 @@ use tikv_util::{
     Either,
 };
 use tokio::sync::Semaphore;
+use tracing::instrument;
+use tracing_active_tree::frame;
 use txn_types::{Key, KvPair, LockType, OldValue, TimeStamp};
 
 use crate::{
@@ -102,6 +104,18 @@ pub(crate) struct Initializer<E> {
     pub(crate) downstream_id: DownstreamId,
     pub(crate) downstream_state: Arc<AtomicCell<DownstreamState>>,
 
+    /// If this scan is resuming a previous one under the same `(region_id,
+    /// conn_id, request_id)` (see the comment on `conn.subscribe` in
+    /// `Endpoint::on_register`), the last key that scan had fully sunk to
+    /// the downstream. `async_incremental_scan` starts from this key
+    /// (inclusive, so the entry for it may be resent once) rather than the
+    /// observed range's start, sparing a rescan of everything already sent.
+    ///
+    /// Only consulted for the `TiDb` kv API: the raw kv scan path iterates
+    /// the whole raw keyspace directly rather than through `start_key`/
+    /// `end_key`, so it has no resume point to apply this to.
+    pub(crate) resume_key: Option<Key>,
+
     pub(crate) tablet: Option<E>,
     pub(crate) sched: Scheduler<Task>,
     pub(crate) sink: crate::channel::Sink,
@@ -112,13 +126,37 @@ pub(crate) struct Initializer<E> {
 
     pub(crate) max_scan_batch_bytes: usize,
     pub(crate) max_scan_batch_size: usize,
+    pub(crate) max_row_size: usize,
 
     pub(crate) ts_filter_ratio: f64,
     pub(crate) kv_api: ChangeDataRequestKvApi,
-    pub(crate) filter_loop: bool,
+    pub(crate) txn_source_filter: crate::txn_source::TxnSourceFilter,
+
+    /// See [`crate::delegate::Downstream::snapshot_only`]'s doc comment.
+    /// Consulted by the caller of [`Self::initialize`] once it returns
+    /// successfully, not by `Initializer` itself -- scanning and sinking
+    /// the completion event is identical either way, only what happens
+    /// afterwards (keep the downstream subscribed, or deregister it)
+    /// differs.
+    pub(crate) snapshot_only: bool,
+
+    /// See [`crate::delegate::Downstream::resource_group_name`]'s doc
+    /// comment. Used by the caller of [`Self::initialize`] to look up a
+    /// [`resource_control::ResourceLimiter`] and wrap the scan future with
+    /// it, so `Initializer` itself stays unaware of resource control.
+    pub(crate) resource_group_name: String,
+
+    /// Whether the incremental scan may populate the block cache. See
+    /// `CdcConfig::incremental_scan_fill_cache`.
+    pub(crate) fill_cache: bool,
 }
 
 impl<E: KvEngine> Initializer<E> {
+    #[instrument(skip_all, fields(
+        region_id = self.region_id,
+        conn_id = ?self.conn_id,
+        request_id = ?self.request_id,
+    ))]
     pub(crate) async fn initialize<T>(&mut self, cdc_handle: T) -> Result<()>
     where
         T: 'static + CdcHandle<E>,
@@ -171,11 +209,11 @@ impl<E: KvEngine> Initializer<E> {
         // Wait all delta changes earlier than the incremental scan snapshot be
         // sent to the downstream, so that they must be consumed before the
         // incremental scan result.
-        if let Err(e) = incremental_scan_barrier_fut.await {
+        if let Err(e) = frame!(incremental_scan_barrier_fut).await {
             return Err(Error::Other(box_err!(e)));
         }
 
-        match fut.await {
+        match frame!(fut).await {
             Ok(resp) => self.on_change_cmd_response(resp).await,
             Err(e) => Err(Error::Other(box_err!(e))),
         }
@@ -205,6 +243,11 @@ impl<E: KvEngine> Initializer<E> {
         }
     }
 
+    #[instrument(skip_all, fields(
+        region_id = self.region_id,
+        conn_id = ?self.conn_id,
+        request_id = ?self.request_id,
+    ))]
     pub(crate) async fn async_incremental_scan<S>(
         &mut self,
         snap: S,
@@ -253,6 +296,15 @@ impl<E: KvEngine> Initializer<E> {
             end_key = self.observed_range.end_key_encoded.clone();
         }
 
+        // Resume from where a previous scan under this `(region_id, conn_id,
+        // request_id)` left off, if it's ahead of the observed range's start
+        // (it always should be, barring the observed range having shrunk
+        // since, which `max` handles safely either way).
+        let start_key = match &self.resume_key {
+            Some(resume_key) if resume_key > &start_key => resume_key.clone(),
+            _ => start_key,
+        };
+
         debug!(
             "cdc async incremental scan";
             "region_id" => region_id,
@@ -297,7 +349,7 @@ impl<E: KvEngine> Initializer<E> {
 
             // Time range: (checkpoint_ts, max]
             let txnkv_scanner = ScannerBuilder::new(snap, TimeStamp::max())
-                .fill_cache(false)
+                .fill_cache(self.fill_cache)
                 .range(Some(start_key), upper_boundary)
                 .hint_min_ts(hint_min_ts)
                 .build_delta_scanner(self.checkpoint_ts, TxnExtraOp::ReadOldValue)
@@ -306,7 +358,7 @@ impl<E: KvEngine> Initializer<E> {
             Scanner::TxnKvScanner(txnkv_scanner)
         } else {
             let mut iter_opt = IterOptions::default();
-            iter_opt.set_fill_cache(false);
+            iter_opt.set_fill_cache(self.fill_cache);
             let (raw_key_prefix, raw_key_prefix_end) = ApiV2::get_rawkv_range();
             iter_opt.set_lower_bound(&[raw_key_prefix], DATA_KEY_PREFIX_LEN);
             iter_opt.set_upper_bound(&[raw_key_prefix_end], DATA_KEY_PREFIX_LEN);
@@ -361,10 +413,25 @@ impl<E: KvEngine> Initializer<E> {
                 done = true;
             }
             debug!("cdc scan entries"; "len" => entries.len(), "region_id" => region_id);
+            let batch_resume_key = Self::resume_key_from_entries(&entries);
             fail_point!("before_schedule_incremental_scan");
             let start_sink = Instant::now_coarse();
             self.sink_scan_events(entries, done).await?;
             sink_time += start_sink.saturating_elapsed();
+
+            // Only report progress for the `TiDb` kv API: the raw kv path
+            // doesn't honor `resume_key` (see its doc comment), so tracking
+            // it would just be dead bookkeeping. A batch with nothing new
+            // (e.g. the terminating empty one) leaves progress untouched
+            // rather than clearing it, so a stray empty batch can't wipe out
+            // a real resume point.
+            if self.kv_api == ChangeDataRequestKvApi::TiDb {
+                if done {
+                    self.report_scan_progress(None);
+                } else if let Some(key) = batch_resume_key {
+                    self.report_scan_progress(Some(key));
+                }
+            }
         }
 
         fail_point!("before_post_incremental_scan");
@@ -497,8 +564,9 @@ impl<E: KvEngine> Initializer<E> {
             self.region_id,
             self.request_id,
             entries,
-            self.filter_loop,
+            self.txn_source_filter,
             &self.observed_range,
+            self.max_row_size,
         )?;
         if done {
             let (cb, fut) = tikv_util::future::paired_future_callback();
@@ -541,6 +609,46 @@ impl<E: KvEngine> Initializer<E> {
         }
     }
 
+    /// Extracts the key of the last real entry in a scanned batch (skipping
+    /// the trailing `None` terminator, if present), suitable for use as
+    /// [`Initializer::resume_key`] on a later resumed scan.
+    ///
+    /// `TxnEntry::Prewrite`'s lock-CF pair is keyed by the plain encoded user
+    /// key with no timestamp suffix, while `TxnEntry::Commit`'s write-CF pair
+    /// is keyed by the encoded key with its commit timestamp appended; both
+    /// are normalized to the former so they compare and resume consistently
+    /// with `start_key`/`end_key`, which are also timestamp-less.
+    fn resume_key_from_entries(entries: &[Option<KvEntry>]) -> Option<Key> {
+        let last = entries.iter().rev().find_map(|e| e.as_ref())?;
+        let key = match last {
+            KvEntry::TxnEntry(TxnEntry::Prewrite { lock, .. }) => {
+                Key::from_encoded_slice(&lock.0)
+            }
+            KvEntry::TxnEntry(TxnEntry::Commit { write, .. }) => {
+                Key::from_encoded_slice(Key::truncate_ts_for(&write.0).ok()?)
+            }
+            // The raw kv path doesn't use `resume_key`; see its doc comment.
+            KvEntry::RawKvEntry(_) => return None,
+        };
+        Some(key)
+    }
+
+    /// Reports this scan's progress via [`Task::UpdateScanProgress`], so a
+    /// resumed scan under the same `(region_id, conn_id, request_id)` can
+    /// pick up from `resume_key` instead of starting over. Best-effort: a
+    /// failure to schedule just means a future resume rescans from scratch,
+    /// which is correct, only slower.
+    fn report_scan_progress(&self, resume_key: Option<Key>) {
+        if let Err(e) = self.sched.schedule(Task::UpdateScanProgress {
+            region_id: self.region_id,
+            conn_id: self.conn_id,
+            request_id: self.request_id,
+            resume_key,
+        }) {
+            error!("cdc schedule task failed"; "error" => ?e);
+        }
+    }
+
     // Deregister downstream when the Initializer fails to initialize.
     pub(crate) fn deregister_downstream(&self, err: Error) {
         let build_resolver = self.build_resolver.load(Ordering::Acquire);
@@ -561,6 +669,7 @@ impl<E: KvEngine> Initializer<E> {
                 request_id: self.request_id,
                 region_id: self.region_id,
                 downstream_id: self.downstream_id,
+                retryable: err.is_retryable(),
                 err: Some(err),
             }
         };
@@ -692,7 +801,7 @@ mod tests {
         buffer: usize,
         engine: Option<RocksEngine>,
         kv_api: ChangeDataRequestKvApi,
-        filter_loop: bool,
+        txn_source_filter: crate::txn_source::TxnSourceFilter,
     ) -> (
         LazyWorker<Task>,
         Runtime,
@@ -702,7 +811,8 @@ mod tests {
     ) {
         let (receiver_worker, rx) = new_receiver_worker();
         let quota = Arc::new(MemoryQuota::new(usize::MAX));
-        let (sink, drain) = crate::channel::channel(buffer, quota);
+        let (sink, drain) =
+            crate::channel::channel(buffer, quota, Arc::new(MemoryQuota::new(usize::MAX)));
 
         let pool = Builder::new_multi_thread()
             .thread_name("test-initializer-worker")
@@ -723,6 +833,7 @@ mod tests {
             observe_handle: ObserveHandle::new(),
             downstream_id: DownstreamId::new(),
             downstream_state,
+            resume_key: None,
 
             tablet: engine.or_else(|| {
                 TestEngineBuilder::new()
@@ -738,10 +849,14 @@ mod tests {
             fetch_speed_limiter: Limiter::new(fetch_limit as _),
             max_scan_batch_bytes: 1024 * 1024,
             max_scan_batch_size: 1024,
+            max_row_size: 6 * 1024 * 1024,
 
             ts_filter_ratio: 1.0, // always enable it.
             kv_api,
-            filter_loop,
+            txn_source_filter,
+            snapshot_only: false,
+            resource_group_name: String::new(),
+            fill_cache: false,
         };
 
         (receiver_worker, pool, initializer, rx, drain)
@@ -780,7 +895,7 @@ mod tests {
             1000,
             engine.kv_engine(),
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
         );
         initializer.observed_range = observed_range.clone();
         initializer.build_resolver.store(true, Ordering::Release);
@@ -859,7 +974,7 @@ mod tests {
             buffer,
             engine.kv_engine(),
             ChangeDataRequestKvApi::TiDb,
-            filter_loop,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(filter_loop),
         );
         let th = pool.spawn(async move {
             initializer
@@ -941,7 +1056,7 @@ mod tests {
                     1000,
                     engine.kv_engine(),
                     ChangeDataRequestKvApi::TiDb,
-                    false,
+                    crate::txn_source::TxnSourceFilter::from_filter_loop(false),
                 );
                 initializer.checkpoint_ts = checkpoint_ts.into();
                 let mut drain = drain.drain();
@@ -1006,7 +1121,7 @@ mod tests {
             buffer,
             None,
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
         );
 
         // Errors reported by region should deregister region.
@@ -1057,7 +1172,14 @@ mod tests {
         let total_bytes = 1;
         let buffer = 1;
         let (mut worker, pool, mut initializer, _rx, _drain) =
-            mock_initializer(total_bytes, total_bytes, buffer, None, kv_api, false);
+            mock_initializer(
+                total_bytes,
+                total_bytes,
+                buffer,
+                None,
+                kv_api,
+                crate::txn_source::TxnSourceFilter::from_filter_loop(false),
+            );
 
         let raft_router = CdcRaftRouter(MockRaftStoreRouter::new());
         initializer.downstream_state.store(DownstreamState::Stopped);
@@ -1118,7 +1240,7 @@ mod tests {
             1000,
             engine.kv_engine(),
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
         );
         initializer.checkpoint_ts = 120.into();
         let snap = engine.snapshot(Default::default()).unwrap();
@@ -1183,7 +1305,7 @@ mod tests {
             1000,
             engine.kv_engine(),
             ChangeDataRequestKvApi::TiDb,
-            false,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(false),
         );
 
         initializer.observed_range = ObservedRange::new(km, kz).unwrap();
@@ -1203,4 +1325,65 @@ mod tests {
         while block_on(drain.drain().next()).is_some() {}
         block_on(th).unwrap();
     }
+
+    #[test]
+    fn test_resume_key_from_entries() {
+        // No real entries (just the terminator, or nothing at all): nothing
+        // to resume from.
+        assert!(Initializer::<RocksEngine>::resume_key_from_entries(&[None]).is_none());
+        assert!(Initializer::<RocksEngine>::resume_key_from_entries(&[]).is_none());
+
+        // `Commit`'s write-CF key carries a commit ts that must be stripped
+        // so the resume key lines up with the ts-less `start_key`/`end_key`.
+        let key = Key::from_raw(b"zkey1");
+        let write_key = key.clone().append_ts(10.into());
+        let entries = vec![
+            Some(KvEntry::TxnEntry(TxnEntry::Commit {
+                default: (vec![], vec![]),
+                write: (write_key.into_encoded(), vec![]),
+                old_value: OldValue::default(),
+            })),
+            None,
+        ];
+        assert_eq!(
+            Initializer::<RocksEngine>::resume_key_from_entries(&entries),
+            Some(key)
+        );
+
+        // `Prewrite`'s lock-CF key is already ts-less.
+        let key = Key::from_raw(b"zkey2");
+        let entries = vec![Some(KvEntry::TxnEntry(TxnEntry::Prewrite {
+            default: (vec![], vec![]),
+            lock: (key.clone().into_encoded(), vec![]),
+            old_value: OldValue::default(),
+        }))];
+        assert_eq!(
+            Initializer::<RocksEngine>::resume_key_from_entries(&entries),
+            Some(key)
+        );
+
+        // Only the last real entry matters.
+        let key1 = Key::from_raw(b"zkey3");
+        let key2 = Key::from_raw(b"zkey4");
+        let entries = vec![
+            Some(KvEntry::TxnEntry(TxnEntry::Prewrite {
+                default: (vec![], vec![]),
+                lock: (key1.into_encoded(), vec![]),
+                old_value: OldValue::default(),
+            })),
+            Some(KvEntry::TxnEntry(TxnEntry::Prewrite {
+                default: (vec![], vec![]),
+                lock: (key2.clone().into_encoded(), vec![]),
+                old_value: OldValue::default(),
+            })),
+        ];
+        assert_eq!(
+            Initializer::<RocksEngine>::resume_key_from_entries(&entries),
+            Some(key2)
+        );
+
+        // The raw kv path doesn't use `resume_key`.
+        let entries = vec![Some(KvEntry::RawKvEntry((b"zkey5".to_vec(), vec![])))];
+        assert!(Initializer::<RocksEngine>::resume_key_from_entries(&entries).is_none());
+    }
 }
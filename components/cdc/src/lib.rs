@@ -2,24 +2,36 @@
 
 #![feature(box_patterns)]
 #![feature(assert_matches)]
+#![cfg_attr(test, feature(test))]
+
+#[cfg(test)]
+extern crate test;
 
 mod channel;
+mod compression;
 mod config;
 mod delegate;
 mod endpoint;
+mod endpoint_pool;
 mod errors;
+mod external_storage_sink;
 mod initializer;
 pub mod metrics;
 mod observer;
 mod old_value;
+mod registry;
 mod service;
+#[cfg(any(test, feature = "testexport"))]
+pub mod test_support;
 mod txn_source;
 
 pub use channel::{recv_timeout, CdcEvent};
 pub use config::CdcConfigManager;
 pub use delegate::Delegate;
 pub use endpoint::{CdcTxnExtraScheduler, Endpoint, Task, Validate};
+pub use endpoint_pool::EndpointPool;
 pub use errors::{Error, Result};
 pub use observer::CdcObserver;
 pub use old_value::OldValueCache;
-pub use service::{FeatureGate, Service};
+pub use registry::{CdcSubscriptionRegistry, RegionSubscription};
+pub use service::{Conn, ConnId, FeatureGate, Service};
@@ -108,6 +108,12 @@ lazy_static! {
         &["type"]
     )
     .unwrap();
+    pub static ref CDC_SCAN_TASKS_PER_CONN: IntGaugeVec = register_int_gauge_vec!(
+        "tikv_cdc_scan_tasks_per_conn",
+        "Pending incremental scan tasks registered on a single connection",
+        &["conn_id"]
+    )
+    .unwrap();
     pub static ref CDC_SCAN_DISK_READ_BYTES: IntCounter = register_int_counter!(
         "tikv_cdc_scan_disk_read_bytes_total",
         "Total disk read bytes of CDC incremental scan"
@@ -121,6 +127,12 @@ lazy_static! {
         "tikv_cdc_min_resolved_ts_lag",
         "The lag between the minimal resolved ts and the current ts"
     ).unwrap();
+    pub static ref CDC_DOWNSTREAM_RESOLVED_TS_LAG: IntGaugeVec = register_int_gauge_vec!(
+        "tikv_cdc_downstream_resolved_ts_lag",
+        "The lag between a single downstream's resolved ts and the current ts",
+        &["conn_id", "request_id"]
+    )
+    .unwrap();
     pub static ref CDC_MIN_RESOLVED_TS: IntGauge = register_int_gauge!(
         "tikv_cdc_min_resolved_ts",
         "The minimal resolved ts for current regions"
@@ -131,6 +143,11 @@ lazy_static! {
         "Bytes in memory of a pending region"
     )
     .unwrap();
+    pub static ref CDC_CAPTURED_REGION_LIMIT: IntGauge = register_int_gauge!(
+        "tikv_cdc_captured_region_limit",
+        "Configured cap on the number of regions this store's CDC endpoint will capture, 0 if unlimited"
+    )
+    .unwrap();
     pub static ref CDC_CAPTURED_REGION_COUNT: IntGauge = register_int_gauge!(
         "tikv_cdc_captured_region_total",
         "Total number of CDC captured regions"
@@ -230,6 +247,91 @@ lazy_static! {
         exponential_buckets(0.01, 2.0, 17).unwrap(),
     )
     .unwrap();
+
+    pub static ref CDC_REGION_ERROR_EVENT_RATE_LIMITED: IntCounter = register_int_counter!(
+        "tikv_cdc_region_error_event_rate_limited_total",
+        "Total number of region error events dropped by the per-region rate \
+         limiter because the region is flapping"
+    )
+    .unwrap();
+
+    pub static ref CDC_SINK_DROPPED_OUT_OF_RANGE_ENTRIES: IntCounterVec = register_int_counter_vec!(
+        "tikv_cdc_sink_dropped_out_of_range_entries_total",
+        "Total number of change entries dropped at the sink because they fall outside a \
+         downstream's observed key range",
+        &["kv_api"]
+    )
+    .unwrap();
+
+    // `cdcpb::Event` has no wire representation for a key-range delete yet, so
+    // a `DeleteRange` raft command that a RawKV downstream would need to
+    // mirror can't be delivered; see `Delegate::sink_delete_range`.
+    pub static ref CDC_SINK_UNDELIVERABLE_DELETE_RANGE: IntCounterVec = register_int_counter_vec!(
+        "tikv_cdc_sink_undeliverable_delete_range_total",
+        "Total number of delete_range commands that couldn't be propagated to a downstream \
+         because cdcpb has no event type for a key range",
+        &["kv_api"]
+    )
+    .unwrap();
+
+    // See `Delegate::last_applied_index`'s doc comment: raftstore can redeliver an already
+    // applied entry to the same delegate after a leader transfer.
+    pub static ref CDC_DUPLICATE_CMD_COUNTER: IntCounter = register_int_counter!(
+        "tikv_cdc_duplicate_cmd_total",
+        "Total number of raft commands skipped by Delegate::on_batch because they were \
+         already observed at or below the delegate's last applied index"
+    )
+    .unwrap();
+
+    // One sample per captured region per `Endpoint::on_timeout` flush (every
+    // `METRICS_FLUSH_INTERVAL`), so the buckets show the distribution of
+    // per-region throughput across the store rather than a single store-wide
+    // average; a long tail points at hot regions. See
+    // `Delegate::sample_and_reset_throughput`.
+    pub static ref CDC_REGION_THROUGHPUT_BYTES_HISTOGRAM: Histogram = register_histogram!(
+        "tikv_cdc_region_throughput_bytes",
+        "Bucketed histogram of per-region CDC event bytes/sec, sampled once per region on \
+         every metrics flush",
+        exponential_buckets(256.0, 2.0, 20).unwrap()
+    )
+    .unwrap();
+
+    // See `Error::is_retryable`'s doc comment: the wire `ErrorEvent` itself can't
+    // carry this classification yet, so this is the only place it's visible in
+    // aggregate, short of TiCDC guessing it back out from which `ErrorEvent`
+    // variant got set.
+    pub static ref CDC_DEREGISTER_REASON: IntCounterVec = register_int_counter_vec!(
+        "tikv_cdc_deregister_downstream_total",
+        "Total number of downstreams deregistered with an error, by whether \
+         Error::is_retryable classified it as retryable",
+        &["retryable"]
+    )
+    .unwrap();
+
+    pub static ref CDC_REGION_THROUGHPUT_EVENTS_HISTOGRAM: Histogram = register_histogram!(
+        "tikv_cdc_region_throughput_events",
+        "Bucketed histogram of per-region CDC events/sec, sampled once per region on every \
+         metrics flush",
+        exponential_buckets(1.0, 2.0, 16).unwrap()
+    )
+    .unwrap();
+
+    pub static ref CDC_EXTERNAL_STORAGE_WRITE_BYTES: IntCounter = register_int_counter!(
+        "tikv_cdc_external_storage_write_bytes_total",
+        "Total bytes flushed by ExternalStorageDrain to the external storage sink"
+    )
+    .unwrap();
+    pub static ref CDC_EXTERNAL_STORAGE_WRITE_ERROR: IntCounter = register_int_counter!(
+        "tikv_cdc_external_storage_write_error_total",
+        "Total number of failed flushes from ExternalStorageDrain to the external storage sink"
+    )
+    .unwrap();
+    pub static ref CDC_OLD_VALUE_BUDGET_EXHAUSTED: IntCounter = register_int_counter!(
+        "tikv_cdc_old_value_budget_exhausted_total",
+        "Total number of old-value lookups skipped because a MultiBatch task's \
+         OldValueBudget ran out"
+    )
+    .unwrap();
 }
 
 thread_local! {
@@ -9,14 +9,37 @@ use kvproto::metapb::{Peer, Region};
 use raft::StateRole;
 use raftstore::{coprocessor::*, store::RegionSnapshot, Error as RaftStoreError};
 use tikv::storage::Statistics;
-use tikv_util::{error, warn, worker::Scheduler};
+use tikv_util::{warn, worker::Scheduler};
 
 use crate::{
     endpoint::{Deregister, Task},
-    old_value::{self, OldValueCache},
+    endpoint_pool::EndpointPool,
+    old_value::{self, OldValueTask},
     Error as CdcError,
 };
 
+/// Where a `CdcObserver` hands off tasks once it's decoded them from
+/// raftstore events: either the single endpoint every region used to go
+/// through, or a pool sharding regions across several endpoints (see
+/// [`EndpointPool`]).
+#[derive(Clone)]
+enum CdcSink {
+    Single(Scheduler<Task>),
+    Sharded(Arc<EndpointPool>),
+}
+
+impl CdcSink {
+    fn schedule(&self, region_id: u64, task: Task) {
+        let result = match self {
+            CdcSink::Single(sched) => sched.schedule(task).map_err(|e| e.to_string()),
+            CdcSink::Sharded(pool) => pool.schedule(region_id, task).map_err(|e| e.to_string()),
+        };
+        if let Err(e) = result {
+            warn!("cdc schedule task failed"; "error" => e);
+        }
+    }
+}
+
 /// An Observer for CDC.
 ///
 /// It observes raftstore internal events, such as:
@@ -24,20 +47,31 @@ use crate::{
 ///   2. Apply command events.
 #[derive(Clone)]
 pub struct CdcObserver {
-    sched: Scheduler<Task>,
+    sink: CdcSink,
     // A shared registry for managing observed regions.
     // TODO: it may become a bottleneck, find a better way to manage the registry.
     observe_regions: Arc<RwLock<HashMap<u64, ObserveId>>>,
 }
 
 impl CdcObserver {
-    /// Create a new `CdcObserver`.
+    /// Create a new `CdcObserver`, backed by a single endpoint.
     ///
     /// Events are strong ordered, so `sched` must be implemented as
     /// a FIFO queue.
     pub fn new(sched: Scheduler<Task>) -> CdcObserver {
         CdcObserver {
-            sched,
+            sink: CdcSink::Single(sched),
+            observe_regions: Arc::default(),
+        }
+    }
+
+    /// Create a new `CdcObserver` that shards regions across `pool`'s
+    /// endpoints instead of a single one. See [`EndpointPool`]'s doc
+    /// comment: `components/server` has to actually spawn the N endpoints
+    /// this pool routes to before this is usable end to end.
+    pub fn with_pool(pool: EndpointPool) -> CdcObserver {
+        CdcObserver {
+            sink: CdcSink::Sharded(Arc::new(pool)),
             observe_regions: Arc::default(),
         }
     }
@@ -108,11 +142,7 @@ impl<E: KvEngine> CmdObserver<E> for CdcObserver {
         if max_level < ObserveLevel::All {
             return;
         }
-        let cmd_batches: Vec<_> = cmd_batches
-            .iter()
-            .filter(|cb| cb.level == ObserveLevel::All && !cb.is_empty())
-            .cloned()
-            .collect();
+        let cmd_batches = Self::filter_cmd_batches(ObserveLevel::All, cmd_batches);
         if cmd_batches.is_empty() {
             return;
         }
@@ -123,17 +153,38 @@ impl<E: KvEngine> CmdObserver<E> for CdcObserver {
         // whether to get it.
         let snapshot =
             RegionSnapshot::from_snapshot(Arc::new(engine.snapshot(None)), Arc::new(region));
-        let get_old_value = move |key,
-                                  query_ts,
-                                  old_value_cache: &mut OldValueCache,
-                                  statistics: &mut Statistics| {
-            old_value::get_old_value(&snapshot, key, query_ts, old_value_cache, statistics)
-        };
-        if let Err(e) = self.sched.schedule(Task::MultiBatch {
-            multi: cmd_batches,
-            old_value_cb: Box::new(get_old_value),
-        }) {
-            warn!("cdc schedule task failed"; "error" => ?e);
+        let old_value_resolver = Arc::new(
+            move |task: OldValueTask, statistics: &mut Statistics| {
+                old_value::resolve_old_value_task(&snapshot, task, statistics)
+            },
+        );
+        match &self.sink {
+            CdcSink::Single(sched) => {
+                if let Err(e) = sched.schedule(Task::MultiBatch {
+                    multi: cmd_batches,
+                    old_value_resolver,
+                }) {
+                    warn!("cdc schedule task failed"; "error" => ?e);
+                }
+            }
+            CdcSink::Sharded(pool) => {
+                // Split the flushed batches by shard so each endpoint only
+                // ever sees the regions it owns, then schedule one
+                // `MultiBatch` per shard that has work.
+                let mut by_shard: HashMap<usize, Vec<CmdBatch>> = HashMap::new();
+                for batch in cmd_batches {
+                    let shard = pool.shard_index(batch.region_id);
+                    by_shard.entry(shard).or_default().push(batch);
+                }
+                for (shard, multi) in by_shard {
+                    if let Err(e) = pool.shard_by_index(shard).schedule(Task::MultiBatch {
+                        multi,
+                        old_value_resolver: old_value_resolver.clone(),
+                    }) {
+                        warn!("cdc schedule task failed"; "error" => ?e);
+                    }
+                }
+            }
         }
     }
 
@@ -163,9 +214,7 @@ impl RoleObserver for CdcObserver {
                     observe_id,
                     err: CdcError::request(store_err.into()),
                 };
-                if let Err(e) = self.sched.schedule(Task::Deregister(deregister)) {
-                    error!("cdc schedule cdc task failed"; "error" => ?e);
-                }
+                self.sink.schedule(region_id, Task::Deregister(deregister));
             }
         }
     }
@@ -192,12 +241,22 @@ impl RegionChangeObserver for CdcObserver {
                         observe_id,
                         err: CdcError::request(store_err.into()),
                     };
-                    if let Err(e) = self.sched.schedule(Task::Deregister(deregister)) {
-                        error!("cdc schedule cdc task failed"; "error" => ?e);
-                    }
+                    self.sink.schedule(region_id, Task::Deregister(deregister));
+                }
+            }
+            RegionChangeEvent::UpdateBuckets(buckets) => {
+                let region_id = ctx.region().get_id();
+                if self.is_subscribed(region_id).is_some() {
+                    self.sink.schedule(
+                        region_id,
+                        Task::RegionBuckets {
+                            region_id,
+                            buckets,
+                        },
+                    );
                 }
             }
-            _ => {}
+            RegionChangeEvent::Create | RegionChangeEvent::Update(_) => {}
         }
     }
 }
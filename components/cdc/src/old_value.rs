@@ -1,6 +1,9 @@
 // Copyright 2021 TiKV Project Authors. Licensed under Apache-2.0.
 
-use std::ops::{Bound, Deref};
+use std::{
+    ops::{Bound, Deref},
+    sync::Arc,
+};
 
 use engine_traits::{ReadOptions, CF_DEFAULT, CF_WRITE};
 use getset::CopyGetters;
@@ -19,9 +22,90 @@ use txn_types::{Key, MutationType, OldValue, TimeStamp, Value, WriteRef, WriteTy
 
 use crate::{metrics::*, Result};
 
-pub(crate) type OldValueCallback = Box<
-    dyn Fn(Key, TimeStamp, &mut OldValueCache, &mut Statistics) -> Result<Option<Vec<u8>>> + Send,
->;
+/// A single unit of engine IO needed to resolve an old value that couldn't
+/// be answered from `OldValueCache` alone.
+///
+/// Unlike the cache lookup itself, resolving a task only needs read access
+/// to an (already cheaply-clonable) engine snapshot, so tasks for different
+/// keys can be resolved concurrently, e.g. on the scan worker pool, instead
+/// of serially on the endpoint thread.
+#[derive(Debug, Clone)]
+pub(crate) enum OldValueTask {
+    /// The cache held a [`OldValue::ValueTimeStamp`] for this key; read the
+    /// value at the recorded `start_ts` from the default cf.
+    GetDefault(Key),
+    /// The cache had no entry for this key; seek for the old value via the
+    /// write cf starting at the encoded query key.
+    SeekWrite(Key),
+}
+
+/// Caps how much engine IO a single `Task::MultiBatch` is willing to spend
+/// resolving old values that `OldValueCache` couldn't answer on its own.
+/// Without this, a batch with a burst of long, uncached update chains could
+/// stall the endpoint thread (well, the scan pool it hands the lookups to)
+/// for an unbounded amount of time before any of the batch's events reach a
+/// downstream.
+///
+/// Shared across every region's `Delegate::on_batch` call within the same
+/// `Task::MultiBatch`, not reset per region -- one noisy region shouldn't
+/// get its own fresh budget just because a neighboring region's lookups
+/// already spent it.
+pub struct OldValueBudget {
+    remaining_bytes: i64,
+    remaining_count: i64,
+}
+
+/// Default per-task budget: enough for a few thousand short old values, or a
+/// couple hundred large ones, before falling back to treating further
+/// lookups in the same task as unavailable.
+pub const OLD_VALUE_BUDGET_BYTES: i64 = 8 * 1024 * 1024;
+pub const OLD_VALUE_BUDGET_COUNT: i64 = 8192;
+
+impl Default for OldValueBudget {
+    fn default() -> Self {
+        OldValueBudget::new(OLD_VALUE_BUDGET_BYTES, OLD_VALUE_BUDGET_COUNT)
+    }
+}
+
+impl OldValueBudget {
+    pub fn new(bytes: i64, count: i64) -> Self {
+        OldValueBudget {
+            remaining_bytes: bytes,
+            remaining_count: count,
+        }
+    }
+
+    /// Whether a lookup estimated at `bytes` can still be charged to this
+    /// budget. Doesn't deduct anything on its own -- see
+    /// [`Self::charge`] -- so callers can check once, decide what to do
+    /// with the key either way, and only charge the path they actually
+    /// took.
+    pub fn has_capacity(&self, bytes: usize) -> bool {
+        self.remaining_count > 0 && self.remaining_bytes > bytes as i64
+    }
+
+    /// Deducts a resolved lookup of `bytes` from the budget. Called only
+    /// after [`Self::has_capacity`] confirmed there was room, so this never
+    /// needs to reject anything itself.
+    pub fn charge(&mut self, bytes: usize) {
+        self.remaining_count -= 1;
+        self.remaining_bytes -= bytes as i64;
+    }
+}
+
+/// Outcome of checking `OldValueCache` for a key.
+pub(crate) enum OldValueLookup {
+    /// Resolved straight from the cache, no engine IO needed.
+    Resolved(Option<Value>),
+    /// The cache couldn't answer this on its own; resolve it with
+    /// [`resolve_old_value_task`].
+    Pending(OldValueTask),
+}
+
+/// Resolves a single [`OldValueTask`] against a captured engine snapshot.
+/// Cloned freely so several pending tasks can be resolved concurrently.
+pub(crate) type OldValueResolver =
+    Arc<dyn Fn(OldValueTask, &mut Statistics) -> Result<Option<Value>> + Send + Sync>;
 
 #[derive(Default)]
 pub struct OldValueCacheSizePolicy(usize);
@@ -102,6 +186,107 @@ impl OldValueCache {
     pub(crate) fn capacity(&self) -> usize {
         self.cache.capacity()
     }
+
+    /// Snapshots cache-wide counters and the `top_n` largest cached entries
+    /// by encoded size, for diagnosing cache thrashing. Unlike
+    /// [`OldValueCache::flush_metrics`], this doesn't reset the counters: it
+    /// can be called at any time, e.g. from `Task::Validate`, without
+    /// disturbing the next metrics flush.
+    ///
+    /// The cache has no notion of which region a key belongs to, so unlike
+    /// `access_count`/`miss_count` this can't be broken down per region;
+    /// only the cache-wide totals are available.
+    pub fn stats(&self, top_n: usize) -> OldValueCacheStats {
+        let mut top_keys: Vec<(Key, usize)> = self
+            .cache
+            .iter()
+            .map(|(key, (old_value, mutation_type))| {
+                let size = key.as_encoded().len()
+                    + old_value.size()
+                    + std::mem::size_of::<Option<MutationType>>();
+                (key.clone(), size)
+            })
+            .collect();
+        top_keys.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+        top_keys.truncate(top_n);
+        OldValueCacheStats {
+            access_count: self.access_count,
+            miss_count: self.miss_count,
+            miss_none_count: self.miss_none_count,
+            update_count: self.update_count,
+            len: self.cache.len(),
+            bytes: self.cache.size(),
+            capacity: self.cache.capacity(),
+            top_keys,
+        }
+    }
+
+    /// Check whether the old value for `key` is already known, without
+    /// touching the engine. Returns the resolved value on a full hit, or
+    /// the [`OldValueTask`] that [`resolve_old_value_task`] must run
+    /// otherwise.
+    pub(crate) fn check(&mut self, key: &Key, query_ts: TimeStamp) -> OldValueLookup {
+        self.access_count += 1;
+        if let Some((old_value, mutation_type)) = self.cache.remove(key) {
+            return match mutation_type {
+                // Old value of an Insert is guaranteed to be None.
+                Some(MutationType::Insert) => {
+                    assert_eq!(old_value, OldValue::None);
+                    OldValueLookup::Resolved(None)
+                }
+                // For Put, Delete or a mutation type we do not know,
+                // we read old value from the cache.
+                Some(MutationType::Put) | Some(MutationType::Delete) | None => match old_value {
+                    OldValue::None => OldValueLookup::Resolved(None),
+                    OldValue::Value { value } => OldValueLookup::Resolved(Some(value)),
+                    OldValue::ValueTimeStamp { start_ts } => {
+                        let prev_key = key.clone().truncate_ts().unwrap().append_ts(start_ts);
+                        OldValueLookup::Pending(OldValueTask::GetDefault(prev_key))
+                    }
+                    // Unspecified and SeekWrite should not be added into cache.
+                    OldValue::Unspecified | OldValue::SeekWrite(_) => unreachable!(),
+                },
+                _ => unreachable!(),
+            };
+        }
+
+        // Cannot get old value from cache, seek for it in engine.
+        self.miss_count += 1;
+        let key = key.clone().truncate_ts().unwrap().append_ts(query_ts);
+        OldValueLookup::Pending(OldValueTask::SeekWrite(key))
+    }
+
+    /// Record that a pending [`OldValueTask::SeekWrite`] resolved to `None`,
+    /// matching the bookkeeping `get_old_value` used to do inline.
+    pub(crate) fn note_miss_none(&mut self) {
+        self.miss_none_count += 1;
+    }
+}
+
+/// A point-in-time snapshot of [`OldValueCache`], returned by
+/// [`OldValueCache::stats`].
+#[derive(Debug, Default)]
+pub struct OldValueCacheStats {
+    pub access_count: usize,
+    pub miss_count: usize,
+    pub miss_none_count: usize,
+    pub update_count: usize,
+    pub len: usize,
+    pub bytes: usize,
+    pub capacity: usize,
+    /// The `top_n` largest entries currently cached, largest first.
+    pub top_keys: Vec<(Key, usize)>,
+}
+
+impl OldValueCacheStats {
+    /// Fraction of lookups that were answered from the cache, in `[0, 1]`.
+    /// `None` if the cache hasn't been queried yet.
+    pub fn hit_ratio(&self) -> Option<f64> {
+        if self.access_count == 0 {
+            return None;
+        }
+        Some((self.access_count - self.miss_count) as f64 / self.access_count as f64)
+    }
 }
 
 /// Fetch old value for `key`. If it can't be found in `old_value_cache`, seek
@@ -120,42 +305,33 @@ pub fn get_old_value<S: EngineSnapshot>(
             .observe(start.saturating_elapsed().as_secs_f64())
     );
 
-    old_value_cache.access_count += 1;
-    if let Some((old_value, mutation_type)) = old_value_cache.cache.remove(&key) {
-        return match mutation_type {
-            // Old value of an Insert is guaranteed to be None.
-            Some(MutationType::Insert) => {
-                assert_eq!(old_value, OldValue::None);
-                Ok(None)
-            }
-            // For Put, Delete or a mutation type we do not know,
-            // we read old value from the cache.
-            Some(MutationType::Put) | Some(MutationType::Delete) | None => {
-                match old_value {
-                    OldValue::None => Ok(None),
-                    OldValue::Value { value } => Ok(Some(value)),
-                    OldValue::ValueTimeStamp { start_ts } => {
-                        let prev_key = key.truncate_ts().unwrap().append_ts(start_ts);
-                        let value = get_value_default(snapshot, &prev_key, statistics);
-                        Ok(value)
-                    }
-                    // Unspecified and SeekWrite should not be added into cache.
-                    OldValue::Unspecified | OldValue::SeekWrite(_) => unreachable!(),
-                }
-            }
-            _ => unreachable!(),
-        };
+    let task = match old_value_cache.check(&key, query_ts) {
+        OldValueLookup::Resolved(value) => return Ok(value),
+        OldValueLookup::Pending(task) => task,
+    };
+    let is_seek = matches!(task, OldValueTask::SeekWrite(_));
+    let value = resolve_old_value_task(snapshot, task, statistics)?;
+    if is_seek && value.is_none() {
+        old_value_cache.note_miss_none();
     }
+    Ok(value)
+}
 
-    // Cannot get old value from cache, seek for it in engine.
-    old_value_cache.miss_count += 1;
-    let key = key.truncate_ts().unwrap().append_ts(query_ts);
-    let mut cursor = new_write_cursor_on_key(snapshot, &key);
-    let value = near_seek_old_value(&key, &mut cursor, Either::Left(snapshot), statistics)?;
-    if value.is_none() {
-        old_value_cache.miss_none_count += 1;
+/// Perform the engine read described by `task`. This only touches the
+/// snapshot and a local `Statistics` accumulator, so unlike `OldValueCache`
+/// itself, it's safe to call for several tasks concurrently.
+pub(crate) fn resolve_old_value_task<S: EngineSnapshot>(
+    snapshot: &S,
+    task: OldValueTask,
+    statistics: &mut Statistics,
+) -> Result<Option<Value>> {
+    match task {
+        OldValueTask::GetDefault(key) => Ok(get_value_default(snapshot, &key, statistics)),
+        OldValueTask::SeekWrite(key) => {
+            let mut cursor = new_write_cursor_on_key(snapshot, &key);
+            near_seek_old_value(&key, &mut cursor, Either::Left(snapshot), statistics)
+        }
     }
-    Ok(value)
 }
 
 pub fn new_old_value_cursor<S: EngineSnapshot>(snapshot: &S, cf: &'static str) -> Cursor<S::Iter> {
@@ -0,0 +1,163 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! State that lets a subscription survive the [`Endpoint`](crate::Endpoint)
+//! that created it.
+//!
+//! `Endpoint` is built exactly once when the CDC worker starts, and nothing
+//! in `tikv_util::worker` catches a panicking `Runnable` and swaps in a
+//! replacement: today a panic in the CDC worker takes the whole process
+//! down. `CdcSubscriptionRegistry` does not add such a supervisor. It only
+//! keeps the small amount of per-region bookkeeping (which downstreams are
+//! watching, under which `ObserveId`, and how far the region's resolved ts
+//! has advanced) in a handle that lives outside of any single `Endpoint`
+//! value. If a restart supervisor is ever added, it can hold on to the same
+//! registry across `Endpoint` instances and use [`snapshot`](Self::snapshot)
+//! to tell a freshly constructed `Endpoint` what was already subscribed,
+//! instead of waiting for every downstream to reconnect and rescan.
+
+use std::sync::{Arc, Mutex};
+
+use collections::{HashMap, HashMapEntry};
+use raftstore::coprocessor::ObserveId;
+use txn_types::TimeStamp;
+
+use crate::{
+    delegate::DownstreamId,
+    service::{ConnId, RequestId},
+};
+
+/// A single downstream's registration under a region's subscription.
+#[derive(Clone, Copy, Debug)]
+pub struct DownstreamSubscription {
+    pub conn_id: ConnId,
+    pub request_id: RequestId,
+    pub downstream_id: DownstreamId,
+}
+
+/// Everything needed to resume a region's CDC subscription without a full
+/// incremental scan: the `ObserveId` it was registered under, the
+/// downstreams waiting on it, and how far its resolved ts has advanced.
+#[derive(Clone, Debug)]
+pub struct RegionSubscription {
+    pub observe_id: ObserveId,
+    pub checkpoint_ts: TimeStamp,
+    pub downstreams: Vec<DownstreamSubscription>,
+}
+
+/// A cheaply cloneable, `Endpoint`-independent registry of live CDC
+/// subscriptions, keyed by region id.
+#[derive(Clone, Default)]
+pub struct CdcSubscriptionRegistry {
+    regions: Arc<Mutex<HashMap<u64, RegionSubscription>>>,
+}
+
+impl CdcSubscriptionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `downstream_id` is now subscribed to `region_id` under
+    /// `observe_id`. Creates the region's entry if this is its first
+    /// downstream, and refreshes `observe_id` if the region was re-observed
+    /// (e.g. after its delegate was previously torn down). `checkpoint_ts`
+    /// is only used when the entry is created; an existing entry keeps its
+    /// own.
+    pub fn upsert_downstream(
+        &self,
+        region_id: u64,
+        observe_id: ObserveId,
+        checkpoint_ts: TimeStamp,
+        downstream: DownstreamSubscription,
+    ) {
+        let mut regions = self.regions.lock().unwrap();
+        let sub = regions.entry(region_id).or_insert_with(|| RegionSubscription {
+            observe_id,
+            checkpoint_ts,
+            downstreams: Vec::new(),
+        });
+        sub.observe_id = observe_id;
+        sub.downstreams.retain(|d| d.downstream_id != downstream.downstream_id);
+        sub.downstreams.push(downstream);
+    }
+
+    /// Removes a single downstream from `region_id`'s subscription. Drops
+    /// the region's entry entirely once it has no downstreams left.
+    pub fn remove_downstream(&self, region_id: u64, downstream_id: DownstreamId) {
+        let mut regions = self.regions.lock().unwrap();
+        if let HashMapEntry::Occupied(mut e) = regions.entry(region_id) {
+            e.get_mut().downstreams.retain(|d| d.downstream_id != downstream_id);
+            if e.get().downstreams.is_empty() {
+                e.remove();
+            }
+        }
+    }
+
+    /// Removes `region_id`'s subscription entirely, e.g. once its delegate
+    /// has been stopped and all of its downstreams notified.
+    pub fn remove_region(&self, region_id: u64) {
+        self.regions.lock().unwrap().remove(&region_id);
+    }
+
+    /// Advances the recorded checkpoint ts for `region_id`, if it is still
+    /// tracked. No-op for regions that aren't subscribed, so callers can
+    /// call this unconditionally for every region touched by a min-ts batch.
+    pub fn advance_checkpoint(&self, region_id: u64, checkpoint_ts: TimeStamp) {
+        if let Some(sub) = self.regions.lock().unwrap().get_mut(&region_id) {
+            if checkpoint_ts > sub.checkpoint_ts {
+                sub.checkpoint_ts = checkpoint_ts;
+            }
+        }
+    }
+
+    /// Returns a point-in-time copy of every tracked region's subscription
+    /// state, for a future restart supervisor to rehydrate a new `Endpoint`
+    /// with.
+    pub fn snapshot(&self) -> HashMap<u64, RegionSubscription> {
+        self.regions.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn downstream() -> DownstreamSubscription {
+        DownstreamSubscription {
+            conn_id: ConnId::new(),
+            request_id: RequestId(1),
+            downstream_id: DownstreamId::new(),
+        }
+    }
+
+    #[test]
+    fn test_upsert_and_remove_downstream() {
+        let registry = CdcSubscriptionRegistry::new();
+        let observe_id = ObserveId::default();
+        let d1 = downstream();
+        let d2 = downstream();
+        registry.upsert_downstream(1, observe_id, TimeStamp::zero(), d1);
+        registry.upsert_downstream(1, observe_id, TimeStamp::zero(), d2);
+        assert_eq!(registry.snapshot()[&1].downstreams.len(), 2);
+
+        registry.advance_checkpoint(1, TimeStamp::new(10));
+        assert_eq!(registry.snapshot()[&1].checkpoint_ts, TimeStamp::new(10));
+        // A smaller checkpoint never moves the recorded one backwards.
+        registry.advance_checkpoint(1, TimeStamp::new(5));
+        assert_eq!(registry.snapshot()[&1].checkpoint_ts, TimeStamp::new(10));
+
+        registry.remove_downstream(1, d1.downstream_id);
+        assert_eq!(registry.snapshot()[&1].downstreams.len(), 1);
+
+        registry.remove_downstream(1, d2.downstream_id);
+        assert!(!registry.snapshot().contains_key(&1));
+    }
+
+    #[test]
+    fn test_remove_region_drops_all_downstreams() {
+        let registry = CdcSubscriptionRegistry::new();
+        let observe_id = ObserveId::default();
+        registry.upsert_downstream(1, observe_id, TimeStamp::zero(), downstream());
+        registry.remove_region(1);
+        assert!(!registry.snapshot().contains_key(&1));
+    }
+}
@@ -1,13 +1,16 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
-use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc,
+use std::{
+    sync::{
+        atomic::{AtomicIsize, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
 use collections::{HashMap, HashMapEntry};
 use crossbeam::atomic::AtomicCell;
-use futures::stream::TryStreamExt;
+use futures::{channel::oneshot, stream::TryStreamExt};
 use grpcio::{DuplexSink, RequestStream, RpcContext, RpcStatus, RpcStatusCode};
 use kvproto::{
     cdcpb::{
@@ -16,12 +19,13 @@ use kvproto::{
     },
     kvrpcpb::ApiVersion,
 };
-use tikv_util::{error, info, memory::MemoryQuota, warn, worker::*};
+use tikv_util::{box_err, error, info, memory::MemoryQuota, warn, worker::*};
 
 use crate::{
     channel::{channel, Sink, CDC_CHANNLE_CAPACITY},
     delegate::{Downstream, DownstreamId, DownstreamState, ObservedRange},
-    endpoint::{Deregister, Task},
+    endpoint::{Deregister, HealthReport, Task, Validate},
+    Error, Result,
 };
 
 static CONNECTION_ID_ALLOC: AtomicUsize = AtomicUsize::new(0);
@@ -42,6 +46,10 @@ impl ConnId {
     pub fn new() -> ConnId {
         ConnId(CONNECTION_ID_ALLOC.fetch_add(1, Ordering::SeqCst))
     }
+
+    pub fn id(&self) -> usize {
+        self.0
+    }
 }
 
 impl Default for ConnId {
@@ -59,6 +67,18 @@ bitflags::bitflags! {
         const BATCH_RESOLVED_TS = 0b00000001;
         const VALIDATE_CLUSTER_ID = 0b00000010;
         const STREAM_MULTIPLEXING = 0b00000100;
+        const REGION_STATS_EVENTS = 0b00001000;
+        /// The client opted in (via the `features` header) to receiving
+        /// `EventRow::value`/`old_value` compressed with a
+        /// `crate::compression::CompressionAlgorithm` above
+        /// `crate::compression::COMPRESSION_MIN_BYTES`. Not yet consulted by
+        /// `Delegate::sink_data`/`channel::Drain::forward` -- `EventRow` has
+        /// no field to say *whether* a value is compressed or with which
+        /// algorithm, so flipping this on today wouldn't be safe to act on
+        /// without a real client that already knows the convention out of
+        /// band. Negotiated here so the rest of the plumbing (feature
+        /// parsing, `Conn::features`) is already in place once that lands.
+        const EVENT_COMPRESSION = 0b00010000;
     }
 }
 
@@ -86,8 +106,34 @@ pub struct Conn {
     downstreams: HashMap<DownstreamKey, DownstreamValue>,
     peer: String,
 
+    /// The TLS peer's `x509_common_name`, captured once at connection
+    /// establishment via `security::get_peer_cn`. `None` when the channel
+    /// isn't secured; `Some("")` when it is but the peer presented no CN.
+    /// Re-checked against `SecurityConfig::cert_allowed_cn` by
+    /// `Endpoint::revalidate_conn_certs` whenever the serving cert rotates,
+    /// since that's the CN this connection was admitted under and the live
+    /// gRPC connection can't be re-handshaked to pick up a new one.
+    peer_cn: Option<String>,
+
     // Set when the connection established, or the first request received.
     version: Option<(semver::Version, FeatureGate)>,
+
+    /// Bytes sent to this connection's downstreams that haven't been
+    /// acknowledged as consumed yet, via `Task::Ack`. Endpoint-level
+    /// backpressure pauses a connection's downstreams (see
+    /// `crate::delegate::pause_downstream`) once this crosses
+    /// `CdcConfig::unacked_bytes_limit`, on top of (not instead of)
+    /// `sink_memory_quota`/`conn_memory_quota`: those only reflect how much
+    /// is still buffered in the local channel, not how far behind the
+    /// downstream's own processing actually is.
+    unacked_bytes: Arc<AtomicUsize>,
+
+    /// Incremental scan tasks registered on this connection that haven't
+    /// finished yet. Scoped per-connection (on top of `Endpoint`'s
+    /// store-wide `scan_task_counter`) so `Endpoint::on_register` can reject
+    /// just the connection piling up scans instead of every connection once
+    /// the store-wide count crosses `incremental_scan_concurrency_limit`.
+    scan_task_count: Arc<AtomicIsize>,
 }
 
 #[derive(PartialEq, Eq, Hash)]
@@ -103,16 +149,51 @@ struct DownstreamValue {
 }
 
 impl Conn {
-    pub fn new(sink: Sink, peer: String) -> Conn {
+    pub fn new(sink: Sink, peer: String, peer_cn: Option<String>) -> Conn {
         Conn {
             id: ConnId::new(),
             sink,
             downstreams: HashMap::default(),
             peer,
+            peer_cn,
             version: None,
+            unacked_bytes: Arc::new(AtomicUsize::new(0)),
+            scan_task_count: Arc::new(AtomicIsize::new(0)),
         }
     }
 
+    /// Records `bytes` as sent to this connection but not yet acked.
+    /// Called once per resolved-ts advance; see `Delegate::on_min_ts`.
+    pub(crate) fn record_sent_bytes(&self, bytes: usize) {
+        self.unacked_bytes.fetch_add(bytes, Ordering::SeqCst);
+    }
+
+    /// Handles `Task::Ack`: marks `bytes` previously sent to this
+    /// connection as consumed by the downstream. Saturating, so a
+    /// duplicate or reordered ack just clamps the window to zero instead
+    /// of underflowing.
+    pub fn ack_bytes(&self, bytes: usize) {
+        let _ = self
+            .unacked_bytes
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |cur| {
+                Some(cur.saturating_sub(bytes))
+            });
+    }
+
+    pub fn unacked_bytes(&self) -> usize {
+        self.unacked_bytes.load(Ordering::SeqCst)
+    }
+
+    /// Pending (queued or running) incremental scan tasks registered on
+    /// this connection. See `scan_task_count`'s doc comment.
+    pub fn scan_task_count(&self) -> isize {
+        self.scan_task_count.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn scan_task_count_handle(&self) -> Arc<AtomicIsize> {
+        self.scan_task_count.clone()
+    }
+
     pub fn check_version_and_set_feature(
         &mut self,
         version: semver::Version,
@@ -121,12 +202,27 @@ impl Conn {
         let mut features = FeatureGate::default_features(&version);
         if explicit_features.contains(&EventFeedHeaders::STREAM_MULTIPLEXING) {
             features.set(FeatureGate::STREAM_MULTIPLEXING, true);
-        } else {
-            // NOTE: we can handle more explicit features here.
+        }
+        if explicit_features.contains(&EventFeedHeaders::REGION_STATS_EVENTS) {
+            features.set(FeatureGate::REGION_STATS_EVENTS, true);
+        }
+        if explicit_features.contains(&EventFeedHeaders::EVENT_COMPRESSION) {
+            features.set(FeatureGate::EVENT_COMPRESSION, true);
         }
 
-        if self.version.replace((version, features)).is_some() {
-            panic!("should never be some");
+        if let Some((old_version, old_features)) = self.version.replace((version, features)) {
+            // A downstream can in principle renegotiate on the same
+            // connection, e.g. TiCDC rolling back to an older version and
+            // reconnecting with the same conn semantics. Re-evaluate rather
+            // than panicking: whatever the new version's feature set says
+            // takes over immediately, so an in-flight behavior the old
+            // version enabled but the new one doesn't support (e.g. batch
+            // resolved ts) gets disabled without restarting the endpoint.
+            let (new_version, new_features) = self.version.as_ref().unwrap();
+            info!("cdc connection renegotiated version";
+                "conn_id" => ?self.id,
+                "old_version" => %old_version, "new_version" => %new_version,
+                "old_features" => ?old_features, "new_features" => ?new_features);
         }
     }
 
@@ -138,6 +234,10 @@ impl Conn {
         &self.peer
     }
 
+    pub fn peer_cn(&self) -> Option<&str> {
+        self.peer_cn.as_deref()
+    }
+
     pub fn get_id(&self) -> ConnId {
         self.id
     }
@@ -222,7 +322,13 @@ struct EventFeedHeaders {
 impl EventFeedHeaders {
     const FEATURES_KEY: &'static str = "features";
     const STREAM_MULTIPLEXING: &'static str = "stream-multiplexing";
-    const FEATURES: &'static [&'static str] = &[Self::STREAM_MULTIPLEXING];
+    const REGION_STATS_EVENTS: &'static str = "region-stats-events";
+    const EVENT_COMPRESSION: &'static str = "event-compression";
+    const FEATURES: &'static [&'static str] = &[
+        Self::STREAM_MULTIPLEXING,
+        Self::REGION_STATS_EVENTS,
+        Self::EVENT_COMPRESSION,
+    ];
 
     fn parse_features(value: &[u8]) -> Result<Vec<&'static str>, String> {
         let value = std::str::from_utf8(value).unwrap_or_default();
@@ -248,19 +354,47 @@ impl EventFeedHeaders {
 pub struct Service {
     scheduler: Scheduler<Task>,
     memory_quota: Arc<MemoryQuota>,
+    conn_memory_quota: usize,
+    sink_batch_wait_duration: Duration,
 }
 
 impl Service {
     /// Create a ChangeData service.
     ///
     /// It requires a scheduler of an `Endpoint` in order to schedule tasks.
-    pub fn new(scheduler: Scheduler<Task>, memory_quota: Arc<MemoryQuota>) -> Service {
+    /// `conn_memory_quota` bounds how many bytes a single connection may
+    /// borrow from `memory_quota` at once. `sink_batch_wait_duration` bounds
+    /// how long a connection's sink may wait to accumulate a bigger batch
+    /// of events before flushing them; see `channel::Drain::forward`.
+    pub fn new(
+        scheduler: Scheduler<Task>,
+        memory_quota: Arc<MemoryQuota>,
+        conn_memory_quota: usize,
+        sink_batch_wait_duration: Duration,
+    ) -> Service {
         Service {
             scheduler,
             memory_quota,
+            conn_memory_quota,
+            sink_batch_wait_duration,
         }
     }
 
+    /// Fetches a snapshot of this store's CDC health, e.g. for a future
+    /// status-server route -- see [`HealthReport`]'s doc comment for why
+    /// this, rather than an RPC, is the entry point for now.
+    pub async fn health_report(&self) -> Result<HealthReport> {
+        let (tx, rx) = oneshot::channel();
+        self.scheduler
+            .schedule(Task::Validate(Validate::HealthReport(Box::new(
+                move |report| {
+                    let _ = tx.send(report);
+                },
+            ))))
+            .map_err(|e| Error::Other(box_err!(e)))?;
+        rx.await.map_err(|e| Error::Other(box_err!(e)))
+    }
+
     // Parse HTTP/2 headers. Only for `Self::event_feed_v2`.
     fn parse_headers(ctx: &RpcContext<'_>) -> Result<EventFeedHeaders, String> {
         let mut header = EventFeedHeaders::default();
@@ -311,6 +445,16 @@ impl Service {
     //   region. 1) if both `request_id` and `region_id` are specified, just
     //   deregister the region; 2) if only `request_id` is specified, all region
     //   subscriptions with the same `request_id` will be deregistered.
+    //
+    // Note: there's no Pause/Resume or Ack command here yet.
+    // `Task::PauseDownstream` and `Task::ResumeDownstream` (see
+    // `cdc::endpoint`) already let a downstream stop and restart receiving
+    // events in place, without a rescan, and `Conn::ack_bytes` already lets
+    // a downstream's unacked send window shrink back down so endpoint-level
+    // backpressure (see `CdcConfig::unacked_bytes_limit`) un-pauses it. But
+    // `ChangeDataRequest_oneof_request` has no variant for a client to
+    // drive either of them over the wire -- that needs a `kvproto` change,
+    // same as the gap noted on `EventRowFilter`.
     fn handle_request(
         scheduler: &Scheduler<Task>,
         peer: &str,
@@ -343,15 +487,35 @@ impl Service {
                 );
                 ObservedRange::default()
             });
+        // `ChangeDataRequest` only carries the old `filter_loop` bool, so a
+        // wire client can't yet ask to exclude e.g. Lightning physical
+        // imports via `TxnSourceFilter::LIGHTNING_PHYSICAL_IMPORT` --
+        // `from_filter_loop` maps it onto the bits that reproduce today's
+        // behavior. Only internal callers constructing a `Downstream`
+        // directly can use the rest of the bitmask for now.
         let downstream = Downstream::new(
             peer.to_owned(),
             request.get_region_epoch().clone(),
             RequestId(request.request_id),
             conn_id,
             request.kv_api,
-            request.filter_loop,
+            crate::txn_source::TxnSourceFilter::from_filter_loop(request.filter_loop),
             observed_range,
         );
+        // `downstream.event_filter` stays at its default (nothing filtered):
+        // `ChangeDataRequest` doesn't carry a way for a client to ask for a
+        // subset of event types yet, see `EventRowFilter`.
+        //
+        // `downstream.snapshot_only` likewise stays at its default (`false`,
+        // keep streaming deltas): `ChangeDataRequest` has no field for a
+        // client to request scan-only mode yet, see its doc comment. Today
+        // it can only be set by an internal caller that builds its own
+        // `Downstream` before scheduling `Task::Register`/`Task::Snapshot`.
+        //
+        // `downstream.resource_group_name` likewise stays at its default
+        // (the empty string, i.e. the default resource group):
+        // `ChangeDataRequest` carries no resource control context yet, see
+        // `Downstream::resource_group_name`'s doc comment.
         let task = Task::Register {
             request,
             downstream,
@@ -401,9 +565,15 @@ impl Service {
         event_feed_v2: bool,
     ) {
         sink.enhance_batch(true);
-        let (event_sink, mut event_drain) =
-            channel(CDC_CHANNLE_CAPACITY, self.memory_quota.clone());
-        let conn = Conn::new(event_sink, ctx.peer());
+        let conn_memory_quota = Arc::new(MemoryQuota::new(self.conn_memory_quota));
+        let (event_sink, mut event_drain) = channel(
+            CDC_CHANNLE_CAPACITY,
+            self.memory_quota.clone(),
+            conn_memory_quota,
+        );
+        event_drain.set_batch_wait_duration(self.sink_batch_wait_duration);
+        let peer_cn = security::get_peer_cn(&ctx);
+        let conn = Conn::new(event_sink, ctx.peer(), peer_cn);
         let conn_id = conn.get_id();
         let mut explicit_features = vec![];
 
@@ -531,7 +701,7 @@ mod tests {
     fn new_rpc_suite(capacity: usize) -> (Server, ChangeDataClient, ReceiverWrapper<Task>) {
         let memory_quota = Arc::new(MemoryQuota::new(capacity));
         let (scheduler, rx) = dummy_scheduler();
-        let cdc_service = Service::new(scheduler, memory_quota);
+        let cdc_service = Service::new(scheduler, memory_quota, capacity, Duration::ZERO);
         let env = Arc::new(EnvBuilder::new().build());
         let builder =
             ServerBuilder::new(env.clone()).register_service(create_change_data(cdc_service));
@@ -544,6 +714,42 @@ mod tests {
         (server, client, rx)
     }
 
+    #[test]
+    fn test_region_stats_events_feature_gate() {
+        // Not requested: stays off even on a new-enough client.
+        let mut conn = Conn::new(new_dummy_sink(), String::new(), None);
+        conn.check_version_and_set_feature(semver::Version::new(8, 0, 0), vec![]);
+        assert!(!conn.features().contains(FeatureGate::REGION_STATS_EVENTS));
+
+        // Requested via the `features` header: turned on regardless of version.
+        let mut conn = Conn::new(new_dummy_sink(), String::new(), None);
+        let explicit_features =
+            EventFeedHeaders::parse_features(b"region-stats-events").unwrap();
+        conn.check_version_and_set_feature(semver::Version::new(4, 0, 0), explicit_features);
+        assert!(conn.features().contains(FeatureGate::REGION_STATS_EVENTS));
+    }
+
+    #[test]
+    fn test_renegotiate_version_disables_unsupported_features() {
+        let mut conn = Conn::new(new_dummy_sink(), String::new(), None);
+        conn.check_version_and_set_feature(semver::Version::new(5, 3, 0), vec![]);
+        assert!(conn.features().contains(FeatureGate::BATCH_RESOLVED_TS));
+        assert!(conn.features().contains(FeatureGate::VALIDATE_CLUSTER_ID));
+
+        // Renegotiating on an older version (e.g. the downstream rolled
+        // back) re-evaluates features instead of panicking, dropping ones
+        // the older version doesn't support.
+        conn.check_version_and_set_feature(semver::Version::new(4, 0, 0), vec![]);
+        assert!(!conn.features().contains(FeatureGate::BATCH_RESOLVED_TS));
+        assert!(!conn.features().contains(FeatureGate::VALIDATE_CLUSTER_ID));
+    }
+
+    fn new_dummy_sink() -> Sink {
+        let memory_quota = Arc::new(MemoryQuota::new(usize::MAX));
+        let (sink, _drain) = crate::channel::channel(1, memory_quota.clone(), memory_quota);
+        sink
+    }
+
     #[test]
     fn test_flow_control() {
         // Disable CDC sink memory quota.
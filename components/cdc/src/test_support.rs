@@ -0,0 +1,172 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Reusable mock-endpoint test harness.
+//!
+//! This started out as a private `TestEndpointSuite` in `endpoint.rs`'s unit
+//! tests. It's promoted here, behind the `testexport` feature, so that other
+//! crates (e.g. TiCDC compatibility integration tests) can build on the same
+//! mock endpoint, channel receive helpers, and version/feature setup instead
+//! of re-implementing them.
+
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
+
+use causal_ts::CausalTsProviderImpl;
+use collections::HashMap;
+use concurrency_manager::ConcurrencyManager;
+use engine_rocks::RocksEngine;
+use grpcio::Environment;
+use kvproto::kvrpcpb::ApiVersion;
+use raftstore::{
+    errors::{DiscardReason, Error as RaftStoreError},
+    router::{CdcRaftRouter, RaftStoreRouter},
+    store::{fsm::StoreMeta, msg::CasualMessage, PeerMsg, ReadDelegate},
+};
+use resolved_ts::LeadershipResolver;
+use security::SecurityManager;
+use test_pd_client::TestPdClient;
+use test_raftstore::MockRaftStoreRouter;
+use tikv::{
+    config::{CdcConfig, ResolvedTsConfig},
+    server::DEFAULT_CLUSTER_ID,
+    storage::{kv::Engine, TestEngineBuilder},
+};
+use tikv_util::{
+    memory::MemoryQuota,
+    worker::{dummy_scheduler, ReceiverWrapper},
+};
+
+use crate::{service::ConnId, CdcObserver, CdcSubscriptionRegistry, Endpoint, Task};
+
+pub fn set_conn_version_task(conn_id: ConnId, version: semver::Version) -> Task {
+    Task::SetConnVersion {
+        conn_id,
+        version,
+        explicit_features: vec![],
+    }
+}
+
+pub struct TestEndpointSuite {
+    // The order must ensure `endpoint` be dropped before other fields.
+    pub endpoint: Endpoint<CdcRaftRouter<MockRaftStoreRouter>, RocksEngine, StoreMeta>,
+    pub cdc_handle: CdcRaftRouter<MockRaftStoreRouter>,
+    pub task_rx: ReceiverWrapper<Task>,
+    pub raft_rxs: HashMap<u64, tikv_util::mpsc::Receiver<PeerMsg<RocksEngine>>>,
+    pub leader_resolver: Option<LeadershipResolver>,
+}
+
+impl TestEndpointSuite {
+    // It's important to matain raft receivers in `raft_rxs`, otherwise all cases
+    // need to drop `endpoint` and `rx` in order manually.
+    pub fn add_region(&mut self, region_id: u64, cap: usize) {
+        let rx = self.cdc_handle.add_region(region_id, cap);
+        self.raft_rxs.insert(region_id, rx);
+        self.add_local_reader(region_id);
+    }
+
+    pub fn add_local_reader(&self, region_id: u64) {
+        self.store_meta
+            .lock()
+            .unwrap()
+            .readers
+            .insert(region_id, ReadDelegate::mock(region_id));
+    }
+
+    pub fn fill_raft_rx(&self, region_id: u64) {
+        let router = &self.cdc_handle;
+        loop {
+            match router.send_casual_msg(region_id, CasualMessage::ClearRegionSize) {
+                Ok(_) => continue,
+                Err(RaftStoreError::Transport(DiscardReason::Full)) => break,
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    pub fn raft_rx(&self, region_id: u64) -> &tikv_util::mpsc::Receiver<PeerMsg<RocksEngine>> {
+        self.raft_rxs.get(&region_id).unwrap()
+    }
+}
+
+impl Deref for TestEndpointSuite {
+    type Target = Endpoint<CdcRaftRouter<MockRaftStoreRouter>, RocksEngine, StoreMeta>;
+    fn deref(&self) -> &Self::Target {
+        &self.endpoint
+    }
+}
+
+impl DerefMut for TestEndpointSuite {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.endpoint
+    }
+}
+
+pub fn mock_endpoint(
+    cfg: &CdcConfig,
+    engine: Option<RocksEngine>,
+    api_version: ApiVersion,
+) -> TestEndpointSuite {
+    mock_endpoint_with_ts_provider(cfg, engine, api_version, None)
+}
+
+pub fn mock_endpoint_with_ts_provider(
+    cfg: &CdcConfig,
+    engine: Option<RocksEngine>,
+    api_version: ApiVersion,
+    causal_ts_provider: Option<Arc<CausalTsProviderImpl>>,
+) -> TestEndpointSuite {
+    let (task_sched, task_rx) = dummy_scheduler();
+    let cdc_handle = CdcRaftRouter(MockRaftStoreRouter::new());
+    let mut store_meta = StoreMeta::new(0);
+    store_meta.store_id = Some(1);
+    let region_read_progress = store_meta.region_read_progress.clone();
+    let pd_client = Arc::new(TestPdClient::new(0, true));
+    let env = Arc::new(Environment::new(1));
+    let security_mgr = Arc::new(SecurityManager::default());
+    let store_resolver_gc_interval = Duration::from_secs(60);
+    let leader_resolver = LeadershipResolver::new(
+        1,
+        pd_client.clone(),
+        env.clone(),
+        security_mgr.clone(),
+        region_read_progress,
+        store_resolver_gc_interval,
+    );
+    let ep = Endpoint::new(
+        DEFAULT_CLUSTER_ID,
+        cfg,
+        &ResolvedTsConfig::default(),
+        false,
+        api_version,
+        pd_client,
+        task_sched.clone(),
+        cdc_handle.clone(),
+        tikv::storage::kv::LocalTablets::Singleton(engine.unwrap_or_else(|| {
+            TestEngineBuilder::new()
+                .build_without_cache()
+                .unwrap()
+                .kv_engine()
+                .unwrap()
+        })),
+        CdcObserver::new(task_sched),
+        Arc::new(StdMutex::new(store_meta)),
+        ConcurrencyManager::new(1.into()),
+        env,
+        security_mgr,
+        Arc::new(MemoryQuota::new(usize::MAX)),
+        causal_ts_provider,
+        CdcSubscriptionRegistry::new(),
+        None,
+    );
+
+    TestEndpointSuite {
+        endpoint: ep,
+        cdc_handle,
+        task_rx,
+        raft_rxs: HashMap::default(),
+        leader_resolver: Some(leader_resolver),
+    }
+}
@@ -37,7 +37,6 @@ impl TxnSource {
         self.0 |= value;
     }
 
-    #[cfg(test)]
     pub(crate) fn get_cdc_write_source(&self) -> u64 {
         self.0 & CDC_WRITE_SOURCE_MAX
     }
@@ -70,6 +69,59 @@ impl From<TxnSource> for u64 {
     }
 }
 
+// TiDB Lightning's physical-import mode stamps writes with this value in
+// the CDC_WRITE_SOURCE byte, from the 16-255 range the doc comment above
+// reserves for extendability. Nothing else in this tree names it, so this
+// is this crate's own label for it, not a value kvproto defines.
+const LIGHTNING_PHYSICAL_IMPORT_SOURCE: u64 = 16;
+
+bitflags::bitflags! {
+    /// Which kinds of write, identified by the bits `TxnSource` already
+    /// encodes, a downstream does not want echoed back to it. Generalizes
+    /// the old `filter_loop: bool` (now [`TxnSourceFilter::CDC_WRITE_LOOP`])
+    /// so a downstream can also exclude other uninteresting sources, e.g. a
+    /// TiDB Lightning physical import it has no business replaying.
+    pub struct TxnSourceFilter: u64 {
+        /// Writes whose CDC_WRITE_SOURCE byte is set, i.e. they were
+        /// produced by replaying another changefeed's events back into
+        /// TiKV -- excluding these is what `filter_loop` used to mean.
+        const CDC_WRITE_LOOP = 0b001;
+        /// Column-reorg backfill writes from a lossy DDL. Exposed here for
+        /// the incremental-scan path (`Delegate::convert_to_grpc_events`);
+        /// the live-apply path (`Delegate::sink_downstream_tidb`) already
+        /// drops these for every downstream unconditionally, so this bit is
+        /// a no-op there.
+        const LOSSY_DDL_REORG = 0b010;
+        /// Writes from a TiDB Lightning physical import
+        /// (see [`LIGHTNING_PHYSICAL_IMPORT_SOURCE`]).
+        const LIGHTNING_PHYSICAL_IMPORT = 0b100;
+    }
+}
+
+impl TxnSourceFilter {
+    /// The filter equivalent to the old `filter_loop: bool`: always
+    /// excludes lossy DDL reorg writes, and excludes write-loop writes only
+    /// if `filter_loop` was set.
+    pub(crate) fn from_filter_loop(filter_loop: bool) -> TxnSourceFilter {
+        let mut filter = TxnSourceFilter::LOSSY_DDL_REORG;
+        if filter_loop {
+            filter |= TxnSourceFilter::CDC_WRITE_LOOP;
+        }
+        filter
+    }
+
+    /// Whether `txn_source` matches a source this filter excludes.
+    pub(crate) fn filter(self, txn_source: u64) -> bool {
+        (self.contains(TxnSourceFilter::LOSSY_DDL_REORG)
+            && TxnSource::is_lossy_ddl_reorg_source_set(txn_source))
+            || (self.contains(TxnSourceFilter::CDC_WRITE_LOOP)
+                && TxnSource::is_cdc_write_source_set(txn_source))
+            || (self.contains(TxnSourceFilter::LIGHTNING_PHYSICAL_IMPORT)
+                && TxnSource(txn_source).get_cdc_write_source()
+                    == LIGHTNING_PHYSICAL_IMPORT_SOURCE)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,4 +165,36 @@ mod tests {
             false
         );
     }
+
+    #[test]
+    fn test_txn_source_filter_from_filter_loop() {
+        let mut loopback = TxnSource::default();
+        loopback.set_cdc_write_source(1);
+        let mut lossy_ddl = TxnSource::default();
+        lossy_ddl.set_lossy_ddl_reorg_source(LOSSY_DDL_COLUMN_REORG_SOURCE);
+
+        // filter_loop = false still drops lossy DDL, but not loopback writes.
+        let filter = TxnSourceFilter::from_filter_loop(false);
+        assert!(!filter.filter(loopback.0));
+        assert!(filter.filter(lossy_ddl.0));
+
+        // filter_loop = true drops both.
+        let filter = TxnSourceFilter::from_filter_loop(true);
+        assert!(filter.filter(loopback.0));
+        assert!(filter.filter(lossy_ddl.0));
+    }
+
+    #[test]
+    fn test_txn_source_filter_lightning_physical_import() {
+        let mut lightning = TxnSource::default();
+        lightning.set_cdc_write_source(LIGHTNING_PHYSICAL_IMPORT_SOURCE);
+
+        // `from_filter_loop(false)` only drops lossy DDL, so a Lightning
+        // import's write source (nonzero, so only `CDC_WRITE_LOOP` or
+        // `LIGHTNING_PHYSICAL_IMPORT` would catch it) passes through it
+        // untouched.
+        assert!(!TxnSourceFilter::from_filter_loop(false).filter(lightning.0));
+        // Asking specifically for LIGHTNING_PHYSICAL_IMPORT catches it.
+        assert!(TxnSourceFilter::LIGHTNING_PHYSICAL_IMPORT.filter(lightning.0));
+    }
 }
@@ -192,7 +192,12 @@ impl TestSuiteBuilder {
                 .entry(id)
                 .or_default()
                 .push(Box::new(move || {
-                    create_change_data(cdc::Service::new(scheduler.clone(), memory_quota_.clone()))
+                    create_change_data(cdc::Service::new(
+                        scheduler.clone(),
+                        memory_quota_.clone(),
+                        memory_quota,
+                        Duration::ZERO,
+                    ))
                 }));
             sim.txn_extra_schedulers.insert(
                 id,
@@ -235,6 +240,8 @@ impl TestSuiteBuilder {
                 sim.security_mgr.clone(),
                 quotas[id].clone(),
                 sim.get_causal_ts_provider(*id),
+                cdc::CdcSubscriptionRegistry::new(),
+                None,
             );
             let mut updated_cfg = cfg.clone();
             updated_cfg.min_ts_interval = ReadableDuration::millis(100);
@@ -15,4 +15,14 @@ impl MvccPropertiesExt for PanicEngine {
     ) -> Option<MvccProperties> {
         panic!()
     }
+
+    fn get_mvcc_properties_cf_by_level(
+        &self,
+        cf: &str,
+        safe_point: TimeStamp,
+        start_key: &[u8],
+        end_key: &[u8],
+    ) -> Option<Vec<MvccProperties>> {
+        panic!()
+    }
 }
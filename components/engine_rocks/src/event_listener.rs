@@ -1,5 +1,7 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::sync::atomic::Ordering;
+
 use engine_traits::PersistenceListener;
 use file_system::{get_io_type, set_io_type, IoType};
 use regex::Regex;
@@ -9,7 +11,7 @@ use rocksdb::{
 };
 use tikv_util::{error, metrics::CRITICAL_ERROR, set_panic_mark, warn, worker::Scheduler};
 
-use crate::rocks_metrics::*;
+use crate::{properties::GLOBAL_WRITE_STALLED, rocks_metrics::*};
 
 // Message for RocksDB status subcode kNoSpace.
 const NO_SPACE_ERROR: &str = "IO error: No space left on device";
@@ -163,6 +165,10 @@ impl rocksdb::EventListener for RocksEventListener {
         STORE_ENGINE_EVENT_COUNTER_VEC
             .with_label_values(&[&self.db_name, info.cf_name(), "stall_conditions_changed"])
             .inc();
+        // Whichever way the condition changed, have the next SST built by any
+        // CF sample its entries rather than examining each one, to shed some
+        // of the ingest-path CPU cost while the store is under pressure.
+        GLOBAL_WRITE_STALLED.store(true, Ordering::Relaxed);
     }
 }
 
@@ -2,10 +2,11 @@
 
 use engine_traits::{ImportExt, IngestExternalFileOptions, Result};
 use rocksdb::IngestExternalFileOptions as RawIngestExternalFileOptions;
-use tikv_util::time::Instant;
+use tikv_util::{info, time::Instant};
 
 use crate::{
-    engine::RocksEngine, perf_context_metrics::INGEST_EXTERNAL_FILE_TIME_HISTOGRAM, r2e, util,
+    engine::RocksEngine, perf_context_metrics::INGEST_EXTERNAL_FILE_TIME_HISTOGRAM, r2e,
+    rocks_metrics::STORE_ENGINE_INGEST_MISSING_PROPERTIES_VEC, sst::RocksSstReader, util,
 };
 
 impl ImportExt for RocksEngine {
@@ -40,10 +41,35 @@ impl ImportExt for RocksEngine {
                 .block
                 .observe(time_cost);
         }
+        self.report_missing_properties(cf_name, files);
         Ok(())
     }
 }
 
+impl RocksEngine {
+    /// Externally ingested SSTs (e.g. from BR/Lightning) are built outside
+    /// of TiKV and may lack the `tikv.*` user properties that TiKV's own
+    /// compaction and flush always attach. Ranges missing them silently fall
+    /// back to non-property-based GC and ts-filter, so just count them for
+    /// now; a follow-up can add a background property backfill.
+    fn report_missing_properties(&self, cf_name: &str, files: &[&str]) {
+        for path in files {
+            let has_props = match RocksSstReader::open_with_env(path, None) {
+                Ok(reader) => reader.has_tikv_properties(),
+                Err(e) => {
+                    info!("failed to open ingested sst to check properties"; "file" => path, "err" => ?e);
+                    continue;
+                }
+            };
+            if !has_props {
+                STORE_ENGINE_INGEST_MISSING_PROPERTIES_VEC
+                    .with_label_values(&[self.as_inner().path(), cf_name])
+                    .inc();
+            }
+        }
+    }
+}
+
 pub struct RocksIngestExternalFileOptions(RawIngestExternalFileOptions);
 
 impl IngestExternalFileOptions for RocksIngestExternalFileOptions {
@@ -1,11 +1,30 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
 use engine_traits::{MvccProperties, MvccPropertiesExt, Result};
+use tikv_util::{
+    codec::number::{self, NumberEncoder},
+    warn,
+};
 use txn_types::TimeStamp;
 
-use crate::{decode_properties::DecodeProperties, RocksEngine, RocksTtlProperties, UserProperties};
+use crate::{
+    decode_properties::DecodeProperties, util, RocksEngine, RocksTtlProperties, UserProperties,
+};
 
 pub const PROP_NUM_ERRORS: &str = "tikv.num_errors";
+pub const PROP_SCHEMA_VERSION: &str = "tikv.mvcc_schema_version";
+
+/// Current version of the `tikv.*` property set written by
+/// [`RocksMvccProperties::encode`].
+///
+/// Bump this whenever a property is added whose absence can't simply be
+/// treated as "old file, use a sane default" -- i.e. when a reader needs to
+/// tell "this field is genuinely zero" apart from "this file predates the
+/// field" in a way a per-field `unwrap_or` (see `PROP_NUM_DELETES` below)
+/// can't express on its own. Most additions, like [`PROP_COMMIT_TS_HIST`],
+/// don't need a bump: a missing histogram is unambiguous and callers already
+/// treat `decode_commit_ts_hist`'s `Err` as "no histogram available".
+pub const CURRENT_SCHEMA_VERSION: u64 = 1;
 pub const PROP_MIN_TS: &str = "tikv.min_ts";
 pub const PROP_MAX_TS: &str = "tikv.max_ts";
 pub const PROP_NUM_ROWS: &str = "tikv.num_rows";
@@ -16,11 +35,58 @@ pub const PROP_MAX_ROW_VERSIONS: &str = "tikv.max_row_versions";
 pub const PROP_ROWS_INDEX: &str = "tikv.rows_index";
 pub const PROP_ROWS_INDEX_DISTANCE: u64 = 10000;
 
+pub const PROP_COMMIT_TS_HIST: &str = "tikv.commit_ts_hist";
+
+/// Number of buckets in a [`CommitTsHistogram`].
+pub const COMMIT_TS_HIST_BUCKETS: usize = 16;
+
+/// Width, in milliseconds of TSO physical time, of a single commit-ts
+/// histogram bucket.
+pub const COMMIT_TS_HIST_BUCKET_MILLIS: u64 = 60 * 60 * 1000; // 1 hour
+
+/// A coarse, fixed-bucket histogram of the physical-time component of
+/// commit timestamps seen in an SST file's write CF. It lets `ts_filter`
+/// (see `engine_rocks::options::TsFilter`) and BR incremental backup skip a
+/// file more aggressively than a plain `min_ts`/`max_ts` range check can,
+/// by telling them apart a file with a gap in its commit history from one
+/// that's densely populated throughout, at the cost of some false
+/// positives (a non-empty bucket doesn't guarantee the queried range is
+/// actually present).
+///
+/// A commit ts falls into `commit_ts_hist_bucket(ts)`, so two commits more
+/// than `COMMIT_TS_HIST_BUCKETS * COMMIT_TS_HIST_BUCKET_MILLIS` (16 hours)
+/// apart can alias into the same bucket. Callers should only trust an
+/// empty bucket for a range that's already known, from this file's own
+/// `min_ts`/`max_ts`, to fit inside one aliasing period.
+pub type CommitTsHistogram = [u32; COMMIT_TS_HIST_BUCKETS];
+
+pub fn commit_ts_hist_bucket(ts: TimeStamp) -> usize {
+    ((ts.physical() / COMMIT_TS_HIST_BUCKET_MILLIS) % COMMIT_TS_HIST_BUCKETS as u64) as usize
+}
+
+pub fn encode_commit_ts_hist(props: &mut UserProperties, hist: &CommitTsHistogram) {
+    let mut buf = Vec::with_capacity(COMMIT_TS_HIST_BUCKETS * 4);
+    for count in hist {
+        buf.encode_u32(*count).unwrap();
+    }
+    props.insert(PROP_COMMIT_TS_HIST.as_bytes().to_owned(), buf);
+}
+
+pub fn decode_commit_ts_hist<T: DecodeProperties>(props: &T) -> Result<CommitTsHistogram> {
+    let mut buf = props.decode(PROP_COMMIT_TS_HIST)?;
+    let mut hist = [0u32; COMMIT_TS_HIST_BUCKETS];
+    for slot in &mut hist {
+        *slot = number::decode_u32(&mut buf)?;
+    }
+    Ok(hist)
+}
+
 pub struct RocksMvccProperties;
 
 impl RocksMvccProperties {
     pub fn encode(mvcc_props: &MvccProperties) -> UserProperties {
         let mut props = UserProperties::new();
+        props.encode_u64(PROP_SCHEMA_VERSION, CURRENT_SCHEMA_VERSION);
         props.encode_u64(PROP_MIN_TS, mvcc_props.min_ts.into_inner());
         props.encode_u64(PROP_MAX_TS, mvcc_props.max_ts.into_inner());
         props.encode_u64(PROP_NUM_ROWS, mvcc_props.num_rows);
@@ -32,7 +98,28 @@ impl RocksMvccProperties {
         props
     }
 
+    /// Decodes a `tikv.*` property set written by a TiKV of any version.
+    ///
+    /// Properties this build doesn't recognize -- written by a newer TiKV
+    /// that's added fields since, or belonging to an unrelated property
+    /// family sharing the same SST -- are naturally preserved rather than
+    /// rejected: `props` is only ever looked up by the specific keys below,
+    /// never enumerated, so an unknown key just never gets read. A schema
+    /// version newer than this build understands is logged, not treated as
+    /// an error, on the assumption that new fields keep following the same
+    /// "absence has a sane default" contract the existing ones do.
     pub fn decode<T: DecodeProperties>(props: &T) -> Result<MvccProperties> {
+        // Missing entirely on a file written before schema versioning existed.
+        let version = props.decode_u64(PROP_SCHEMA_VERSION).unwrap_or(0);
+        if version > CURRENT_SCHEMA_VERSION {
+            warn!(
+                "mvcc properties schema version is newer than this binary understands, \
+                 decoding only the fields this version knows about";
+                "file_version" => version,
+                "current_version" => CURRENT_SCHEMA_VERSION,
+            );
+        }
+
         let mut res = MvccProperties::new();
         res.min_ts = props.decode_u64(PROP_MIN_TS)?.into();
         res.max_ts = props.decode_u64(PROP_MAX_TS)?.into();
@@ -75,4 +162,41 @@ impl MvccPropertiesExt for RocksEngine {
         }
         Some(props)
     }
+
+    fn get_mvcc_properties_cf_by_level(
+        &self,
+        cf: &str,
+        safe_point: TimeStamp,
+        start_key: &[u8],
+        end_key: &[u8],
+    ) -> Option<Vec<MvccProperties>> {
+        let collection = match self.get_range_properties_cf(cf, start_key, end_key) {
+            Ok(c) if !c.is_empty() => c,
+            _ => return None,
+        };
+        let handle = util::get_cf_handle(self.as_inner(), cf).ok()?;
+        let levels = self.as_inner().get_column_family_meta_data(handle);
+        let levels = levels.get_levels();
+        let mut props_by_level = vec![MvccProperties::new(); levels.len()];
+        for (file_path, v) in collection.iter() {
+            let mvcc = match RocksMvccProperties::decode(v.user_collected_properties()) {
+                Ok(m) => m,
+                Err(_) => return None,
+            };
+            // Filter out properties after safe_point.
+            if mvcc.min_ts > safe_point {
+                continue;
+            }
+            // The SST file may have already been compacted away by the time we read
+            // the level metadata; just skip accounting for it in that case, the
+            // total (via `get_mvcc_properties_cf`) isn't affected.
+            let level = levels
+                .iter()
+                .position(|l| l.get_files().iter().any(|f| file_path.ends_with(f.get_name())));
+            if let Some(level) = level {
+                props_by_level[level].add(&mvcc);
+            }
+        }
+        Some(props_by_level)
+    }
 }
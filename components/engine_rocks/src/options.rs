@@ -1,9 +1,20 @@
 // Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::cmp;
+
 use rocksdb::{
     ReadOptions as RawReadOptions, TableFilter, TableProperties, WriteOptions as RawWriteOptions,
 };
 use tikv_util::codec::number;
+use txn_types::TimeStamp;
+
+use crate::{
+    mvcc_properties::{
+        commit_ts_hist_bucket, decode_commit_ts_hist, COMMIT_TS_HIST_BUCKETS,
+        COMMIT_TS_HIST_BUCKET_MILLIS,
+    },
+    properties::UserCollectedPropertiesDecoder,
+};
 
 pub struct RocksReadOptions(RawReadOptions);
 
@@ -117,25 +128,53 @@ impl TableFilter for TsFilter {
 
         let user_props = props.user_collected_properties();
 
-        if let Some(hint_min_ts) = self.hint_min_ts {
-            // TODO avoid hard code after refactor MvccProperties from
-            // tikv/src/raftstore/coprocessor/ into some component about engine.
-            if let Some(mut p) = user_props.get("tikv.max_ts") {
-                if let Ok(get_max) = number::decode_u64(&mut p) {
-                    if get_max < hint_min_ts {
-                        return false;
-                    }
-                }
+        // TODO avoid hard code after refactor MvccProperties from
+        // tikv/src/raftstore/coprocessor/ into some component about engine.
+        let file_max_ts = user_props
+            .get("tikv.max_ts")
+            .and_then(|mut p| number::decode_u64(&mut p).ok());
+        let file_min_ts = user_props
+            .get("tikv.min_ts")
+            .and_then(|mut p| number::decode_u64(&mut p).ok());
+
+        if let (Some(hint_min_ts), Some(file_max_ts)) = (self.hint_min_ts, file_max_ts) {
+            if file_max_ts < hint_min_ts {
+                return false;
+            }
+        }
+
+        if let (Some(hint_max_ts), Some(file_min_ts)) = (self.hint_max_ts, file_min_ts) {
+            if file_min_ts > hint_max_ts {
+                return false;
             }
         }
 
-        if let Some(hint_max_ts) = self.hint_max_ts {
-            // TODO avoid hard code after refactor MvccProperties from
-            // tikv/src/raftstore/coprocessor/ into some component about engine.
-            if let Some(mut p) = user_props.get("tikv.min_ts") {
-                if let Ok(get_min) = number::decode_u64(&mut p) {
-                    if get_min > hint_max_ts {
-                        return false;
+        // The min/max check above can't tell a file with a gap in its
+        // commit-ts range from one that's densely populated throughout; the
+        // coarse per-file commit-ts histogram can. Only consult it when this
+        // file's own span fits within one aliasing period, since outside of
+        // that the bucket-to-time mapping is ambiguous.
+        if let (Some(file_min_ts), Some(file_max_ts)) = (file_min_ts, file_max_ts) {
+            let span =
+                TimeStamp::from(file_max_ts).physical() - TimeStamp::from(file_min_ts).physical();
+            if span < COMMIT_TS_HIST_BUCKETS as u64 * COMMIT_TS_HIST_BUCKET_MILLIS {
+                let lo = self.hint_min_ts.map_or(file_min_ts, |t| cmp::max(t, file_min_ts));
+                let hi = self.hint_max_ts.map_or(file_max_ts, |t| cmp::min(t, file_max_ts));
+                if lo <= hi {
+                    if let Ok(hist) =
+                        decode_commit_ts_hist(&UserCollectedPropertiesDecoder(user_props))
+                    {
+                        let lo_bucket = commit_ts_hist_bucket(TimeStamp::from(lo));
+                        let hi_bucket = commit_ts_hist_bucket(TimeStamp::from(hi));
+                        // `span` was checked above, so [lo, hi] covers less
+                        // than one full cycle of buckets; it can still wrap
+                        // past the last bucket, and then the histogram is
+                        // not consulted.
+                        if lo_bucket <= hi_bucket
+                            && hist[lo_bucket..=hi_bucket].iter().all(|&c| c == 0)
+                        {
+                            return false;
+                        }
                     }
                 }
             }
@@ -5,6 +5,7 @@ use std::{
     collections::HashMap,
     io::Read,
     ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, Ordering},
     u64,
 };
 
@@ -30,9 +31,21 @@ use crate::{
 
 const PROP_TOTAL_SIZE: &str = "tikv.total_size";
 const PROP_SIZE_INDEX: &str = "tikv.size_index";
-const PROP_RANGE_INDEX: &str = "tikv.range_index";
+pub(crate) const PROP_RANGE_INDEX: &str = "tikv.range_index";
+pub(crate) const PROP_RANGE_SAMPLED: &str = "tikv.range_sampled";
 pub const DEFAULT_PROP_SIZE_INDEX_DISTANCE: u64 = 4 * 1024 * 1024;
 pub const DEFAULT_PROP_KEYS_INDEX_DISTANCE: u64 = 40 * 1024;
+// Only examine one key out of every `SAMPLED_MODE_STRIDE` while the store is
+// write-stalled, scaling the examined entry's size and key count up to keep
+// the running totals in `RangeOffsets` an unbiased (but less precise)
+// estimate.
+const SAMPLED_MODE_STRIDE: u64 = 8;
+
+/// Set by [`crate::event_listener::RocksEventListener`] when RocksDB reports
+/// a write-stall condition change. The next [`RangePropertiesCollector`]
+/// created afterwards switches to sampled mode for the SST it's building,
+/// then this is left alone again until the next stall signal arrives.
+pub static GLOBAL_WRITE_STALLED: AtomicBool = AtomicBool::new(false);
 
 fn get_entry_size(value: &[u8], entry_type: DBEntryType) -> std::result::Result<u64, ()> {
     match entry_type {
@@ -140,6 +153,12 @@ pub struct RangeOffsets {
 #[derive(Debug, Default)]
 pub struct RangeProperties {
     pub offsets: Vec<(Vec<u8>, RangeOffsets)>,
+    /// Whether `offsets` was built in sampled mode, i.e. the collector only
+    /// examined a fraction of the entries and scaled up what it saw. Callers
+    /// that need exact numbers (rather than just a cheap estimate) should
+    /// treat the distances computed from a sampled file as rougher than
+    /// usual.
+    pub sampled: bool,
 }
 
 impl RangeProperties {
@@ -158,6 +177,9 @@ impl RangeProperties {
         }
         let mut props = UserProperties::new();
         props.encode(PROP_RANGE_INDEX, buf);
+        if self.sampled {
+            props.encode_u64(PROP_RANGE_SAMPLED, 1);
+        }
         props
     }
 
@@ -185,6 +207,7 @@ impl RangeProperties {
             };
             res.offsets.push((k, offsets));
         }
+        res.sampled = props.decode_u64(PROP_RANGE_SAMPLED).unwrap_or(0) != 0;
         Ok(res)
     }
 
@@ -287,6 +310,10 @@ pub struct RangePropertiesCollector {
     cur_offsets: RangeOffsets,
     prop_size_index_distance: u64,
     prop_keys_index_distance: u64,
+    // Sampled mode: only one out of every `SAMPLED_MODE_STRIDE` entries is
+    // examined, with its size and key count scaled up to estimate the rest.
+    sampled: bool,
+    entries_seen: u64,
 }
 
 impl Default for RangePropertiesCollector {
@@ -298,6 +325,8 @@ impl Default for RangePropertiesCollector {
             cur_offsets: RangeOffsets::default(),
             prop_size_index_distance: DEFAULT_PROP_SIZE_INDEX_DISTANCE,
             prop_keys_index_distance: DEFAULT_PROP_KEYS_INDEX_DISTANCE,
+            sampled: false,
+            entries_seen: 0,
         }
     }
 }
@@ -327,14 +356,19 @@ impl RangePropertiesCollector {
 
 impl TablePropertiesCollector for RangePropertiesCollector {
     fn add(&mut self, key: &[u8], value: &[u8], entry_type: DBEntryType, _: u64, _: u64) {
+        self.entries_seen += 1;
+        if self.sampled && self.entries_seen % SAMPLED_MODE_STRIDE != 0 {
+            return;
+        }
         // size
         let size = match get_entry_size(value, entry_type) {
             Ok(entry_size) => key.len() as u64 + entry_size,
             Err(_) => return,
         };
-        self.cur_offsets.size += size;
+        let weight = if self.sampled { SAMPLED_MODE_STRIDE } else { 1 };
+        self.cur_offsets.size += size * weight;
         // keys
-        self.cur_offsets.keys += 1;
+        self.cur_offsets.keys += weight;
         // Add the start key for convenience.
         if self.last_key.is_empty()
             || self.size_in_last_range() >= self.prop_size_index_distance
@@ -351,6 +385,7 @@ impl TablePropertiesCollector for RangePropertiesCollector {
             let key = self.last_key.clone();
             self.insert_new_point(key);
         }
+        self.props.sampled = self.sampled;
         self.props.encode().0
     }
 }
@@ -371,7 +406,12 @@ impl Default for RangePropertiesCollectorFactory {
 
 impl TablePropertiesCollectorFactory<RangePropertiesCollector> for RangePropertiesCollectorFactory {
     fn create_table_properties_collector(&mut self, _: u32) -> RangePropertiesCollector {
-        RangePropertiesCollector::new(self.prop_size_index_distance, self.prop_keys_index_distance)
+        let mut collector =
+            RangePropertiesCollector::new(self.prop_size_index_distance, self.prop_keys_index_distance);
+        // Edge-triggered: sample the next SST built after a stall signal,
+        // then go back to examining every entry until the next signal.
+        collector.sampled = GLOBAL_WRITE_STALLED.swap(false, Ordering::Relaxed);
+        collector
     }
 }
 
@@ -386,6 +426,7 @@ pub struct MvccPropertiesCollector {
     row_index_handles: IndexHandles,
     key_mode: KeyMode, // Use KeyMode::Txn for both TiDB & TxnKV, KeyMode::Raw for RawKV.
     current_ts: u64,
+    commit_ts_hist: CommitTsHistogram,
 }
 
 impl MvccPropertiesCollector {
@@ -399,6 +440,7 @@ impl MvccPropertiesCollector {
             row_index_handles: IndexHandles::new(),
             key_mode,
             current_ts: ttl_current_ts(),
+            commit_ts_hist: [0; COMMIT_TS_HIST_BUCKETS],
         }
     }
 }
@@ -430,6 +472,7 @@ impl TablePropertiesCollector for MvccPropertiesCollector {
 
         self.props.min_ts = cmp::min(self.props.min_ts, ts);
         self.props.max_ts = cmp::max(self.props.max_ts, ts);
+        self.commit_ts_hist[commit_ts_hist_bucket(ts)] += 1;
         if entry_type == DBEntryType::Delete {
             // Empty value for delete entry type, skip following properties.
             return;
@@ -511,6 +554,7 @@ impl TablePropertiesCollector for MvccPropertiesCollector {
         let mut res = RocksMvccProperties::encode(&self.props);
         res.encode_u64(PROP_NUM_ERRORS, self.num_errors);
         res.encode_handles(PROP_ROWS_INDEX, &self.row_index_handles);
+        encode_commit_ts_hist(&mut res, &self.commit_ts_hist);
         res.0
     }
 }
@@ -536,6 +580,197 @@ impl TablePropertiesCollectorFactory<MvccPropertiesCollector>
     }
 }
 
+pub const PROP_NUM_RANGE_DELETIONS: &str = "tikv.num_range_deletions";
+pub const PROP_RANGE_DELETION_EXTENT: &str = "tikv.range_deletion_extent";
+
+/// Count and key extent of range-deletion tombstones (`DeleteRange`) seen
+/// while building one SST file.
+///
+/// Range deletions never show up as `Put`/`Delete` entries, so they're
+/// invisible to [`MvccPropertiesCollector`]'s row/version counters and to a
+/// CDC incremental scan, both of which assume every live change is a point
+/// entry. Recording them separately lets GC and scan planners tell a file
+/// that's mostly shadowed by range tombstones apart from one that still
+/// holds about as many live versions as its entry count suggests.
+#[derive(Debug, Default, Clone)]
+pub struct RangeTombstoneProperties {
+    pub num_range_deletions: u64,
+    pub min_start_key: Option<Vec<u8>>,
+    pub max_end_key: Option<Vec<u8>>,
+}
+
+impl RangeTombstoneProperties {
+    pub fn add(&mut self, other: &RangeTombstoneProperties) {
+        self.num_range_deletions += other.num_range_deletions;
+        self.min_start_key = match (self.min_start_key.take(), other.min_start_key.as_ref()) {
+            (Some(a), Some(b)) => Some(cmp::min(a, b.clone())),
+            (Some(a), None) => Some(a),
+            (None, b) => b.cloned(),
+        };
+        self.max_end_key = match (self.max_end_key.take(), other.max_end_key.as_ref()) {
+            (Some(a), Some(b)) => Some(cmp::max(a, b.clone())),
+            (Some(a), None) => Some(a),
+            (None, b) => b.cloned(),
+        };
+    }
+
+    pub fn encode(&self) -> UserProperties {
+        let mut props = UserProperties::new();
+        props.encode_u64(PROP_NUM_RANGE_DELETIONS, self.num_range_deletions);
+        let start = self.min_start_key.as_deref().unwrap_or(&[]);
+        let end = self.max_end_key.as_deref().unwrap_or(&[]);
+        let mut buf = Vec::with_capacity(16 + start.len() + end.len());
+        buf.encode_u64(start.len() as u64).unwrap();
+        buf.extend_from_slice(start);
+        buf.encode_u64(end.len() as u64).unwrap();
+        buf.extend_from_slice(end);
+        props.encode(PROP_RANGE_DELETION_EXTENT, buf);
+        props
+    }
+
+    pub fn decode<T: DecodeProperties>(props: &T) -> Result<RangeTombstoneProperties> {
+        let num_range_deletions = props.decode_u64(PROP_NUM_RANGE_DELETIONS)?;
+        let mut buf = props.decode(PROP_RANGE_DELETION_EXTENT)?;
+        let start_len = number::decode_u64(&mut buf)? as usize;
+        let mut start = vec![0; start_len];
+        buf.read_exact(&mut start)?;
+        let end_len = number::decode_u64(&mut buf)? as usize;
+        let mut end = vec![0; end_len];
+        buf.read_exact(&mut end)?;
+        Ok(RangeTombstoneProperties {
+            num_range_deletions,
+            min_start_key: if start.is_empty() { None } else { Some(start) },
+            max_end_key: if end.is_empty() { None } else { Some(end) },
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct RangeTombstonePropertiesCollector {
+    props: RangeTombstoneProperties,
+}
+
+impl TablePropertiesCollector for RangeTombstonePropertiesCollector {
+    fn add(&mut self, key: &[u8], value: &[u8], entry_type: DBEntryType, _: u64, _: u64) {
+        if entry_type != DBEntryType::RangeDeletion {
+            return;
+        }
+        self.props.num_range_deletions += 1;
+        if self.props.min_start_key.as_deref().map_or(true, |k| key < k) {
+            self.props.min_start_key = Some(key.to_owned());
+        }
+        if self.props.max_end_key.as_deref().map_or(true, |k| value > k) {
+            self.props.max_end_key = Some(value.to_owned());
+        }
+    }
+
+    fn finish(&mut self) -> HashMap<Vec<u8>, Vec<u8>> {
+        self.props.encode().0
+    }
+}
+
+#[derive(Default)]
+pub struct RangeTombstonePropertiesCollectorFactory {}
+
+impl TablePropertiesCollectorFactory<RangeTombstonePropertiesCollector>
+    for RangeTombstonePropertiesCollectorFactory
+{
+    fn create_table_properties_collector(&mut self, _: u32) -> RangeTombstonePropertiesCollector {
+        RangeTombstonePropertiesCollector::default()
+    }
+}
+
+pub const PROP_CREATION_REASON: &str = "tikv.creation_reason";
+
+/// How the data in one SST file was produced.
+///
+/// Ingested files (from BR/Lightning, or raftstore snapshot application) are
+/// built by [`crate::RocksSstWriter`] entirely outside of RocksDB's own
+/// flush/compaction pipeline, so they commonly lack the precise `min_ts`/
+/// `max_ts` range [`MvccPropertiesCollector`] would otherwise record. Knowing
+/// that a file is [`SstCreationReason::Ingest`] lets a consumer (CDC's
+/// ts-filter, GC) treat a missing or imprecise ts range as expected, rather
+/// than as a bug.
+///
+/// The table properties collector context this crate's `rocksdb` binding
+/// hands to [`TablePropertiesCollectorFactory::create_table_properties_collector`]
+/// only carries a column family id, not a flush-vs-compaction distinction, so
+/// both are recorded as [`SstCreationReason::Write`] for now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SstCreationReason {
+    /// Produced by RocksDB's own flush or compaction of live writes.
+    Write = 0,
+    /// Produced by [`crate::RocksSstWriter`] for later ingestion.
+    Ingest = 1,
+}
+
+impl SstCreationReason {
+    fn encode(self) -> UserProperties {
+        let mut props = UserProperties::new();
+        props.encode_u64(PROP_CREATION_REASON, self as u64);
+        props
+    }
+
+    pub fn decode<T: DecodeProperties>(props: &T) -> Result<SstCreationReason> {
+        match props.decode_u64(PROP_CREATION_REASON)? {
+            0 => Ok(SstCreationReason::Write),
+            1 => Ok(SstCreationReason::Ingest),
+            _ => Err(Error::ValueMeta),
+        }
+    }
+}
+
+/// Tags every SST file it's attached to with a fixed [`SstCreationReason`],
+/// decided once when the collector is created rather than derived from the
+/// entries it sees.
+pub struct CreationReasonPropertiesCollector {
+    reason: SstCreationReason,
+}
+
+impl TablePropertiesCollector for CreationReasonPropertiesCollector {
+    fn add(&mut self, _key: &[u8], _value: &[u8], _entry_type: DBEntryType, _: u64, _: u64) {}
+
+    fn finish(&mut self) -> HashMap<Vec<u8>, Vec<u8>> {
+        self.reason.encode().0
+    }
+}
+
+pub struct CreationReasonPropertiesCollectorFactory {
+    reason: SstCreationReason,
+}
+
+impl CreationReasonPropertiesCollectorFactory {
+    pub fn new(reason: SstCreationReason) -> Self {
+        CreationReasonPropertiesCollectorFactory { reason }
+    }
+}
+
+impl TablePropertiesCollectorFactory<CreationReasonPropertiesCollector>
+    for CreationReasonPropertiesCollectorFactory
+{
+    fn create_table_properties_collector(&mut self, _: u32) -> CreationReasonPropertiesCollector {
+        CreationReasonPropertiesCollector {
+            reason: self.reason,
+        }
+    }
+}
+
+pub fn get_range_tombstone_properties_cf(
+    engine: &crate::RocksEngine,
+    cf: &str,
+    start: &[u8],
+    end: &[u8],
+) -> Option<RangeTombstoneProperties> {
+    let range = Range::new(start, end);
+    let collection = engine.get_properties_of_tables_in_range(cf, &[range]).ok()?;
+    let mut props = RangeTombstoneProperties::default();
+    for (_, v) in collection.iter() {
+        let file_props = RangeTombstoneProperties::decode(v.user_collected_properties()).ok()?;
+        props.add(&file_props);
+    }
+    Some(props)
+}
+
 pub fn get_range_stats(
     engine: &crate::RocksEngine,
     cf: &str,
@@ -745,6 +980,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_range_properties_sampled_mode() {
+        let mut factory = RangePropertiesCollectorFactory::default();
+        GLOBAL_WRITE_STALLED.store(true, Ordering::Relaxed);
+        let mut collector = factory.create_table_properties_collector(0);
+        // The flag is edge-triggered: once consumed by the collector above,
+        // the next file should go back to examining every entry.
+        assert!(!GLOBAL_WRITE_STALLED.load(Ordering::Relaxed));
+
+        let num_entries = (SAMPLED_MODE_STRIDE * 4) as usize;
+        for i in 0..num_entries {
+            let k = format!("{:04}", i);
+            collector.add(k.as_bytes(), b"v", DBEntryType::Put, 0, 0);
+        }
+        let result = UserProperties(collector.finish());
+        let props = RangeProperties::decode(&result).unwrap();
+        assert!(props.sampled);
+        assert_eq!(
+            props.get_approximate_keys_in_range(b"", &[0xff]),
+            num_entries as u64
+        );
+
+        let mut exact_collector = factory.create_table_properties_collector(0);
+        for i in 0..num_entries {
+            let k = format!("{:04}", i);
+            exact_collector.add(k.as_bytes(), b"v", DBEntryType::Put, 0, 0);
+        }
+        let result = UserProperties(exact_collector.finish());
+        let props = RangeProperties::decode(&result).unwrap();
+        assert!(!props.sampled);
+    }
+
     #[test]
     fn test_get_range_entries_and_versions() {
         let path = Builder::new()
@@ -787,6 +1054,33 @@ mod tests {
         assert_eq!(range_stats.num_versions, cases.len() as u64);
     }
 
+    #[test]
+    fn test_range_tombstone_properties() {
+        let cases = [
+            ("ab", "ac"),
+            ("ac", "b"),
+            ("aa", "ab"),
+        ];
+        let mut collector = RangeTombstonePropertiesCollector::default();
+        for &(start, end) in &cases {
+            collector.add(
+                start.as_bytes(),
+                end.as_bytes(),
+                DBEntryType::RangeDeletion,
+                0,
+                0,
+            );
+        }
+        // Non range-deletion entries must be ignored.
+        collector.add(b"zz", b"value", DBEntryType::Put, 0, 0);
+        let result = UserProperties(collector.finish());
+
+        let props = RangeTombstoneProperties::decode(&result).unwrap();
+        assert_eq!(props.num_range_deletions, cases.len() as u64);
+        assert_eq!(props.min_start_key, Some(b"aa".to_vec()));
+        assert_eq!(props.max_end_key, Some(b"b".to_vec()));
+    }
+
     #[test]
     fn test_mvcc_properties() {
         let cases = [
@@ -817,6 +1111,44 @@ mod tests {
         assert_eq!(props.num_puts, 4);
         assert_eq!(props.num_versions, 7);
         assert_eq!(props.max_row_versions, 3);
+
+        let hist = decode_commit_ts_hist(&result).unwrap();
+        assert_eq!(hist.iter().sum::<u32>(), cases.len() as u32);
+    }
+
+    #[test]
+    fn test_mvcc_properties_schema_compatibility() {
+        let mut collector = MvccPropertiesCollector::new(KeyMode::Txn);
+        let ts = 1.into();
+        let k = Key::from_raw(b"ab").append_ts(ts);
+        let k = keys::data_key(k.as_encoded());
+        let v = Write::new(WriteType::Put, ts, None).as_ref().to_bytes();
+        collector.add(&k, &v, DBEntryType::Put, 0, 0);
+        let current = UserProperties(collector.finish());
+
+        // A file from before schema versioning and PROP_NUM_DELETES existed:
+        // both are simply absent, not zero.
+        let mut old = UserProperties(current.0.clone());
+        old.remove(PROP_SCHEMA_VERSION.as_bytes());
+        old.remove(PROP_NUM_DELETES.as_bytes());
+        let props = RocksMvccProperties::decode(&old).unwrap();
+        assert_eq!(props.num_versions, 1);
+        assert_eq!(props.num_puts, 1);
+        assert_eq!(props.num_deletes, props.num_versions - props.num_puts);
+
+        // A file at the current schema version decodes straightforwardly.
+        let props = RocksMvccProperties::decode(&current).unwrap();
+        assert_eq!(props.num_versions, 1);
+
+        // A file from a hypothetical future schema version, carrying a
+        // property this build has never heard of: still decodes every field
+        // it does know about, rather than failing outright.
+        let mut future = UserProperties(current.0.clone());
+        future.encode_u64(PROP_SCHEMA_VERSION, CURRENT_SCHEMA_VERSION + 1);
+        future.encode_u64("tikv.some_future_property", 42);
+        let props = RocksMvccProperties::decode(&future).unwrap();
+        assert_eq!(props.num_versions, 1);
+        assert_eq!(props.num_puts, 1);
     }
 
     #[test]
@@ -857,6 +1189,26 @@ mod tests {
         assert_eq!(props.ttl.min_expire_ts, Some(10));
     }
 
+    #[test]
+    fn test_creation_reason_properties() {
+        let mut collector = CreationReasonPropertiesCollectorFactory::new(SstCreationReason::Ingest)
+            .create_table_properties_collector(0);
+        collector.add(b"k", b"v", DBEntryType::Put, 0, 0);
+        let result = UserProperties(collector.finish());
+        assert_eq!(
+            SstCreationReason::decode(&result).unwrap(),
+            SstCreationReason::Ingest
+        );
+
+        let mut collector = CreationReasonPropertiesCollectorFactory::new(SstCreationReason::Write)
+            .create_table_properties_collector(0);
+        let result = UserProperties(collector.finish());
+        assert_eq!(
+            SstCreationReason::decode(&result).unwrap(),
+            SstCreationReason::Write
+        );
+    }
+
     #[bench]
     fn bench_mvcc_properties(b: &mut Bencher) {
         let ts = 1.into();
@@ -1262,6 +1262,11 @@ pub fn flush_engine_statistics(statistics: &RocksStatistics, name: &str, is_tita
 // For property metrics
 #[rustfmt::skip]
 lazy_static! {
+    pub static ref STORE_ENGINE_INGEST_MISSING_PROPERTIES_VEC: IntCounterVec = register_int_counter_vec!(
+        "tikv_engine_ingest_missing_properties",
+        "Number of externally ingested SST files that lack tikv.* user properties",
+        &["db", "cf"]
+    ).unwrap();
     pub static ref STORE_ENGINE_SIZE_GAUGE_VEC: IntGaugeVec = register_int_gauge_vec!(
         "tikv_engine_size_bytes",
         "Sizes of each column families",
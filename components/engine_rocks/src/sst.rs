@@ -15,7 +15,17 @@ use rocksdb::{
     SstFileWriter, DB,
 };
 
-use crate::{engine::RocksEngine, get_env, options::RocksReadOptions, r2e};
+use crate::{
+    engine::RocksEngine,
+    get_env,
+    mvcc_properties::PROP_NUM_VERSIONS,
+    options::RocksReadOptions,
+    properties::{
+        CreationReasonPropertiesCollectorFactory, SstCreationReason, UserCollectedPropertiesDecoder,
+        PROP_RANGE_INDEX,
+    },
+    r2e,
+};
 
 impl SstExt for RocksEngine {
     type SstReader = RocksSstReader;
@@ -45,6 +55,37 @@ impl RocksSstReader {
         });
         result
     }
+
+    /// Returns whether this SST carries any of TiKV's own `tikv.*` user
+    /// collected properties (e.g. mvcc or range properties).
+    ///
+    /// SSTs produced by TiKV's own compaction/flush always carry these, but
+    /// SSTs ingested from BR/Lightning are built externally and may lack
+    /// them entirely, which silently disables property-based GC and
+    /// ts-filter for the ranges they cover.
+    pub fn has_tikv_properties(&self) -> bool {
+        let mut has_props = false;
+        self.inner.read_table_properties(|p| {
+            let props = p.user_collected_properties();
+            has_props = props.get(PROP_RANGE_INDEX.as_bytes()).is_some()
+                || props.get(PROP_NUM_VERSIONS.as_bytes()).is_some();
+        });
+        has_props
+    }
+
+    /// Returns this SST's recorded [`SstCreationReason`], if any.
+    ///
+    /// `None` for SSTs written before this property existed, or for any
+    /// other file that, like pre-#203 ingested SSTs, carries no `tikv.*`
+    /// properties at all.
+    pub fn creation_reason(&self) -> Option<SstCreationReason> {
+        let mut reason = None;
+        self.inner.read_table_properties(|p| {
+            let decoder = UserCollectedPropertiesDecoder(p.user_collected_properties());
+            reason = SstCreationReason::decode(&decoder).ok();
+        });
+        reason
+    }
 }
 
 impl SstReader for RocksSstReader {
@@ -231,6 +272,12 @@ impl SstWriterBuilder<RocksEngine> for RocksSstWriterBuilder {
         // being used, we must set them empty or disabled.
         io_options.compression_per_level(&[]);
         io_options.bottommost_compression(DBCompressionType::Disable);
+        // Every file built by this writer is meant for later ingestion, never
+        // for RocksDB's own flush/compaction output, so the reason is fixed.
+        io_options.add_table_properties_collector_factory(
+            "tikv.creation-reason-collector",
+            CreationReasonPropertiesCollectorFactory::new(SstCreationReason::Ingest),
+        );
         let mut writer = SstFileWriter::new(EnvOptions::new(), io_options);
         fail_point!("on_open_sst_writer");
         writer.open(path).map_err(r2e)?;
@@ -402,4 +449,48 @@ mod tests {
         // There must not be a file in disk.
         std::fs::metadata(p).unwrap_err();
     }
+
+    #[test]
+    fn test_has_tikv_properties() {
+        let path = Builder::new().tempdir().unwrap();
+        let engine = new_default_engine(path.path().to_str().unwrap()).unwrap();
+        let (k, v) = (b"foo", b"bar");
+
+        // An SST built through TiKV's own writer carries no range/mvcc
+        // properties, since this writer doesn't attach those collectors -
+        // mirroring an externally ingested SST from BR/Lightning.
+        let p = path.path().join("no_props.sst");
+        let mut writer = RocksSstWriterBuilder::new()
+            .set_cf(CF_DEFAULT)
+            .set_db(&engine)
+            .build(p.to_str().unwrap())
+            .unwrap();
+        writer.put(k, v).unwrap();
+        writer.finish().unwrap();
+
+        let reader = RocksSstReader::open_with_env(p.to_str().unwrap(), None).unwrap();
+        assert!(!reader.has_tikv_properties());
+    }
+
+    #[test]
+    fn test_creation_reason_ingest() {
+        let path = Builder::new().tempdir().unwrap();
+        let engine = new_default_engine(path.path().to_str().unwrap()).unwrap();
+        let (k, v) = (b"foo", b"bar");
+
+        // Every file built through this writer is meant for ingestion, so it
+        // should always be tagged as such, regardless of the lack of other
+        // tikv.* properties.
+        let p = path.path().join("ingest.sst");
+        let mut writer = RocksSstWriterBuilder::new()
+            .set_cf(CF_DEFAULT)
+            .set_db(&engine)
+            .build(p.to_str().unwrap())
+            .unwrap();
+        writer.put(k, v).unwrap();
+        writer.finish().unwrap();
+
+        let reader = RocksSstReader::open_with_env(p.to_str().unwrap(), None).unwrap();
+        assert_eq!(reader.creation_reason(), Some(SstCreationReason::Ingest));
+    }
 }
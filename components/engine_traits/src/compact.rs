@@ -82,6 +82,29 @@ pub trait CompactExt: CfNamesExt {
         output_level: Option<i32>,
     ) -> Result<()>;
 
+    /// Forces table properties (e.g. MVCC counts) to be recomputed over
+    /// `[start_key, end_key)` of the given column family.
+    ///
+    /// Table properties are collected when an SST file is written, so after
+    /// a large batch of deletes the properties of files that still overlap
+    /// the range stay stale until those files are next rewritten by
+    /// compaction. This runs a targeted, bottommost compaction over the
+    /// range to force that rewrite, so property-derived decisions (GC,
+    /// split-check, ...) stop relying on stale data.
+    fn recompute_properties_in_range(
+        &self,
+        cf: &str,
+        start_key: Option<&[u8]>,
+        end_key: Option<&[u8]>,
+    ) -> Result<()> {
+        self.compact_range_cf(
+            cf,
+            start_key,
+            end_key,
+            ManualCompactionOptions::new(true, 1, true),
+        )
+    }
+
     fn compact_files_cf(
         &self,
         cf: &str,
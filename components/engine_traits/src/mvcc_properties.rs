@@ -58,4 +58,19 @@ pub trait MvccPropertiesExt {
         start_key: &[u8],
         end_key: &[u8],
     ) -> Option<MvccProperties>;
+
+    /// Like `get_mvcc_properties_cf`, but also breaks the aggregate down by
+    /// LSM level, to help diagnose why stale data isn't being compacted away
+    /// (e.g. it is stuck in the bottom levels because of range deletions).
+    ///
+    /// The returned vector is indexed by level, i.e. `result[i]` is the
+    /// aggregate of all properties of SST files of level `i` overlapping the
+    /// range.
+    fn get_mvcc_properties_cf_by_level(
+        &self,
+        cf: &str,
+        safe_point: TimeStamp,
+        start_key: &[u8],
+        end_key: &[u8],
+    ) -> Option<Vec<MvccProperties>>;
 }
@@ -15,6 +15,10 @@ define_error_codes! {
         "Some of quota has been exceed, hence the task cannot continue.",
         "For memory quotas, please check whether there are huge transactions. You may also increase the quota by modifying config."
     ),
+    TEMP_FILE_STORAGE_FULL => ("TempFileStorageFull",
+        "The local temporary storage for log backup is full.",
+        "Please enlarge `log-backup.temp-file-disk-quota`, free up local disk space, or wait until pending data has been flushed to the external storage."
+    ),
     OBSERVE_CANCELED => (
         "ObserveCancel",
         "When doing initial scanning, the observe of that region has been canceled",
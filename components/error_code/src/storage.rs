@@ -46,6 +46,7 @@ define_error_codes!(
     PRIMARY_MISMATCH => ("PrimaryMismatch", "", ""),
     UNDETERMINED => ("Undetermined", "", ""),
     GENERATION_OUT_OF_ORDER => ("GenerationOutOfOrder", "", ""),
+    FORCE_UNLOCK_WITHOUT_FORCE_FLAG => ("ForceUnlockWithoutForceFlag", "", ""),
 
     UNKNOWN => ("Unknown", "", "")
 );
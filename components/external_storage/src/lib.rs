@@ -110,6 +110,20 @@ pub trait ExternalStorage: 'static + Send + Sync {
     /// Read part of contents of the given path.
     fn read_part(&self, name: &str, off: u64, len: u64) -> ExternalData<'_>;
 
+    /// Delete the file at the given path.
+    ///
+    /// Implementations that can't support this yet should return an error
+    /// with [`io::ErrorKind::Unsupported`] rather than silently doing
+    /// nothing, so callers that rely on the file actually being gone (e.g.
+    /// reclaiming a file that was written but never published) can tell the
+    /// difference between "deleted" and "this backend can't delete".
+    async fn delete(&self, _name: &str) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("the storage backend {} does not support delete", self.name()),
+        ))
+    }
+
     /// Read from external storage and restore to the given path
     async fn restore(
         &self,
@@ -177,6 +191,10 @@ impl ExternalStorage for Arc<dyn ExternalStorage> {
         (**self).read_part(name, off, len)
     }
 
+    async fn delete(&self, name: &str) -> io::Result<()> {
+        (**self).delete(name).await
+    }
+
     async fn restore(
         &self,
         storage_name: &str,
@@ -219,6 +237,10 @@ impl ExternalStorage for Box<dyn ExternalStorage> {
         self.as_ref().read_part(name, off, len)
     }
 
+    async fn delete(&self, name: &str) -> io::Result<()> {
+        self.as_ref().delete(name).await
+    }
+
     async fn restore(
         &self,
         storage_name: &str,
@@ -145,6 +145,18 @@ impl ExternalStorage for LocalStorage {
         let take = reader.take(len);
         Box::new(AllowStdIo::new(take)) as _
     }
+
+    async fn delete(&self, name: &str) -> io::Result<()> {
+        debug!("delete file from local storage";
+            "name" => %name, "base" => %self.base.display());
+        match fs::remove_file(self.base.join(name)).await {
+            Ok(()) => Ok(()),
+            // Deleting a file that is already gone is not an error: callers use this to
+            // reclaim files that were written but never got a chance to be published.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -228,6 +240,29 @@ mod tests {
         .unwrap_err();
     }
 
+    #[tokio::test]
+    async fn test_delete() {
+        let temp_dir = Builder::new().tempdir().unwrap();
+        let path = temp_dir.path();
+        let ls = LocalStorage::new(path).unwrap();
+
+        let magic_contents: &[u8] = b"5678";
+        ls.write(
+            "a.log",
+            UnpinReader(Box::new(magic_contents)),
+            magic_contents.len() as u64,
+        )
+        .await
+        .unwrap();
+        assert!(path.join("a.log").exists());
+
+        ls.delete("a.log").await.unwrap();
+        assert!(!path.join("a.log").exists());
+
+        // Deleting a file that is already gone is not an error.
+        ls.delete("a.log").await.unwrap();
+    }
+
     #[test]
     fn test_url_of_backend() {
         assert_eq!(url_for(Path::new("/tmp/a")).to_string(), "local:///tmp/a");
@@ -48,6 +48,10 @@ impl ExternalStorage for NoopStorage {
     fn read_part(&self, _name: &str, _off: u64, _len: u64) -> ExternalData<'_> {
         Box::new(io::empty().compat())
     }
+
+    async fn delete(&self, _name: &str) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -20,4 +20,15 @@ where
         self.disk_engine()
             .get_mvcc_properties_cf(cf, safe_point, start_key, end_key)
     }
+
+    fn get_mvcc_properties_cf_by_level(
+        &self,
+        cf: &str,
+        safe_point: TimeStamp,
+        start_key: &[u8],
+        end_key: &[u8],
+    ) -> Option<Vec<MvccProperties>> {
+        self.disk_engine()
+            .get_mvcc_properties_cf_by_level(cf, safe_point, start_key, end_key)
+    }
 }
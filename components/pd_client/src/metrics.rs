@@ -156,4 +156,16 @@ lazy_static! {
         "Total number of pending tso requests"
     )
     .unwrap();
+    // How many individual `get_tso` callers (e.g. CDC's and resolved_ts's
+    // own `register_min_ts_event` ticks, both sharing this `RpcClient`) got
+    // coalesced into a single `TsoRequest` sent to PD. A value consistently
+    // close to 1 means callers aren't actually overlapping in time and
+    // nothing is being saved; higher values confirm the batching in
+    // `TsoRequestStream::poll_next` is paying off.
+    pub static ref PD_TSO_BATCH_SIZE_HISTOGRAM: Histogram = register_histogram!(
+        "tikv_pd_tso_batch_size",
+        "Bucketed histogram of how many get_tso callers were coalesced into a single TsoRequest",
+        exponential_buckets(1.0, 2.0, 7).unwrap()
+    )
+    .unwrap();
 }
@@ -70,7 +70,7 @@ impl<EK: KvEngine, ER: RaftEngine> Peer<EK, ER> {
 
         store_ctx.coprocessor_host.on_region_changed(
             self.region(),
-            RegionChangeEvent::UpdateBuckets(buckets_count),
+            RegionChangeEvent::UpdateBuckets(region_buckets.meta.clone()),
             self.state_role(),
         );
         let meta = region_buckets.meta.clone();
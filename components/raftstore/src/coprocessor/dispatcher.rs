@@ -777,11 +777,13 @@ impl<E: KvEngine> CoprocessorHost<E> {
     }
 
     pub fn on_region_changed(&self, region: &Region, event: RegionChangeEvent, role: StateRole) {
+        // `RegionChangeEvent` isn't `Copy` (it can carry an `Arc<BucketMeta>`), so clone it for
+        // each observer rather than moving it out from under the loop.
         loop_ob!(
             region,
             &self.registry.region_change_observers,
             on_region_changed,
-            event,
+            event.clone(),
             role
         );
     }
@@ -19,7 +19,7 @@ use kvproto::{
     },
     raft_serverpb::RaftApplyState,
 };
-use pd_client::RegionStat;
+use pd_client::{BucketMeta, RegionStat};
 use raft::{eraftpb, StateRole};
 
 pub mod config;
@@ -322,12 +322,15 @@ pub enum RegionChangeReason {
     Flashback,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum RegionChangeEvent {
     Create,
     Update(RegionChangeReason),
     Destroy,
-    UpdateBuckets(usize),
+    // Carries the freshly refreshed bucket boundaries/stats, not just a count, so that
+    // observers (e.g. CDC) can expose bucket-level granularity to interested parties
+    // instead of only knowing how many buckets a region was split into.
+    UpdateBuckets(Arc<BucketMeta>),
 }
 
 pub trait RegionChangeObserver: Coprocessor {
@@ -588,6 +591,22 @@ pub trait CmdObserver<E>: Coprocessor {
     // `Coprocessor`
     /// Hook to call at the first time the leader applied on its term
     fn on_applied_current_term(&self, role: StateRole, region: &Region);
+
+    /// Picks the batches this observer cares about out of the batches
+    /// flushed by the coprocessor host, cloning only those.
+    ///
+    /// Multiple `CmdObserver`s (e.g. cdc and backup-stream) are registered
+    /// against the same flushed `cmd_batches` and each used to re-derive
+    /// this subset independently with its own copy of the filter; sharing
+    /// it here keeps the per-consumer quota (`min_level`) explicit and
+    /// avoids the filtering logic drifting between implementations.
+    fn filter_cmd_batches(min_level: ObserveLevel, cmd_batches: &[CmdBatch]) -> Vec<CmdBatch> {
+        cmd_batches
+            .iter()
+            .filter(|cb| !cb.is_empty() && cb.level >= min_level)
+            .cloned()
+            .collect()
+    }
 }
 
 pub trait ReadIndexObserver: Coprocessor {
@@ -68,7 +68,7 @@ pub enum RaftStoreEvent {
     },
     UpdateRegionBuckets {
         region: Region,
-        buckets: usize,
+        keys: Vec<Vec<u8>>,
     },
     UpdateRegionActivity {
         region: Region,
@@ -94,6 +94,11 @@ pub struct RegionInfo {
     pub region: Region,
     pub role: StateRole,
     pub buckets: usize,
+    /// The bucket boundary keys within this region, as reported by the last
+    /// `UpdateBuckets` region-change event. Empty if the region has no
+    /// bucket information yet (e.g. bucketing is disabled, or no heartbeat
+    /// has arrived).
+    pub bucket_keys: Vec<Vec<u8>>,
 }
 
 impl RegionInfo {
@@ -102,6 +107,7 @@ impl RegionInfo {
             region,
             role,
             buckets: 1,
+            bucket_keys: vec![],
         }
     }
 }
@@ -167,6 +173,10 @@ pub enum RegionInfoQuery {
         count: usize,
         callback: Callback<TopRegions>,
     },
+    GetRegionBucketKeys {
+        region_id: u64,
+        callback: Callback<Vec<Vec<u8>>>,
+    },
     /// Gets all contents from the collection. Only used for testing.
     DebugDump(mpsc::Sender<(RegionsMap, RegionRangesMap)>),
 }
@@ -192,6 +202,9 @@ impl Display for RegionInfoQuery {
             RegionInfoQuery::GetTopRegions { count, .. } => {
                 write!(f, "GetTopRegions(count: {})", count)
             }
+            RegionInfoQuery::GetRegionBucketKeys { region_id, .. } => {
+                write!(f, "GetRegionBucketKeys(region_id: {})", region_id)
+            }
             RegionInfoQuery::DebugDump(_) => write!(f, "DebugDump"),
         }
     }
@@ -219,9 +232,10 @@ impl RegionChangeObserver for RegionEventListener {
             RegionChangeEvent::Create => RaftStoreEvent::CreateRegion { region, role },
             RegionChangeEvent::Update(_) => RaftStoreEvent::UpdateRegion { region, role },
             RegionChangeEvent::Destroy => RaftStoreEvent::DestroyRegion { region },
-            RegionChangeEvent::UpdateBuckets(buckets) => {
-                RaftStoreEvent::UpdateRegionBuckets { region, buckets }
-            }
+            RegionChangeEvent::UpdateBuckets(meta) => RaftStoreEvent::UpdateRegionBuckets {
+                region,
+                keys: meta.keys.clone(),
+            },
         };
         self.scheduler
             .schedule(RegionInfoQuery::RaftStoreEvent(event))
@@ -354,11 +368,12 @@ impl RegionCollector {
         *old_region = region;
     }
 
-    fn update_region_buckets(&mut self, region: Region, buckets: usize) {
+    fn update_region_buckets(&mut self, region: Region, keys: Vec<Vec<u8>>) {
         let existing_region_info = self.regions.get_mut(&region.get_id()).unwrap();
         let old_region = &mut existing_region_info.region;
         assert_eq!(old_region.get_id(), region.get_id());
-        existing_region_info.buckets = buckets;
+        existing_region_info.buckets = keys.len().saturating_sub(1);
+        existing_region_info.bucket_keys = keys;
     }
 
     fn handle_create_region(&mut self, region: Region, role: StateRole) {
@@ -395,9 +410,9 @@ impl RegionCollector {
         }
     }
 
-    fn handle_update_region_buckets(&mut self, region: Region, buckets: usize) {
+    fn handle_update_region_buckets(&mut self, region: Region, keys: Vec<Vec<u8>>) {
         if self.regions.contains_key(&region.get_id()) {
-            self.update_region_buckets(region, buckets);
+            self.update_region_buckets(region, keys);
         } else {
             warn!(
                 "trying to update region buckets but the region doesn't exist, ignore";
@@ -542,6 +557,15 @@ impl RegionCollector {
         callback(self.regions.get(&region_id).cloned());
     }
 
+    pub fn handle_get_region_bucket_keys(&self, region_id: u64, callback: Callback<Vec<Vec<u8>>>) {
+        callback(
+            self.regions
+                .get(&region_id)
+                .map(|r| r.bucket_keys.clone())
+                .unwrap_or_default(),
+        );
+    }
+
     // It returns the regions covered by [start_key, end_key]
     pub fn handle_get_regions_in_range(
         &self,
@@ -666,8 +690,8 @@ impl RegionCollector {
             RaftStoreEvent::RoleChange { region, role, .. } => {
                 self.handle_role_change(region, role);
             }
-            RaftStoreEvent::UpdateRegionBuckets { region, buckets } => {
-                self.handle_update_region_buckets(region, buckets);
+            RaftStoreEvent::UpdateRegionBuckets { region, keys } => {
+                self.handle_update_region_buckets(region, keys);
             }
             RaftStoreEvent::UpdateRegionActivity { region, activity } => {
                 self.handle_update_region_activity(region.get_id(), &activity)
@@ -703,6 +727,12 @@ impl Runnable for RegionCollector {
             RegionInfoQuery::GetTopRegions { count, callback } => {
                 self.handle_get_top_regions(count, callback);
             }
+            RegionInfoQuery::GetRegionBucketKeys {
+                region_id,
+                callback,
+            } => {
+                self.handle_get_region_bucket_keys(region_id, callback);
+            }
             RegionInfoQuery::DebugDump(tx) => {
                 tx.send((self.regions.clone(), self.region_ranges.clone()))
                     .unwrap();
@@ -833,6 +863,13 @@ pub trait RegionInfoProvider: Send + Sync {
     fn get_top_regions(&self, _count: Option<NonZeroUsize>) -> Result<TopRegions> {
         unimplemented!()
     }
+
+    /// Gets the bucket boundary keys of the given region, as last reported by
+    /// an `UpdateBuckets` region-change event. Returns an empty `Vec` if the
+    /// region has no bucket information.
+    fn get_region_bucket_keys(&self, _region_id: u64) -> Result<Vec<Vec<u8>>> {
+        unimplemented!()
+    }
 }
 
 impl RegionInfoProvider for RegionInfoAccessor {
@@ -927,6 +964,28 @@ impl RegionInfoProvider for RegionInfoAccessor {
                 })
             })
     }
+    fn get_region_bucket_keys(&self, region_id: u64) -> Result<Vec<Vec<u8>>> {
+        let (tx, rx) = mpsc::channel();
+        let msg = RegionInfoQuery::GetRegionBucketKeys {
+            region_id,
+            callback: Box::new(move |keys| {
+                if let Err(e) = tx.send(keys) {
+                    warn!("failed to send get_region_bucket_keys result: {:?}", e);
+                }
+            }),
+        };
+        self.scheduler
+            .schedule(msg)
+            .map_err(|e| box_err!("failed to send request to region collector: {:?}", e))
+            .and_then(|_| {
+                rx.recv().map_err(|e| {
+                    box_err!(
+                        "failed to receive get_region_bucket_keys result from region collector: {:?}",
+                        e
+                    )
+                })
+            })
+    }
 }
 
 // Use in tests only.
@@ -942,6 +1001,16 @@ impl MockRegionInfoProvider {
                 .collect_vec(),
         ))
     }
+
+    pub fn set_region_bucket_keys(&self, region_id: u64, keys: Vec<Vec<u8>>) {
+        let mut regions = self.0.lock().unwrap();
+        let region_info = regions
+            .iter_mut()
+            .find(|r| r.region.get_id() == region_id)
+            .unwrap();
+        region_info.buckets = keys.len().saturating_sub(1);
+        region_info.bucket_keys = keys;
+    }
 }
 
 impl Clone for MockRegionInfoProvider {
@@ -1024,6 +1093,17 @@ impl RegionInfoProvider for MockRegionInfoProvider {
         }
         Ok(regions)
     }
+
+    fn get_region_bucket_keys(&self, region_id: u64) -> Result<Vec<Vec<u8>>> {
+        Ok(self
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|r| r.region.get_id() == region_id)
+            .map(|r| r.bucket_keys.clone())
+            .unwrap_or_default())
+    }
 }
 
 #[cfg(test)]
@@ -1171,14 +1251,16 @@ mod tests {
         }
     }
 
-    fn must_update_region_buckets(c: &mut RegionCollector, region: &Region, buckets: usize) {
+    fn must_update_region_buckets(c: &mut RegionCollector, region: &Region, keys: Vec<Vec<u8>>) {
+        let buckets = keys.len().saturating_sub(1);
         c.handle_raftstore_event(RaftStoreEvent::UpdateRegionBuckets {
             region: region.clone(),
-            buckets,
+            keys: keys.clone(),
         });
         let r = c.regions.get(&region.get_id()).unwrap();
         assert_eq!(r.region, *region);
         assert_eq!(r.buckets, buckets);
+        assert_eq!(r.bucket_keys, keys);
     }
 
     fn must_destroy_region(c: &mut RegionCollector, region: Region) {
@@ -1432,7 +1514,17 @@ mod tests {
         );
         must_update_region(&mut c, &new_region(2, b"k3", b"k7", 3), StateRole::Leader);
         // test region buckets update
-        must_update_region_buckets(&mut c, &new_region(2, b"k3", b"k7", 3), 4);
+        must_update_region_buckets(
+            &mut c,
+            &new_region(2, b"k3", b"k7", 3),
+            vec![
+                b"k3".to_vec(),
+                b"k4".to_vec(),
+                b"k5".to_vec(),
+                b"k6".to_vec(),
+                b"k7".to_vec(),
+            ],
+        );
         must_create_region(&mut c, &new_region(4, b"k1", b"k3", 3), StateRole::Follower);
         check_collection(
             &c,
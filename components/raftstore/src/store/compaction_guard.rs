@@ -6,7 +6,7 @@ use engine_traits::{
     CfName, SstPartitioner, SstPartitionerContext, SstPartitionerFactory, SstPartitionerRequest,
     SstPartitionerResult, CF_DEFAULT, CF_LOCK, CF_RAFT, CF_WRITE,
 };
-use keys::{data_end_key, origin_key};
+use keys::{data_end_key, data_key, origin_key};
 use lazy_static::lazy_static;
 use tikv_util::warn;
 
@@ -100,7 +100,9 @@ pub struct CompactionGuardGenerator<P: RegionInfoProvider> {
     provider: P,
     initialized: bool,
     use_guard: bool,
-    // The boundary keys are exclusive.
+    // The boundary keys are exclusive. Includes both region boundaries and,
+    // within each region, its bucket boundaries (if any), so SST files don't
+    // straddle either.
     boundaries: Vec<Vec<u8>>,
     /// The SST boundaries overlapped with the compaction input at the next
     /// level of output level (let we call it L+2). When the output level is the
@@ -153,7 +155,26 @@ impl<P: RegionInfoProvider> CompactionGuardGenerator<P> {
                         .iter()
                         .map(|region| data_end_key(&region.end_key))
                         .collect::<Vec<Vec<u8>>>();
+                    // Also split along bucket boundaries within each region, if the region
+                    // info provider has any on record, so output files align with buckets
+                    // too: this improves the precision of per-bucket statistics and the
+                    // efficiency of bucket-level scans (e.g. by CDC and the coprocessor).
+                    for region in &regions {
+                        match self.provider.get_region_bucket_keys(region.get_id()) {
+                            Ok(keys) => {
+                                boundaries.extend(keys.into_iter().map(|k| data_key(&k)));
+                            }
+                            Err(e) => {
+                                warn!(
+                                    "failed to get region bucket keys for compaction guard";
+                                    "region_id" => region.get_id(),
+                                    "err" => ?e,
+                                );
+                            }
+                        }
+                    }
                     boundaries.sort();
+                    boundaries.dedup();
                     self.boundaries = boundaries;
                     true
                 }
@@ -342,6 +363,41 @@ mod tests {
         assert_eq!(guard.use_guard, false);
     }
 
+    #[test]
+    fn test_compaction_guard_initialize_with_bucket_keys() {
+        let provider = simple_regions();
+        provider.set_region_bucket_keys(2, vec![b"b".to_vec(), b"bm".to_vec(), b"c".to_vec()]);
+        let mut guard = CompactionGuardGenerator {
+            cf_name: CfNames::default,
+            smallest_key: keys::data_key(b"a"),
+            largest_key: keys::data_key(b"d"),
+            min_output_file_size: 8 << 20,
+            provider,
+            initialized: false,
+            use_guard: false,
+            boundaries: vec![],
+            pos: 0,
+            current_next_level_size: 0,
+            next_level_pos: 0,
+            next_level_boundaries: vec![],
+            next_level_size: vec![],
+            max_compaction_size: 1 << 30,
+        };
+        guard.initialize();
+        assert_eq!(guard.use_guard, true);
+        // Region boundaries (b, c, d) plus the extra bucket boundary within region 2
+        // (bm), all converted to data keys.
+        assert_eq!(
+            guard.boundaries,
+            vec![
+                keys::data_key(b"b"),
+                keys::data_key(b"bm"),
+                keys::data_key(b"c"),
+                keys::data_key(b"d"),
+            ]
+        );
+    }
+
     #[test]
     fn test_compaction_guard_should_partition() {
         let mut guard = CompactionGuardGenerator {
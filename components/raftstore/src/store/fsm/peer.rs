@@ -6359,7 +6359,7 @@ where
         }
         self.ctx.coprocessor_host.on_region_changed(
             self.region(),
-            RegionChangeEvent::UpdateBuckets(buckets_count),
+            RegionChangeEvent::UpdateBuckets(region_buckets.meta.clone()),
             self.fsm.peer.get_role(),
         );
         let keys = region_buckets.meta.keys.clone();
@@ -3458,6 +3458,7 @@ mod tests {
                     },
                 ),
             ],
+            ..Default::default()
         };
         let event = RocksCompactedEvent {
             cf: "default".to_owned(),
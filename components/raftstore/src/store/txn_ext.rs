@@ -1,16 +1,18 @@
 // Copyright 2021 TiKV Project Authors. Licensed under Apache-2.0.
 
 use std::{
+    array,
     collections::{BTreeMap, Bound},
     fmt,
     sync::atomic::{AtomicU64, Ordering},
 };
 
+use collections::HashMap;
 use kvproto::metapb;
 use lazy_static::lazy_static;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use prometheus::{register_int_gauge, IntGauge};
-use txn_types::{Key, Lock, PessimisticLock};
+use txn_types::{Key, Lock, PessimisticLock, TimeStamp};
 
 /// Transaction extensions related to a peer.
 #[derive(Default)]
@@ -62,6 +64,83 @@ const GLOBAL_MEM_SIZE_LIMIT: usize = 100 << 20; // 100 MiB
 // command.
 const PEER_MEM_SIZE_LIMIT: usize = 512 << 10;
 
+const LOCK_COUNT_SHARD_BITS: u32 = 6;
+const LOCK_COUNT_SHARD_COUNT: usize = 1 << LOCK_COUNT_SHARD_BITS; // 64
+
+lazy_static! {
+    /// Approximate number of pessimistic locks held by each transaction
+    /// (keyed by start_ts) across all regions on this store.
+    ///
+    /// It's sharded by start_ts so that concurrent `insert`/`remove` calls
+    /// on `PeerPessimisticLocks` of different regions don't contend on a
+    /// single lock. The scheduler can use it to cap pathological
+    /// transactions before they accumulate too many locks, and diagnostics
+    /// can use it to report the transactions currently holding the most
+    /// locks.
+    pub static ref GLOBAL_LOCK_COUNT_BY_START_TS: LockCountByStartTs = LockCountByStartTs::default();
+}
+
+/// A sharded map from `start_ts` to the approximate number of pessimistic
+/// locks held by that transaction on this store.
+pub struct LockCountByStartTs {
+    shards: [Mutex<HashMap<TimeStamp, usize>>; LOCK_COUNT_SHARD_COUNT],
+}
+
+impl Default for LockCountByStartTs {
+    fn default() -> Self {
+        LockCountByStartTs {
+            shards: array::from_fn(|_| Mutex::new(HashMap::default())),
+        }
+    }
+}
+
+impl LockCountByStartTs {
+    fn shard(&self, start_ts: TimeStamp) -> &Mutex<HashMap<TimeStamp, usize>> {
+        let idx = start_ts.into_inner() as usize & (LOCK_COUNT_SHARD_COUNT - 1);
+        &self.shards[idx]
+    }
+
+    fn incr(&self, start_ts: TimeStamp) {
+        *self.shard(start_ts).lock().entry(start_ts).or_insert(0) += 1;
+    }
+
+    fn decr(&self, start_ts: TimeStamp) {
+        let mut shard = self.shard(start_ts).lock();
+        if let Some(count) = shard.get_mut(&start_ts) {
+            *count -= 1;
+            if *count == 0 {
+                shard.remove(&start_ts);
+            }
+        }
+    }
+
+    /// Returns the approximate number of pessimistic locks held by
+    /// `start_ts` across all regions on this store.
+    pub fn get(&self, start_ts: TimeStamp) -> usize {
+        self.shard(start_ts)
+            .lock()
+            .get(&start_ts)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns up to `limit` transactions holding the most pessimistic
+    /// locks, sorted by lock count in descending order.
+    ///
+    /// The result is approximate: shards are snapshotted one at a time, so
+    /// it does not reflect a single consistent instant across the whole
+    /// store under concurrent modification.
+    pub fn top_lock_holders(&self, limit: usize) -> Vec<(TimeStamp, usize)> {
+        let mut holders: Vec<(TimeStamp, usize)> = Vec::new();
+        for shard in &self.shards {
+            holders.extend(shard.lock().iter().map(|(&ts, &count)| (ts, count)));
+        }
+        holders.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+        holders.truncate(limit);
+        holders
+    }
+}
+
 /// Pessimistic locks of a region peer.
 #[derive(PartialEq)]
 pub struct PeerPessimisticLocks {
@@ -176,7 +255,15 @@ impl PeerPessimisticLocks {
         // Insert after check has passed.
         for pair in pairs {
             let (key, lock) = pair.into_pair();
-            self.map.insert(key, (lock, false));
+            let start_ts = lock.start_ts;
+            match self.map.insert(key, (lock, false)) {
+                Some((old_lock, _)) if old_lock.start_ts != start_ts => {
+                    GLOBAL_LOCK_COUNT_BY_START_TS.decr(old_lock.start_ts);
+                    GLOBAL_LOCK_COUNT_BY_START_TS.incr(start_ts);
+                }
+                Some(_) => {}
+                None => GLOBAL_LOCK_COUNT_BY_START_TS.incr(start_ts),
+            }
         }
         self.memory_size += incr;
         GLOBAL_MEM_SIZE.add(incr as i64);
@@ -188,10 +275,14 @@ impl PeerPessimisticLocks {
             let desc = key.len() + lock.memory_size();
             self.memory_size -= desc;
             GLOBAL_MEM_SIZE.sub(desc as i64);
+            GLOBAL_LOCK_COUNT_BY_START_TS.decr(lock.start_ts);
         }
     }
 
     pub fn clear(&mut self) {
+        for (_, lock) in self.map.values() {
+            GLOBAL_LOCK_COUNT_BY_START_TS.decr(lock.start_ts);
+        }
         self.map = BTreeMap::default();
         GLOBAL_MEM_SIZE.sub(self.memory_size as i64);
         self.memory_size = 0;
@@ -323,6 +414,9 @@ impl<'a> IntoIterator for &'a PeerPessimisticLocks {
 impl Drop for PeerPessimisticLocks {
     fn drop(&mut self) {
         GLOBAL_MEM_SIZE.sub(self.memory_size as i64);
+        for (_, lock) in self.map.values() {
+            GLOBAL_LOCK_COUNT_BY_START_TS.decr(lock.start_ts);
+        }
     }
 }
 
@@ -447,6 +541,59 @@ mod tests {
         assert_eq!(GLOBAL_MEM_SIZE.get(), 0);
     }
 
+    fn lock_with_start_ts(primary: &[u8], start_ts: u64) -> PessimisticLock {
+        PessimisticLock {
+            start_ts: start_ts.into(),
+            ..lock(primary)
+        }
+    }
+
+    #[test]
+    fn test_lock_count_by_start_ts() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+
+        let mut locks1 = PeerPessimisticLocks::default();
+        let mut locks2 = PeerPessimisticLocks::default();
+        let k1 = Key::from_raw(b"k1");
+        let k2 = Key::from_raw(b"k22");
+        let k3 = Key::from_raw(b"k333");
+
+        // Two locks from different regions but the same transaction are
+        // counted together.
+        locks1
+            .insert(vec![(k1.clone(), lock_with_start_ts(b"k1", 10))])
+            .unwrap();
+        locks2
+            .insert(vec![(k2.clone(), lock_with_start_ts(b"k1", 10))])
+            .unwrap();
+        locks2
+            .insert(vec![(k3.clone(), lock_with_start_ts(b"k3", 20))])
+            .unwrap();
+        assert_eq!(GLOBAL_LOCK_COUNT_BY_START_TS.get(10.into()), 2);
+        assert_eq!(GLOBAL_LOCK_COUNT_BY_START_TS.get(20.into()), 1);
+        assert_eq!(GLOBAL_LOCK_COUNT_BY_START_TS.get(999.into()), 0);
+
+        let holders = GLOBAL_LOCK_COUNT_BY_START_TS.top_lock_holders(1);
+        assert_eq!(holders, vec![(10.into(), 2)]);
+
+        // Re-inserting with a different start_ts (a re-lock with a new
+        // transaction) moves the count from the old transaction to the new
+        // one.
+        locks1
+            .insert(vec![(k1.clone(), lock_with_start_ts(b"k1", 40))])
+            .unwrap();
+        assert_eq!(GLOBAL_LOCK_COUNT_BY_START_TS.get(10.into()), 1);
+        assert_eq!(GLOBAL_LOCK_COUNT_BY_START_TS.get(40.into()), 1);
+
+        locks1.remove(&k1);
+        assert_eq!(GLOBAL_LOCK_COUNT_BY_START_TS.get(40.into()), 0);
+
+        drop(locks1);
+        drop(locks2);
+        assert_eq!(GLOBAL_LOCK_COUNT_BY_START_TS.get(10.into()), 0);
+        assert_eq!(GLOBAL_LOCK_COUNT_BY_START_TS.get(20.into()), 0);
+    }
+
     #[test]
     fn test_insert_checking_memory_limit() {
         let _guard = TEST_MUTEX.lock().unwrap();
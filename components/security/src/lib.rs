@@ -275,6 +275,22 @@ fn check_common_name(
     }
 }
 
+/// Extracts the TLS peer's `x509_common_name` from `ctx`, for callers that
+/// need to remember it past this RPC and re-run `match_peer_names` against
+/// it later, e.g. to revalidate a long-lived streaming connection after a
+/// cert rotation.
+/// Returns `None` when the channel isn't secured at all; `Some("")` when it
+/// is, but the peer didn't present a `x509_common_name` property.
+pub fn get_peer_cn(ctx: &RpcContext<'_>) -> Option<String> {
+    let auth_ctx = ctx.auth_context()?;
+    let peer_cn = auth_ctx
+        .into_iter()
+        .find(|x| x.name() == "x509_common_name")
+        .and_then(|p| p.value_str().ok())
+        .unwrap_or_default();
+    Some(peer_cn.to_owned())
+}
+
 /// Check peer CN with a set of allowed CN.
 pub fn match_peer_names(allowed_cn: &HashSet<String>, name: &str) -> bool {
     for cn in allowed_cn {
@@ -949,6 +949,7 @@ where
                 self.concurrency_manager.clone(),
                 BackupStreamResolver::V1(leadership_resolver),
                 self.core.encryption_key_manager.clone(),
+                self.core.config.storage.api_version(),
             );
             backup_stream_worker.start(backup_stream_endpoint);
             self.core.to_stop.push(backup_stream_worker);
@@ -1098,6 +1099,8 @@ where
             self.security_mgr.clone(),
             cdc_memory_quota.clone(),
             self.causal_ts_provider.clone(),
+            cdc::CdcSubscriptionRegistry::new(),
+            self.resource_manager.clone(),
         );
         cdc_worker.start_with_timer(cdc_endpoint);
         self.core.to_stop.push(cdc_worker);
@@ -1287,6 +1290,8 @@ where
         let cdc_service = cdc::Service::new(
             servers.cdc_scheduler.clone(),
             servers.cdc_memory_quota.clone(),
+            self.core.config.cdc.conn_memory_quota.0 as _,
+            self.core.config.cdc.sink_batch_wait_duration.0,
         );
         if servers
             .server
@@ -1570,6 +1575,7 @@ where
                 self.engines.as_ref().unwrap().engine.raft_extension(),
                 self.resource_manager.clone(),
                 self.grpc_service_mgr.clone(),
+                Some(self.core.cdc_scheduler.clone()),
             ) {
                 Ok(status_server) => Box::new(status_server),
                 Err(e) => {
@@ -669,6 +669,8 @@ where
             self.security_mgr.clone(),
             cdc_memory_quota.clone(),
             self.causal_ts_provider.clone(),
+            cdc::CdcSubscriptionRegistry::new(),
+            self.resource_manager.clone(),
         );
         cdc_worker.start_with_timer(cdc_endpoint);
         self.core.to_stop.push(cdc_worker);
@@ -736,6 +738,7 @@ where
                 self.concurrency_manager.clone(),
                 BackupStreamResolver::V2(self.router.clone().unwrap(), PhantomData),
                 self.core.encryption_key_manager.clone(),
+                self.core.config.storage.api_version(),
             );
             backup_stream_worker.start(backup_stream_endpoint);
             self.core.to_stop.push(backup_stream_worker);
@@ -1041,6 +1044,8 @@ where
         let cdc_service = cdc::Service::new(
             self.cdc_scheduler.as_ref().unwrap().clone(),
             self.cdc_memory_quota.as_ref().unwrap().clone(),
+            self.core.config.cdc.conn_memory_quota.0 as _,
+            self.core.config.cdc.sink_batch_wait_duration.0,
         );
         if servers
             .server
@@ -1342,6 +1347,7 @@ where
                 self.engines.as_ref().unwrap().engine.raft_extension(),
                 self.resource_manager.clone(),
                 self.grpc_service_mgr.clone(),
+                self.cdc_scheduler.clone(),
             ) {
                 Ok(status_server) => Box::new(status_server),
                 Err(e) => {
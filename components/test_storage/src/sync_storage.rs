@@ -285,7 +285,7 @@ impl<E: Engine, F: KvFormat> SyncTestStorage<E, F> {
     ) -> Result<()> {
         wait_op!(|cb| self
             .store
-            .sched_txn_command(commands::Rollback::new(keys, start_ts.into(), ctx), cb))
+            .sched_txn_command(commands::Rollback::new(keys, start_ts.into(), None, ctx), cb))
         .unwrap()
     }
 
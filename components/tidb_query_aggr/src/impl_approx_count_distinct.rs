@@ -0,0 +1,390 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::{
+    convert::TryFrom,
+    hash::{Hash, Hasher},
+};
+
+use tidb_query_codegen::AggrFunction;
+use tidb_query_common::Result;
+use tidb_query_datatype::{
+    builder::FieldTypeBuilder,
+    codec::{collation::Collator, data_type::*},
+    expr::EvalContext,
+    match_template_collator, Collation, EvalType, FieldTypeTp,
+};
+use tidb_query_expr::RpnExpression;
+use tipb::{Expr, ExprType, FieldType};
+
+use super::*;
+
+/// `2^HLL_PRECISION` registers are kept in the sketch. 14 bits of precision
+/// gives a standard error of about 0.8%, which matches the precision TiDB
+/// itself uses for `APPROX_COUNT_DISTINCT()`.
+const HLL_PRECISION: u32 = 14;
+const HLL_REGISTERS: usize = 1 << HLL_PRECISION;
+
+/// A HyperLogLog sketch, used as the intermediate state of
+/// `APPROX_COUNT_DISTINCT()`.
+///
+/// The sketch serializes to a fixed-size byte string (one byte per
+/// register), which is exactly the form TiDB's aggregation planner expects
+/// when merging partial results computed by different TiKV regions: merging
+/// two sketches is simply taking the register-wise maximum.
+#[derive(Debug, Clone)]
+struct HyperLogLog {
+    registers: Box<[u8; HLL_REGISTERS]>,
+}
+
+impl HyperLogLog {
+    fn new() -> Self {
+        Self {
+            registers: Box::new([0; HLL_REGISTERS]),
+        }
+    }
+
+    #[inline]
+    fn insert_hash(&mut self, hash: u64) {
+        let index = (hash & (HLL_REGISTERS as u64 - 1)) as usize;
+        // +1 so that a tail (index bits aside) with no trailing zeros counts
+        // as rank 1, leaving 0 to mean an untouched register.
+        let rank = (hash >> HLL_PRECISION).trailing_zeros() as u8 + 1;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    fn to_bytes(&self) -> Bytes {
+        self.registers.to_vec()
+    }
+}
+
+/// A `Hasher` backed by `farmhash::fingerprint64`, whose algorithm (unlike
+/// `std::collections::hash_map::DefaultHasher`'s) is documented and fixed
+/// across Rust toolchain versions. Region-level sketches are merged
+/// register-wise (max) across TiKV instances, potentially built by
+/// different toolchain versions during a rolling upgrade, so every instance
+/// must hash the same value to the same bits or merged sketches silently
+/// corrupt.
+#[derive(Default)]
+struct FarmHasher {
+    buf: Vec<u8>,
+}
+
+impl Hasher for FarmHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        farmhash::fingerprint64(&self.buf)
+    }
+}
+
+/// A trait for types `APPROX_COUNT_DISTINCT()` knows how to feed into the
+/// sketch. Implemented for the eval types that already implement
+/// `std::hash::Hash`; types without a faithful `Hash` (e.g. `Json`) are not
+/// supported.
+trait DistinctHash: Evaluable + EvaluableRet {
+    fn distinct_hash(&self) -> u64;
+}
+
+macro_rules! impl_distinct_hash {
+    ($ty:ty) => {
+        impl DistinctHash for $ty {
+            #[inline]
+            fn distinct_hash(&self) -> u64 {
+                let mut hasher = FarmHasher::default();
+                self.hash(&mut hasher);
+                hasher.finish()
+            }
+        }
+    };
+}
+
+impl_distinct_hash! { Int }
+impl_distinct_hash! { Real }
+impl_distinct_hash! { Decimal }
+impl_distinct_hash! { Duration }
+
+/// The parser for `APPROX_COUNT_DISTINCT` aggregate function.
+pub struct AggrFnDefinitionParserApproxCountDistinct;
+
+impl super::AggrDefinitionParser for AggrFnDefinitionParserApproxCountDistinct {
+    fn check_supported(&self, aggr_def: &Expr) -> Result<()> {
+        assert_eq!(aggr_def.get_tp(), ExprType::ApproxCountDistinct);
+        super::util::check_aggr_exp_supported_one_child(aggr_def)
+    }
+
+    #[inline]
+    fn parse_rpn(
+        &self,
+        root_expr: Expr,
+        exp: RpnExpression,
+        _ctx: &mut EvalContext,
+        src_schema: &[FieldType],
+        out_schema: &mut Vec<FieldType>,
+        out_exp: &mut Vec<RpnExpression>,
+    ) -> Result<Box<dyn AggrFunction>> {
+        use tidb_query_datatype::FieldTypeAccessor;
+
+        assert_eq!(root_expr.get_tp(), ExprType::ApproxCountDistinct);
+
+        let eval_type = box_try!(EvalType::try_from(
+            exp.ret_field_type(src_schema).as_accessor().tp()
+        ));
+
+        // `APPROX_COUNT_DISTINCT` outputs a single column holding the
+        // serialized HyperLogLog sketch, so that TiDB can merge sketches
+        // from multiple regions before computing the final estimate.
+        out_schema.push(FieldTypeBuilder::new().tp(FieldTypeTp::VarString).build());
+        out_exp.push(exp);
+
+        Ok(match eval_type {
+            EvalType::Int => Box::new(AggrFnApproxCountDistinct::<Int>::new()),
+            EvalType::Real => Box::new(AggrFnApproxCountDistinct::<Real>::new()),
+            EvalType::Decimal => Box::new(AggrFnApproxCountDistinct::<Decimal>::new()),
+            EvalType::Duration => Box::new(AggrFnApproxCountDistinct::<Duration>::new()),
+            EvalType::Bytes => {
+                // Hash by the collation's sort key, not the raw bytes, so
+                // e.g. `'A'` and `'a'` count as one distinct value under a
+                // case-insensitive collation like `utf8mb4_general_ci`.
+                let out_coll = box_try!(exp.ret_field_type(src_schema).as_accessor().collation());
+                match_template_collator! {
+                    C, match out_coll {
+                        Collation::C => Box::new(AggrFnApproxCountDistinctForBytes::<C>::new())
+                    }
+                }
+            }
+            _ => return Err(other_err!("Unsupported eval type {:?}", eval_type)),
+        })
+    }
+}
+
+/// The `APPROX_COUNT_DISTINCT` aggregate function.
+#[derive(Debug, AggrFunction)]
+#[aggr_function(state = AggrFnStateApproxCountDistinct::<T>::new())]
+pub struct AggrFnApproxCountDistinct<T>
+where
+    T: DistinctHash,
+    VectorValue: VectorValueExt<T>,
+{
+    _phantom: std::marker::PhantomData<T>,
+}
+
+impl<T> AggrFnApproxCountDistinct<T>
+where
+    T: DistinctHash,
+    VectorValue: VectorValueExt<T>,
+{
+    pub fn new() -> Self {
+        Self {
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+/// The state of the `APPROX_COUNT_DISTINCT` aggregate function.
+#[derive(Debug)]
+pub struct AggrFnStateApproxCountDistinct<T>
+where
+    T: DistinctHash,
+    VectorValue: VectorValueExt<T>,
+{
+    sketch: HyperLogLog,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+impl<T> AggrFnStateApproxCountDistinct<T>
+where
+    T: DistinctHash,
+    VectorValue: VectorValueExt<T>,
+{
+    pub fn new() -> Self {
+        Self {
+            sketch: HyperLogLog::new(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    #[inline]
+    fn update_concrete<'a, TT>(&mut self, _ctx: &mut EvalContext, value: Option<TT>) -> Result<()>
+    where
+        TT: EvaluableRef<'a, EvaluableType = T>,
+    {
+        if let Some(value) = value {
+            self.sketch.insert_hash(value.into_owned_value().distinct_hash());
+        }
+        Ok(())
+    }
+}
+
+impl<T> super::ConcreteAggrFunctionState for AggrFnStateApproxCountDistinct<T>
+where
+    T: DistinctHash,
+    VectorValue: VectorValueExt<T>,
+{
+    type ParameterType = &'static T;
+
+    impl_concrete_state! { Self::ParameterType }
+
+    #[inline]
+    fn push_result(&self, _ctx: &mut EvalContext, target: &mut [VectorValue]) -> Result<()> {
+        assert_eq!(target.len(), 1);
+        target[0].push(Some(self.sketch.to_bytes()));
+        Ok(())
+    }
+}
+
+/// The `APPROX_COUNT_DISTINCT` aggregate function over `Bytes`, kept
+/// separate from the generic implementation above because `Bytes` is
+/// evaluated via `BytesRef<'_>` rather than `&'static Bytes`, and because it
+/// must hash by the collation's sort key rather than its raw bytes.
+#[derive(Debug, AggrFunction)]
+#[aggr_function(state = AggrFnStateApproxCountDistinctForBytes::<C>::new())]
+pub struct AggrFnApproxCountDistinctForBytes<C: Collator> {
+    _phantom: std::marker::PhantomData<C>,
+}
+
+impl<C: Collator> AggrFnApproxCountDistinctForBytes<C> {
+    fn new() -> Self {
+        Self {
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AggrFnStateApproxCountDistinctForBytes<C: Collator> {
+    sketch: HyperLogLog,
+    _phantom: std::marker::PhantomData<C>,
+}
+
+impl<C: Collator> AggrFnStateApproxCountDistinctForBytes<C> {
+    pub fn new() -> Self {
+        Self {
+            sketch: HyperLogLog::new(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    #[inline]
+    fn update_concrete(
+        &mut self,
+        _ctx: &mut EvalContext,
+        value: Option<BytesRef<'_>>,
+    ) -> Result<()> {
+        if let Some(value) = value {
+            let mut hasher = FarmHasher::default();
+            C::sort_hash(&mut hasher, value)?;
+            self.sketch.insert_hash(hasher.finish());
+        }
+        Ok(())
+    }
+}
+
+impl<C: Collator> super::ConcreteAggrFunctionState for AggrFnStateApproxCountDistinctForBytes<C> {
+    type ParameterType = BytesRef<'static>;
+
+    impl_concrete_state! { Self::ParameterType }
+
+    #[inline]
+    fn push_result(&self, _ctx: &mut EvalContext, target: &mut [VectorValue]) -> Result<()> {
+        assert_eq!(target.len(), 1);
+        target[0].push(Some(self.sketch.to_bytes()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tidb_query_datatype::{codec::collation::collator::*, EvalType};
+
+    use super::{super::AggrFunction, *};
+
+    #[test]
+    fn test_update() {
+        let mut ctx = EvalContext::default();
+        let function = AggrFnApproxCountDistinct::<Int>::new();
+        let mut state = function.create_state();
+
+        let mut result = [VectorValue::with_capacity(0, EvalType::Bytes)];
+        state.push_result(&mut ctx, &mut result).unwrap();
+        let empty_sketch = result[0].to_bytes_vec()[0].clone().unwrap();
+        assert_eq!(empty_sketch.len(), HLL_REGISTERS);
+        assert!(empty_sketch.iter().all(|&r| r == 0));
+
+        for v in 0..2000i64 {
+            update!(state, &mut ctx, Some(&v)).unwrap();
+        }
+        // Repeat a value that was already inserted; it must not change the
+        // sketch since it does not introduce a new distinct value.
+        update!(state, &mut ctx, Some(&0i64)).unwrap();
+
+        result[0].clear();
+        state.push_result(&mut ctx, &mut result).unwrap();
+        let sketch = result[0].to_bytes_vec()[0].clone().unwrap();
+        assert_eq!(sketch.len(), HLL_REGISTERS);
+        assert_ne!(sketch, empty_sketch);
+    }
+
+    #[test]
+    fn test_update_bytes() {
+        let mut ctx = EvalContext::default();
+        let function = AggrFnApproxCountDistinctForBytes::<CollatorUtf8Mb4Bin>::new();
+        let mut state = function.create_state();
+
+        let mut result = [VectorValue::with_capacity(0, EvalType::Bytes)];
+
+        update!(state, &mut ctx, Some(b"foo".as_ref())).unwrap();
+        update!(state, &mut ctx, Some(b"bar".as_ref())).unwrap();
+        update!(state, &mut ctx, Option::<BytesRef<'_>>::None).unwrap();
+
+        state.push_result(&mut ctx, &mut result).unwrap();
+        let sketch = result[0].to_bytes_vec()[0].clone().unwrap();
+        assert_eq!(sketch.len(), HLL_REGISTERS);
+        assert!(sketch.iter().any(|&r| r > 0));
+    }
+
+    #[test]
+    fn test_update_bytes_respects_collation() {
+        let mut ctx = EvalContext::default();
+        let mut result = [VectorValue::with_capacity(0, EvalType::Bytes)];
+
+        // Under a case-insensitive collation, "A" and "a" are the same
+        // distinct value, so inserting both leaves the sketch identical to
+        // inserting just one of them.
+        let function = AggrFnApproxCountDistinctForBytes::<CollatorUtf8Mb4GeneralCi>::new();
+        let mut ci_only_a = function.create_state();
+        update!(ci_only_a, &mut ctx, Some(b"A".as_ref())).unwrap();
+        let mut ci_both = function.create_state();
+        update!(ci_both, &mut ctx, Some(b"A".as_ref())).unwrap();
+        update!(ci_both, &mut ctx, Some(b"a".as_ref())).unwrap();
+
+        ci_only_a.push_result(&mut ctx, &mut result).unwrap();
+        let only_a_sketch = result[0].to_bytes_vec()[0].clone().unwrap();
+        result[0].clear();
+        ci_both.push_result(&mut ctx, &mut result).unwrap();
+        let both_sketch = result[0].to_bytes_vec()[0].clone().unwrap();
+        assert_eq!(only_a_sketch, both_sketch);
+
+        // Under a case-sensitive (binary) collation, they're two distinct
+        // values, so the same pair of inserts changes the sketch.
+        let function = AggrFnApproxCountDistinctForBytes::<CollatorUtf8Mb4Bin>::new();
+        let mut bin_only_a = function.create_state();
+        update!(bin_only_a, &mut ctx, Some(b"A".as_ref())).unwrap();
+        let mut bin_both = function.create_state();
+        update!(bin_both, &mut ctx, Some(b"A".as_ref())).unwrap();
+        update!(bin_both, &mut ctx, Some(b"a".as_ref())).unwrap();
+
+        result[0].clear();
+        bin_only_a.push_result(&mut ctx, &mut result).unwrap();
+        let only_a_sketch = result[0].to_bytes_vec()[0].clone().unwrap();
+        result[0].clear();
+        bin_both.push_result(&mut ctx, &mut result).unwrap();
+        let both_sketch = result[0].to_bytes_vec()[0].clone().unwrap();
+        assert_ne!(only_a_sketch, both_sketch);
+    }
+}
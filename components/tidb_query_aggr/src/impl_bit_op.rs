@@ -132,8 +132,9 @@ impl<T: BitOp> super::ConcreteAggrFunctionState for AggrFnStateBitOp<T> {
 #[cfg(test)]
 mod tests {
     use tidb_query_datatype::{
+        builder::FieldTypeBuilder,
         codec::batch::{LazyBatchColumn, LazyBatchColumnVec},
-        EvalType, FieldTypeAccessor, FieldTypeTp,
+        EvalType, FieldTypeAccessor, FieldTypeFlag, FieldTypeTp,
     };
     use tipb_helper::ExprDefBuilder;
 
@@ -461,4 +462,52 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_unsigned_column() {
+        // `BIGINT UNSIGNED` values are stored and transferred as their `i64` bit
+        // pattern. Values past `i64::MAX`, e.g. `u64::MAX`, must be aggregated by
+        // that bit pattern, not by the (nonsensical, wrapped) signed value.
+        let bit_or_parser = AggrFnDefinitionParserBitOp::<BitOr>::new();
+        let unsigned_field_type = FieldTypeBuilder::new()
+            .tp(FieldTypeTp::LongLong)
+            .flag(FieldTypeFlag::UNSIGNED)
+            .build();
+
+        let bit_or = ExprDefBuilder::aggr_func(ExprType::AggBitOr, unsigned_field_type.clone())
+            .push_child(ExprDefBuilder::column_ref(0, unsigned_field_type))
+            .build();
+        bit_or_parser.check_supported(&bit_or).unwrap();
+
+        let src_schema = [FieldTypeBuilder::new()
+            .tp(FieldTypeTp::LongLong)
+            .flag(FieldTypeFlag::UNSIGNED)
+            .build()];
+        let mut columns = LazyBatchColumnVec::from(vec![{
+            let mut col = LazyBatchColumn::decoded_with_capacity_and_tp(0, EvalType::Int);
+            col.mut_decoded().push_int(Some(1));
+            col.mut_decoded().push_int(Some(u64::MAX as i64));
+            col
+        }]);
+        let logical_rows = vec![0, 1];
+
+        let mut schema = vec![];
+        let mut exp = vec![];
+        let mut ctx = EvalContext::default();
+        let bit_or_fn = bit_or_parser
+            .parse(bit_or, &mut ctx, &src_schema, &mut schema, &mut exp)
+            .unwrap();
+
+        let mut state = bit_or_fn.create_state();
+        let result = exp[0]
+            .eval(&mut ctx, &src_schema, &mut columns, &logical_rows, 2)
+            .unwrap();
+        let result = result.vector_value().unwrap();
+        let vec: ChunkedVecSized<Int> = result.as_ref().to_int_vec().into();
+        update_vector!(state, &mut ctx, vec, result.logical_rows()).unwrap();
+
+        let mut aggr_result = [VectorValue::with_capacity(0, EvalType::Int)];
+        state.push_result(&mut ctx, &mut aggr_result).unwrap();
+        assert_eq!(aggr_result[0].to_int_vec(), &[Some(u64::MAX as i64)]);
+    }
 }
@@ -0,0 +1,231 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+use tidb_query_codegen::AggrFunction;
+use tidb_query_common::Result;
+use tidb_query_datatype::{
+    builder::FieldTypeBuilder,
+    codec::data_type::*,
+    expr::{EvalContext, Error},
+    FieldTypeTp,
+};
+use tidb_query_expr::{RpnExpression, RpnExpressionBuilder, RpnExpressionNode};
+use tipb::{Expr, ExprType, FieldType};
+
+use super::*;
+
+/// The parser for `GROUP_CONCAT` aggregate function.
+///
+/// `GROUP_CONCAT(value SEPARATOR sep)` is encoded as a two-child expression:
+/// the value to concatenate, followed by a constant string separator as the
+/// last child. Unlike MySQL, this parser does not accept more than one value
+/// column.
+///
+/// Per-group ordering (`GROUP_CONCAT(... ORDER BY ...)`) is not something an
+/// incremental aggregate state can apply by itself: like
+/// `BatchStreamAggregationExecutor`'s group-by boundary detection, this
+/// parser trusts that rows already arrive in the desired order, which is a
+/// property the query plan above this executor (an ordered index scan, or a
+/// sort) is responsible for establishing.
+pub struct AggrFnDefinitionParserGroupConcat;
+
+impl super::AggrDefinitionParser for AggrFnDefinitionParserGroupConcat {
+    fn check_supported(&self, aggr_def: &Expr) -> Result<()> {
+        assert_eq!(aggr_def.get_tp(), ExprType::GroupConcat);
+        let children = aggr_def.get_children();
+        if children.len() != 2 {
+            return Err(other_err!(
+                "Expect value and separator parameters, but got {} parameters",
+                children.len()
+            ));
+        }
+        for child in children {
+            RpnExpressionBuilder::check_expr_tree_supported(child)?;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn parse(
+        &self,
+        mut aggr_def: Expr,
+        ctx: &mut EvalContext,
+        src_schema: &[FieldType],
+        out_schema: &mut Vec<FieldType>,
+        out_exp: &mut Vec<RpnExpression>,
+    ) -> Result<Box<dyn AggrFunction>> {
+        assert_eq!(aggr_def.get_tp(), ExprType::GroupConcat);
+
+        let mut children = aggr_def.take_children().into_iter();
+        let value_def = children.next().unwrap();
+        let separator_def = children.next().unwrap();
+
+        let separator_exp =
+            RpnExpressionBuilder::build_from_expr_tree(separator_def, ctx, src_schema.len())?;
+        let separator = match separator_exp.as_ref() {
+            [RpnExpressionNode::Constant { value, .. }] => value.as_bytes().map(<[u8]>::to_vec),
+            _ => None,
+        }
+        .ok_or_else(|| other_err!("GROUP_CONCAT separator must be a constant string"))?;
+
+        let mut value_exp =
+            RpnExpressionBuilder::build_from_expr_tree(value_def, ctx, src_schema.len())?;
+        super::util::rewrite_exp_for_group_concat(src_schema, &mut value_exp)?;
+
+        // GROUP_CONCAT outputs one column holding the concatenated string.
+        out_schema.push(FieldTypeBuilder::new().tp(FieldTypeTp::VarString).build());
+        out_exp.push(value_exp);
+
+        Ok(Box::new(AggrFnGroupConcat::new(
+            separator,
+            ctx.cfg.group_concat_max_len as usize,
+        )))
+    }
+}
+
+/// The `GROUP_CONCAT` aggregate function.
+#[derive(Debug, AggrFunction)]
+#[aggr_function(state = AggrFnStateGroupConcat::new(self.separator.clone(), self.max_len))]
+pub struct AggrFnGroupConcat {
+    separator: Vec<u8>,
+    max_len: usize,
+}
+
+impl AggrFnGroupConcat {
+    pub fn new(separator: Vec<u8>, max_len: usize) -> Self {
+        Self { separator, max_len }
+    }
+}
+
+/// The state of the `GROUP_CONCAT` aggregate function.
+#[derive(Debug)]
+pub struct AggrFnStateGroupConcat {
+    separator: Vec<u8>,
+    max_len: usize,
+    result: Option<Vec<u8>>,
+    truncated: bool,
+}
+
+impl AggrFnStateGroupConcat {
+    pub fn new(separator: Vec<u8>, max_len: usize) -> Self {
+        Self {
+            separator,
+            max_len,
+            result: None,
+            truncated: false,
+        }
+    }
+
+    #[inline]
+    fn update_concrete(
+        &mut self,
+        ctx: &mut EvalContext,
+        value: Option<BytesRef<'_>>,
+    ) -> Result<()> {
+        // Once truncated there is nothing more useful `group_concat_max_len`
+        // would let us append, so stop charging memory and appending rows.
+        if self.truncated {
+            return Ok(());
+        }
+        let value = match value {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+
+        let result = self.result.get_or_insert_with(Vec::new);
+        let separator_len = if result.is_empty() {
+            0
+        } else {
+            self.separator.len()
+        };
+        ctx.charge_group_concat_memory(separator_len + value.len())?;
+
+        if !result.is_empty() {
+            result.extend_from_slice(&self.separator);
+        }
+        result.extend_from_slice(value);
+
+        if result.len() > self.max_len {
+            result.truncate(self.max_len);
+            self.truncated = true;
+            ctx.warnings
+                .append_warning(Error::group_concat_max_len_exceeded());
+        }
+        Ok(())
+    }
+}
+
+impl super::ConcreteAggrFunctionState for AggrFnStateGroupConcat {
+    type ParameterType = BytesRef<'static>;
+
+    impl_concrete_state! { Self::ParameterType }
+
+    #[inline]
+    fn push_result(&self, _ctx: &mut EvalContext, target: &mut [VectorValue]) -> Result<()> {
+        assert_eq!(target.len(), 1);
+        // A group with no non-NULL values to concatenate is SQL `NULL`, not
+        // an empty string.
+        target[0].push(self.result.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tidb_query_datatype::EvalType;
+
+    use super::{super::AggrFunction, *};
+
+    #[test]
+    fn test_update() {
+        let mut ctx = EvalContext::default();
+        let function = AggrFnGroupConcat::new(b",".to_vec(), 1024);
+        let mut state = function.create_state();
+
+        let mut result = [VectorValue::with_capacity(0, EvalType::Bytes)];
+        state.push_result(&mut ctx, &mut result).unwrap();
+        assert_eq!(result[0].to_bytes_vec(), &[None]);
+
+        update!(state, &mut ctx, Some(b"foo".as_ref())).unwrap();
+        update!(state, &mut ctx, Option::<BytesRef<'_>>::None).unwrap();
+        update!(state, &mut ctx, Some(b"bar".as_ref())).unwrap();
+
+        result[0].clear();
+        state.push_result(&mut ctx, &mut result).unwrap();
+        assert_eq!(result[0].to_bytes_vec(), &[Some(b"foo,bar".to_vec())]);
+    }
+
+    #[test]
+    fn test_update_all_null() {
+        let mut ctx = EvalContext::default();
+        let function = AggrFnGroupConcat::new(b",".to_vec(), 1024);
+        let mut state = function.create_state();
+
+        update!(state, &mut ctx, Option::<BytesRef<'_>>::None).unwrap();
+        update!(state, &mut ctx, Option::<BytesRef<'_>>::None).unwrap();
+
+        let mut result = [VectorValue::with_capacity(0, EvalType::Bytes)];
+        state.push_result(&mut ctx, &mut result).unwrap();
+        assert_eq!(result[0].to_bytes_vec(), &[None]);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut ctx = EvalContext::default();
+        let function = AggrFnGroupConcat::new(b",".to_vec(), 5);
+        let mut state = function.create_state();
+
+        update!(state, &mut ctx, Some(b"foo".as_ref())).unwrap();
+        update!(state, &mut ctx, Some(b"bar".as_ref())).unwrap();
+
+        let mut result = [VectorValue::with_capacity(0, EvalType::Bytes)];
+        state.push_result(&mut ctx, &mut result).unwrap();
+        assert_eq!(result[0].to_bytes_vec(), &[Some(b"foo,b".to_vec())]);
+        assert_eq!(ctx.warnings.warning_cnt, 1);
+
+        // Further updates are dropped once truncated.
+        update!(state, &mut ctx, Some(b"baz".as_ref())).unwrap();
+        result[0].clear();
+        state.push_result(&mut ctx, &mut result).unwrap();
+        assert_eq!(result[0].to_bytes_vec(), &[Some(b"foo,b".to_vec())]);
+    }
+}
@@ -12,10 +12,12 @@ extern crate tikv_util;
 #[macro_use(other_err)]
 extern crate tidb_query_common;
 
+mod impl_approx_count_distinct;
 mod impl_avg;
 mod impl_bit_op;
 mod impl_count;
 mod impl_first;
+mod impl_group_concat;
 mod impl_max_min;
 mod impl_sum;
 mod impl_variance;
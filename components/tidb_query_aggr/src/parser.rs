@@ -5,7 +5,10 @@ use tidb_query_datatype::expr::EvalContext;
 use tidb_query_expr::{RpnExpression, RpnExpressionBuilder};
 use tipb::{Expr, ExprType, FieldType};
 
-use crate::{impl_bit_op::*, impl_max_min::*, impl_variance::*, AggrFunction};
+use crate::{
+    impl_approx_count_distinct::*, impl_bit_op::*, impl_group_concat::*, impl_max_min::*,
+    impl_variance::*, AggrFunction,
+};
 
 /// Parse a specific aggregate function definition from protobuf.
 ///
@@ -79,6 +82,8 @@ fn map_pb_sig_to_aggr_func_parser(value: ExprType) -> Result<Box<dyn AggrDefinit
             Ok(Box::new(AggrFnDefinitionParserVariance::<Population>::new()))
         }
         ExprType::VarSamp => Ok(Box::new(AggrFnDefinitionParserVariance::<Sample>::new())),
+        ExprType::ApproxCountDistinct => Ok(Box::new(AggrFnDefinitionParserApproxCountDistinct)),
+        ExprType::GroupConcat => Ok(Box::new(AggrFnDefinitionParserGroupConcat)),
         v => Err(other_err!(
             "Aggregation function meet blacklist aggr function {:?}",
             v
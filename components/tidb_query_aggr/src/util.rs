@@ -63,6 +63,21 @@ pub fn rewrite_exp_for_sum_avg(schema: &[FieldType], exp: &mut RpnExpression) ->
     Ok(())
 }
 
+/// Rewrites the expression to insert a cast-to-string function if necessary,
+/// since `GROUP_CONCAT` always operates on the string representation of its
+/// argument.
+pub fn rewrite_exp_for_group_concat(schema: &[FieldType], exp: &mut RpnExpression) -> Result<()> {
+    let ret_field_type = exp.ret_field_type(schema);
+    let ret_eval_type = box_try!(EvalType::try_from(ret_field_type.as_accessor().tp()));
+    if ret_eval_type == EvalType::Bytes {
+        return Ok(());
+    }
+    let new_ret_field_type = FieldTypeBuilder::new().tp(FieldTypeTp::VarString).build();
+    let node = get_cast_fn_rpn_node(exp.is_last_constant(), ret_field_type, new_ret_field_type)?;
+    exp.push(node);
+    Ok(())
+}
+
 /// Rewrites the expression to insert necessary cast functions for Bit operation
 /// family functions.
 pub fn rewrite_exp_for_bit_op(schema: &[FieldType], exp: &mut RpnExpression) -> Result<()> {
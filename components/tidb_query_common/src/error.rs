@@ -10,6 +10,9 @@ pub enum EvaluateError {
     #[error("Execution terminated due to exceeding the deadline")]
     DeadlineExceeded,
 
+    #[error("Execution terminated due to exceeding the memory quota")]
+    MemoryQuotaExceeded,
+
     #[error("Invalid {charset} character string")]
     InvalidCharacterString { charset: String },
 
@@ -28,6 +31,7 @@ impl EvaluateError {
         match self {
             EvaluateError::InvalidCharacterString { .. } => 1300,
             EvaluateError::DeadlineExceeded => 9007,
+            EvaluateError::MemoryQuotaExceeded => 9008,
             EvaluateError::Custom { code, .. } => *code,
             EvaluateError::Other(_) => 10000,
         }
@@ -49,6 +53,13 @@ impl From<tikv_util::deadline::DeadlineError> for EvaluateError {
     }
 }
 
+impl From<tikv_util::memory::MemoryQuotaExceeded> for EvaluateError {
+    #[inline]
+    fn from(_: tikv_util::memory::MemoryQuotaExceeded) -> Self {
+        EvaluateError::MemoryQuotaExceeded
+    }
+}
+
 impl From<Infallible> for EvaluateError {
     fn from(e: Infallible) -> Self {
         match e {}
@@ -77,6 +88,7 @@ impl ErrorCodeExt for EvaluateError {
     fn error_code(&self) -> ErrorCode {
         match self {
             EvaluateError::DeadlineExceeded => error_code::coprocessor::DEADLINE_EXCEEDED,
+            EvaluateError::MemoryQuotaExceeded => error_code::coprocessor::MEMORY_QUOTA_EXCEEDED,
             EvaluateError::InvalidCharacterString { .. } => {
                 error_code::coprocessor::INVALID_CHARACTER_STRING
             }
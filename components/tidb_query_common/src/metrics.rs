@@ -15,6 +15,7 @@ make_auto_flush_static_metric! {
         batch_limit,
         batch_top_n,
         batch_projection,
+        batch_window,
         table_scan,
         index_scan,
         selection,
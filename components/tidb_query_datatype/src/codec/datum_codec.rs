@@ -12,8 +12,8 @@ use crate::{
     codec::{
         datum,
         mysql::{
-            DecimalDecoder, DecimalEncoder, DurationDecoder, EnumDecoder, EnumEncoder, JsonDecoder,
-            JsonEncoder, TimeDecoder,
+            validate_binary_json, DecimalDecoder, DecimalEncoder, DurationDecoder, EnumDecoder,
+            EnumEncoder, JsonDecoder, JsonEncoder, TimeDecoder,
         },
         Error, Result,
     },
@@ -521,7 +521,18 @@ pub fn decode_json_datum(mut raw_datum: &[u8]) -> Result<Option<Json>> {
     match flag {
         datum::NIL_FLAG => Ok(None),
         // In both index and record, it's flag is `JSON`. See TiDB's `encode()`.
-        datum::JSON_FLAG => Ok(Some(raw_datum.read_datum_payload_json()?)),
+        datum::JSON_FLAG => {
+            let json = raw_datum.read_datum_payload_json()?;
+            // `raw_datum` isn't necessarily `JsonEncoder`-produced: it may
+            // come straight from an SST written by an ingestion path (e.g.
+            // lightning) that bypasses it. `read_datum_payload_json` only
+            // checks that enough bytes were present, not that the offsets
+            // and lengths *inside* them are self-consistent, so validate
+            // structurally before this is later read via `JsonRef`'s
+            // unchecked accessors.
+            validate_binary_json(json.get_type() as u8, &json.value)?;
+            Ok(Some(json))
+        }
         _ => Err(Error::InvalidDataType(format!(
             "Unsupported datum flag {} for Json vector",
             flag
@@ -17,13 +17,16 @@ pub const ERR_REGEXP: i32 = 1139;
 pub const ZLIB_LENGTH_CORRUPTED: i32 = 1258;
 pub const ZLIB_DATA_CORRUPTED: i32 = 1259;
 pub const WARN_DATA_TRUNCATED: i32 = 1265;
+pub const ERR_CUT_VALUE_GROUP_CONCAT: i32 = 1260;
 pub const ERR_TRUNCATE_WRONG_VALUE: i32 = 1292;
 pub const ERR_UNKNOWN_TIMEZONE: i32 = 1298;
 pub const ERR_DIVISION_BY_ZERO: i32 = 1365;
 pub const ERR_DATA_TOO_LONG: i32 = 1406;
+pub const ERR_WRONG_PARAMCOUNT_TO_NATIVE_FCT: i32 = 1582;
 pub const ERR_INCORRECT_PARAMETERS: i32 = 1583;
 pub const ERR_DATA_OUT_OF_RANGE: i32 = 1690;
 pub const ERR_CANNOT_CONVERT_STRING: i32 = 3854;
+pub const ERR_JSON_DOCUMENT_NULL_KEY: i32 = 3158;
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -39,6 +42,8 @@ pub enum Error {
     Eval(String, i32),
     #[error("corrupted data: {0}")]
     CorruptedData(String),
+    #[error("memory quota exceeded while evaluating expression")]
+    MemoryQuotaExceeded,
     #[error("{0}")]
     Other(#[from] Box<dyn error::Error + Send + Sync>),
 }
@@ -87,6 +92,11 @@ impl Error {
         Error::Eval(msg.into(), ERR_DIVISION_BY_ZERO)
     }
 
+    pub fn group_concat_max_len_exceeded() -> Error {
+        let msg = "Row was cut by GROUP_CONCAT()";
+        Error::Eval(msg.into(), ERR_CUT_VALUE_GROUP_CONCAT)
+    }
+
     pub fn data_too_long(msg: String) -> Error {
         if msg.is_empty() {
             Error::Eval("Data Too Long".into(), ERR_DATA_TOO_LONG)
@@ -146,6 +156,19 @@ impl Error {
         Error::Eval(msg, ERR_INCORRECT_PARAMETERS)
     }
 
+    pub fn incorrect_parameter_count(val: &str) -> Error {
+        let msg = format!(
+            "Incorrect parameter count in the call to native function '{}'",
+            val
+        );
+        Error::Eval(msg, ERR_WRONG_PARAMCOUNT_TO_NATIVE_FCT)
+    }
+
+    pub fn json_document_null_key() -> Error {
+        let msg = "JSON documents may not contain NULL member names".to_owned();
+        Error::Eval(msg, ERR_JSON_DOCUMENT_NULL_KEY)
+    }
+
     pub fn regexp_error(msg: String) -> Error {
         Error::Eval(msg, ERR_REGEXP)
     }
@@ -210,12 +233,19 @@ impl From<crate::DataTypeError> for Error {
     }
 }
 
+impl From<tikv_util::memory::MemoryQuotaExceeded> for Error {
+    fn from(_: tikv_util::memory::MemoryQuotaExceeded) -> Error {
+        Error::MemoryQuotaExceeded
+    }
+}
+
 // TODO: `codec::Error` should be substituted by EvaluateError.
 impl From<Error> for EvaluateError {
     #[inline]
     fn from(err: Error) -> Self {
         match err {
             Error::Eval(msg, code) => EvaluateError::Custom { code, msg },
+            Error::MemoryQuotaExceeded => EvaluateError::MemoryQuotaExceeded,
             e => EvaluateError::Other(e.to_string()),
         }
     }
@@ -232,6 +262,7 @@ impl ErrorCodeExt for Error {
             Error::UnknownSignature(_) => error_code::coprocessor::UNKNOWN_SIGNATURE,
             Error::CorruptedData(_) => error_code::coprocessor::CORRUPTED_DATA,
             Error::Eval(..) => error_code::coprocessor::EVAL,
+            Error::MemoryQuotaExceeded => error_code::coprocessor::MEMORY_QUOTA_EXCEEDED,
             Error::Other(_) => error_code::UNKNOWN,
         }
     }
@@ -76,6 +76,17 @@ impl<'a> JsonRef<'a> {
         None
     }
 
+    /// Like `object_search_key`, but compares keys case-insensitively
+    /// (ASCII case folding).
+    ///
+    /// Object keys are stored sorted by their exact bytes to allow
+    /// `object_search_key`'s binary search, so a case-insensitive lookup
+    /// can't rely on that ordering and has to fall back to a linear scan.
+    pub fn object_search_key_ci(&self, key: &[u8]) -> Option<usize> {
+        let len = self.get_elem_count();
+        (0..len).find(|&i| self.object_get_key(i).eq_ignore_ascii_case(key))
+    }
+
     /// Gets the value (JsonRef) by the given offset of the value entry
     ///
     /// See `arrayGetElem()` in TiDB `json/binary.go`
@@ -15,8 +15,16 @@ impl<'a> JsonRef<'a> {
     /// may be autowrapped as an array. If there is no any expression matched,
     /// it returns None.
     ///
+    /// `case_insensitive` controls how object keys in `path_expr_list` are
+    /// matched against the keys of this JSON: when set, matching ignores
+    /// ASCII case instead of using MySQL's default binary comparison.
+    ///
     /// See `Extract()` in TiDB `json.binary_function.go`
-    pub fn extract(&self, path_expr_list: &[PathExpression]) -> Result<Option<Json>> {
+    pub fn extract(
+        &self,
+        path_expr_list: &[PathExpression],
+        case_insensitive: bool,
+    ) -> Result<Option<Json>> {
         let mut could_return_multiple_matches = path_expr_list.len() > 1;
 
         let mut elem_list = Vec::with_capacity(path_expr_list.len());
@@ -24,7 +32,7 @@ impl<'a> JsonRef<'a> {
             could_return_multiple_matches |= path_expr.contains_any_asterisk();
             could_return_multiple_matches |= path_expr.contains_any_range();
 
-            elem_list.append(&mut extract_json(*self, &path_expr.legs)?)
+            elem_list.append(&mut extract_json(*self, &path_expr.legs, case_insensitive)?)
         }
 
         if elem_list.is_empty() {
@@ -37,6 +45,42 @@ impl<'a> JsonRef<'a> {
             Ok(Some(elem_list.remove(0).to_owned()))
         }
     }
+
+    /// Like [`Self::extract`], but flattens any array result (recursing into
+    /// nested arrays too) down to its numeric leaves instead of returning a
+    /// `Json`. A match that isn't numeric -- a string, object, bool, null, or
+    /// no match at all -- contributes nothing, the same way MySQL's `SUM`/
+    /// `AVG` skip non-numeric input rather than erroring on it. Used by
+    /// `tidb_query_expr::impl_json`'s `json_sum_path`/`json_avg_path`, which
+    /// aggregate a multi-valued index's extracted values without
+    /// materializing the intermediate `Json` array per row.
+    pub fn extract_numerics(
+        &self,
+        path_expr_list: &[PathExpression],
+        case_insensitive: bool,
+    ) -> Result<Vec<f64>> {
+        let mut values = Vec::new();
+        if let Some(extracted) = self.extract(path_expr_list, case_insensitive)? {
+            collect_numerics(extracted.as_ref(), &mut values);
+        }
+        Ok(values)
+    }
+}
+
+fn collect_numerics(j: JsonRef<'_>, values: &mut Vec<f64>) {
+    match j.get_type() {
+        JsonType::Array => {
+            for i in 0..j.get_elem_count() {
+                if let Ok(elem) = j.array_get_elem(i) {
+                    collect_numerics(elem, values);
+                }
+            }
+        }
+        JsonType::I64 => values.push(j.get_i64() as f64),
+        JsonType::U64 => values.push(j.get_u64() as f64),
+        JsonType::Double => values.push(j.get_double()),
+        _ => {}
+    }
 }
 
 #[derive(Eq)]
@@ -77,7 +121,14 @@ fn append_if_ref_unique<'a>(elem_list: &mut Vec<JsonRef<'a>>, other: &Vec<JsonRe
 }
 
 /// `extract_json` is used by JSON::extract().
-pub fn extract_json<'a>(j: JsonRef<'a>, path_legs: &[PathLeg]) -> Result<Vec<JsonRef<'a>>> {
+///
+/// `case_insensitive` controls how the `Key` path legs are matched against
+/// object keys; see `JsonRef::extract`.
+pub fn extract_json<'a>(
+    j: JsonRef<'a>,
+    path_legs: &[PathLeg],
+    case_insensitive: bool,
+) -> Result<Vec<JsonRef<'a>>> {
     if path_legs.is_empty() {
         return Ok(vec![j]);
     }
@@ -92,7 +143,11 @@ pub fn extract_json<'a>(j: JsonRef<'a>, path_legs: &[PathLeg]) -> Result<Vec<Jso
                         for k in 0..elem_count {
                             append_if_ref_unique(
                                 &mut ret,
-                                &extract_json(j.array_get_elem(k)?, sub_path_legs)?,
+                                &extract_json(
+                                    j.array_get_elem(k)?,
+                                    sub_path_legs,
+                                    case_insensitive,
+                                )?,
                             )
                         }
                     }
@@ -101,7 +156,11 @@ pub fn extract_json<'a>(j: JsonRef<'a>, path_legs: &[PathLeg]) -> Result<Vec<Jso
                             if index < elem_count {
                                 append_if_ref_unique(
                                     &mut ret,
-                                    &extract_json(j.array_get_elem(index)?, sub_path_legs)?,
+                                    &extract_json(
+                                        j.array_get_elem(index)?,
+                                        sub_path_legs,
+                                        case_insensitive,
+                                    )?,
                                 )
                             }
                         }
@@ -117,7 +176,11 @@ pub fn extract_json<'a>(j: JsonRef<'a>, path_legs: &[PathLeg]) -> Result<Vec<Jso
                                 for i in start..=end {
                                     append_if_ref_unique(
                                         &mut ret,
-                                        &extract_json(j.array_get_elem(i)?, sub_path_legs)?,
+                                        &extract_json(
+                                            j.array_get_elem(i)?,
+                                            sub_path_legs,
+                                            case_insensitive,
+                                        )?,
                                     )
                                 }
                             }
@@ -131,15 +194,19 @@ pub fn extract_json<'a>(j: JsonRef<'a>, path_legs: &[PathLeg]) -> Result<Vec<Jso
                 //
                 // as the element is not array, don't use `array_get_index`
                 match selection {
-                    ArraySelection::Index(ArrayIndex::Left(0)) => {
-                        append_if_ref_unique(&mut ret, &extract_json(j, sub_path_legs)?)
-                    }
+                    ArraySelection::Index(ArrayIndex::Left(0)) => append_if_ref_unique(
+                        &mut ret,
+                        &extract_json(j, sub_path_legs, case_insensitive)?,
+                    ),
                     ArraySelection::Range(
                         ArrayIndex::Left(0),
                         ArrayIndex::Right(0) | ArrayIndex::Left(_),
                     ) => {
                         // for [0 to Non-negative Number] and [0 to last], it extracts itself
-                        append_if_ref_unique(&mut ret, &extract_json(j, sub_path_legs)?)
+                        append_if_ref_unique(
+                            &mut ret,
+                            &extract_json(j, sub_path_legs, case_insensitive)?,
+                        )
                     }
                     _ => {}
                 }
@@ -153,28 +220,43 @@ pub fn extract_json<'a>(j: JsonRef<'a>, path_legs: &[PathLeg]) -> Result<Vec<Jso
                         for i in 0..elem_count {
                             append_if_ref_unique(
                                 &mut ret,
-                                &extract_json(j.object_get_val(i)?, sub_path_legs)?,
+                                &extract_json(
+                                    j.object_get_val(i)?,
+                                    sub_path_legs,
+                                    case_insensitive,
+                                )?,
                             )
                         }
                     }
                     KeySelection::Key(key) => {
-                        if let Some(idx) = j.object_search_key(key.as_bytes()) {
+                        let idx = if case_insensitive {
+                            j.object_search_key_ci(key.as_bytes())
+                        } else {
+                            j.object_search_key(key.as_bytes())
+                        };
+                        if let Some(idx) = idx {
                             let val = j.object_get_val(idx)?;
-                            append_if_ref_unique(&mut ret, &extract_json(val, sub_path_legs)?)
+                            append_if_ref_unique(
+                                &mut ret,
+                                &extract_json(val, sub_path_legs, case_insensitive)?,
+                            )
                         }
                     }
                 }
             }
         }
         PathLeg::DoubleAsterisk => {
-            append_if_ref_unique(&mut ret, &extract_json(j, sub_path_legs)?);
+            append_if_ref_unique(
+                &mut ret,
+                &extract_json(j, sub_path_legs, case_insensitive)?,
+            );
             match j.get_type() {
                 JsonType::Array => {
                     let elem_count = j.get_elem_count();
                     for k in 0..elem_count {
                         append_if_ref_unique(
                             &mut ret,
-                            &extract_json(j.array_get_elem(k)?, path_legs)?,
+                            &extract_json(j.array_get_elem(k)?, path_legs, case_insensitive)?,
                         )
                     }
                 }
@@ -183,7 +265,7 @@ pub fn extract_json<'a>(j: JsonRef<'a>, path_legs: &[PathLeg]) -> Result<Vec<Jso
                     for i in 0..elem_count {
                         append_if_ref_unique(
                             &mut ret,
-                            &extract_json(j.object_get_val(i)?, path_legs)?,
+                            &extract_json(j.object_get_val(i)?, path_legs, case_insensitive)?,
                         )
                     }
                 }
@@ -623,7 +705,7 @@ mod tests {
             };
             let got = j
                 .as_ref()
-                .extract(&exprs[..])
+                .extract(&exprs[..], false)
                 .unwrap()
                 .map(|got| got.to_string());
             assert_eq!(
@@ -6,8 +6,14 @@ use super::{super::Result, path_expr::PathExpression, Json, JsonRef, JsonType};
 
 impl<'a> JsonRef<'a> {
     /// Evaluates a (possibly empty) list of values and returns a JSON array
-    /// containing those values specified by `path_expr_list`
-    pub fn keys(&self, path_expr_list: &[PathExpression]) -> Result<Option<Json>> {
+    /// containing those values specified by `path_expr_list`.
+    ///
+    /// `case_insensitive` is forwarded to `extract`; see its doc comment.
+    pub fn keys(
+        &self,
+        path_expr_list: &[PathExpression],
+        case_insensitive: bool,
+    ) -> Result<Option<Json>> {
         if !path_expr_list.is_empty() {
             if path_expr_list.len() > 1 {
                 return Err(box_err!(
@@ -24,7 +30,7 @@ impl<'a> JsonRef<'a> {
                     path_expr_list
                 ));
             }
-            match self.extract(path_expr_list)? {
+            match self.extract(path_expr_list, case_insensitive)? {
                 Some(j) => json_keys(&j.as_ref()),
                 None => Ok(None),
             }
@@ -104,7 +110,7 @@ mod tests {
                 Some(p) => vec![parse_json_path_expr(p).unwrap()],
                 None => vec![],
             };
-            let got = j.as_ref().keys(&exprs[..]);
+            let got = j.as_ref().keys(&exprs[..], false);
             if success {
                 assert!(got.is_ok(), "#{} expect modify ok but got {:?}", i, got);
                 let result = got.unwrap();
@@ -19,7 +19,9 @@ impl<'a> JsonRef<'a> {
         if path_expr_list.len() == 1 && path_expr_list[0].contains_any_asterisk() {
             return Ok(None);
         }
-        Ok(self.extract(path_expr_list)?.map(|j| j.as_ref().len()))
+        Ok(self
+            .extract(path_expr_list, false)?
+            .map(|j| j.as_ref().len()))
     }
 }
 
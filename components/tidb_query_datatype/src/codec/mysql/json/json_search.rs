@@ -0,0 +1,557 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::str::{self, FromStr};
+
+use collections::HashSet;
+
+use super::{
+    super::Result,
+    path_expr::{ArrayIndex, ArraySelection, KeySelection, PathExpression, PathLeg},
+    Error, Json, JsonRef, JsonType,
+};
+
+/// The `one_or_all` argument to `JSON_SEARCH`: whether to stop at the first
+/// match (`one`) or collect every match (`all`).
+///
+/// See `json_search` in `tidb_query_expr::impl_json`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OneOrAll {
+    One,
+    All,
+}
+
+impl FromStr for OneOrAll {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case("one") {
+            Ok(OneOrAll::One)
+        } else if s.eq_ignore_ascii_case("all") {
+            Ok(OneOrAll::All)
+        } else {
+            Err(Error::incorrect_parameters("json_search"))
+        }
+    }
+}
+
+impl<'a> JsonRef<'a> {
+    /// Implements `JSON_SEARCH(json_doc, one_or_all, search_str[,
+    /// escape_char[, path] ...])`: finds every string *value* (never an
+    /// object key, and never a non-string scalar) whose whole value matches
+    /// `search_str`'s LIKE-style pattern, optionally restricted to the
+    /// subtrees `paths` selects, and returns:
+    ///   - `None` if nothing matches;
+    ///   - a single path string if exactly one match is found, or
+    ///     `one_or_all` is [`OneOrAll::One`] (which stops at the first
+    ///     match);
+    ///   - otherwise a JSON array of the (duplicate-free) matched paths.
+    ///
+    /// `case_insensitive` makes the `search_str` match ASCII
+    /// case-insensitive, and is also forwarded to the same object-key
+    /// matching `extract` uses for `paths`; see its doc comment.
+    pub fn search(
+        &self,
+        one_or_all: OneOrAll,
+        search_str: &str,
+        escape: u8,
+        case_insensitive: bool,
+        paths: &[PathExpression],
+    ) -> Result<Option<Json>> {
+        let one = one_or_all == OneOrAll::One;
+        let mut legs = Vec::new();
+        let mut seen = HashSet::default();
+        let mut matches = Vec::new();
+
+        if paths.is_empty() {
+            collect_matches(
+                *self,
+                &mut legs,
+                search_str,
+                escape,
+                case_insensitive,
+                one,
+                &mut seen,
+                &mut matches,
+            )?;
+        } else {
+            for path in paths {
+                if one && !matches.is_empty() {
+                    break;
+                }
+                walk_paths(
+                    *self,
+                    &path.legs,
+                    &mut legs,
+                    case_insensitive,
+                    &mut |root, legs| {
+                        collect_matches(
+                            root,
+                            legs,
+                            search_str,
+                            escape,
+                            case_insensitive,
+                            one,
+                            &mut seen,
+                            &mut matches,
+                        )
+                    },
+                )?;
+            }
+        }
+
+        Ok(match matches.len() {
+            0 => None,
+            1 => Some(Json::from_string(matches.remove(0))?),
+            _ => Some(Json::from_array(
+                matches
+                    .into_iter()
+                    .map(Json::from_string)
+                    .collect::<Result<Vec<_>>>()?,
+            )?),
+        })
+    }
+}
+
+/// Walks `j` along `path_legs`, the same way [`super::json_extract::extract_json`]
+/// does, except that instead of collecting the matched `JsonRef`s it resolves
+/// every wildcard/range/double-asterisk leg to the concrete key or array
+/// index it matched, pushes that concrete leg onto `legs`, and invokes
+/// `visit` with the matched subtree once `path_legs` is exhausted -- so
+/// `visit` always sees the absolute path to its subtree in `legs`, not just
+/// the fixed part `path_legs` specified.
+fn walk_paths<'a>(
+    j: JsonRef<'a>,
+    path_legs: &[PathLeg],
+    legs: &mut Vec<PathLeg>,
+    case_insensitive: bool,
+    visit: &mut dyn FnMut(JsonRef<'a>, &mut Vec<PathLeg>) -> Result<()>,
+) -> Result<()> {
+    if path_legs.is_empty() {
+        return visit(j, legs);
+    }
+    let (current_leg, sub_path_legs) = (&path_legs[0], &path_legs[1..]);
+    match current_leg {
+        PathLeg::ArraySelection(selection) => {
+            if j.get_type() == JsonType::Array {
+                let elem_count = j.get_elem_count();
+                match selection {
+                    ArraySelection::Asterisk => {
+                        for k in 0..elem_count {
+                            walk_array_elem(j, k, sub_path_legs, legs, case_insensitive, visit)?;
+                        }
+                    }
+                    ArraySelection::Index(index) => {
+                        if let Some(index) = j.array_get_index(*index) {
+                            if index < elem_count {
+                                walk_array_elem(
+                                    j,
+                                    index,
+                                    sub_path_legs,
+                                    legs,
+                                    case_insensitive,
+                                    visit,
+                                )?;
+                            }
+                        }
+                    }
+                    ArraySelection::Range(start, end) => {
+                        if let (Some(start), Some(mut end)) =
+                            (j.array_get_index(*start), j.array_get_index(*end))
+                        {
+                            if end >= elem_count {
+                                end = elem_count - 1;
+                            }
+                            if start <= end {
+                                for i in start..=end {
+                                    walk_array_elem(
+                                        j,
+                                        i,
+                                        sub_path_legs,
+                                        legs,
+                                        case_insensitive,
+                                        visit,
+                                    )?;
+                                }
+                            }
+                        }
+                    }
+                }
+            } else {
+                // Same auto-wrapping rule as `extract_json`: a non-array
+                // value is treated as a one-element array for `[0]` (and a
+                // `[0 to N]`/`[0 to last]` range).
+                match selection {
+                    ArraySelection::Index(ArrayIndex::Left(0)) => {
+                        walk_paths(j, sub_path_legs, legs, case_insensitive, visit)?;
+                    }
+                    ArraySelection::Range(
+                        ArrayIndex::Left(0),
+                        ArrayIndex::Right(0) | ArrayIndex::Left(_),
+                    ) => {
+                        walk_paths(j, sub_path_legs, legs, case_insensitive, visit)?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        PathLeg::Key(key) => {
+            if j.get_type() == JsonType::Object {
+                match key {
+                    KeySelection::Asterisk => {
+                        let elem_count = j.get_elem_count();
+                        for i in 0..elem_count {
+                            walk_object_val(j, i, sub_path_legs, legs, case_insensitive, visit)?;
+                        }
+                    }
+                    KeySelection::Key(key) => {
+                        let idx = if case_insensitive {
+                            j.object_search_key_ci(key.as_bytes())
+                        } else {
+                            j.object_search_key(key.as_bytes())
+                        };
+                        if let Some(idx) = idx {
+                            walk_object_val(j, idx, sub_path_legs, legs, case_insensitive, visit)?;
+                        }
+                    }
+                }
+            }
+        }
+        PathLeg::DoubleAsterisk => {
+            walk_paths(j, sub_path_legs, legs, case_insensitive, visit)?;
+            match j.get_type() {
+                JsonType::Array => {
+                    let elem_count = j.get_elem_count();
+                    for k in 0..elem_count {
+                        walk_array_elem(j, k, path_legs, legs, case_insensitive, visit)?;
+                    }
+                }
+                JsonType::Object => {
+                    let elem_count = j.get_elem_count();
+                    for i in 0..elem_count {
+                        walk_object_val(j, i, path_legs, legs, case_insensitive, visit)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+fn walk_array_elem<'a>(
+    j: JsonRef<'a>,
+    index: usize,
+    path_legs: &[PathLeg],
+    legs: &mut Vec<PathLeg>,
+    case_insensitive: bool,
+    visit: &mut dyn FnMut(JsonRef<'a>, &mut Vec<PathLeg>) -> Result<()>,
+) -> Result<()> {
+    legs.push(PathLeg::ArraySelection(ArraySelection::Index(
+        ArrayIndex::Left(index as u32),
+    )));
+    let result = walk_paths(
+        j.array_get_elem(index)?,
+        path_legs,
+        legs,
+        case_insensitive,
+        visit,
+    );
+    legs.pop();
+    result
+}
+
+fn walk_object_val<'a>(
+    j: JsonRef<'a>,
+    index: usize,
+    path_legs: &[PathLeg],
+    legs: &mut Vec<PathLeg>,
+    case_insensitive: bool,
+    visit: &mut dyn FnMut(JsonRef<'a>, &mut Vec<PathLeg>) -> Result<()>,
+) -> Result<()> {
+    let key = str::from_utf8(j.object_get_key(index))?.to_owned();
+    legs.push(PathLeg::Key(KeySelection::Key(key)));
+    let result = walk_paths(
+        j.object_get_val(index)?,
+        path_legs,
+        legs,
+        case_insensitive,
+        visit,
+    );
+    legs.pop();
+    result
+}
+
+/// Recurses through every string-scalar leaf of `j`, matching each one
+/// against `search_str` (see [`like_match`]) and recording its absolute path
+/// (rendered by [`render_path`]) the first time it matches. Only string
+/// *values* are candidates -- object keys and non-string scalars (numbers,
+/// booleans, null) are never returned by `JSON_SEARCH`, matching MySQL.
+#[allow(clippy::too_many_arguments)]
+fn collect_matches(
+    j: JsonRef<'_>,
+    legs: &mut Vec<PathLeg>,
+    search_str: &str,
+    escape: u8,
+    case_insensitive: bool,
+    one: bool,
+    seen: &mut HashSet<String>,
+    matches: &mut Vec<String>,
+) -> Result<()> {
+    if one && !matches.is_empty() {
+        return Ok(());
+    }
+    match j.get_type() {
+        JsonType::String => {
+            if like_match(j.get_str()?, search_str, escape, case_insensitive) {
+                let path = render_path(legs);
+                if seen.insert(path.clone()) {
+                    matches.push(path);
+                }
+            }
+        }
+        JsonType::Object => {
+            let elem_count = j.get_elem_count();
+            for i in 0..elem_count {
+                if one && !matches.is_empty() {
+                    break;
+                }
+                let key = str::from_utf8(j.object_get_key(i))?.to_owned();
+                legs.push(PathLeg::Key(KeySelection::Key(key)));
+                let result = collect_matches(
+                    j.object_get_val(i)?,
+                    legs,
+                    search_str,
+                    escape,
+                    case_insensitive,
+                    one,
+                    seen,
+                    matches,
+                );
+                legs.pop();
+                result?;
+            }
+        }
+        JsonType::Array => {
+            let elem_count = j.get_elem_count();
+            for i in 0..elem_count {
+                if one && !matches.is_empty() {
+                    break;
+                }
+                legs.push(PathLeg::ArraySelection(ArraySelection::Index(
+                    ArrayIndex::Left(i as u32),
+                )));
+                let result = collect_matches(
+                    j.array_get_elem(i)?,
+                    legs,
+                    search_str,
+                    escape,
+                    case_insensitive,
+                    one,
+                    seen,
+                    matches,
+                );
+                legs.pop();
+                result?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Renders `legs` back into MySQL path-expression syntax, e.g.
+/// `[Key("a"), ArraySelection(Index(Left(0)))]` -> `"$.a[0]"`. `legs` only
+/// ever holds the concrete `Key`/`Index(Left(_))` legs [`walk_paths`] and
+/// [`collect_matches`] push, never a wildcard, range, or `**` -- those are
+/// always resolved to a concrete leg before being pushed.
+fn render_path(legs: &[PathLeg]) -> String {
+    let mut out = String::from("$");
+    for leg in legs {
+        match leg {
+            PathLeg::Key(KeySelection::Key(key)) => {
+                out.push('.');
+                if key_needs_quoting(key) {
+                    // `Json`'s own `Display` (see `mod.rs`) likewise quotes
+                    // via `serde_json`, so this reuses the same quoting
+                    // convention rather than hand-rolling another one.
+                    out.push_str(&serde_json::to_string(key).unwrap());
+                } else {
+                    out.push_str(key);
+                }
+            }
+            PathLeg::ArraySelection(ArraySelection::Index(ArrayIndex::Left(index))) => {
+                out.push('[');
+                out.push_str(&index.to_string());
+                out.push(']');
+            }
+            _ => unreachable!("json_search only ever resolves legs to a concrete key or index"),
+        }
+    }
+    out
+}
+
+/// Mirrors `key_selection_key`'s unquoted-key acceptance rule in
+/// `path_expr.rs`'s parser, so a path rendered by [`render_path`]
+/// round-trips through `parse_json_path_expr` the same way it was produced.
+fn key_needs_quoting(key: &str) -> bool {
+    match key.chars().next() {
+        None => true,
+        Some(c) if c.is_ascii_digit() => true,
+        _ => key.chars().any(|c| {
+            c.is_whitespace()
+                || c == '.'
+                || c == '['
+                || c == '*'
+                || (c.is_ascii() && !c.is_ascii_alphanumeric() && c != '_' && c != '$')
+        }),
+    }
+}
+
+enum PatternToken {
+    Literal(char),
+    AnyChar,
+    AnyString,
+}
+
+fn compile_pattern(pattern: &[char], escape: char) -> Vec<PatternToken> {
+    let mut tokens = Vec::with_capacity(pattern.len());
+    let mut i = 0;
+    while i < pattern.len() {
+        match pattern[i] {
+            c if c == escape && i + 1 < pattern.len() => {
+                tokens.push(PatternToken::Literal(pattern[i + 1]));
+                i += 2;
+            }
+            '%' => {
+                tokens.push(PatternToken::AnyString);
+                i += 1;
+            }
+            '_' => {
+                tokens.push(PatternToken::AnyChar);
+                i += 1;
+            }
+            c => {
+                tokens.push(PatternToken::Literal(c));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// A standalone `%`/`_` wildcard matcher for `JSON_SEARCH`'s `search_str`,
+/// supporting a configurable escape byte the same way SQL's `LIKE ...
+/// ESCAPE` does. Deliberately not a call into
+/// `tidb_query_expr::impl_like`'s `#[rpn_fn]`-annotated `like`: nothing else
+/// in this crate calls an `#[rpn_fn]`-annotated function directly outside
+/// the generated metadata-table dispatch, and this datatype crate doesn't
+/// depend on `tidb_query_expr` regardless.
+fn like_match(text: &str, pattern: &str, escape: u8, case_insensitive: bool) -> bool {
+    let norm = |c: char| {
+        if case_insensitive {
+            c.to_ascii_lowercase()
+        } else {
+            c
+        }
+    };
+    let text: Vec<char> = text.chars().map(norm).collect();
+    let pattern: Vec<char> = pattern.chars().map(norm).collect();
+    let tokens = compile_pattern(&pattern, escape as char);
+
+    let (text_len, token_len) = (text.len(), tokens.len());
+    // dp[t][k] == the first `t` characters of `text` match the first `k`
+    // tokens of the compiled pattern.
+    let mut dp = vec![vec![false; token_len + 1]; text_len + 1];
+    dp[0][0] = true;
+    for k in 1..=token_len {
+        if let PatternToken::AnyString = tokens[k - 1] {
+            dp[0][k] = dp[0][k - 1];
+        }
+    }
+    for t in 1..=text_len {
+        for k in 1..=token_len {
+            dp[t][k] = match tokens[k - 1] {
+                PatternToken::Literal(c) => dp[t - 1][k - 1] && text[t - 1] == c,
+                PatternToken::AnyChar => dp[t - 1][k - 1],
+                PatternToken::AnyString => dp[t][k - 1] || dp[t - 1][k],
+            };
+        }
+    }
+    dp[text_len][token_len]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_one_or_all_from_str() {
+        assert_eq!(OneOrAll::from_str("one").unwrap(), OneOrAll::One);
+        assert_eq!(OneOrAll::from_str("ONE").unwrap(), OneOrAll::One);
+        assert_eq!(OneOrAll::from_str("all").unwrap(), OneOrAll::All);
+        assert_eq!(OneOrAll::from_str("ALL").unwrap(), OneOrAll::All);
+        OneOrAll::from_str("any").unwrap_err();
+    }
+
+    #[test]
+    fn test_like_match() {
+        let cases = vec![
+            ("abc", "abc", b'\\', false, true),
+            ("abc", "a%c", b'\\', false, true),
+            ("abc", "a_c", b'\\', false, true),
+            ("abc", "a_", b'\\', false, false),
+            ("a%c", "a\\%c", b'\\', false, true),
+            ("a%c", "a%c", b'\\', false, true),
+            ("abc", "a\\%c", b'\\', false, false),
+            ("ABC", "abc", b'\\', true, true),
+            ("ABC", "abc", b'\\', false, false),
+        ];
+        for (text, pattern, escape, case_insensitive, expected) in cases {
+            assert_eq!(
+                like_match(text, pattern, escape, case_insensitive),
+                expected,
+                "text: {}, pattern: {}",
+                text,
+                pattern
+            );
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let cases = vec![
+            (r#"["abc", [{"k": "10"}, "abc"]]"#, OneOrAll::One, "abc", None, Some(r#""$[0]""#)),
+            (
+                r#"["abc", [{"k": "10"}, "abc"]]"#,
+                OneOrAll::All,
+                "abc",
+                None,
+                Some(r#"["$[0]", "$[1][1]"]"#),
+            ),
+            (
+                r#"{"a": "abc", "b": {"c": "abc", "d": "xyz"}}"#,
+                OneOrAll::All,
+                "abc",
+                None,
+                Some(r#"["$.a", "$.b.c"]"#),
+            ),
+            (r#"{"a": "abc"}"#, OneOrAll::One, "xyz", None, None),
+            // object keys are never matched, only string values
+            (r#"{"abc": 1}"#, OneOrAll::All, "abc", None, None),
+            // non-string scalars are never matched
+            (r#"{"a": 123}"#, OneOrAll::All, "123", None, None),
+        ];
+        for (doc, one_or_all, search_str, paths, expected) in cases {
+            let j: Json = doc.parse().unwrap();
+            let expected = expected.map(|s| s.parse::<Json>().unwrap());
+            let paths: Vec<PathExpression> = paths.unwrap_or_default();
+            let got = j
+                .as_ref()
+                .search(one_or_all, search_str, b'\\', false, &paths)
+                .unwrap();
+            assert_eq!(got, expected, "doc: {}", doc);
+        }
+    }
+}
@@ -74,8 +74,10 @@ mod json_memberof;
 mod json_merge;
 mod json_modify;
 mod json_remove;
+mod json_search;
 mod json_type;
 pub mod json_unquote;
+mod validate;
 
 use std::{
     collections::BTreeMap,
@@ -90,7 +92,9 @@ use tikv_util::is_even;
 pub use self::{
     jcodec::{JsonDatumPayloadChunkEncoder, JsonDecoder, JsonEncoder},
     json_modify::ModifyType,
+    json_search::OneOrAll,
     path_expr::{parse_json_path_expr, PathExpression},
+    validate::validate_binary_json,
 };
 use super::super::{datum::Datum, Error, Result};
 use crate::{
@@ -459,10 +463,7 @@ pub fn json_array(elems: Vec<Datum>) -> Result<Json> {
 pub fn json_object(kvs: Vec<Datum>) -> Result<Json> {
     let len = kvs.len();
     if !is_even(len) {
-        return Err(Error::Other(box_err!(
-            "Incorrect parameter count in the call to native \
-             function 'JSON_OBJECT'"
-        )));
+        return Err(Error::incorrect_parameter_count("JSON_OBJECT"));
     }
     let mut map = BTreeMap::new();
     let mut key = None;
@@ -470,9 +471,7 @@ pub fn json_object(kvs: Vec<Datum>) -> Result<Json> {
         if key.is_none() {
             // take elem as key
             if elem == Datum::Null {
-                return Err(invalid_type!(
-                    "JSON documents may not contain NULL member names"
-                ));
+                return Err(Error::json_document_null_key());
             }
             key = Some(elem.into_string()?);
         } else {
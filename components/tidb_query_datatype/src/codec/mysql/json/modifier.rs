@@ -43,7 +43,7 @@ impl<'a> BinaryModifier<'a> {
     /// Replaces the existing value JSON and adds nonexisting value
     /// specified by the expression path with `new`
     pub fn set(mut self, path: &PathExpression, new: Json) -> Result<Json> {
-        let result = extract_json(self.old, path.legs.as_slice())?;
+        let result = extract_json(self.old, path.legs.as_slice(), false)?;
         if !result.is_empty() {
             self.to_be_modified_ptr = result[0].as_ptr();
             self.new_value = Some(new);
@@ -56,7 +56,7 @@ impl<'a> BinaryModifier<'a> {
     /// Replaces the existing value JSON specified by the expression path with
     /// `new`
     pub fn replace(mut self, path: &PathExpression, new: Json) -> Result<Json> {
-        let result = extract_json(self.old, path.legs.as_slice())?;
+        let result = extract_json(self.old, path.legs.as_slice(), false)?;
         if result.is_empty() {
             return Ok(self.old.to_owned());
         }
@@ -68,7 +68,7 @@ impl<'a> BinaryModifier<'a> {
     /// Inserts a `new` into `old` JSON document by given expression path
     /// without replacing existing values
     pub fn insert(mut self, path: &PathExpression, new: Json) -> Result<Json> {
-        let result = extract_json(self.old, path.legs.as_slice())?;
+        let result = extract_json(self.old, path.legs.as_slice(), false)?;
         if !result.is_empty() {
             // The path-value is existing. The insertion is ignored with no overwrite.
             return Ok(self.old.to_owned());
@@ -83,7 +83,7 @@ impl<'a> BinaryModifier<'a> {
         }
         let legs_len = path_legs.len();
         let (parent_legs, last_leg) = (&path_legs[..legs_len - 1], &path_legs[legs_len - 1]);
-        let result = extract_json(self.old, parent_legs)?;
+        let result = extract_json(self.old, parent_legs, false)?;
         if result.is_empty() {
             return Ok(());
         }
@@ -148,7 +148,7 @@ impl<'a> BinaryModifier<'a> {
     }
 
     pub fn remove(mut self, path_legs: &[PathLeg]) -> Result<Json> {
-        let result = extract_json(self.old, path_legs)?;
+        let result = extract_json(self.old, path_legs, false)?;
         if result.is_empty() {
             return Ok(self.old.to_owned());
         }
@@ -162,7 +162,7 @@ impl<'a> BinaryModifier<'a> {
         }
         let legs_len = path_legs.len();
         let (parent_legs, last_leg) = (&path_legs[..legs_len - 1], &path_legs[legs_len - 1]);
-        let result = extract_json(self.old, parent_legs)?;
+        let result = extract_json(self.old, parent_legs, false)?;
         if result.is_empty() {
             // No parent found, just return
             return Ok(());
@@ -0,0 +1,265 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! A non-panicking structural validator for the binary JSON format described
+//! in the module doc comment of `super`.
+//!
+//! `JsonRef`'s accessors (see `super::binary`) assume the bytes they're
+//! handed were produced by `JsonEncoder`, and use that assumption to skip
+//! bounds checks for speed -- a corrupted or adversarially crafted blob can
+//! make them read out of bounds and panic. Import/ingestion paths (e.g.
+//! lightning writing already-encoded JSON column bytes straight into an SST)
+//! don't go through `JsonEncoder`, so they need a way to check untrusted
+//! bytes are safe to later build a `JsonRef` from and evaluate, without
+//! paying for a full decode into an owned `Json`. See
+//! `datum_codec::decode_json_datum`, which calls this on every `Json`
+//! decoded from a raw datum, for exactly that reason.
+
+use codec::number::NumberCodec;
+
+use super::{constants::*, JsonType};
+use crate::codec::Result;
+
+/// Mirrors MySQL's own `JSON_DOCUMENT_MAX_DEPTH`: nesting deeper than this is
+/// rejected rather than recursed into, so a crafted blob can't drive
+/// `validate_value`/`validate_container` deep enough to overflow the stack --
+/// an unrecoverable process abort, which is strictly worse than the panics
+/// this validator exists to prevent in the first place.
+const MAX_DEPTH: usize = 100;
+
+/// Validates that `value` is a structurally well-formed binary JSON value of
+/// type `tp`, i.e. every offset and length it contains stays in bounds, so
+/// that `JsonRef::new(tp, value)`'s accessors (`array_get_elem`,
+/// `object_get_key`, `val_entry_get`, ...) can't panic or read out of bounds
+/// on it.
+///
+/// This only checks structural safety, not full MySQL semantics (e.g. it
+/// doesn't require object keys to be sorted, even though a well-formed
+/// encoder always produces them sorted).
+pub fn validate_binary_json(tp: u8, value: &[u8]) -> Result<()> {
+    validate_value(JsonType::try_from(tp)?, value, 0)
+}
+
+fn validate_value(type_code: JsonType, value: &[u8], depth: usize) -> Result<()> {
+    if depth > MAX_DEPTH {
+        return Err(box_err!(
+            "corrupted json: nesting depth exceeds max depth {}",
+            MAX_DEPTH
+        ));
+    }
+    match type_code {
+        JsonType::Literal => check_len(value, LITERAL_LEN),
+        JsonType::I64 | JsonType::U64 | JsonType::Double => check_len(value, NUMBER_LEN),
+        JsonType::Date | JsonType::Datetime | JsonType::Timestamp => check_len(value, TIME_LEN),
+        JsonType::Time => check_len(value, DURATION_LEN),
+        JsonType::String => {
+            let (str_len, len_len) = NumberCodec::try_decode_var_u64(value)?;
+            let want = len_len
+                .checked_add(str_len as usize)
+                .ok_or_else(|| box_err!("corrupted json: string length overflows"))?;
+            check_len(value, want)
+        }
+        JsonType::Opaque => {
+            check_len(value, 1)?;
+            let (opaque_len, len_len) = NumberCodec::try_decode_var_u64(&value[1..])?;
+            let want = len_len
+                .checked_add(opaque_len as usize)
+                .ok_or_else(|| box_err!("corrupted json: opaque length overflows"))?;
+            check_len(&value[1..], want)
+        }
+        JsonType::Object | JsonType::Array => validate_container(type_code, value, depth),
+    }
+}
+
+fn validate_container(type_code: JsonType, value: &[u8], depth: usize) -> Result<()> {
+    check_len(value, HEADER_LEN)?;
+    let elem_count = NumberCodec::decode_u32_le(value) as usize;
+    let size = NumberCodec::decode_u32_le(&value[ELEMENT_COUNT_LEN..]) as usize;
+    check_len(value, size)?;
+    // Everything below is relative to the container's own declared size, not the
+    // (possibly larger, if there's trailing garbage or a sibling value) rest of `value`.
+    let value = &value[..size];
+
+    let key_entries_len = if type_code == JsonType::Object {
+        elem_count
+            .checked_mul(KEY_ENTRY_LEN)
+            .ok_or_else(|| box_err!("corrupted json: element count {} overflows", elem_count))?
+    } else {
+        0
+    };
+    let value_entries_len = elem_count
+        .checked_mul(VALUE_ENTRY_LEN)
+        .ok_or_else(|| box_err!("corrupted json: element count {} overflows", elem_count))?;
+    let header_len = HEADER_LEN
+        .checked_add(key_entries_len)
+        .and_then(|n| n.checked_add(value_entries_len))
+        .ok_or_else(|| box_err!("corrupted json: header length overflows"))?;
+    check_len(value, header_len)?;
+
+    if type_code == JsonType::Object {
+        for i in 0..elem_count {
+            let key_entry_off = HEADER_LEN + i * KEY_ENTRY_LEN;
+            let key_off = NumberCodec::decode_u32_le(&value[key_entry_off..]) as usize;
+            let key_len =
+                NumberCodec::decode_u16_le(&value[key_entry_off + KEY_OFFSET_LEN..]) as usize;
+            check_range(value.len(), key_off, key_len)?;
+        }
+    }
+
+    let value_entries_off = HEADER_LEN + key_entries_len;
+    for i in 0..elem_count {
+        let entry_off = value_entries_off + i * VALUE_ENTRY_LEN;
+        let val_type = JsonType::try_from(value[entry_off])?;
+        if val_type == JsonType::Literal {
+            // The literal byte is inlined in the entry itself; there's no offset to check.
+            continue;
+        }
+        let val_offset = NumberCodec::decode_u32_le(&value[entry_off + TYPE_LEN..]) as usize;
+        if val_offset > value.len() {
+            return Err(box_err!(
+                "corrupted json: value offset {} exceeds container size {}",
+                val_offset,
+                value.len()
+            ));
+        }
+        validate_value(val_type, &value[val_offset..], depth + 1)?;
+    }
+    Ok(())
+}
+
+fn check_len(value: &[u8], want: usize) -> Result<()> {
+    if value.len() < want {
+        return Err(box_err!(
+            "corrupted json: expected at least {} bytes, got {}",
+            want,
+            value.len()
+        ));
+    }
+    Ok(())
+}
+
+fn check_range(len: usize, offset: usize, size: usize) -> Result<()> {
+    if offset > len {
+        return Err(box_err!(
+            "corrupted json: offset {} exceeds length {}",
+            offset,
+            len
+        ));
+    }
+    if size > len - offset {
+        return Err(box_err!(
+            "corrupted json: range of size {} at offset {} exceeds length {}",
+            size,
+            offset,
+            len
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::mysql::Json;
+
+    fn encoded(json: &str) -> (u8, Vec<u8>) {
+        let j: Json = json.parse().unwrap();
+        (j.get_type() as u8, j.value)
+    }
+
+    #[test]
+    fn test_validate_well_formed() {
+        let cases = vec![
+            r#"null"#,
+            r#"true"#,
+            r#"3"#,
+            r#"3.5"#,
+            r#""hello""#,
+            r#"[1,"2",{"aa":"bb"},4.0]"#,
+            r#"{"a":[1,2,3],"b":{"c":"d"}}"#,
+        ];
+        for case in cases {
+            let (tp, value) = encoded(case);
+            validate_binary_json(tp, &value).unwrap_or_else(|e| {
+                panic!("expected {:?} to be valid, got {:?}", case, e);
+            });
+        }
+    }
+
+    #[test]
+    fn test_validate_truncated() {
+        let (tp, value) = encoded(r#"{"a":[1,2,3],"b":{"c":"d"}}"#);
+        for len in 0..value.len() {
+            // Every strict prefix of a well-formed value is either itself malformed, or
+            // (very rarely, e.g. a literal's inlined byte) happens to still parse; either
+            // way it must never panic.
+            let _ = validate_binary_json(tp, &value[..len]);
+        }
+    }
+
+    #[test]
+    fn test_validate_corrupted_container_header() {
+        // Claims to be an object with u32::MAX elements.
+        let mut value = vec![0u8; HEADER_LEN];
+        value[0..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        value[4..8].copy_from_slice(&(HEADER_LEN as u32).to_le_bytes());
+        assert!(validate_binary_json(JsonType::Object as u8, &value).is_err());
+    }
+
+    #[test]
+    fn test_validate_corrupted_value_entry_offset() {
+        // A 1-element array whose single value-entry points past the end of the
+        // container.
+        let elem_count = 1u32;
+        let value_entries_off = HEADER_LEN;
+        let size = value_entries_off + VALUE_ENTRY_LEN;
+        let mut value = vec![0u8; size];
+        value[0..4].copy_from_slice(&elem_count.to_le_bytes());
+        value[4..8].copy_from_slice(&(size as u32).to_le_bytes());
+        value[value_entries_off] = JsonType::I64 as u8;
+        value[value_entries_off + TYPE_LEN..value_entries_off + VALUE_ENTRY_LEN]
+            .copy_from_slice(&(size as u32 + 100).to_le_bytes());
+        assert!(validate_binary_json(JsonType::Array as u8, &value).is_err());
+    }
+
+    /// Builds a binary-encoded single-element array nested `depth` levels
+    /// deep, bottom-up (so the test itself never recurses), with an `I64` at
+    /// the bottom. Returns `(root_type, root_value)`.
+    fn nested_array(depth: usize) -> (JsonType, Vec<u8>) {
+        let mut value = vec![0u8; NUMBER_LEN];
+        let mut inner_type = JsonType::I64;
+        for _ in 0..depth {
+            let header_len = HEADER_LEN + VALUE_ENTRY_LEN;
+            let size = header_len + value.len();
+            let mut wrapped = vec![0u8; size];
+            wrapped[0..4].copy_from_slice(&1u32.to_le_bytes());
+            wrapped[4..8].copy_from_slice(&(size as u32).to_le_bytes());
+            wrapped[HEADER_LEN] = inner_type as u8;
+            wrapped[HEADER_LEN + TYPE_LEN..header_len]
+                .copy_from_slice(&(header_len as u32).to_le_bytes());
+            wrapped[header_len..].copy_from_slice(&value);
+            value = wrapped;
+            inner_type = JsonType::Array;
+        }
+        (inner_type, value)
+    }
+
+    #[test]
+    fn test_validate_rejects_excessive_depth() {
+        let (tp, value) = nested_array(MAX_DEPTH);
+        validate_binary_json(tp as u8, &value).unwrap();
+
+        let (tp, value) = nested_array(MAX_DEPTH + 1);
+        assert!(validate_binary_json(tp as u8, &value).is_err());
+    }
+
+    #[test]
+    fn test_validate_bad_type_code() {
+        assert!(validate_binary_json(0xff, &[]).is_err());
+    }
+
+    #[test]
+    fn test_validate_empty_value() {
+        assert!(validate_binary_json(JsonType::Literal as u8, &[]).is_err());
+        assert!(validate_binary_json(JsonType::I64 as u8, &[]).is_err());
+    }
+}
@@ -39,8 +39,8 @@ pub use self::{
     duration::{Duration, DurationDecoder, DurationEncoder},
     enums::{Enum, EnumDecoder, EnumEncoder, EnumRef},
     json::{
-        parse_json_path_expr, Json, JsonDatumPayloadChunkEncoder, JsonDecoder, JsonEncoder,
-        JsonType, ModifyType, PathExpression,
+        parse_json_path_expr, validate_binary_json, Json, JsonDatumPayloadChunkEncoder,
+        JsonDecoder, JsonEncoder, JsonType, ModifyType, PathExpression,
     },
     set::{Set, SetRef},
     time::{Time, TimeDecoder, TimeEncoder, TimeType, Tz},
@@ -1363,6 +1363,17 @@ impl Time {
         Time::try_from_chrono_datetime(ctx, timestamp.naive_local(), time_type, fsp as i8)
     }
 
+    /// Builds a `DateTime` from a Unix timestamp (seconds and sub-second
+    /// microseconds since the epoch, UTC), interpreted in the session's
+    /// `time_zone` (`ctx.cfg.tz`). This is the shared construction path for
+    /// both `FROM_UNIXTIME` signatures.
+    pub fn from_unixtime(ctx: &mut EvalContext, secs: i64, micros: u32, fsp: i8) -> Result<Time> {
+        let fsp = check_fsp(fsp)?;
+        let utc = Utc.timestamp(secs, micros * 1000);
+        let timestamp = ctx.cfg.tz.from_utc_datetime(&utc.naive_utc());
+        Time::try_from_chrono_datetime(ctx, timestamp.naive_local(), TimeType::DateTime, fsp as i8)
+    }
+
     pub fn from_year(
         ctx: &mut EvalContext,
         year: u32,
@@ -103,8 +103,15 @@ impl RowSlice<'_> {
             }
         };
         if with_checksum {
-            let mut checksum_bytes = row.cut_checksum_bytes(non_null_cnt);
-            assert!(checksum_bytes.len() == 5 || checksum_bytes.len() == 9);
+            let mut checksum_bytes = row.cut_checksum_bytes(non_null_cnt)?;
+            // `write_row_with_checksum` only ever produces 5 (no extra
+            // checksum) or 9 (with extra checksum) trailing bytes.
+            debug_assert!(checksum_bytes.len() == 5 || checksum_bytes.len() == 9);
+            if checksum_bytes.len() != 5 && checksum_bytes.len() != 9 {
+                return Err(Error::CorruptedData(
+                    log_wrappers::Value(origin).to_string(),
+                ));
+            }
             let header = checksum_bytes.read_u8()?;
             let val = checksum_bytes.read_u32_le()?;
             let mut checksum = Checksum::new(header, val);
@@ -167,6 +174,92 @@ impl RowSlice<'_> {
         Ok(None)
     }
 
+    /// Looks up a batch of column ids in the non-null id array in a single
+    /// linear pass, instead of running one binary search per id.
+    ///
+    /// `ids` must be sorted ascending with no duplicates (the natural shape
+    /// of a projected column list once it's been prepared for a scan). For
+    /// every id that's present, `on_found(id, start, offset)` is called with
+    /// the same `values` byte range that `search_in_non_null_ids` would
+    /// return for that id. Ids that aren't present, or that are out of
+    /// range for this row's id width, are skipped silently, matching
+    /// `search_in_non_null_ids`'s `None` case.
+    ///
+    /// This only pays off once more than a few columns are projected out of
+    /// a wide row: `k` binary searches cost `O(k log n)` and each one walks
+    /// the id array from scratch, while this walks it once for `O(n + k)`.
+    /// Wiring it into callers like `TableScanExecutor`, whose projected
+    /// columns are currently tracked in an unordered `HashMap`, would need
+    /// the column id list sorted once per scan rather than per row; that's
+    /// left as a follow-up since it touches the executor's hot loop.
+    ///
+    /// # Errors
+    ///
+    /// Same as `search_in_non_null_ids`: returns `Error::ColumnOffset` if an
+    /// id is found with no corresponding offset, which only happens when
+    /// the row data is broken.
+    pub fn search_ids_in_non_null_ids(
+        &self,
+        ids: &[i64],
+        mut on_found: impl FnMut(i64, usize, usize),
+    ) -> Result<()> {
+        match self {
+            RowSlice::Big {
+                non_null_ids,
+                offsets,
+                ..
+            } => {
+                let len = non_null_ids.slice.len() / std::mem::size_of::<u32>();
+                let mut cursor = 0usize;
+                for &id in ids {
+                    if id <= 0 || id > i64::from(u32::max_value()) {
+                        continue;
+                    }
+                    let target = id as u32;
+                    while cursor < len && unsafe { non_null_ids.get_unchecked(cursor) } < target {
+                        cursor += 1;
+                    }
+                    if cursor < len && unsafe { non_null_ids.get_unchecked(cursor) } == target {
+                        let offset = offsets.get(cursor).ok_or(Error::ColumnOffset(cursor))?;
+                        let start = if cursor > 0 {
+                            unsafe { offsets.get_unchecked(cursor - 1) as usize }
+                        } else {
+                            0usize
+                        };
+                        on_found(id, start, offset as usize);
+                    }
+                }
+            }
+            RowSlice::Small {
+                non_null_ids,
+                offsets,
+                ..
+            } => {
+                let len = non_null_ids.slice.len() / std::mem::size_of::<u8>();
+                let mut cursor = 0usize;
+                for &id in ids {
+                    if id <= 0 || id > i64::from(u8::max_value()) {
+                        continue;
+                    }
+                    let target = id as u8;
+                    while cursor < len && unsafe { non_null_ids.get_unchecked(cursor) } < target {
+                        cursor += 1;
+                    }
+                    if cursor < len && unsafe { non_null_ids.get_unchecked(cursor) } == target {
+                        let offset = offsets.get(cursor).ok_or(Error::ColumnOffset(cursor))?;
+                        let start = if cursor > 0 {
+                            unsafe { offsets.get_unchecked(cursor - 1) as usize }
+                        } else {
+                            0usize
+                        };
+                        on_found(id, start, offset as usize);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Search `id` in null ids
     ///
     /// Returns true if found
@@ -198,6 +291,19 @@ impl RowSlice<'_> {
         }
     }
 
+    /// Returns the number of non-null columns stored in this row.
+    #[inline]
+    pub fn non_null_ids_len(&self) -> usize {
+        match self {
+            RowSlice::Big { non_null_ids, .. } => {
+                non_null_ids.slice.len() / std::mem::size_of::<u32>()
+            }
+            RowSlice::Small { non_null_ids, .. } => {
+                non_null_ids.slice.len() / std::mem::size_of::<u8>()
+            }
+        }
+    }
+
     #[inline]
     pub fn values(&self) -> &[u8] {
         match self {
@@ -228,7 +334,12 @@ impl RowSlice<'_> {
     #[inline]
     // Return the checksum byte slice, remove it from the `values` field of
     // `RowSlice`.
-    pub fn cut_checksum_bytes(&mut self, non_null_col_num: usize) -> &[u8] {
+    //
+    // The last offset is read straight out of the row's bytes, so a
+    // corrupted row can claim an offset past the end of `values`. Bail out
+    // with `Error::CorruptedData` instead of panicking on the out-of-bounds
+    // slice.
+    pub fn cut_checksum_bytes(&mut self, non_null_col_num: usize) -> Result<&[u8]> {
         match self {
             RowSlice::Big {
                 offsets, values, ..
@@ -239,8 +350,9 @@ impl RowSlice<'_> {
                     offsets.get(non_null_col_num - 1).unwrap() as usize
                 };
                 let slice = values.slice;
-                *values = LeBytes::new(&slice[..last_slice_idx]);
-                &slice[last_slice_idx..]
+                let (values_slice, checksum_slice) = split_at_checked(slice, last_slice_idx)?;
+                *values = LeBytes::new(values_slice);
+                Ok(checksum_slice)
             }
             RowSlice::Small {
                 offsets, values, ..
@@ -251,8 +363,9 @@ impl RowSlice<'_> {
                     offsets.get(non_null_col_num - 1).unwrap() as usize
                 };
                 let slice = values.slice;
-                *values = LeBytes::new(&slice[..last_slice_idx]);
-                &slice[last_slice_idx..]
+                let (values_slice, checksum_slice) = split_at_checked(slice, last_slice_idx)?;
+                *values = LeBytes::new(values_slice);
+                Ok(checksum_slice)
             }
         }
     }
@@ -274,6 +387,20 @@ impl RowSlice<'_> {
     }
 }
 
+// Like `<[u8]>::split_at`, but returns `Error::CorruptedData` instead of
+// panicking when `mid` is out of range.
+#[inline]
+fn split_at_checked(slice: &[u8], mid: usize) -> Result<(&[u8], &[u8])> {
+    if mid > slice.len() {
+        return Err(Error::CorruptedData(format!(
+            "row value offset {} is out of bounds for {} bytes of values",
+            mid,
+            slice.len()
+        )));
+    }
+    Ok(slice.split_at(mid))
+}
+
 /// Decodes `len` number of ints from `buf` in little endian
 ///
 /// Note:
@@ -490,6 +617,41 @@ mod tests {
         buf
     }
 
+    #[test]
+    fn test_search_ids_in_non_null_ids() {
+        let data = encoded_data_big();
+        let row = RowSlice::from_bytes(&data).unwrap();
+        let ids = [1, 33, 64123, 64124];
+        let mut found = vec![];
+        row.search_ids_in_non_null_ids(&ids, |id, start, end| found.push((id, start, end)))
+            .unwrap();
+        let expected: Vec<_> = ids
+            .iter()
+            .filter_map(|&id| {
+                row.search_in_non_null_ids(id)
+                    .unwrap()
+                    .map(|(start, end)| (id, start, end))
+            })
+            .collect();
+        assert_eq!(found, expected);
+
+        let data = encoded_data();
+        let row = RowSlice::from_bytes(&data).unwrap();
+        let ids = [1, 2, 3, 35];
+        let mut found = vec![];
+        row.search_ids_in_non_null_ids(&ids, |id, start, end| found.push((id, start, end)))
+            .unwrap();
+        let expected: Vec<_> = ids
+            .iter()
+            .filter_map(|&id| {
+                row.search_in_non_null_ids(id)
+                    .unwrap()
+                    .map(|(start, end)| (id, start, end))
+            })
+            .collect();
+        assert_eq!(found, expected);
+    }
+
     #[test]
     fn test_decode_with_checksum() {
         for null_row_id in [235, 355] {
@@ -518,6 +680,115 @@ mod tests {
     }
 }
 
+#[cfg(test)]
+mod proptests {
+    use proptest::prelude::*;
+
+    use super::{
+        super::encoder_for_test::{Column, RowEncoder},
+        RowSlice,
+    };
+    use crate::{codec::data_type::ScalarValue, expr::EvalContext};
+
+    // Ids above 255 force the "big" row layout; mixing both ranges in the same
+    // row exercises both `RowSlice::Small` and `RowSlice::Big`.
+    fn arb_columns() -> impl Strategy<Value = Vec<(i64, Option<i64>)>> {
+        prop::collection::vec(
+            (1i64..2000, prop::option::of(-1_000_000i64..1_000_000i64)),
+            0..30,
+        )
+        .prop_map(|mut raw| {
+            // Column ids must be unique, `write_row_impl` doesn't dedup them.
+            raw.sort_by_key(|(id, _)| *id);
+            raw.dedup_by_key(|(id, _)| *id);
+            raw
+        })
+    }
+
+    fn to_columns(raw: &[(i64, Option<i64>)]) -> Vec<Column> {
+        raw.iter()
+            .map(|&(id, value)| match value {
+                Some(v) => Column::new(id, v),
+                None => Column::new(id, ScalarValue::Int(None)),
+            })
+            .collect()
+    }
+
+    fn encode(columns: Vec<Column>, with_checksum: bool) -> Vec<u8> {
+        let mut buf = vec![];
+        if with_checksum {
+            buf.write_row_with_checksum(&mut EvalContext::default(), columns, None)
+                .unwrap();
+        } else {
+            buf.write_row(&mut EvalContext::default(), columns).unwrap();
+        }
+        buf
+    }
+
+    proptest! {
+        // A well-formed row, big or small, checksum or not, must always
+        // decode successfully and every encoded column must be found again.
+        #[test]
+        fn decodes_valid_rows(raw in arb_columns(), with_checksum in any::<bool>()) {
+            let ids: Vec<i64> = raw.iter().map(|&(id, _)| id).collect();
+            let buf = encode(to_columns(&raw), with_checksum);
+
+            let row = RowSlice::from_bytes(&buf).unwrap();
+            for id in ids {
+                row.search_in_non_null_ids(id).unwrap();
+                row.search_in_null_ids(id);
+            }
+        }
+
+        // Truncating a valid row anywhere after the version/flag header must
+        // be rejected with an error, never a panic or an out-of-bounds read.
+        #[test]
+        fn truncated_rows_never_panic(
+            raw in arb_columns(),
+            with_checksum in any::<bool>(),
+            cut_from_end in 0usize..64,
+        ) {
+            let buf = encode(to_columns(&raw), with_checksum);
+            if buf.len() <= 2 {
+                return Ok(());
+            }
+            let new_len = buf.len().saturating_sub(cut_from_end).max(2);
+            let truncated = &buf[..new_len];
+
+            if let Ok(row) = RowSlice::from_bytes(truncated) {
+                for id in 0..300i64 {
+                    let _ = row.search_in_non_null_ids(id);
+                    let _ = row.search_in_null_ids(id);
+                }
+            }
+        }
+
+        // Flipping a byte anywhere past the version marker must never panic,
+        // regardless of whether the resulting row still parses as valid.
+        #[test]
+        fn corrupted_rows_never_panic(
+            raw in arb_columns(),
+            with_checksum in any::<bool>(),
+            flip_at in 1usize..4096,
+            flip_mask in 1u8..=255,
+        ) {
+            let mut buf = encode(to_columns(&raw), with_checksum);
+            if buf.len() <= 1 {
+                return Ok(());
+            }
+            let idx = 1 + flip_at % (buf.len() - 1);
+            buf[idx] ^= flip_mask;
+
+            if let Ok(row) = RowSlice::from_bytes(&buf) {
+                for id in 0..300i64 {
+                    let _ = row.search_in_non_null_ids(id);
+                    let _ = row.search_in_null_ids(id);
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod benches {
     use test::black_box;
@@ -591,4 +862,38 @@ mod benches {
             black_box(&row);
         });
     }
+
+    // A projection of every 10th column out of a 350-column row, which is
+    // the shape `search_ids_in_non_null_ids` is meant for: a handful of
+    // columns scattered across a wide row.
+    fn projected_ids(row_len: usize) -> Vec<i64> {
+        (0..row_len as i64).step_by(10).collect()
+    }
+
+    #[bench]
+    fn bench_search_in_non_null_ids_projection_binary_search(b: &mut test::Bencher) {
+        let data = encoded_data(350);
+        let row = RowSlice::from_bytes(&data).unwrap();
+        let ids = projected_ids(350);
+
+        b.iter(|| {
+            for &id in &ids {
+                black_box(row.search_in_non_null_ids(black_box(id)).unwrap());
+            }
+        });
+    }
+
+    #[bench]
+    fn bench_search_ids_in_non_null_ids_projection_merge(b: &mut test::Bencher) {
+        let data = encoded_data(350);
+        let row = RowSlice::from_bytes(&data).unwrap();
+        let ids = projected_ids(350);
+
+        b.iter(|| {
+            row.search_ids_in_non_null_ids(black_box(&ids), |_, start, end| {
+                black_box((start, end));
+            })
+            .unwrap();
+        });
+    }
 }
@@ -3,6 +3,7 @@
 use std::{i64, mem, sync::Arc, u64};
 
 use bitflags::bitflags;
+use tikv_util::memory::MemoryQuota;
 use tipb::DagRequest;
 
 use super::{Error, Result};
@@ -49,6 +50,14 @@ bitflags! {
         const DIVIDED_BY_ZERO_AS_WARNING = 1 << 8;
         /// `IN_LOAD_DATA_STMT` indicates if this is a LOAD DATA statement.
         const IN_LOAD_DATA_STMT = 1 << 10;
+        /// `IGNORE_JSON_KEY_CASE` indicates whether `json_extract` and
+        /// `json_keys` should look up object keys case-insensitively
+        /// instead of using MySQL's default binary comparison.
+        const IGNORE_JSON_KEY_CASE = 1 << 11;
+        /// `VERIFY_ROW_CHECKSUM` indicates whether the table scan executor
+        /// should recompute and verify the row checksum embedded by TiDB in
+        /// row format v2, reporting corrupted rows instead of returning them.
+        const VERIFY_ROW_CHECKSUM = 1 << 12;
     }
 }
 
@@ -61,6 +70,21 @@ impl SqlMode {
 
 const DEFAULT_MAX_WARNING_CNT: usize = 64;
 
+/// The default cap on memory that a single request's JSON functions (e.g.
+/// `json_merge`) may hold onto at once. `DagRequest` has no field to override
+/// this per-request, so it is a fixed constant rather than something
+/// `EvalConfig::from_request` can pick up; see [`EvalConfig::json_memory_quota`].
+const DEFAULT_JSON_MEMORY_QUOTA_BYTES: usize = 128 * 1024 * 1024;
+
+/// MySQL's own default for the `group_concat_max_len` session variable, in
+/// bytes. Used unless overridden by [`EvalConfig::set_group_concat_max_len`].
+const DEFAULT_GROUP_CONCAT_MAX_LEN: u64 = 1024;
+
+/// Same rationale as [`DEFAULT_JSON_MEMORY_QUOTA_BYTES`]: `DagRequest` has no
+/// field carrying a per-request cap on how much memory all of a request's
+/// `GROUP_CONCAT()` groups may hold at once, so this is a fixed constant.
+const DEFAULT_GROUP_CONCAT_MEMORY_QUOTA_BYTES: usize = 128 * 1024 * 1024;
+
 #[derive(Clone, Debug)]
 pub struct EvalConfig {
     /// timezone to use when parse/calculate time.
@@ -73,6 +97,23 @@ pub struct EvalConfig {
 
     pub paging_size: Option<u64>,
     pub div_precision_increment: u8,
+
+    /// Bounds how much memory the JSON functions evaluated through this
+    /// config may hold onto at once (e.g. the result of `json_merge` over
+    /// huge documents), so that one request cannot balloon memory usage
+    /// unnoticed. Shared by every [`EvalContext`] created from this config,
+    /// so the bound applies across the whole request, not per-context.
+    pub json_memory_quota: Arc<MemoryQuota>,
+
+    /// The maximum length, in bytes, of a single `GROUP_CONCAT()` result;
+    /// longer results are truncated and a warning is raised, mirroring
+    /// MySQL's `group_concat_max_len` session variable.
+    pub group_concat_max_len: u64,
+
+    /// Bounds how much memory all of a request's `GROUP_CONCAT()` groups may
+    /// hold onto at once. Shared by every [`EvalContext`] created from this
+    /// config, so the bound applies across the whole request.
+    pub group_concat_memory_quota: Arc<MemoryQuota>,
 }
 
 impl Default for EvalConfig {
@@ -102,6 +143,9 @@ impl EvalConfig {
         if req.has_div_precision_increment() {
             eval_cfg.set_div_precision_incr(req.get_div_precision_increment() as u8);
         }
+        if req.has_group_concat_max_len() {
+            eval_cfg.set_group_concat_max_len(req.get_group_concat_max_len());
+        }
         Ok(eval_cfg)
     }
 
@@ -113,6 +157,11 @@ impl EvalConfig {
             sql_mode: SqlMode::empty(),
             paging_size: None,
             div_precision_increment: DEFAULT_DIV_FRAC_INCR,
+            json_memory_quota: Arc::new(MemoryQuota::new(DEFAULT_JSON_MEMORY_QUOTA_BYTES)),
+            group_concat_max_len: DEFAULT_GROUP_CONCAT_MAX_LEN,
+            group_concat_memory_quota: Arc::new(MemoryQuota::new(
+                DEFAULT_GROUP_CONCAT_MEMORY_QUOTA_BYTES,
+            )),
         }
     }
 
@@ -162,6 +211,21 @@ impl EvalConfig {
         self
     }
 
+    pub fn set_json_memory_quota_capacity(&mut self, bytes: usize) -> &mut Self {
+        self.json_memory_quota.set_capacity(bytes);
+        self
+    }
+
+    pub fn set_group_concat_max_len(&mut self, new_value: u64) -> &mut Self {
+        self.group_concat_max_len = new_value;
+        self
+    }
+
+    pub fn set_group_concat_memory_quota_capacity(&mut self, bytes: usize) -> &mut Self {
+        self.group_concat_memory_quota.set_capacity(bytes);
+        self
+    }
+
     pub fn new_eval_warnings(&self) -> EvalWarnings {
         EvalWarnings::new(self.max_warning_cnt)
     }
@@ -325,6 +389,33 @@ impl EvalContext {
         )
     }
 
+    /// Accounts `bytes` against this request's JSON memory quota
+    /// (`cfg.json_memory_quota`), returning [`Error::MemoryQuotaExceeded`] if
+    /// doing so would exceed it. Intended for JSON functions (e.g.
+    /// `json_merge`) to charge the size of a newly built intermediate value
+    /// before returning it.
+    ///
+    /// The charge is never released: the quota is scoped to the request (it
+    /// lives as long as the `Arc<EvalConfig>` shared by all contexts of that
+    /// request), so letting it accumulate for the request's lifetime is
+    /// exactly the "don't let one request balloon memory unnoticed" goal.
+    pub fn charge_json_memory(&self, bytes: usize) -> Result<()> {
+        self.cfg.json_memory_quota.alloc(bytes)?;
+        Ok(())
+    }
+
+    /// Accounts `bytes` against this request's `GROUP_CONCAT()` memory quota
+    /// (`cfg.group_concat_memory_quota`), returning
+    /// [`Error::MemoryQuotaExceeded`] if doing so would exceed it. Intended
+    /// to charge the size of a newly appended separator/value before it is
+    /// appended to a group's accumulated string.
+    ///
+    /// Like [`Self::charge_json_memory`], the charge is never released.
+    pub fn charge_group_concat_memory(&self, bytes: usize) -> Result<()> {
+        self.cfg.group_concat_memory_quota.alloc(bytes)?;
+        Ok(())
+    }
+
     /// Indicates whether values less than 0 should be clipped to 0 for unsigned
     /// integer types. This is the case for `insert`, `update`, `alter table`
     /// and `load data infile` statements, when not in strict SQL mode.
@@ -421,6 +512,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_charge_json_memory() {
+        let mut cfg = EvalConfig::new();
+        cfg.set_json_memory_quota_capacity(100);
+        let ctx = EvalContext::new(Arc::new(cfg));
+
+        ctx.charge_json_memory(60).unwrap();
+        ctx.charge_json_memory(40).unwrap();
+        // The quota is shared across the whole request and never released, so a
+        // third charge that would exceed it is rejected even though none of the
+        // individual charges above did.
+        ctx.charge_json_memory(1).unwrap_err();
+    }
+
     #[test]
     fn test_handle_invalid_time_error() {
         let cases = vec![
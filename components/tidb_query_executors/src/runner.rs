@@ -155,7 +155,9 @@ impl BatchExecutorsRunner<()> {
                     return Err(other_err!("Sort executor not implemented"));
                 }
                 ExecType::TypeWindow => {
-                    return Err(other_err!("Window executor not implemented"));
+                    let descriptor = ed.get_window();
+                    BatchWindowExecutor::check_supported(descriptor)
+                        .map_err(|e| other_err!("BatchWindowExecutor: {}", e))?;
                 }
                 ExecType::TypeExpand => {
                     return Err(other_err!("Expand executor not implemented"));
@@ -406,6 +408,32 @@ pub fn build_executors<S: Storage + 'static, F: KvFormat>(
                     )
                 }
             }
+            ExecType::TypeWindow => {
+                EXECUTOR_COUNT_METRICS.batch_window.inc();
+
+                let mut d = ed.take_window();
+                let partition_by = d
+                    .take_partition_by()
+                    .into_iter()
+                    .map(|mut item| item.take_expr())
+                    .collect_vec();
+                let order_bys = d.get_order_by().len();
+                let mut order_exprs_def = Vec::with_capacity(order_bys);
+                for mut item in d.take_order_by().into_iter() {
+                    order_exprs_def.push(item.take_expr());
+                }
+
+                Box::new(
+                    BatchWindowExecutor::new(
+                        config.clone(),
+                        executor,
+                        d.take_func_desc().into(),
+                        partition_by,
+                        order_exprs_def,
+                    )?
+                    .collect_summary(summary_slot_index),
+                )
+            }
             _ => {
                 return Err(other_err!(
                     "Unexpected non-first executor {:?}",
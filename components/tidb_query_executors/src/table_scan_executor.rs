@@ -14,10 +14,13 @@ use tidb_query_common::{
 use tidb_query_datatype::{
     codec::{
         batch::{LazyBatchColumn, LazyBatchColumnVec},
-        row, table,
+        data_type::ScalarValue,
+        row,
+        row::v2::RowSlice,
+        table,
     },
-    expr::{EvalConfig, EvalContext},
-    EvalType, FieldTypeAccessor,
+    expr::{EvalConfig, EvalContext, Flag},
+    match_template_evaltype, EvalType, FieldTypeAccessor, FieldTypeTp,
 };
 use tipb::{ColumnInfo, FieldType, TableScan};
 
@@ -229,14 +232,12 @@ impl TableScanExecutorImpl {
 
     fn process_v2(
         &mut self,
+        key: &[u8],
         value: &[u8],
         columns: &mut LazyBatchColumnVec,
         decoded_columns: &mut usize,
     ) -> Result<()> {
-        use tidb_query_datatype::codec::{
-            datum,
-            row::v2::{RowSlice, V1CompatibleEncoder},
-        };
+        use tidb_query_datatype::codec::{datum, row::v2::V1CompatibleEncoder};
 
         let row = RowSlice::from_bytes(value)?;
         for (col_id, idx) in &self.column_id_index {
@@ -258,6 +259,83 @@ impl TableScanExecutorImpl {
                 // later.
             }
         }
+
+        if self.context.cfg.flag.contains(Flag::VERIFY_ROW_CHECKSUM) {
+            self.verify_row_checksum(key, &row)?;
+        }
+        Ok(())
+    }
+
+    /// Recomputes the row checksum TiDB embeds in row format v2 (see
+    /// `docs/design/2018-07-19-row-format.md`) from the decoded columns and
+    /// compares it against the value stored alongside the row, returning a
+    /// "corrupted data" error that names the offending key on mismatch.
+    ///
+    /// Verification is skipped, rather than risking a false corruption
+    /// report, whenever it cannot be done with full confidence:
+    /// - the row carries no checksum at all;
+    /// - this scan's schema does not cover every non-null column of the row
+    ///   (TiDB computes the checksum over the whole row, so a pruned
+    ///   projection can't reproduce it);
+    /// - the row has a `BIT` column, whose checksum encoding
+    ///   `Column::encode_for_checksum` does not implement correctly yet.
+    fn verify_row_checksum(&mut self, key: &[u8], row: &RowSlice<'_>) -> Result<()> {
+        use tidb_query_datatype::codec::{
+            datum_codec::RawDatumDecoder,
+            row::v2::{
+                encoder_for_test::{
+                    ChecksumHandler, Column as ChecksumColumn, Crc32RowChecksumHandler,
+                },
+                V1CompatibleEncoder,
+            },
+        };
+
+        let checksum = match row.get_checksum() {
+            Some(checksum) => checksum,
+            None => return Ok(()),
+        };
+        if row.non_null_ids_len() != self.column_id_index.len() {
+            return Ok(());
+        }
+
+        let mut cols = Vec::with_capacity(self.column_id_index.len());
+        for (col_id, idx) in &self.column_id_index {
+            let ft = &self.schema[*idx];
+            if ft.as_accessor().tp() == FieldTypeTp::Bit {
+                return Ok(());
+            }
+            let (start, offset) = match row.search_in_non_null_ids(*col_id)? {
+                Some(range) => range,
+                // Not every id in our schema is a non-null id of this row after
+                // all, so the counts above matched by coincidence.
+                None => return Ok(()),
+            };
+            let mut v1_buf = vec![];
+            v1_buf.write_v2_as_datum(&row.values()[start..offset], ft)?;
+            let eval_type = box_try!(EvalType::try_from(ft.as_accessor().tp()));
+            let value = match_template_evaltype! {
+                TT, match eval_type {
+                    EvalType::TT => {
+                        let decoded: Option<TT> = v1_buf.decode(ft, &mut self.context)?;
+                        ScalarValue::TT(decoded)
+                    }
+                }
+            };
+            cols.push(ChecksumColumn::new_with_ft(*col_id, ft.clone(), value));
+        }
+
+        let mut handler = Crc32RowChecksumHandler::default();
+        if handler.checksum(&cols).is_err() {
+            // A column type `encode_for_checksum` does not (yet) support;
+            // treat it the same as the known `BIT` gap above.
+            return Ok(());
+        }
+        if handler.value() != checksum.get_checksum_val() {
+            return Err(other_err!(
+                "Data is corrupted, row checksum mismatch (key = {})",
+                log_wrappers::Value::key(key),
+            ));
+        }
         Ok(())
     }
 }
@@ -353,7 +431,9 @@ impl ScanExecutorImpl for TableScanExecutorImpl {
             // Do nothing
         } else {
             match value[0] {
-                row::v2::CODEC_VERSION => self.process_v2(value, columns, &mut decoded_columns)?,
+                row::v2::CODEC_VERSION => {
+                    self.process_v2(key, value, columns, &mut decoded_columns)?
+                }
                 _ => self.process_v1(key, value, columns, &mut decoded_columns)?,
             }
         }
@@ -449,8 +529,14 @@ mod tests {
         execute_stats::*, storage::test_fixture::FixtureStorage, util::convert_to_prefix_next,
     };
     use tidb_query_datatype::{
-        codec::{batch::LazyBatchColumnVec, data_type::*, datum, table, Datum},
-        expr::EvalConfig,
+        codec::{
+            batch::LazyBatchColumnVec,
+            data_type::*,
+            datum,
+            row::v2::encoder_for_test::{self, RowEncoder},
+            table, Datum,
+        },
+        expr::{EvalConfig, Flag},
         Collation, EvalType, FieldTypeAccessor, FieldTypeTp,
     };
     use tipb::{ColumnInfo, FieldType};
@@ -972,6 +1058,144 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_checksum_verification() {
+        const TABLE_ID: i64 = 7;
+
+        let columns_info = vec![
+            {
+                let mut ci = ColumnInfo::default();
+                ci.as_mut_accessor().set_tp(FieldTypeTp::LongLong);
+                ci.set_pk_handle(true);
+                ci.set_column_id(1);
+                ci
+            },
+            {
+                let mut ci = ColumnInfo::default();
+                ci.as_mut_accessor().set_tp(FieldTypeTp::LongLong);
+                ci.set_column_id(2);
+                ci
+            },
+            {
+                let mut ci = ColumnInfo::default();
+                ci.as_mut_accessor().set_tp(FieldTypeTp::VarChar);
+                ci.set_column_id(3);
+                ci
+            },
+        ];
+
+        let row_cols = || {
+            vec![
+                encoder_for_test::Column::new_with_ft(2, FieldTypeTp::LongLong.into(), 7i64),
+                encoder_for_test::Column::new_with_ft(
+                    3,
+                    FieldTypeTp::VarChar.into(),
+                    b"hello".to_vec(),
+                ),
+            ]
+        };
+
+        let mut ctx = EvalContext::default();
+        let valid_value = {
+            let mut buf = vec![];
+            buf.write_row_with_checksum(&mut ctx, row_cols(), None)
+                .unwrap();
+            buf
+        };
+        let corrupted_value = {
+            let mut buf = valid_value.clone();
+            // Flip a byte inside the encoded row value, leaving the trailing
+            // stored checksum stale.
+            let i = buf.len() - 5 - 1;
+            buf[i] ^= 0xff;
+            buf
+        };
+
+        let kv = vec![
+            (table::encode_row_key(TABLE_ID, 0), valid_value),
+            (table::encode_row_key(TABLE_ID, 1), corrupted_value),
+        ];
+        let key_range_point: Vec<_> = kv
+            .iter()
+            .enumerate()
+            .map(|(index, _)| {
+                let mut r = KeyRange::default();
+                r.set_start(table::encode_row_key(TABLE_ID, index as i64));
+                r.set_end(r.get_start().to_vec());
+                convert_to_prefix_next(r.mut_end());
+                r
+            })
+            .collect();
+        let store = FixtureStorage::from(kv);
+
+        // With the flag off (the default), checksum mismatches are not
+        // checked at all: both rows are returned as-is.
+        let mut executor = BatchTableScanExecutor::<_, ApiV1>::new(
+            store.clone(),
+            Arc::new(EvalConfig::default()),
+            columns_info.clone(),
+            key_range_point.clone(),
+            vec![],
+            false,
+            false,
+            vec![],
+        )
+        .unwrap();
+        let result = block_on(executor.next_batch(10));
+        result.is_drained.unwrap().stop();
+        assert_eq!(result.physical_columns.rows_len(), 2);
+
+        // With the flag on, the row with a stale checksum surfaces as a
+        // corrupted-data error instead of being silently returned.
+        let mut executor = BatchTableScanExecutor::<_, ApiV1>::new(
+            store.clone(),
+            Arc::new(EvalConfig::from_flag(Flag::VERIFY_ROW_CHECKSUM)),
+            columns_info.clone(),
+            vec![key_range_point[0].clone()],
+            vec![],
+            false,
+            false,
+            vec![],
+        )
+        .unwrap();
+        let result = block_on(executor.next_batch(10));
+        result.is_drained.unwrap().stop();
+        assert_eq!(result.physical_columns.rows_len(), 1);
+
+        let mut executor = BatchTableScanExecutor::<_, ApiV1>::new(
+            store.clone(),
+            Arc::new(EvalConfig::from_flag(Flag::VERIFY_ROW_CHECKSUM)),
+            columns_info.clone(),
+            vec![key_range_point[1].clone()],
+            vec![],
+            false,
+            false,
+            vec![],
+        )
+        .unwrap();
+        let mut result = block_on(executor.next_batch(10));
+        result.is_drained.unwrap_err();
+        assert_eq!(result.physical_columns.rows_len(), 0);
+
+        // A query that prunes a column can't reproduce the whole-row
+        // checksum, so it must not report corruption even for the row with a
+        // stale checksum.
+        let mut executor = BatchTableScanExecutor::<_, ApiV1>::new(
+            store,
+            Arc::new(EvalConfig::from_flag(Flag::VERIFY_ROW_CHECKSUM)),
+            vec![columns_info[0].clone(), columns_info[1].clone()],
+            key_range_point,
+            vec![],
+            false,
+            false,
+            vec![],
+        )
+        .unwrap();
+        let result = block_on(executor.next_batch(10));
+        result.is_drained.unwrap().stop();
+        assert_eq!(result.physical_columns.rows_len(), 2);
+    }
+
     #[test]
     fn test_locked_data() {
         const TABLE_ID: i64 = 42;
@@ -0,0 +1,567 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::{cmp::Ordering, sync::Arc};
+
+use async_trait::async_trait;
+use tidb_query_common::{storage::IntervalRange, Result};
+use tidb_query_datatype::{
+    codec::{
+        batch::{LazyBatchColumn, LazyBatchColumnVec},
+        data_type::{Int, ScalarValue, VectorValue, BATCH_MAX_SIZE},
+    },
+    expr::{EvalConfig, EvalContext},
+    FieldTypeTp,
+};
+use tidb_query_expr::{RpnExpression, RpnExpressionBuilder, RpnStackNode};
+use tipb::{Expr, ExprType, FieldType, Window};
+
+use crate::{interface::*, util::ensure_columns_decoded};
+
+/// The window functions supported by `BatchWindowExecutor`.
+///
+/// Each of these is ranking-only: it doesn't look at any argument column, only
+/// at where the partition and order-by boundaries fall, so there is no `args`
+/// to evaluate once we know which variant a `func_desc` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WindowFuncType {
+    RowNumber,
+    Rank,
+    DenseRank,
+}
+
+impl WindowFuncType {
+    fn from_expr(expr: &Expr) -> Result<Self> {
+        match expr.get_tp() {
+            ExprType::RowNumber => Ok(Self::RowNumber),
+            ExprType::Rank => Ok(Self::Rank),
+            ExprType::DenseRank => Ok(Self::DenseRank),
+            tp => Err(other_err!("Unsupported window function {:?}", tp)),
+        }
+    }
+}
+
+pub struct BatchWindowExecutor<Src: BatchExecutor> {
+    /// The data must already be sorted by the partition expression and then
+    /// by the order-by expression within each partition -- typically by an
+    /// ordered index scan or a `BatchSortExecutor` upstream. This executor
+    /// does not sort; it only detects boundaries in already-sorted input.
+    partition_exprs: Box<[RpnExpression]>,
+    order_exprs: Box<[RpnExpression]>,
+    partition_field_types: Box<[FieldType]>,
+    order_field_types: Box<[FieldType]>,
+
+    window_funcs: Box<[WindowFuncType]>,
+
+    /// The partition key of the last row seen so far, across batches.
+    last_partition_key: Option<Vec<ScalarValue>>,
+    /// The order-by key of the last row seen so far, across batches. Reset
+    /// whenever the partition changes.
+    last_order_key: Option<Vec<ScalarValue>>,
+
+    /// `ROW_NUMBER()` of the last row seen so far, within its partition.
+    row_number: i64,
+    /// `RANK()` of the last row seen so far, within its partition.
+    rank: i64,
+    /// `DENSE_RANK()` of the last row seen so far, within its partition.
+    dense_rank: i64,
+
+    schema: Vec<FieldType>,
+
+    context: EvalContext,
+    src: Src,
+}
+
+// We assign a dummy type `Box<dyn BatchExecutor<StorageStats = ()>>` so that we
+// can omit the type when calling `check_supported`.
+impl BatchWindowExecutor<Box<dyn BatchExecutor<StorageStats = ()>>> {
+    /// Checks whether this executor can be used.
+    #[inline]
+    pub fn check_supported(descriptor: &Window) -> Result<()> {
+        if descriptor.get_func_desc().is_empty() {
+            return Err(other_err!("Missing window function"));
+        }
+        for func_desc in descriptor.get_func_desc() {
+            WindowFuncType::from_expr(func_desc)?;
+        }
+        for item in descriptor.get_partition_by() {
+            RpnExpressionBuilder::check_expr_tree_supported(item.get_expr())?;
+        }
+        for item in descriptor.get_order_by() {
+            RpnExpressionBuilder::check_expr_tree_supported(item.get_expr())?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `key` differs from `last`, comparing component-wise according to
+/// `field_types`. A `None` `last` (i.e. no row has been seen yet in this
+/// group) always counts as "different".
+fn key_changed(
+    last: &Option<Vec<ScalarValue>>,
+    key: &[ScalarValue],
+    field_types: &[FieldType],
+) -> Result<bool> {
+    let last = match last {
+        Some(last) => last,
+        None => return Ok(true),
+    };
+    for ((lhs, rhs), field_type) in last.iter().zip(key).zip(field_types) {
+        if lhs
+            .as_scalar_value_ref()
+            .cmp_sort_key(&rhs.as_scalar_value_ref(), field_type)?
+            != Ordering::Equal
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Advances the ranking counters for one row, given whether it starts a new
+/// partition and whether its order-by key differs from the previous row's
+/// (within the same partition).
+fn advance(
+    row_number: &mut i64,
+    rank: &mut i64,
+    dense_rank: &mut i64,
+    new_partition: bool,
+    new_order_key: bool,
+) {
+    if new_partition {
+        *row_number = 1;
+        *rank = 1;
+        *dense_rank = 1;
+    } else {
+        *row_number += 1;
+        if new_order_key {
+            *rank = *row_number;
+            *dense_rank += 1;
+        }
+    }
+}
+
+fn eval_window_func(func: WindowFuncType, row_number: i64, rank: i64, dense_rank: i64) -> i64 {
+    match func {
+        WindowFuncType::RowNumber => row_number,
+        WindowFuncType::Rank => rank,
+        WindowFuncType::DenseRank => dense_rank,
+    }
+}
+
+impl<Src: BatchExecutor> BatchWindowExecutor<Src> {
+    #[cfg(test)]
+    fn new_for_test(
+        src: Src,
+        window_funcs: Vec<WindowFuncType>,
+        partition_exprs: Vec<RpnExpression>,
+        order_exprs: Vec<RpnExpression>,
+    ) -> Self {
+        let partition_field_types: Vec<FieldType> = partition_exprs
+            .iter()
+            .map(|expr| expr.ret_field_type(src.schema()).clone())
+            .collect();
+        let order_field_types: Vec<FieldType> = order_exprs
+            .iter()
+            .map(|expr| expr.ret_field_type(src.schema()).clone())
+            .collect();
+
+        let mut schema = src.schema().to_vec();
+        for _ in &window_funcs {
+            schema.push(FieldTypeTp::LongLong.into());
+        }
+
+        Self {
+            partition_exprs: partition_exprs.into_boxed_slice(),
+            order_exprs: order_exprs.into_boxed_slice(),
+            partition_field_types: partition_field_types.into_boxed_slice(),
+            order_field_types: order_field_types.into_boxed_slice(),
+            window_funcs: window_funcs.into_boxed_slice(),
+            last_partition_key: None,
+            last_order_key: None,
+            row_number: 0,
+            rank: 0,
+            dense_rank: 0,
+            schema,
+            context: EvalContext::default(),
+            src,
+        }
+    }
+
+    pub fn new(
+        config: Arc<EvalConfig>,
+        src: Src,
+        window_func_descs: Vec<Expr>,
+        partition_exprs_def: Vec<Expr>,
+        order_exprs_def: Vec<Expr>,
+    ) -> Result<Self> {
+        let window_funcs: Vec<WindowFuncType> = window_func_descs
+            .iter()
+            .map(WindowFuncType::from_expr)
+            .collect::<Result<_>>()?;
+
+        let mut ctx = EvalContext::new(config.clone());
+
+        let mut partition_exprs: Vec<RpnExpression> =
+            Vec::with_capacity(partition_exprs_def.len());
+        for def in partition_exprs_def {
+            partition_exprs.push(RpnExpressionBuilder::build_from_expr_tree(
+                def,
+                &mut ctx,
+                src.schema().len(),
+            )?);
+        }
+        let partition_field_types: Vec<FieldType> = partition_exprs
+            .iter()
+            .map(|expr| expr.ret_field_type(src.schema()).clone())
+            .collect();
+
+        let mut order_exprs: Vec<RpnExpression> = Vec::with_capacity(order_exprs_def.len());
+        for def in order_exprs_def {
+            order_exprs.push(RpnExpressionBuilder::build_from_expr_tree(
+                def,
+                &mut ctx,
+                src.schema().len(),
+            )?);
+        }
+        let order_field_types: Vec<FieldType> = order_exprs
+            .iter()
+            .map(|expr| expr.ret_field_type(src.schema()).clone())
+            .collect();
+
+        let mut schema = src.schema().to_vec();
+        for _ in &window_funcs {
+            schema.push(FieldTypeTp::LongLong.into());
+        }
+
+        Ok(Self {
+            partition_exprs: partition_exprs.into_boxed_slice(),
+            order_exprs: order_exprs.into_boxed_slice(),
+            partition_field_types: partition_field_types.into_boxed_slice(),
+            order_field_types: order_field_types.into_boxed_slice(),
+            window_funcs: window_funcs.into_boxed_slice(),
+            last_partition_key: None,
+            last_order_key: None,
+            row_number: 0,
+            rank: 0,
+            dense_rank: 0,
+            schema,
+            context: EvalContext::new(config),
+            src,
+        })
+    }
+
+    #[inline]
+    async fn handle_next_batch(
+        &mut self,
+    ) -> Result<(LazyBatchColumnVec, Vec<usize>, BatchExecIsDrain)> {
+        let src_result = self.src.next_batch(BATCH_MAX_SIZE).await;
+        self.context.warnings = src_result.warnings;
+        let src_is_drained = src_result.is_drained?;
+
+        let (mut physical_columns, logical_rows) =
+            (src_result.physical_columns, src_result.logical_rows);
+
+        let mut window_columns: Vec<Vec<Option<Int>>> = self
+            .window_funcs
+            .iter()
+            .map(|_| vec![None; physical_columns.rows_len()])
+            .collect();
+
+        if !logical_rows.is_empty() {
+            ensure_columns_decoded(
+                &mut self.context,
+                &self.partition_exprs,
+                self.src.schema(),
+                &mut physical_columns,
+                &logical_rows,
+            )?;
+            ensure_columns_decoded(
+                &mut self.context,
+                &self.order_exprs,
+                self.src.schema(),
+                &mut physical_columns,
+                &logical_rows,
+            )?;
+
+            let mut partition_eval_columns: Vec<RpnStackNode<'_>> =
+                Vec::with_capacity(self.partition_exprs.len());
+            for expr in self.partition_exprs.iter() {
+                partition_eval_columns.push(expr.eval_decoded(
+                    &mut self.context,
+                    self.src.schema(),
+                    &physical_columns,
+                    &logical_rows,
+                    logical_rows.len(),
+                )?);
+            }
+            let mut order_eval_columns: Vec<RpnStackNode<'_>> =
+                Vec::with_capacity(self.order_exprs.len());
+            for expr in self.order_exprs.iter() {
+                order_eval_columns.push(expr.eval_decoded(
+                    &mut self.context,
+                    self.src.schema(),
+                    &physical_columns,
+                    &logical_rows,
+                    logical_rows.len(),
+                )?);
+            }
+
+            for (row_index, &physical_row) in logical_rows.iter().enumerate() {
+                let partition_key: Vec<ScalarValue> = partition_eval_columns
+                    .iter()
+                    .map(|col| col.get_logical_scalar_ref(row_index).to_owned())
+                    .collect();
+                let new_partition = key_changed(
+                    &self.last_partition_key,
+                    &partition_key,
+                    &self.partition_field_types,
+                )?;
+                self.last_partition_key = Some(partition_key);
+                if new_partition {
+                    self.last_order_key = None;
+                }
+
+                let order_key: Vec<ScalarValue> = order_eval_columns
+                    .iter()
+                    .map(|col| col.get_logical_scalar_ref(row_index).to_owned())
+                    .collect();
+                let new_order_key =
+                    key_changed(&self.last_order_key, &order_key, &self.order_field_types)?;
+                self.last_order_key = Some(order_key);
+
+                advance(
+                    &mut self.row_number,
+                    &mut self.rank,
+                    &mut self.dense_rank,
+                    new_partition,
+                    new_order_key,
+                );
+
+                for (func_index, func) in self.window_funcs.iter().enumerate() {
+                    window_columns[func_index][physical_row] =
+                        Some(eval_window_func(*func, self.row_number, self.rank, self.dense_rank));
+                }
+            }
+        }
+
+        for column in window_columns {
+            physical_columns.push(LazyBatchColumn::Decoded(VectorValue::Int(column.into())));
+        }
+
+        Ok((physical_columns, logical_rows, src_is_drained))
+    }
+}
+
+#[async_trait]
+impl<Src: BatchExecutor> BatchExecutor for BatchWindowExecutor<Src> {
+    type StorageStats = Src::StorageStats;
+
+    #[inline]
+    fn schema(&self) -> &[FieldType] {
+        &self.schema
+    }
+
+    #[inline]
+    async fn next_batch(&mut self, _scan_rows: usize) -> BatchExecuteResult {
+        let result = self.handle_next_batch().await;
+
+        match result {
+            Err(e) => BatchExecuteResult {
+                physical_columns: LazyBatchColumnVec::empty(),
+                logical_rows: Vec::new(),
+                warnings: self.context.take_warnings(),
+                is_drained: Err(e),
+            },
+            Ok((physical_columns, logical_rows, is_drained)) => BatchExecuteResult {
+                physical_columns,
+                logical_rows,
+                warnings: self.context.take_warnings(),
+                is_drained: Ok(is_drained),
+            },
+        }
+    }
+
+    #[inline]
+    fn collect_exec_stats(&mut self, dest: &mut ExecuteStats) {
+        self.src.collect_exec_stats(dest);
+    }
+
+    #[inline]
+    fn collect_storage_stats(&mut self, dest: &mut Self::StorageStats) {
+        self.src.collect_storage_stats(dest);
+    }
+
+    #[inline]
+    fn take_scanned_range(&mut self) -> IntervalRange {
+        self.src.take_scanned_range()
+    }
+
+    #[inline]
+    fn can_be_cached(&self) -> bool {
+        self.src.can_be_cached()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+    use tidb_query_datatype::{
+        codec::{batch::LazyBatchColumnVec, data_type::VectorValue},
+        expr::EvalWarnings,
+        FieldTypeTp,
+    };
+
+    use super::*;
+    use crate::util::mock_executor::MockExecutor;
+
+    #[test]
+    fn test_row_number_no_partition() {
+        let src_exec = MockExecutor::new(
+            vec![FieldTypeTp::Long.into()],
+            vec![BatchExecuteResult {
+                physical_columns: LazyBatchColumnVec::from(vec![VectorValue::Int(
+                    vec![Some(1), Some(2), Some(3)].into(),
+                )]),
+                logical_rows: (0..3).collect(),
+                warnings: EvalWarnings::default(),
+                is_drained: Ok(BatchExecIsDrain::Drain),
+            }],
+        );
+
+        let mut exec = BatchWindowExecutor::new_for_test(
+            src_exec,
+            vec![WindowFuncType::RowNumber],
+            vec![],
+            vec![],
+        );
+
+        let r = block_on(exec.next_batch(3));
+        assert_eq!(&r.logical_rows, &[0, 1, 2]);
+        assert_eq!(r.physical_columns.columns_len(), 2);
+        assert_eq!(
+            r.physical_columns[1].decoded().to_int_vec(),
+            &[Some(1), Some(2), Some(3)]
+        );
+        assert!(r.is_drained.unwrap().stop());
+    }
+
+    #[test]
+    fn test_rank_and_dense_rank_with_ties() {
+        let src_exec = MockExecutor::new(
+            vec![FieldTypeTp::Long.into()],
+            vec![BatchExecuteResult {
+                physical_columns: LazyBatchColumnVec::from(vec![VectorValue::Int(
+                    vec![Some(1), Some(1), Some(2)].into(),
+                )]),
+                logical_rows: (0..3).collect(),
+                warnings: EvalWarnings::default(),
+                is_drained: Ok(BatchExecIsDrain::Drain),
+            }],
+        );
+
+        let mut exec = BatchWindowExecutor::new_for_test(
+            src_exec,
+            vec![WindowFuncType::Rank, WindowFuncType::DenseRank],
+            vec![],
+            vec![
+                RpnExpressionBuilder::new_for_test()
+                    .push_column_ref_for_test(0)
+                    .build_for_test(),
+            ],
+        );
+
+        let r = block_on(exec.next_batch(3));
+        assert_eq!(
+            r.physical_columns[1].decoded().to_int_vec(),
+            &[Some(1), Some(1), Some(3)]
+        );
+        assert_eq!(
+            r.physical_columns[2].decoded().to_int_vec(),
+            &[Some(1), Some(1), Some(2)]
+        );
+        assert!(r.is_drained.unwrap().stop());
+    }
+
+    #[test]
+    fn test_row_number_resets_per_partition() {
+        let src_exec = MockExecutor::new(
+            vec![FieldTypeTp::Long.into()],
+            vec![BatchExecuteResult {
+                physical_columns: LazyBatchColumnVec::from(vec![VectorValue::Int(
+                    vec![Some(1), Some(1), Some(2), Some(2), Some(2)].into(),
+                )]),
+                logical_rows: (0..5).collect(),
+                warnings: EvalWarnings::default(),
+                is_drained: Ok(BatchExecIsDrain::Drain),
+            }],
+        );
+
+        let mut exec = BatchWindowExecutor::new_for_test(
+            src_exec,
+            vec![WindowFuncType::RowNumber],
+            vec![
+                RpnExpressionBuilder::new_for_test()
+                    .push_column_ref_for_test(0)
+                    .build_for_test(),
+            ],
+            vec![],
+        );
+
+        let r = block_on(exec.next_batch(5));
+        assert_eq!(
+            r.physical_columns[1].decoded().to_int_vec(),
+            &[Some(1), Some(2), Some(1), Some(2), Some(3)]
+        );
+        assert!(r.is_drained.unwrap().stop());
+    }
+
+    #[test]
+    fn test_partition_continues_across_batches() {
+        let src_exec = MockExecutor::new(
+            vec![FieldTypeTp::Long.into()],
+            vec![
+                BatchExecuteResult {
+                    physical_columns: LazyBatchColumnVec::from(vec![VectorValue::Int(
+                        vec![Some(1), Some(1)].into(),
+                    )]),
+                    logical_rows: (0..2).collect(),
+                    warnings: EvalWarnings::default(),
+                    is_drained: Ok(BatchExecIsDrain::Remain),
+                },
+                BatchExecuteResult {
+                    physical_columns: LazyBatchColumnVec::from(vec![VectorValue::Int(
+                        vec![Some(1), Some(2)].into(),
+                    )]),
+                    logical_rows: (0..2).collect(),
+                    warnings: EvalWarnings::default(),
+                    is_drained: Ok(BatchExecIsDrain::Drain),
+                },
+            ],
+        );
+
+        let mut exec = BatchWindowExecutor::new_for_test(
+            src_exec,
+            vec![WindowFuncType::RowNumber],
+            vec![
+                RpnExpressionBuilder::new_for_test()
+                    .push_column_ref_for_test(0)
+                    .build_for_test(),
+            ],
+            vec![],
+        );
+
+        let r1 = block_on(exec.next_batch(2));
+        assert_eq!(
+            r1.physical_columns[1].decoded().to_int_vec(),
+            &[Some(1), Some(2)]
+        );
+        assert!(!r1.is_drained.unwrap().stop());
+
+        let r2 = block_on(exec.next_batch(2));
+        assert_eq!(
+            r2.physical_columns[1].decoded().to_int_vec(),
+            &[Some(3), Some(1)]
+        );
+        assert!(r2.is_drained.unwrap().stop());
+    }
+}
@@ -1,14 +1,29 @@
 // Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
 
-use std::collections::BTreeMap;
+use std::{borrow::Cow, collections::BTreeMap};
 
 use serde::de::IgnoredAny;
 use tidb_query_codegen::rpn_fn;
 use tidb_query_common::Result;
 use tidb_query_datatype::{
-    codec::{data_type::*, mysql::json::*},
+    codec::{convert::*, data_type::*, mysql::json::*},
+    expr::{EvalConfig, EvalContext, Flag},
     EvalType,
 };
+use tipb::{Expr, ExprType};
+
+// Like MySQL, most JSON functions propagate SQL NULL: if a "document"
+// argument (as opposed to an optional path list, which has its own
+// NULL-means-"no paths given" rules) is NULL, the whole result is NULL.
+// Same idea as `impl_cast.rs`'s macro of the same name.
+macro_rules! skip_none {
+    ($val:expr) => {
+        match $val {
+            None => return Ok(None),
+            Some(v) => v,
+        }
+    };
+}
 
 #[rpn_fn]
 #[inline]
@@ -43,9 +58,12 @@ fn json_replace(args: &[ScalarValueRef]) -> Result<Option<Json>> {
 #[inline]
 fn json_modify(args: &[ScalarValueRef], mt: ModifyType) -> Result<Option<Json>> {
     assert!(args.len() >= 2);
-    // base Json argument
-    let base: Option<JsonRef> = args[0].as_json();
-    let base = base.map_or(Json::none(), |json| Ok(json.to_owned()))?;
+    // Like MySQL, a NULL base document makes the whole result NULL, same as
+    // `json_array_append` below. Only a NULL *value* is folded into the JSON
+    // `null` literal (see the loop below), since that's a value to be stored
+    // in the document, not the document itself.
+    let base: JsonRef = skip_none!(args[0].as_json());
+    let base = base.to_owned();
 
     let buf_size = args.len() / 2;
 
@@ -70,13 +88,10 @@ fn json_modify(args: &[ScalarValueRef], mt: ModifyType) -> Result<Option<Json>>
 #[inline]
 fn json_array_append(args: &[ScalarValueRef]) -> Result<Option<Json>> {
     assert!(args.len() >= 2);
-    // Returns None if Base is None
-    if args[0].to_owned().is_none() {
-        return Ok(None);
-    }
-    // base Json argument
-    let base: Option<JsonRef> = args[0].as_json();
-    let mut base = base.map_or(Json::none(), |json| Ok(json.to_owned()))?;
+    // base Json argument. A NULL base makes the whole result NULL, same as
+    // `json_modify` above.
+    let base: JsonRef = skip_none!(args[0].as_json());
+    let mut base = base.to_owned();
 
     for chunk in args[1..].chunks(2) {
         let path: Option<BytesRef> = chunk[0].as_bytes();
@@ -88,7 +103,7 @@ fn json_array_append(args: &[ScalarValueRef]) -> Result<Option<Json>> {
         // extract the element from the path, then merge the value into the element
         // 1. extrace the element from the path
         let tmp_path_expr_list = vec![try_opt!(parse_json_path(path))];
-        let element: Option<Json> = base.as_ref().extract(&tmp_path_expr_list)?;
+        let element: Option<Json> = base.as_ref().extract(&tmp_path_expr_list, false)?;
         // 2. merge the value into the element
         if let Some(elem) = element {
             // if both elem and value are json object, wrap elem into a vector
@@ -117,9 +132,9 @@ fn json_modify_validator(expr: &tipb::Expr) -> Result<()> {
     let children = expr.get_children();
     assert!(children.len() >= 2);
     if children.len() % 2 != 1 {
-        return Err(other_err!(
-            "Incorrect parameter count in the call to native function 'JSON_OBJECT'"
-        ));
+        return Err(
+            tidb_query_datatype::codec::Error::incorrect_parameter_count("JSON_OBJECT").into(),
+        );
     }
     super::function::validate_expr_return_type(&children[0], EvalType::Json)?;
     for chunk in children[1..].chunks(2) {
@@ -145,9 +160,9 @@ fn json_array(args: &[Option<JsonRef>]) -> Result<Option<Json>> {
 fn json_object_validator(expr: &tipb::Expr) -> Result<()> {
     let chunks = expr.get_children();
     if chunks.len() % 2 == 1 {
-        return Err(other_err!(
-            "Incorrect parameter count in the call to native function 'JSON_OBJECT'"
-        ));
+        return Err(
+            tidb_query_datatype::codec::Error::incorrect_parameter_count("JSON_OBJECT").into(),
+        );
     }
     for chunk in chunks.chunks(2) {
         super::function::validate_expr_return_type(&chunk[0], EvalType::Bytes)?;
@@ -165,9 +180,7 @@ fn json_object(raw_args: &[ScalarValueRef]) -> Result<Option<Json>> {
         assert_eq!(chunk.len(), 2);
         let key: Option<BytesRef> = chunk[0].as_bytes();
         if key.is_none() {
-            return Err(other_err!(
-                "Data truncation: JSON documents may not contain NULL member names."
-            ));
+            return Err(tidb_query_datatype::codec::Error::json_document_null_key().into());
         }
         let key = String::from_utf8(key.unwrap().to_owned())
             .map_err(tidb_query_datatype::codec::Error::from)?;
@@ -185,9 +198,9 @@ fn json_object(raw_args: &[ScalarValueRef]) -> Result<Option<Json>> {
 
 // According to mysql 5.7,
 // arguments of json_merge should not be less than 2.
-#[rpn_fn(nullable, varg, min_args = 2)]
+#[rpn_fn(nullable, varg, min_args = 2, capture = [ctx])]
 #[inline]
-pub fn json_merge(args: &[Option<JsonRef>]) -> Result<Option<Json>> {
+pub fn json_merge(ctx: &mut EvalContext, args: &[Option<JsonRef>]) -> Result<Option<Json>> {
     // min_args = 2, so it's ok to call args[0]
     if args[0].is_none() {
         return Ok(None);
@@ -200,7 +213,9 @@ pub fn json_merge(args: &[Option<JsonRef>]) -> Result<Option<Json>> {
             Some(j) => jsons.push(*j),
         }
     }
-    Ok(Some(Json::merge(jsons)?))
+    let merged = Json::merge(jsons)?;
+    ctx.charge_json_memory(merged.as_ref().binary_len())?;
+    Ok(Some(merged))
 }
 
 // `json_merge_patch` is the implementation for JSON_MERGE_PATCH in mysql
@@ -223,9 +238,12 @@ pub fn json_merge(args: &[Option<JsonRef>]) -> Result<Option<Json>> {
 // See `MergePatchBinaryJSON()` in TiDB
 // `pkg/types/json_binary_functions.go`
 // arguments of json_merge_patch should not be less than 2.
-#[rpn_fn(nullable, varg, min_args = 2)]
+#[rpn_fn(nullable, varg, min_args = 2, capture = [ctx])]
 #[inline]
-pub fn json_merge_patch(args: &[Option<JsonRef>]) -> Result<Option<Json>> {
+pub fn json_merge_patch(
+    ctx: &mut EvalContext,
+    args: &[Option<JsonRef>],
+) -> Result<Option<Json>> {
     let mut jsons: Vec<Option<JsonRef>> = vec![];
     let mut index = 0;
     // according to the implements of RFC7396
@@ -250,6 +268,7 @@ pub fn json_merge_patch(args: &[Option<JsonRef>]) -> Result<Option<Json>> {
             target = Json::merge_patch(target.as_ref(), jsons[i].unwrap())?;
         }
     }
+    ctx.charge_json_memory(target.as_ref().binary_len())?;
     Ok(Some(target.to_owned()))
 }
 
@@ -362,19 +381,514 @@ fn unquote_string(s: &str) -> Result<String> {
     }
 }
 
-#[rpn_fn(nullable, raw_varg, min_args = 2, extra_validator = json_with_paths_validator)]
+// Parses the constant `Bytes`/`String` path arguments starting at child
+// index `START` into a `PathExpression` list once, at expression-build time,
+// so row-by-row evaluation doesn't re-parse the same path text on every call.
+// Returns `Ok(None)` when any path argument isn't a constant (e.g. it's a
+// column reference or itself an expression) -- the caller falls back to
+// `parse_json_path_list` per row in that case, same as before this cache
+// existed. Same shape as `impl_regexp.rs`'s `init_regexp_data`.
+fn init_json_path_list<const START: usize>(expr: &mut Expr) -> Result<Option<Vec<PathExpression>>> {
+    let children = expr.mut_children();
+    if children.len() <= START {
+        return Ok(None);
+    }
+    let mut path_expr_list = Vec::with_capacity(children.len() - START);
+    for child in &children[START..] {
+        let path = match child.get_tp() {
+            ExprType::Bytes | ExprType::String => child.get_val(),
+            _ => return Ok(None),
+        };
+        let path = std::str::from_utf8(path).map_err(tidb_query_datatype::codec::Error::from)?;
+        path_expr_list.push(parse_json_path_expr(path)?);
+    }
+    Ok(Some(path_expr_list))
+}
+
+#[rpn_fn(
+    nullable,
+    raw_varg,
+    min_args = 2,
+    capture = [ctx, metadata],
+    extra_validator = json_with_paths_validator,
+    metadata_mapper = init_json_path_list::<1>
+)]
 #[inline]
-fn json_extract(args: &[ScalarValueRef]) -> Result<Option<Json>> {
+fn json_extract(
+    ctx: &mut EvalContext,
+    metadata: &Option<Vec<PathExpression>>,
+    args: &[ScalarValueRef],
+) -> Result<Option<Json>> {
     assert!(args.len() >= 2);
-    let j: Option<JsonRef> = args[0].as_json();
-    let j = match j {
-        None => return Ok(None),
-        Some(j) => j.to_owned(),
+    let j: JsonRef = skip_none!(args[0].as_json());
+    let j = j.to_owned();
+
+    let path_expr_list = match metadata {
+        Some(path_expr_list) => Cow::Borrowed(path_expr_list),
+        None => Cow::Owned(try_opt!(parse_json_path_list(&args[1..]))),
     };
+    let case_insensitive = ctx.cfg.flag.contains(Flag::IGNORE_JSON_KEY_CASE);
+
+    Ok(j.as_ref().extract(&path_expr_list, case_insensitive)?)
+}
+
+// `json_sum_path`/`json_avg_path` implement TiDB's extract-then-aggregate
+// push-down for multi-valued index maintenance: rather than extracting a
+// `Json` array and aggregating it in a separate step, they flatten and sum
+// the numeric values matched by the given paths in one pass, skipping
+// non-numeric matches the way `SUM`/`AVG` skip non-numeric rows.
+//
+// NOTE: neither is reachable from a pushed-down query plan yet --
+// `tipb::ScalarFuncSig` has no `JsonSumPathSig`/`JsonAvgPathSig` variant, so
+// `lib.rs`'s `map_pb_sig_to_rpn_func` has nothing to dispatch to
+// `json_sum_path_fn_meta()`/`json_avg_path_fn_meta()`. Wiring them up needs
+// those variants added to tipb first.
+#[rpn_fn(nullable, raw_varg, min_args = 2, capture = [ctx], extra_validator = json_with_paths_validator)]
+#[inline]
+fn json_sum_path(ctx: &mut EvalContext, args: &[ScalarValueRef]) -> Result<Option<Real>> {
+    assert!(args.len() >= 2);
+    let j: JsonRef = skip_none!(args[0].as_json());
+
+    let path_expr_list = try_opt!(parse_json_path_list(&args[1..]));
+    let case_insensitive = ctx.cfg.flag.contains(Flag::IGNORE_JSON_KEY_CASE);
+    let values = j.extract_numerics(&path_expr_list, case_insensitive)?;
+    if values.is_empty() {
+        return Ok(None);
+    }
+
+    let sum: f64 = values.iter().sum();
+    if !sum.is_finite() {
+        return Err(tidb_query_datatype::codec::Error::overflow("DOUBLE", "json_sum_path").into());
+    }
+    Ok(Some(box_try!(Real::new(sum))))
+}
+
+#[rpn_fn(nullable, raw_varg, min_args = 2, capture = [ctx], extra_validator = json_with_paths_validator)]
+#[inline]
+fn json_avg_path(ctx: &mut EvalContext, args: &[ScalarValueRef]) -> Result<Option<Real>> {
+    assert!(args.len() >= 2);
+    let j: JsonRef = skip_none!(args[0].as_json());
 
     let path_expr_list = try_opt!(parse_json_path_list(&args[1..]));
+    let case_insensitive = ctx.cfg.flag.contains(Flag::IGNORE_JSON_KEY_CASE);
+    let values = j.extract_numerics(&path_expr_list, case_insensitive)?;
+    if values.is_empty() {
+        return Ok(None);
+    }
+
+    let avg = values.iter().sum::<f64>() / values.len() as f64;
+    if !avg.is_finite() {
+        return Err(tidb_query_datatype::codec::Error::overflow("DOUBLE", "json_avg_path").into());
+    }
+    Ok(Some(box_try!(Real::new(avg))))
+}
+
+// `json_value_as_signed`/`json_value_as_unsigned`/`json_value_as_double`/
+// `json_value_as_string` implement MySQL's `JSON_VALUE(json_doc, path
+// RETURNING type)`: extract the single path match and cast it straight to
+// the requested SQL type, the way a generated column or index expression
+// declared as `JSON_VALUE(doc, '$.path' RETURNING SIGNED)` needs it. Unlike
+// `JSON_EXTRACT`, `JSON_VALUE` takes exactly one path and only ever yields a
+// scalar -- a match that is itself an object or array is treated as no
+// value (`NULL`) rather than auto-wrapped into an array, matching MySQL's
+// behavior under the default `NULL ON ERROR` clause. `ON EMPTY`/`ON ERROR`
+// value clauses other than the `NULL` default aren't implemented.
+//
+// Like `json_sum_path`/`json_avg_path` above, none of these are reachable
+// from a pushed-down query plan yet -- `tipb::ScalarFuncSig` has no
+// `JsonValueSig` variant family, so `lib.rs`'s `map_pb_sig_to_rpn_func` has
+// nothing to dispatch to `json_value_as_signed_fn_meta()` etc. Wiring them
+// up needs those variants added to tipb first.
+fn json_value_validator(expr: &tipb::Expr) -> Result<()> {
+    assert!(expr.get_children().len() == 2);
+    let children = expr.get_children();
+    super::function::validate_expr_return_type(&children[0], EvalType::Json)?;
+    super::function::validate_expr_return_type(&children[1], EvalType::Bytes)?;
+    Ok(())
+}
+
+fn json_value_extract(
+    metadata: &Option<Vec<PathExpression>>,
+    args: &[ScalarValueRef],
+) -> Result<Option<Json>> {
+    assert!(args.len() == 2);
+    let j: JsonRef = skip_none!(args[0].as_json());
+
+    let path_expr_list = match metadata {
+        Some(path_expr_list) => Cow::Borrowed(path_expr_list),
+        None => Cow::Owned(try_opt!(parse_json_path_list(&args[1..]))),
+    };
+    match j.extract(&path_expr_list, false)? {
+        Some(json) if matches!(json.get_type(), JsonType::Object | JsonType::Array) => Ok(None),
+        other => Ok(other),
+    }
+}
+
+#[rpn_fn(
+    nullable,
+    raw_varg,
+    min_args = 2,
+    max_args = 2,
+    capture = [ctx, metadata],
+    extra_validator = json_value_validator,
+    metadata_mapper = init_json_path_list::<1>
+)]
+#[inline]
+fn json_value_as_signed(
+    ctx: &mut EvalContext,
+    metadata: &Option<Vec<PathExpression>>,
+    args: &[ScalarValueRef],
+) -> Result<Option<Int>> {
+    match json_value_extract(metadata, args)? {
+        Some(json) => Ok(Some(json.as_ref().convert(ctx)?)),
+        None => Ok(None),
+    }
+}
+
+#[rpn_fn(
+    nullable,
+    raw_varg,
+    min_args = 2,
+    max_args = 2,
+    capture = [ctx, metadata],
+    extra_validator = json_value_validator,
+    metadata_mapper = init_json_path_list::<1>
+)]
+#[inline]
+fn json_value_as_unsigned(
+    ctx: &mut EvalContext,
+    metadata: &Option<Vec<PathExpression>>,
+    args: &[ScalarValueRef],
+) -> Result<Option<Int>> {
+    match json_value_extract(metadata, args)? {
+        Some(json) => {
+            let val: u64 = json.as_ref().convert(ctx)?;
+            Ok(Some(val as i64))
+        }
+        None => Ok(None),
+    }
+}
+
+#[rpn_fn(
+    nullable,
+    raw_varg,
+    min_args = 2,
+    max_args = 2,
+    capture = [ctx, metadata],
+    extra_validator = json_value_validator,
+    metadata_mapper = init_json_path_list::<1>
+)]
+#[inline]
+fn json_value_as_double(
+    ctx: &mut EvalContext,
+    metadata: &Option<Vec<PathExpression>>,
+    args: &[ScalarValueRef],
+) -> Result<Option<Real>> {
+    match json_value_extract(metadata, args)? {
+        Some(json) => Ok(Some(json.as_ref().convert(ctx)?)),
+        None => Ok(None),
+    }
+}
+
+#[rpn_fn(
+    nullable,
+    raw_varg,
+    min_args = 2,
+    max_args = 2,
+    capture = [ctx, metadata],
+    extra_validator = json_value_validator,
+    metadata_mapper = init_json_path_list::<1>
+)]
+#[inline]
+fn json_value_as_string(
+    ctx: &mut EvalContext,
+    metadata: &Option<Vec<PathExpression>>,
+    args: &[ScalarValueRef],
+) -> Result<Option<Bytes>> {
+    match json_value_extract(metadata, args)? {
+        Some(json) => Ok(Some(json.as_ref().convert(ctx)?)),
+        None => Ok(None),
+    }
+}
+
+// `CompiledJsonSchema` backs `json_schema_valid`, caching the parsed schema
+// document (the function's first argument) the same way `init_json_path_list`
+// caches parsed paths, so a constant schema is compiled once per expression
+// rather than once per row. Only the subset of draft-04-style keywords MySQL
+// 8.0's `JSON_SCHEMA_VALID` documents support is implemented -- `type`,
+// `enum`, `required`, `properties`, `items`, `minimum`/`maximum`,
+// `minLength`/`maxLength`, `minItems`/`maxItems`,
+// `minProperties`/`maxProperties`, and `multipleOf`. Any other keyword in the
+// schema document is ignored rather than rejected, matching MySQL's behavior
+// for keywords it doesn't recognize.
+#[derive(Debug, Clone)]
+struct CompiledJsonSchema {
+    types: Option<Vec<String>>,
+    enum_values: Option<Vec<serde_json::Value>>,
+    required: Vec<String>,
+    properties: Vec<(String, CompiledJsonSchema)>,
+    items: Option<Box<CompiledJsonSchema>>,
+    minimum: Option<f64>,
+    exclusive_minimum: bool,
+    maximum: Option<f64>,
+    exclusive_maximum: bool,
+    min_length: Option<u64>,
+    max_length: Option<u64>,
+    min_items: Option<u64>,
+    max_items: Option<u64>,
+    min_properties: Option<u64>,
+    max_properties: Option<u64>,
+    multiple_of: Option<f64>,
+}
+
+impl CompiledJsonSchema {
+    fn validate(&self, doc: &serde_json::Value) -> bool {
+        if let Some(types) = &self.types {
+            if !types.iter().any(|ty| json_schema_type_matches(doc, ty)) {
+                return false;
+            }
+        }
+        if let Some(values) = &self.enum_values {
+            if !values.contains(doc) {
+                return false;
+            }
+        }
+        match doc {
+            serde_json::Value::Object(map) => {
+                if !self.required.iter().all(|key| map.contains_key(key)) {
+                    return false;
+                }
+                if self.min_properties.is_some_and(|min| (map.len() as u64) < min)
+                    || self.max_properties.is_some_and(|max| (map.len() as u64) > max)
+                {
+                    return false;
+                }
+                self.properties.iter().all(|(key, schema)| match map.get(key) {
+                    Some(value) => schema.validate(value),
+                    None => true,
+                })
+            }
+            serde_json::Value::Array(items) => {
+                if self.min_items.is_some_and(|min| (items.len() as u64) < min)
+                    || self.max_items.is_some_and(|max| (items.len() as u64) > max)
+                {
+                    return false;
+                }
+                match &self.items {
+                    Some(schema) => items.iter().all(|item| schema.validate(item)),
+                    None => true,
+                }
+            }
+            serde_json::Value::String(s) => {
+                let len = s.chars().count() as u64;
+                !(self.min_length.is_some_and(|min| len < min)
+                    || self.max_length.is_some_and(|max| len > max))
+            }
+            serde_json::Value::Number(n) => match n.as_f64() {
+                None => true,
+                Some(value) => {
+                    if self.exclusive_minimum {
+                        if self.minimum.is_some_and(|min| value <= min) {
+                            return false;
+                        }
+                    } else if self.minimum.is_some_and(|min| value < min) {
+                        return false;
+                    }
+                    if self.exclusive_maximum {
+                        if self.maximum.is_some_and(|max| value >= max) {
+                            return false;
+                        }
+                    } else if self.maximum.is_some_and(|max| value > max) {
+                        return false;
+                    }
+                    !self
+                        .multiple_of
+                        .is_some_and(|m| m != 0.0 && (value / m).fract().abs() > f64::EPSILON)
+                }
+            },
+            serde_json::Value::Bool(_) | serde_json::Value::Null => true,
+        }
+    }
+}
+
+fn json_schema_type_matches(value: &serde_json::Value, ty: &str) -> bool {
+    match ty {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value
+            .as_f64()
+            .is_some_and(|f| f.fract() == 0.0 && f.is_finite()),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+fn invalid_json_schema(msg: impl std::fmt::Display) -> tidb_query_common::Error {
+    tidb_query_datatype::codec::Error::InvalidDataType(format!("Invalid JSON Schema: {}", msg))
+        .into()
+}
+
+fn compile_json_schema(schema: &serde_json::Value) -> Result<CompiledJsonSchema> {
+    let obj = schema
+        .as_object()
+        .ok_or_else(|| invalid_json_schema("schema document must be a JSON object"))?;
+
+    let types = match obj.get("type") {
+        None => None,
+        Some(serde_json::Value::String(ty)) => Some(vec![ty.clone()]),
+        Some(serde_json::Value::Array(types)) => Some(
+            types
+                .iter()
+                .map(|ty| {
+                    ty.as_str()
+                        .map(str::to_owned)
+                        .ok_or_else(|| invalid_json_schema("`type` entries must be strings"))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        Some(_) => return Err(invalid_json_schema("`type` must be a string or array of strings")),
+    };
+
+    let enum_values = match obj.get("enum") {
+        None => None,
+        Some(values) => Some(
+            values
+                .as_array()
+                .ok_or_else(|| invalid_json_schema("`enum` must be an array"))?
+                .clone(),
+        ),
+    };
+
+    let required = match obj.get("required") {
+        None => Vec::new(),
+        Some(values) => values
+            .as_array()
+            .ok_or_else(|| invalid_json_schema("`required` must be an array"))?
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(str::to_owned)
+                    .ok_or_else(|| invalid_json_schema("`required` entries must be strings"))
+            })
+            .collect::<Result<Vec<_>>>()?,
+    };
+
+    let properties = match obj.get("properties") {
+        None => Vec::new(),
+        Some(values) => values
+            .as_object()
+            .ok_or_else(|| invalid_json_schema("`properties` must be an object"))?
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), compile_json_schema(value)?)))
+            .collect::<Result<Vec<_>>>()?,
+    };
+
+    let items = match obj.get("items") {
+        Some(value) if value.is_object() => Some(Box::new(compile_json_schema(value)?)),
+        _ => None,
+    };
+
+    let as_u64 = |key: &str| -> Result<Option<u64>> {
+        match obj.get(key) {
+            None => Ok(None),
+            Some(value) => Ok(Some(value.as_u64().ok_or_else(|| {
+                invalid_json_schema(format!("`{}` must be a non-negative integer", key))
+            })?)),
+        }
+    };
+    let as_f64 = |key: &str| -> Result<Option<f64>> {
+        match obj.get(key) {
+            None => Ok(None),
+            Some(value) => Ok(Some(
+                value
+                    .as_f64()
+                    .ok_or_else(|| invalid_json_schema(format!("`{}` must be a number", key)))?,
+            )),
+        }
+    };
+    let as_bool = |key: &str| -> bool {
+        obj.get(key).and_then(|v| v.as_bool()).unwrap_or(false)
+    };
 
-    Ok(j.as_ref().extract(&path_expr_list)?)
+    Ok(CompiledJsonSchema {
+        types,
+        enum_values,
+        required,
+        properties,
+        items,
+        minimum: as_f64("minimum")?,
+        exclusive_minimum: as_bool("exclusiveMinimum"),
+        maximum: as_f64("maximum")?,
+        exclusive_maximum: as_bool("exclusiveMaximum"),
+        min_length: as_u64("minLength")?,
+        max_length: as_u64("maxLength")?,
+        min_items: as_u64("minItems")?,
+        max_items: as_u64("maxItems")?,
+        min_properties: as_u64("minProperties")?,
+        max_properties: as_u64("maxProperties")?,
+        multiple_of: as_f64("multipleOf")?,
+    })
+}
+
+fn json_to_schema_value(j: JsonRef) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(j)?)
+}
+
+fn json_schema_validator(expr: &tipb::Expr) -> Result<()> {
+    assert!(expr.get_children().len() == 2);
+    let children = expr.get_children();
+    super::function::validate_expr_return_type(&children[0], EvalType::Json)?;
+    super::function::validate_expr_return_type(&children[1], EvalType::Json)?;
+    Ok(())
+}
+
+fn init_json_schema(expr: &mut Expr) -> Result<Option<CompiledJsonSchema>> {
+    let children = expr.mut_children();
+    if children.is_empty() || children[0].get_tp() != ExprType::MysqlJson {
+        return Ok(None);
+    }
+    let schema = children[0].get_val().read_json()?;
+    Ok(Some(compile_json_schema(&json_to_schema_value(
+        schema.as_ref(),
+    )?)?))
+}
+
+/// `JSON_SCHEMA_VALID(schema, document)` reports whether `document` conforms
+/// to the given JSON Schema, for the subset of keywords `CompiledJsonSchema`
+/// understands. Like `json_sum_path`/`json_avg_path` above, this isn't
+/// reachable from a pushed-down query plan yet -- `tipb::ScalarFuncSig` has
+/// no `JsonSchemaValidSig` variant, so `lib.rs`'s `map_pb_sig_to_rpn_func`
+/// has nothing to dispatch to `json_schema_valid_fn_meta()`. Wiring it up
+/// needs that variant added to tipb first.
+#[rpn_fn(
+    nullable,
+    raw_varg,
+    min_args = 2,
+    max_args = 2,
+    capture = [metadata],
+    extra_validator = json_schema_validator,
+    metadata_mapper = init_json_schema
+)]
+#[inline]
+fn json_schema_valid(
+    metadata: &Option<CompiledJsonSchema>,
+    args: &[ScalarValueRef],
+) -> Result<Option<i64>> {
+    assert!(args.len() == 2);
+    let doc: JsonRef = skip_none!(args[1].as_json());
+
+    let schema = match metadata {
+        Some(schema) => Cow::Borrowed(schema),
+        None => {
+            let schema_json: JsonRef = skip_none!(args[0].as_json());
+            Cow::Owned(compile_json_schema(&json_to_schema_value(schema_json)?)?)
+        }
+    };
+
+    Ok(Some(schema.validate(&json_to_schema_value(doc)?) as i64))
 }
 
 // Args should be like `(Option<JsonRef> , &[Option<BytesRef>])`.
@@ -383,13 +897,14 @@ fn json_with_path_validator(expr: &tipb::Expr) -> Result<()> {
     valid_paths(expr)
 }
 
-#[rpn_fn(nullable, raw_varg,min_args= 1, max_args = 2, extra_validator = json_with_path_validator)]
+#[rpn_fn(nullable, raw_varg,min_args= 1, max_args = 2, capture = [ctx], extra_validator = json_with_path_validator)]
 #[inline]
-fn json_keys(args: &[ScalarValueRef]) -> Result<Option<Json>> {
+fn json_keys(ctx: &mut EvalContext, args: &[ScalarValueRef]) -> Result<Option<Json>> {
     assert!(!args.is_empty() && args.len() <= 2);
     if let Some(j) = args[0].as_json() {
         if let Some(list) = parse_json_path_list(&args[1..])? {
-            return Ok(j.keys(&list)?);
+            let case_insensitive = ctx.cfg.flag.contains(Flag::IGNORE_JSON_KEY_CASE);
+            return Ok(j.keys(&list, case_insensitive)?);
         }
     }
     Ok(None)
@@ -399,11 +914,8 @@ fn json_keys(args: &[ScalarValueRef]) -> Result<Option<Json>> {
 #[inline]
 fn json_length(args: &[ScalarValueRef]) -> Result<Option<Int>> {
     assert!(!args.is_empty() && args.len() <= 2);
-    let j: Option<JsonRef> = args[0].as_json();
-    let j = match j {
-        None => return Ok(None),
-        Some(j) => j.to_owned(),
-    };
+    let j: JsonRef = skip_none!(args[0].as_json());
+    let j = j.to_owned();
     Ok(match parse_json_path_list(&args[1..])? {
         Some(path_expr_list) => j.as_ref().json_length(&path_expr_list)?,
         None => None,
@@ -423,36 +935,41 @@ fn json_contains_validator(expr: &tipb::Expr) -> Result<()> {
     Ok(())
 }
 
-#[rpn_fn(nullable, raw_varg,min_args= 2, max_args = 3, extra_validator = json_contains_validator)]
+#[rpn_fn(
+    nullable,
+    raw_varg,
+    min_args = 2,
+    max_args = 3,
+    capture = [metadata],
+    extra_validator = json_contains_validator,
+    metadata_mapper = init_json_path_list::<2>
+)]
 #[inline]
-fn json_contains(args: &[ScalarValueRef]) -> Result<Option<i64>> {
+fn json_contains(
+    metadata: &Option<Vec<PathExpression>>,
+    args: &[ScalarValueRef],
+) -> Result<Option<i64>> {
     assert!(args.len() == 2 || args.len() == 3);
-    let j: Option<JsonRef> = args[0].as_json();
-    let mut j = match j {
-        None => return Ok(None),
-        Some(j) => j.to_owned(),
-    };
-    let target: Option<JsonRef> = args[1].as_json();
-    let target = match target {
-        None => return Ok(None),
-        Some(target) => target,
-    };
+    let mut j: Json = skip_none!(args[0].as_json()).to_owned();
+    let target: JsonRef = skip_none!(args[1].as_json());
 
     if args.len() == 3 {
-        match parse_json_path_list(&args[2..])? {
-            Some(path_expr_list) => {
-                if path_expr_list.len() == 1 && path_expr_list[0].contains_any_asterisk() {
-                    return Ok(None);
-                }
-                match j.as_ref().extract(&path_expr_list)? {
-                    Some(json) => {
-                        j = json;
-                    }
-                    _ => return Ok(None),
-                }
-            }
-            None => return Ok(None),
+        let path_expr_list = match metadata {
+            Some(path_expr_list) => Cow::Borrowed(path_expr_list),
+            None => match parse_json_path_list(&args[2..])? {
+                Some(path_expr_list) => Cow::Owned(path_expr_list),
+                None => return Ok(None),
+            },
         };
+        if path_expr_list.len() == 1 && path_expr_list[0].contains_any_asterisk() {
+            return Ok(None);
+        }
+        match j.as_ref().extract(&path_expr_list, false)? {
+            Some(json) => {
+                j = json;
+            }
+            _ => return Ok(None),
+        }
     }
     Ok(Some(j.as_ref().json_contains(target)? as i64))
 }
@@ -470,17 +987,8 @@ fn member_of_validator(expr: &tipb::Expr) -> Result<()> {
 #[inline]
 fn member_of(args: &[ScalarValueRef]) -> Result<Option<i64>> {
     assert!(args.len() == 2);
-    let value: Option<JsonRef> = args[0].as_json();
-    let value = match value {
-        None => return Ok(None),
-        Some(value) => value.to_owned(),
-    };
-
-    let json_array: Option<JsonRef> = args[1].as_json();
-    let json_array = match json_array {
-        None => return Ok(None),
-        Some(json_array) => json_array,
-    };
+    let value: Json = skip_none!(args[0].as_json()).to_owned();
+    let json_array: JsonRef = skip_none!(args[1].as_json());
 
     Ok(Some(value.as_ref().member_of(json_array)? as i64))
 }
@@ -489,17 +997,72 @@ fn member_of(args: &[ScalarValueRef]) -> Result<Option<i64>> {
 #[inline]
 fn json_remove(args: &[ScalarValueRef]) -> Result<Option<Json>> {
     assert!(args.len() >= 2);
-    let j: Option<JsonRef> = args[0].as_json();
-    let j = match j {
-        None => return Ok(None),
-        Some(j) => j.to_owned(),
-    };
+    let j: JsonRef = skip_none!(args[0].as_json());
+    let j = j.to_owned();
 
     let path_expr_list = try_opt!(parse_json_path_list(&args[1..]));
 
     Ok(Some(j.as_ref().remove(&path_expr_list)?))
 }
 
+// Args should be like `(Option<JsonRef>, Option<BytesRef>, Option<BytesRef>,
+// [Option<BytesRef>], &[Option<BytesRef>])`, i.e. `json_doc`, `one_or_all`,
+// `search_str`, an optional `escape_char`, then zero or more `path`s.
+fn json_search_validator(expr: &tipb::Expr) -> Result<()> {
+    let children = expr.get_children();
+    assert!(children.len() >= 3);
+    super::function::validate_expr_return_type(&children[0], EvalType::Json)?;
+    for child in &children[1..] {
+        super::function::validate_expr_return_type(child, EvalType::Bytes)?;
+    }
+    Ok(())
+}
+
+/// `JSON_SEARCH(json_doc, one_or_all, search_str[, escape_char[, path]
+/// ...])`, see [`JsonRef::search`]'s doc comment for the matching semantics.
+/// The optional `escape_char` defaults to `\` when omitted or `NULL`, same
+/// as `one_or_all`/`search_str`/`json_doc` each making the whole result NULL
+/// if they're NULL, except `escape_char`, which uses its default instead --
+/// MySQL doesn't define a NULL-propagation rule for it since it's the one
+/// argument that merely tunes how `search_str` is interpreted rather than
+/// contributing a value of its own.
+#[rpn_fn(nullable, raw_varg, min_args = 3, capture = [ctx], extra_validator = json_search_validator)]
+#[inline]
+fn json_search(ctx: &mut EvalContext, args: &[ScalarValueRef]) -> Result<Option<Json>> {
+    assert!(args.len() >= 3);
+    let j: JsonRef = skip_none!(args[0].as_json());
+
+    let one_or_all: BytesRef = skip_none!(args[1].as_bytes());
+    let one_or_all: OneOrAll = std::str::from_utf8(one_or_all)
+        .map_err(tidb_query_datatype::codec::Error::from)?
+        .parse()?;
+
+    let search_str: BytesRef = skip_none!(args[2].as_bytes());
+    let search_str =
+        std::str::from_utf8(search_str).map_err(tidb_query_datatype::codec::Error::from)?;
+
+    let escape = match args.get(3).and_then(|arg| arg.as_bytes()) {
+        Some(bytes) if bytes.len() == 1 => bytes[0],
+        Some(_) => {
+            return Err(
+                tidb_query_datatype::codec::Error::incorrect_parameters("json_search").into(),
+            );
+        }
+        None => b'\\',
+    };
+
+    let path_expr_list = try_opt!(parse_json_path_list(args.get(4..).unwrap_or_default()));
+    let case_insensitive = ctx.cfg.flag.contains(Flag::IGNORE_JSON_KEY_CASE);
+
+    Ok(j.search(
+        one_or_all,
+        search_str,
+        escape,
+        case_insensitive,
+        &path_expr_list,
+    )?)
+}
+
 fn parse_json_path_list(args: &[ScalarValueRef]) -> Result<Option<Vec<PathExpression>>> {
     let mut path_expr_list = Vec::with_capacity(args.len());
     for arg in args {
@@ -654,6 +1217,35 @@ mod tests {
                 ],
                 Some(r#"{"a":null}"#.parse().unwrap()),
             ),
+            // A NULL base document makes the whole result NULL, regardless of
+            // whether the path and value are themselves non-NULL.
+            (
+                ScalarFuncSig::JsonSetSig,
+                vec![
+                    None::<Json>.into(),
+                    Some(b"$.a".to_vec()).into(),
+                    Some(Json::from_i64(1).unwrap()).into(),
+                ],
+                None,
+            ),
+            (
+                ScalarFuncSig::JsonInsertSig,
+                vec![
+                    None::<Json>.into(),
+                    Some(b"$.a".to_vec()).into(),
+                    Some(Json::from_i64(1).unwrap()).into(),
+                ],
+                None,
+            ),
+            (
+                ScalarFuncSig::JsonReplaceSig,
+                vec![
+                    None::<Json>.into(),
+                    Some(b"$.a".to_vec()).into(),
+                    Some(Json::from_i64(1).unwrap()).into(),
+                ],
+                None,
+            ),
         ];
         for (sig, args, expect_output) in cases {
             let output: Option<Json> = RpnFnScalarEvaluator::new()
@@ -727,6 +1319,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_json_merge_memory_quota_exceeded() {
+        let vargs = vec![
+            Json::from_str("[1, 2]").unwrap(),
+            Json::from_str("[3, 4]").unwrap(),
+        ];
+
+        let mut cfg = EvalConfig::new();
+        cfg.set_json_memory_quota_capacity(1);
+        let ctx = EvalContext::new(std::sync::Arc::new(cfg));
+
+        let output = RpnFnScalarEvaluator::new()
+            .context(ctx)
+            .push_params(vargs)
+            .evaluate::<Json>(ScalarFuncSig::JsonMergeSig);
+        assert!(output.is_err());
+    }
+
     #[test]
     fn test_json_object() {
         let cases = vec![
@@ -867,6 +1477,12 @@ mod tests {
     fn test_json_extract() {
         let cases: Vec<(Vec<ScalarValue>, _)> = vec![
             (vec![None::<Json>.into(), None::<Bytes>.into()], None),
+            // A NULL document makes the whole result NULL even if the path
+            // is non-NULL.
+            (
+                vec![None::<Json>.into(), Some(b"$[1]".to_vec()).into()],
+                None,
+            ),
             (
                 vec![
                     Some(Json::from_str("[10, 20, [30, 40]]").unwrap()).into(),
@@ -912,13 +1528,21 @@ mod tests {
 
     #[test]
     fn test_json_remove() {
-        let cases: Vec<(Vec<ScalarValue>, _)> = vec![(
-            vec![
-                Some(Json::from_str(r#"["a", ["b", "c"], "d"]"#).unwrap()).into(),
-                Some(b"$[1]".to_vec()).into(),
-            ],
-            Some(r#"["a", "d"]"#),
-        )];
+        let cases: Vec<(Vec<ScalarValue>, _)> = vec![
+            (
+                vec![
+                    Some(Json::from_str(r#"["a", ["b", "c"], "d"]"#).unwrap()).into(),
+                    Some(b"$[1]".to_vec()).into(),
+                ],
+                Some(r#"["a", "d"]"#),
+            ),
+            // A NULL document makes the whole result NULL even if the path
+            // is non-NULL.
+            (
+                vec![None::<Json>.into(), Some(b"$[1]".to_vec()).into()],
+                None,
+            ),
+        ];
 
         for (vargs, expected) in cases {
             let expected = expected.map(|s| Json::from_str(s).unwrap());
@@ -941,6 +1565,12 @@ mod tests {
                 ],
                 None,
             ),
+            // A NULL document makes the whole result NULL even if the path
+            // is non-NULL.
+            (
+                vec![None::<Json>.into(), Some(b"$".to_vec()).into()],
+                None,
+            ),
             (
                 vec![
                     Some(Json::from_str("false").unwrap()).into(),
@@ -2060,4 +2690,158 @@ mod tests {
             assert_eq!(output, expected, "{:?}", vargs);
         }
     }
+
+    #[test]
+    fn test_json_search() {
+        let cases: Vec<(Vec<ScalarValue>, _, bool)> = vec![
+            // A NULL document, one_or_all, or search_str makes the whole
+            // result NULL.
+            (
+                vec![
+                    None::<Json>.into(),
+                    Some(b"one".to_vec()).into(),
+                    Some(b"abc".to_vec()).into(),
+                ],
+                None,
+                true,
+            ),
+            (
+                vec![
+                    Some(Json::from_str(r#"["abc"]"#).unwrap()).into(),
+                    None::<Bytes>.into(),
+                    Some(b"abc".to_vec()).into(),
+                ],
+                None,
+                true,
+            ),
+            (
+                vec![
+                    Some(Json::from_str(r#"["abc", "def"]"#).unwrap()).into(),
+                    Some(b"one".to_vec()).into(),
+                    Some(b"abc".to_vec()).into(),
+                ],
+                Some(r#""$[0]""#),
+                true,
+            ),
+            (
+                vec![
+                    Some(Json::from_str(r#"["abc", [{"k": "abc"}, "xyz"]]"#).unwrap()).into(),
+                    Some(b"all".to_vec()).into(),
+                    Some(b"abc".to_vec()).into(),
+                ],
+                Some(r#"["$[0]", "$[1][0].k"]"#),
+                true,
+            ),
+            // no match
+            (
+                vec![
+                    Some(Json::from_str(r#"{"a": "xyz"}"#).unwrap()).into(),
+                    Some(b"one".to_vec()).into(),
+                    Some(b"abc".to_vec()).into(),
+                ],
+                None,
+                true,
+            ),
+            // object keys are never matched, only string values
+            (
+                vec![
+                    Some(Json::from_str(r#"{"abc": 1}"#).unwrap()).into(),
+                    Some(b"one".to_vec()).into(),
+                    Some(b"abc".to_vec()).into(),
+                ],
+                None,
+                true,
+            ),
+            // `%`/`_` wildcards
+            (
+                vec![
+                    Some(Json::from_str(r#"["foobar"]"#).unwrap()).into(),
+                    Some(b"one".to_vec()).into(),
+                    Some(b"foo%".to_vec()).into(),
+                ],
+                Some(r#""$[0]""#),
+                true,
+            ),
+            // restricted to the given path
+            (
+                vec![
+                    Some(Json::from_str(r#"{"a": "abc", "b": "abc"}"#).unwrap()).into(),
+                    Some(b"all".to_vec()).into(),
+                    Some(b"abc".to_vec()).into(),
+                    None::<Bytes>.into(),
+                    Some(b"$.a".to_vec()).into(),
+                ],
+                Some(r#""$.a""#),
+                true,
+            ),
+            // invalid one_or_all
+            (
+                vec![
+                    Some(Json::from_str(r#"["abc"]"#).unwrap()).into(),
+                    Some(b"any".to_vec()).into(),
+                    Some(b"abc".to_vec()).into(),
+                ],
+                None,
+                false,
+            ),
+        ];
+
+        for (vargs, expected, is_success) in cases {
+            let expected = expected.map(|s| Json::from_str(s).unwrap());
+
+            let output: Result<Option<Json>> = RpnFnScalarEvaluator::new()
+                .push_params(vargs.clone())
+                .evaluate(ScalarFuncSig::JsonSearchSig);
+            if is_success {
+                assert_eq!(output.unwrap(), expected, "{:?}", vargs);
+            } else {
+                output.unwrap_err();
+            }
+        }
+    }
+
+    #[bench]
+    fn bench_json_extract_with_constant_path(b: &mut test::Bencher) {
+        use tidb_query_datatype::{
+            codec::batch::{LazyBatchColumn, LazyBatchColumnVec},
+            FieldTypeTp,
+        };
+        use tipb::FieldType;
+        use tipb_helper::ExprDefBuilder;
+
+        use super::super::map_expr_node_to_rpn_func;
+        use crate::RpnExpressionBuilder;
+
+        let node = ExprDefBuilder::scalar_func(ScalarFuncSig::JsonExtractSig, FieldTypeTp::Json)
+            .push_child(ExprDefBuilder::column_ref(0, FieldTypeTp::Json))
+            .push_child(ExprDefBuilder::constant_bytes(b"$.a".to_vec()))
+            .build();
+        let exp = RpnExpressionBuilder::build_from_expr_tree_with_fn_mapper(
+            node,
+            map_expr_node_to_rpn_func,
+            1,
+        )
+        .unwrap();
+
+        let mut ctx = EvalContext::default();
+        let schema: &[FieldType] = &[FieldTypeTp::Json.into()];
+        let mut col = LazyBatchColumn::decoded_with_capacity_and_tp(1024, EvalType::Json);
+        let json = Json::from_str(r#"{"a": 1}"#).unwrap();
+        for _ in 0..1024 {
+            col.mut_decoded().push_json(Some(json.clone()));
+        }
+        let mut columns = LazyBatchColumnVec::from(vec![col]);
+        let logical_rows: &[usize] = &(0..1024).collect::<Vec<usize>>();
+        b.iter(|| {
+            test::black_box(&exp)
+                .eval(
+                    test::black_box(&mut ctx),
+                    test::black_box(schema),
+                    test::black_box(&mut columns),
+                    test::black_box(logical_rows),
+                    test::black_box(1024),
+                )
+                .unwrap();
+        });
+    }
 }
@@ -2,7 +2,10 @@
 
 use tidb_query_codegen::rpn_fn;
 use tidb_query_common::Result;
-use tidb_query_datatype::codec::{collation::*, data_type::*};
+use tidb_query_datatype::codec::{
+    collation::{collator::CollatorUtf8Mb4GeneralCi, *},
+    data_type::*,
+};
 
 #[rpn_fn]
 #[inline]
@@ -68,6 +71,28 @@ pub fn like<C: Collator, CS: Charset>(
     Ok(Some(true as i64))
 }
 
+/// `ilike` implements `ILIKE`: the same pattern matching as `like`, but
+/// always case-insensitive, regardless of the collation pushed down for
+/// `target`/`pattern`. Case folding is delegated to
+/// `CollatorUtf8Mb4GeneralCi`, the same general-purpose case-insensitive
+/// comparator TiDB's own `_general_ci` collations use, rather than deriving a
+/// per-collation case-insensitive counterpart.
+///
+/// Like `json_sum_path`/`json_avg_path` in `impl_json.rs`, this isn't
+/// reachable from a pushed-down query plan yet -- `tipb::ScalarFuncSig` has
+/// no `IlikeSig` variant, so `lib.rs`'s `map_pb_sig_to_rpn_func` has nothing
+/// to dispatch to `ilike_fn_meta()`. Wiring it up needs that variant added to
+/// tipb first.
+#[rpn_fn]
+#[inline]
+pub fn ilike<CS: Charset>(
+    target: BytesRef,
+    pattern: BytesRef,
+    escape: &i64,
+) -> Result<Option<i64>> {
+    like::<CollatorUtf8Mb4GeneralCi, CS>(target, pattern, escape)
+}
+
 #[cfg(test)]
 mod tests {
     use tidb_query_datatype::{builder::FieldTypeBuilder, Collation, FieldTypeTp};
@@ -368,4 +393,46 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_ilike() {
+        use tidb_query_datatype::codec::collation::{
+            collator::{CollatorBinary, CollatorUtf8Mb4Bin},
+            Collator,
+        };
+
+        use super::ilike;
+
+        let cases = vec![
+            (r#"hello"#, r#"%HELLO%"#, '\\', Some(1)),
+            (r#"Hello, World"#, r#"hello, world"#, '\\', Some(1)),
+            (r#"IpHONE"#, r#"iphone"#, '\\', Some(1)),
+            (r#"IpHONE xs mAX"#, r#"iPhone XS Max"#, '\\', Some(1)),
+            (r#"test"#, r#"TE%ST"#, '\\', Some(1)),
+            (r#"test"#, r#"TE%SZ"#, '\\', Some(0)),
+        ];
+        for (target, pattern, escape, expected) in cases {
+            let output = ilike::<<CollatorUtf8Mb4Bin as Collator>::Charset>(
+                target.as_bytes(),
+                pattern.as_bytes(),
+                &(escape as i64),
+            )
+            .unwrap();
+            assert_eq!(
+                output, expected,
+                "target={}, pattern={}, escape={}",
+                target, pattern, escape
+            );
+        }
+
+        // Binary-charset input still decodes byte-by-byte, but comparison
+        // remains case-insensitive.
+        let output = ilike::<<CollatorBinary as Collator>::Charset>(
+            b"HELLO",
+            b"hello",
+            &('\\' as i64),
+        )
+        .unwrap();
+        assert_eq!(output, Some(1));
+    }
 }
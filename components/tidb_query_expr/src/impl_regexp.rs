@@ -1,11 +1,12 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
-use std::{borrow::Cow, collections::HashSet};
+use std::{borrow::Cow, cell::RefCell, collections::HashSet};
 
 use regex::Regex;
 use tidb_query_codegen::rpn_fn;
 use tidb_query_common::Result;
 use tidb_query_datatype::codec::{collation::Collator, data_type::*, Error};
+use tikv_util::lru::LruCache;
 use tipb::{Expr, ExprType};
 
 const PATTERN_IDX: usize = 1;
@@ -70,6 +71,18 @@ fn build_regexp<C: Collator>(pattern: &[u8], match_type: &[u8]) -> Result<Regex>
         .map_err(|e| Error::regexp_error(format!("Invalid regexp pattern: {:?}", e)).into())
 }
 
+// Bound on the number of distinct (pattern, match_type) pairs cached per
+// thread per call site below. Pushdown patterns are almost always drawn from
+// a small, fixed set of literals even when they're not provable constants at
+// plan-build time (e.g. `regexp_like(col, ?)` with a parameter bound once per
+// query), so a modest cache avoids recompiling the same regex for every row.
+const REGEXP_CACHE_CAPACITY: usize = 64;
+
+/// Falls back to per-row regex compilation for patterns the metadata_mapper
+/// couldn't cache once (i.e. the pattern argument isn't a constant). Caches
+/// compiled regexes by their raw `(pattern, match_type)` bytes so that a
+/// pattern which happens to repeat across rows -- the common case -- is only
+/// compiled once per thread rather than once per row.
 fn build_regexp_from_args<C: Collator>(
     args: &[ScalarValueRef<'_>],
     match_idx: usize,
@@ -88,7 +101,23 @@ fn build_regexp_from_args<C: Collator>(
         b""
     };
 
-    build_regexp::<C>(pattern, match_type).map(Some)
+    // One cache per monomorphization of this function, i.e. one per distinct
+    // `C: Collator`, since the compiled regex embeds `C::IS_CASE_INSENSITIVE`.
+    thread_local! {
+        static CACHE: RefCell<LruCache<(Vec<u8>, Vec<u8>), Regex>> =
+            RefCell::new(LruCache::with_capacity(REGEXP_CACHE_CAPACITY));
+    }
+
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let key = (pattern.to_vec(), match_type.to_vec());
+        if let Some(regex) = cache.get(&key) {
+            return Ok(Some(regex.clone()));
+        }
+        let regex = build_regexp::<C>(pattern, match_type)?;
+        cache.insert(key, regex.clone());
+        Ok(Some(regex))
+    })
 }
 
 fn init_regexp_data<C: Collator, const N: usize>(expr: &mut Expr) -> Result<Option<Regex>> {
@@ -308,27 +337,32 @@ fn init_replace_instructions(replace_expr: &[u8]) -> Vec<ReplaceInstruction> {
     let mut literal = Vec::new();
     let mut i = 0;
     while i < len {
+        // `\1`..`\9` are capture-group backreferences, per MySQL/TiDB's
+        // documented `REGEXP_REPLACE` syntax. `$1`..`$9` are plain literal
+        // text: a `$` isn't special here, so e.g. a currency amount like
+        // "$9" in the replacement string passes through unchanged.
+        if replace_expr[i] == b'\\' && i + 1 < len && replace_expr[i + 1].is_ascii_digit() {
+            if !literal.is_empty() {
+                instructions.push(ReplaceInstruction::Literal(literal));
+                literal = Vec::new();
+            }
+            instructions.push(ReplaceInstruction::SubstitutionNum(
+                (replace_expr[i + 1] - b'0').into(),
+            ));
+            i += 2;
+            continue;
+        }
         if replace_expr[i] == b'\\' {
             if i + 1 >= len {
                 // This slash is in the end. Ignore it and break the loop.
                 break;
             }
-            if replace_expr[i + 1].is_ascii_digit() {
-                if !literal.is_empty() {
-                    instructions.push(ReplaceInstruction::Literal(literal));
-                    literal = Vec::new();
-                }
-                instructions.push(ReplaceInstruction::SubstitutionNum(
-                    (replace_expr[i + 1] - b'0').into(),
-                ));
-            } else {
-                literal.push(replace_expr[i + 1]);
-            }
+            literal.push(replace_expr[i + 1]);
             i += 2;
-        } else {
-            literal.push(replace_expr[i]);
-            i += 1;
+            continue;
         }
+        literal.push(replace_expr[i]);
+        i += 1;
     }
     if !literal.is_empty() {
         instructions.push(ReplaceInstruction::Literal(literal));
@@ -1361,6 +1395,17 @@ mod tests {
                 Some(r"seazd2 zl2"),
                 false,
             ),
+            // `$1`-style backreferences behave the same as `\1`.
+            (
+                r"seafood fool",
+                r"foo(.?)",
+                r"z$12",
+                Some(3),
+                None,
+                None,
+                Some(r"seazd2 zl2"),
+                false,
+            ),
             (
                 r"seafood fool",
                 r"foo(.?)",
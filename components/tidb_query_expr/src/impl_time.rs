@@ -4,6 +4,7 @@ use tidb_query_codegen::rpn_fn;
 use tidb_query_common::Result;
 use tidb_query_datatype::{
     codec::{
+        convert::ConvertTo,
         data_type::*,
         mysql::{
             duration::{
@@ -243,64 +244,125 @@ pub fn to_seconds(ctx: &mut EvalContext, t: &DateTime) -> Result<Option<Int>> {
     Ok(Some(t.second_number()))
 }
 
-#[rpn_fn(writer, capture = [ctx])]
-#[inline]
-pub fn add_string_and_duration(
+fn add_string_and_duration_imp(
     ctx: &mut EvalContext,
     arg0: BytesRef,
-    arg1: &Duration,
-    writer: BytesWriter,
-) -> Result<BytesGuard> {
+    arg1: Duration,
+) -> Result<Option<Bytes>> {
     let arg0 = std::str::from_utf8(arg0).map_err(Error::Encoding)?;
     if let Ok(arg0) = Duration::parse_exactly(ctx, arg0, MAX_FSP) {
-        return match arg0.checked_add(*arg1) {
-            Some(result) => Ok(writer.write(Some(duration_to_string(result).into_bytes()))),
+        return match arg0.checked_add(arg1) {
+            Some(result) => Ok(Some(duration_to_string(result).into_bytes())),
             None => ctx
                 .handle_overflow_err(Error::overflow("DURATION", format!("{} + {}", arg0, arg1)))
-                .map(|_| Ok(writer.write(None)))?,
+                .map(|_| Ok(None))?,
         };
     };
     if let Ok(arg0) = DateTime::parse_datetime(ctx, arg0, MAX_FSP, true) {
-        return match arg0.checked_add(ctx, *arg1) {
-            Some(result) => Ok(writer.write(Some(datetime_to_string(result).into_bytes()))),
+        return match arg0.checked_add(ctx, arg1) {
+            Some(result) => Ok(Some(datetime_to_string(result).into_bytes())),
             None => ctx
                 .handle_overflow_err(Error::overflow("DATETIME", format!("{} + {}", arg0, arg1)))
-                .map(|_| Ok(writer.write(None)))?,
+                .map(|_| Ok(None))?,
         };
     };
     ctx.handle_invalid_time_error(Error::incorrect_datetime_value(arg0))?;
 
-    Ok(writer.write(None))
+    Ok(None)
 }
 
-#[rpn_fn(writer, capture = [ctx])]
-#[inline]
-pub fn sub_string_and_duration(
+fn sub_string_and_duration_imp(
     ctx: &mut EvalContext,
     arg0: BytesRef,
-    arg1: &Duration,
-    writer: BytesWriter,
-) -> Result<BytesGuard> {
+    arg1: Duration,
+) -> Result<Option<Bytes>> {
     let arg0 = std::str::from_utf8(arg0).map_err(Error::Encoding)?;
     if let Ok(arg0) = Duration::parse_exactly(ctx, arg0, MAX_FSP) {
-        return match arg0.checked_sub(*arg1) {
-            Some(result) => Ok(writer.write(Some(duration_to_string(result).into_bytes()))),
+        return match arg0.checked_sub(arg1) {
+            Some(result) => Ok(Some(duration_to_string(result).into_bytes())),
             None => ctx
                 .handle_overflow_err(Error::overflow("DURATION", format!("{} - {}", arg0, arg1)))
-                .map(|_| Ok(writer.write(None)))?,
+                .map(|_| Ok(None))?,
         };
     };
     if let Ok(arg0) = DateTime::parse_datetime(ctx, arg0, MAX_FSP, true) {
-        return match arg0.checked_sub(ctx, *arg1) {
-            Some(result) => Ok(writer.write(Some(datetime_to_string(result).into_bytes()))),
+        return match arg0.checked_sub(ctx, arg1) {
+            Some(result) => Ok(Some(datetime_to_string(result).into_bytes())),
             None => ctx
                 .handle_overflow_err(Error::overflow("DATETIME", format!("{} - {}", arg0, arg1)))
-                .map(|_| Ok(writer.write(None)))?,
+                .map(|_| Ok(None))?,
         };
     };
     ctx.handle_invalid_time_error(Error::incorrect_datetime_value(arg0))?;
 
-    Ok(writer.write(None))
+    Ok(None)
+}
+
+/// Parses `arg` as a string-encoded duration, for use by the `*AndString`
+/// signatures where the second operand arrives as text (e.g.
+/// `ADDTIME(dt, '10:11:12')`) instead of an already-typed `Duration`.
+fn parse_duration_operand(ctx: &mut EvalContext, arg: BytesRef) -> Result<Option<Duration>> {
+    let arg = std::str::from_utf8(arg).map_err(Error::Encoding)?;
+    match Duration::parse(ctx, arg, MAX_FSP) {
+        Ok(duration) => Ok(Some(duration)),
+        Err(_) => Ok(None),
+    }
+}
+
+#[rpn_fn(writer, capture = [ctx])]
+#[inline]
+pub fn add_string_and_duration(
+    ctx: &mut EvalContext,
+    arg0: BytesRef,
+    arg1: &Duration,
+    writer: BytesWriter,
+) -> Result<BytesGuard> {
+    let result = add_string_and_duration_imp(ctx, arg0, *arg1)?;
+    Ok(writer.write(result))
+}
+
+#[rpn_fn(writer, capture = [ctx])]
+#[inline]
+pub fn add_string_and_string(
+    ctx: &mut EvalContext,
+    arg0: BytesRef,
+    arg1: BytesRef,
+    writer: BytesWriter,
+) -> Result<BytesGuard> {
+    let arg1 = match parse_duration_operand(ctx, arg1)? {
+        Some(arg1) => arg1,
+        None => return Ok(writer.write(None)),
+    };
+    let result = add_string_and_duration_imp(ctx, arg0, arg1)?;
+    Ok(writer.write(result))
+}
+
+#[rpn_fn(writer, capture = [ctx])]
+#[inline]
+pub fn sub_string_and_duration(
+    ctx: &mut EvalContext,
+    arg0: BytesRef,
+    arg1: &Duration,
+    writer: BytesWriter,
+) -> Result<BytesGuard> {
+    let result = sub_string_and_duration_imp(ctx, arg0, *arg1)?;
+    Ok(writer.write(result))
+}
+
+#[rpn_fn(writer, capture = [ctx])]
+#[inline]
+pub fn sub_string_and_string(
+    ctx: &mut EvalContext,
+    arg0: BytesRef,
+    arg1: BytesRef,
+    writer: BytesWriter,
+) -> Result<BytesGuard> {
+    let arg1 = match parse_duration_operand(ctx, arg1)? {
+        Some(arg1) => arg1,
+        None => return Ok(writer.write(None)),
+    };
+    let result = sub_string_and_duration_imp(ctx, arg0, arg1)?;
+    Ok(writer.write(result))
 }
 
 #[rpn_fn]
@@ -412,6 +474,24 @@ pub fn add_time_string_null(_arg0: &DateTime, _arg1: &DateTime) -> Result<Option
     Ok(None)
 }
 
+#[rpn_fn()]
+#[inline]
+pub fn sub_time_datetime_null(_arg0: &DateTime, _arg1: &DateTime) -> Result<Option<DateTime>> {
+    Ok(None)
+}
+
+#[rpn_fn()]
+#[inline]
+pub fn sub_time_duration_null(_arg0: &DateTime, _arg1: &DateTime) -> Result<Option<Duration>> {
+    Ok(None)
+}
+
+#[rpn_fn()]
+#[inline]
+pub fn sub_time_string_null(_arg0: &DateTime, _arg1: &DateTime) -> Result<Option<Bytes>> {
+    Ok(None)
+}
+
 #[rpn_fn(capture = [ctx])]
 #[inline]
 pub fn sub_duration_and_duration(
@@ -518,6 +598,52 @@ pub fn from_days(ctx: &mut EvalContext, arg: &Int) -> Result<Option<DateTime>> {
     Ok(Some(time))
 }
 
+// MySQL's upper bound for `FROM_UNIXTIME`, i.e. `2^31 - 1` seconds past the
+// epoch plus the largest representable fractional part: '3001-01-18 23:59:59.999999' UTC.
+const MAX_FROM_UNIXTIME_TIMESTAMP: f64 = 32536771199.999999;
+
+/// Builds the `DateTime` shared by both `FROM_UNIXTIME` signatures, deriving
+/// `fsp` from the decimal's own fractional digit count (capped at `MAX_FSP`)
+/// so that e.g. `FROM_UNIXTIME(1.5)` keeps one digit of precision, exactly as
+/// MySQL does.
+fn from_unixtime(ctx: &mut EvalContext, ts: &Decimal) -> Result<Option<DateTime>> {
+    let (_, frac_digits) = ts.prec_and_frac();
+    let fsp = frac_digits.min(MAX_FSP as u8) as i8;
+
+    let ts: f64 = ts.convert(ctx)?;
+    if !(0f64..=MAX_FROM_UNIXTIME_TIMESTAMP).contains(&ts) {
+        return Ok(None);
+    }
+    let secs = ts.trunc() as i64;
+    let micros = ((ts - ts.trunc()) * 1_000_000f64).round() as u32;
+
+    Ok(Some(DateTime::from_unixtime(ctx, secs, micros, fsp)?))
+}
+
+#[rpn_fn(capture = [ctx])]
+#[inline]
+pub fn from_unix_time_1_arg(ctx: &mut EvalContext, ts: &Decimal) -> Result<Option<DateTime>> {
+    from_unixtime(ctx, ts)
+}
+
+#[rpn_fn(capture = [ctx])]
+#[inline]
+pub fn from_unix_time_2_arg(
+    ctx: &mut EvalContext,
+    ts: &Decimal,
+    format: BytesRef,
+) -> Result<Option<Bytes>> {
+    let time = match from_unixtime(ctx, ts)? {
+        Some(time) => time,
+        None => return Ok(None),
+    };
+    let format = std::str::from_utf8(format).map_err(Error::Encoding)?;
+    match time.date_format(format) {
+        Ok(formatted) => Ok(Some(formatted.into_bytes())),
+        Err(err) => ctx.handle_invalid_time_error(err).map(|_| Ok(None))?,
+    }
+}
+
 #[rpn_fn(capture = [ctx])]
 #[inline]
 pub fn make_date(ctx: &mut EvalContext, year: &Int, day: &Int) -> Result<Option<DateTime>> {
@@ -1520,6 +1646,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_add_sub_string_and_string() {
+        let cases = vec![
+            (
+                Some("01:00:00.999999"),
+                Some("02:00:00.999998"),
+                Some("03:00:01.999997"),
+            ),
+            (
+                Some("2018-12-31 23:00:00"),
+                Some("1 01:30:30"),
+                Some("2019-01-02 00:30:30"),
+            ),
+            (None, None, None),
+            (None, Some("11:30:45.123456"), None),
+        ];
+
+        for (arg_str, arg_dur_str, sum) in cases {
+            let arg_str = arg_str.map(|str| str.as_bytes().to_vec());
+            let arg_dur_str = arg_dur_str.map(|str| str.as_bytes().to_vec());
+            let sum = sum.map(|str| str.as_bytes().to_vec());
+
+            let add_output = RpnFnScalarEvaluator::new()
+                .push_param(arg_str.clone())
+                .push_param(arg_dur_str.clone())
+                .evaluate(ScalarFuncSig::AddStringAndString)
+                .unwrap();
+            assert_eq!(add_output, sum);
+
+            let sub_output = RpnFnScalarEvaluator::new()
+                .push_param(sum)
+                .push_param(arg_dur_str)
+                .evaluate(ScalarFuncSig::SubStringAndString)
+                .unwrap();
+            assert_eq!(sub_output, arg_str);
+        }
+    }
+
     #[test]
     fn test_date_diff() {
         let cases = vec![
@@ -1866,6 +2030,27 @@ mod tests {
                 .evaluate(ScalarFuncSig::AddTimeStringNull);
             let output = output.unwrap();
             assert_eq!(output, None);
+
+            let output: Result<Option<DateTime>> = RpnFnScalarEvaluator::new()
+                .push_param(arg0)
+                .push_param(arg1)
+                .evaluate(ScalarFuncSig::SubTimeDateTimeNull);
+            let output = output.unwrap();
+            assert_eq!(output, None);
+
+            let output: Result<Option<Duration>> = RpnFnScalarEvaluator::new()
+                .push_param(arg0)
+                .push_param(arg1)
+                .evaluate(ScalarFuncSig::SubTimeDurationNull);
+            let output = output.unwrap();
+            assert_eq!(output, None);
+
+            let output: Result<Option<Bytes>> = RpnFnScalarEvaluator::new()
+                .push_param(arg0)
+                .push_param(arg1)
+                .evaluate(ScalarFuncSig::SubTimeStringNull);
+            let output = output.unwrap();
+            assert_eq!(output, None);
         }
     }
 
@@ -1905,6 +2090,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_from_unix_time() {
+        use std::str::FromStr;
+
+        let mut ctx = EvalContext::default();
+        let cases = vec![
+            ("0", Some("1970-01-01 00:00:00")),
+            ("1.5", Some("1970-01-01 00:00:01.5")),
+            ("1234567890.123456", Some("2009-02-13 23:31:30.123456")),
+            ("-1", None),
+            ("32536771200", None),
+        ];
+        for (ts, exp) in cases {
+            let ts = Decimal::from_str(ts).unwrap();
+            let exp: Option<Time> =
+                exp.map(|exp| Time::parse_datetime(&mut ctx, exp, MAX_FSP, true).unwrap());
+            let output: Option<Time> = RpnFnScalarEvaluator::new()
+                .push_param(ts)
+                .evaluate(ScalarFuncSig::FromUnixTime1Arg)
+                .unwrap();
+            assert_eq!(output, exp);
+        }
+
+        let cases = vec![
+            ("0", "%Y-%m-%d", Some("1970-01-01")),
+            ("1234567890.5", "%Y-%m-%d %H:%i:%s", Some("2009-02-13 23:31:30")),
+            ("-1", "%Y-%m-%d", None),
+        ];
+        for (ts, format, exp) in cases {
+            let ts = Decimal::from_str(ts).unwrap();
+            let exp: Option<Bytes> = exp.map(|exp| exp.as_bytes().to_vec());
+            let output: Option<Bytes> = RpnFnScalarEvaluator::new()
+                .push_param(ts)
+                .push_param(format.as_bytes().to_vec())
+                .evaluate(ScalarFuncSig::FromUnixTime2Arg)
+                .unwrap();
+            assert_eq!(output, exp);
+        }
+    }
+
     #[test]
     fn test_make_date() {
         let null_cases = vec![
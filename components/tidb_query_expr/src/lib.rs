@@ -629,6 +629,7 @@ fn map_expr_node_to_rpn_func(expr: &Expr) -> Result<RpnFnMeta> {
         ScalarFuncSig::JsonMemberOfSig => member_of_fn_meta(),
         ScalarFuncSig::JsonArrayAppendSig => json_array_append_fn_meta(),
         ScalarFuncSig::JsonMergePatchSig => json_merge_patch_fn_meta(),
+        ScalarFuncSig::JsonSearchSig => json_search_fn_meta(),
         // impl_like
         ScalarFuncSig::LikeSig => map_like_sig(ft, children)?,
         // impl_regexp
@@ -764,7 +765,9 @@ fn map_expr_node_to_rpn_func(expr: &Expr) -> Result<RpnFnMeta> {
         ScalarFuncSig::Rpad => rpad_fn_meta(),
         ScalarFuncSig::RpadUtf8 => rpad_utf8_fn_meta(),
         ScalarFuncSig::AddStringAndDuration => add_string_and_duration_fn_meta(),
+        ScalarFuncSig::AddStringAndString => add_string_and_string_fn_meta(),
         ScalarFuncSig::SubStringAndDuration => sub_string_and_duration_fn_meta(),
+        ScalarFuncSig::SubStringAndString => sub_string_and_string_fn_meta(),
         ScalarFuncSig::Trim1Arg => trim_1_arg_fn_meta(),
         ScalarFuncSig::Trim2Args => trim_2_args_fn_meta(),
         ScalarFuncSig::Trim3Args => trim_3_args_fn_meta(),
@@ -830,7 +833,12 @@ fn map_expr_node_to_rpn_func(expr: &Expr) -> Result<RpnFnMeta> {
         ScalarFuncSig::AddTimeStringNull => add_time_string_null_fn_meta(),
         ScalarFuncSig::SubDatetimeAndDuration => sub_datetime_and_duration_fn_meta(),
         ScalarFuncSig::SubDatetimeAndString => sub_datetime_and_string_fn_meta(),
+        ScalarFuncSig::SubTimeDateTimeNull => sub_time_datetime_null_fn_meta(),
+        ScalarFuncSig::SubTimeDurationNull => sub_time_duration_null_fn_meta(),
+        ScalarFuncSig::SubTimeStringNull => sub_time_string_null_fn_meta(),
         ScalarFuncSig::FromDays => from_days_fn_meta(),
+        ScalarFuncSig::FromUnixTime1Arg => from_unix_time_1_arg_fn_meta(),
+        ScalarFuncSig::FromUnixTime2Arg => from_unix_time_2_arg_fn_meta(),
         ScalarFuncSig::Year => year_fn_meta(),
         ScalarFuncSig::Month => month_fn_meta(),
         ScalarFuncSig::MonthName => month_name_fn_meta(),
@@ -7,6 +7,7 @@ pub use tidb_query_datatype::codec::data_type::{
 use tidb_query_datatype::{
     codec::{batch::LazyBatchColumnVec, data_type::*},
     expr::EvalContext,
+    EvalType, FieldTypeAccessor,
 };
 use tipb::FieldType;
 
@@ -303,13 +304,36 @@ impl RpnExpression {
                     let stack_slice_begin = stack.len() - *args_len;
                     let stack_slice = &stack[stack_slice_begin..];
                     let mut call_extra = RpnFnCallExtra { ret_field_type };
-                    let ret = (func_meta.fn_ptr)(
-                        ctx,
-                        output_rows,
-                        stack_slice,
-                        &mut call_extra,
-                        &**metadata,
-                    )?;
+                    // A JSON-typed call is often used unmodified as an ORDER BY / GROUP BY
+                    // key. When every argument is itself a scalar (e.g. the call is made up
+                    // of literals, or of other constant-folded JSON calls), its result is
+                    // identical for every row. Evaluate it once and broadcast the result
+                    // instead of asking the function to recompute the same JSON value
+                    // `output_rows` times.
+                    let all_args_scalar = stack_slice.iter().all(RpnStackNode::is_scalar);
+                    let ret = if output_rows > 1
+                        && all_args_scalar
+                        && matches!(
+                            EvalType::try_from(ret_field_type.as_accessor().tp()),
+                            Ok(EvalType::Json)
+                        ) {
+                        let single = (func_meta.fn_ptr)(
+                            ctx,
+                            1,
+                            stack_slice,
+                            &mut call_extra,
+                            &**metadata,
+                        )?;
+                        VectorValue::from_scalar(&single.get_scalar_ref(0).to_owned(), output_rows)
+                    } else {
+                        (func_meta.fn_ptr)(
+                            ctx,
+                            output_rows,
+                            stack_slice,
+                            &mut call_extra,
+                            &**metadata,
+                        )?
+                    };
                     stack.truncate(stack_slice_begin);
                     stack.push(RpnStackNode::Vector {
                         value: RpnStackNodeVectorValue::Generated {
@@ -535,6 +559,34 @@ mod tests {
         assert_eq!(val.field_type().as_accessor().tp(), FieldTypeTp::Double);
     }
 
+    /// A JSON-typed function whose arguments are all scalar should be
+    /// evaluated once and have its result broadcast down the column, instead
+    /// of being recomputed for every row.
+    #[test]
+    fn test_eval_constant_json_function_is_not_recomputed_per_row() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        #[rpn_fn(nullable)]
+        fn foo(v: Option<&Int>) -> Result<Option<Json>> {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            Ok(v.map(|v| Json::from_i64(*v).unwrap()))
+        }
+
+        let exp = RpnExpressionBuilder::new_for_test()
+            .push_constant_for_test(1i64)
+            .push_fn_call_for_test(foo_fn_meta(), 1, FieldTypeTp::Json)
+            .build_for_test();
+        let mut ctx = EvalContext::default();
+        let mut columns = LazyBatchColumnVec::empty();
+        let result = exp.eval(&mut ctx, &[], &mut columns, &[], 3);
+        let val = result.unwrap();
+        assert!(val.is_vector());
+        assert_eq!(val.vector_value().unwrap().as_ref().len(), 3);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    }
+
     /// Unary function (argument is vector)
     #[test]
     fn test_eval_unary_function_vector() {
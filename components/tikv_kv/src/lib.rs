@@ -481,6 +481,18 @@ pub trait Snapshot: Sync + Send + Clone {
     /// in `opts`
     fn get_cf_opt(&self, opts: ReadOptions, cf: CfName, key: &Key) -> Result<Option<Value>>;
 
+    /// Get the values associated with `keys` in `cf` column family.
+    ///
+    /// Results are returned in the same order as `keys`.
+    ///
+    /// The default implementation is a point lookup per key. Backends that
+    /// can batch the underlying storage access (e.g. a `multi_get`-style API)
+    /// should override this to cut down on per-key overhead for large
+    /// batches of point lookups.
+    fn multi_get_cf(&self, cf: CfName, keys: &[Key]) -> Result<Vec<Option<Value>>> {
+        keys.iter().map(|key| self.get_cf(cf, key)).collect()
+    }
+
     fn iter(&self, cf: CfName, iter_opt: IterOptions) -> Result<Self::Iter>;
 
     // The minimum key this snapshot can retrieve.
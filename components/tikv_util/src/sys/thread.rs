@@ -148,6 +148,27 @@ mod imp {
         }
     }
 
+    /// Pins the calling thread to the given set of CPU ids. A `cpus` of `[]`
+    /// is a no-op, leaving scheduling up to the OS.
+    pub fn set_current_thread_affinity(cpus: &[usize]) -> io::Result<()> {
+        if cpus.is_empty() {
+            return Ok(());
+        }
+        // Unsafe due to FFI.
+        unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            libc::CPU_ZERO(&mut set);
+            for &cpu in cpus {
+                libc::CPU_SET(cpu, &mut set);
+            }
+            let tid = libc::syscall(libc::SYS_gettid) as libc::pid_t;
+            if libc::sched_setaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                return Err(Error::last_os_error());
+            }
+            Ok(())
+        }
+    }
+
     // Sadly the std lib does not have any support for setting `errno`, so we
     // have to implement this ourselves.
     extern "C" {
@@ -185,6 +206,15 @@ mod imp {
                 assert_eq!(get_priority().unwrap(), HIGH_PRI);
             }
         }
+
+        #[test]
+        fn test_set_current_thread_affinity() {
+            // An empty set is a no-op regardless of how many CPUs are actually
+            // available.
+            set_current_thread_affinity(&[]).unwrap();
+            // Every CI/dev machine has at least a CPU 0.
+            set_current_thread_affinity(&[0]).unwrap();
+        }
     }
 }
 
@@ -298,6 +328,13 @@ mod imp {
     pub fn get_priority() -> io::Result<i32> {
         Ok(0)
     }
+
+    // macOS does not expose a portable thread-affinity API comparable to
+    // Linux's `sched_setaffinity`, so this is a no-op: it pins nothing and
+    // returns `Ok(())`.
+    pub fn set_current_thread_affinity(_cpus: &[usize]) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 #[cfg(not(any(target_os = "linux", target_os = "macos")))]
@@ -349,6 +386,10 @@ mod imp {
     pub fn get_priority() -> io::Result<i32> {
         Ok(0)
     }
+
+    pub fn set_current_thread_affinity(_cpus: &[usize]) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 pub use self::imp::*;
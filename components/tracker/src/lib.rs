@@ -119,6 +119,7 @@ pub enum RequestType {
     KvCommit,
     KvPessimisticLock,
     KvCheckTxnStatus,
+    KvCheckTxnStatusAndRollback,
     KvCheckSecondaryLocks,
     KvCleanup,
     KvResolveLock,
@@ -131,6 +132,7 @@ pub enum RequestType {
     CoprocessorChecksum,
     KvFlush,
     KvBufferBatchGet,
+    KvForceUnlockPessimisticLock,
 }
 
 #[derive(Debug, Default, Clone)]
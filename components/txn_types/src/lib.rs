@@ -15,7 +15,8 @@ use thiserror::Error;
 pub use timestamp::{TimeStamp, TsSet, TSO_PHYSICAL_SHIFT_BITS};
 pub use types::{
     insert_old_value_if_resolved, is_short_value, Key, KvPair, LastChange, Mutation, MutationType,
-    OldValue, OldValues, TxnExtra, TxnExtraScheduler, Value, WriteBatchFlags, SHORT_VALUE_MAX_LEN,
+    OldValue, OldValues, RollbackReason, TxnExtra, TxnExtraScheduler, Value, WriteBatchFlags,
+    SHORT_VALUE_MAX_LEN,
 };
 pub use write::{Write, WriteRef, WriteType};
 
@@ -552,6 +552,28 @@ pub fn insert_old_value_if_resolved(
     }
 }
 
+/// Why a key's lock was rolled back. Threaded from the storage layer through
+/// [`TxnExtra`] so that downstream consumers such as CDC can tell a
+/// client-initiated abort apart from one the system decided on its own,
+/// which auditing systems need to distinguish.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollbackReason {
+    /// The client explicitly asked to abort the transaction (e.g. the
+    /// `Rollback` command).
+    ClientInitiated,
+    /// The lock's TTL had expired when another transaction or GC rolled it
+    /// back (e.g. `CheckTxnStatus`, `Cleanup`).
+    LockTtlExpired,
+    /// The lock was released because its transaction lost a deadlock.
+    DeadlockVictim,
+}
+
+impl Default for RollbackReason {
+    fn default() -> Self {
+        RollbackReason::ClientInitiated
+    }
+}
+
 // Extra data fields filled by kvrpcpb::ExtraOp.
 #[derive(Default, Debug, Clone)]
 pub struct TxnExtra {
@@ -561,11 +583,16 @@ pub struct TxnExtra {
     pub one_pc: bool,
     // Marks that this transaction is allowed in the flashback state.
     pub allowed_in_flashback: bool,
+    /// Records why each rolled-back key's lock was released, keyed by the
+    /// encoded key (a `Key`, without a timestamp). Populated by `MvccTxn`
+    /// whenever a rollback
+    /// happens, so CDC can surface the reason on the corresponding event.
+    pub rollback_reasons: HashMap<Key, RollbackReason>,
 }
 
 impl TxnExtra {
     pub fn is_empty(&self) -> bool {
-        self.old_values.is_empty()
+        self.old_values.is_empty() && self.rollback_reasons.is_empty()
     }
 }
 
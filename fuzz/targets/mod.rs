@@ -323,3 +323,29 @@ pub fn fuzz_coprocessor_codec_row_v2_binary_search(data: &[u8]) -> Result<()> {
 
     Ok(())
 }
+
+// Unlike `fuzz_coprocessor_codec_row_v2_binary_search`, this target doesn't
+// gate on the fuzzer having guessed the version byte: it pins byte 0 to
+// `CODEC_VERSION` itself, so every run spends its budget on the
+// flags/counts/ids/offsets/checksum fields instead of bailing out on the
+// version check. That's where `RowSlice::from_bytes` does its real decoding,
+// including the big/small row layout split and the optional checksum tail.
+pub fn fuzz_coprocessor_codec_row_v2_from_bytes(data: &[u8]) -> Result<()> {
+    use tidb_query_datatype::codec::row::v2::{RowSlice, CODEC_VERSION};
+
+    if data.is_empty() {
+        return Ok(());
+    }
+    let mut data = data.to_vec();
+    data[0] = CODEC_VERSION;
+
+    if let Ok(row_slice) = RowSlice::from_bytes(&data) {
+        let mut cursor = Cursor::new(&data[1..]);
+        while let Ok(id) = cursor.read_as_i64() {
+            let _ = row_slice.search_in_non_null_ids(id);
+            let _ = row_slice.search_in_null_ids(id);
+        }
+    }
+
+    Ok(())
+}
@@ -37,10 +37,12 @@ use engine_rocks::{
         FixedPrefixSliceTransform, FixedSuffixSliceTransform, NoopSliceTransform,
         RangeCompactionFilterFactory, StackingCompactionFilterFactory,
     },
-    RaftDbLogger, RangePropertiesCollectorFactory, RawMvccPropertiesCollectorFactory,
-    RocksCfOptions, RocksDbOptions, RocksEngine, RocksEventListener, RocksStatistics,
-    RocksTitanDbOptions, RocksdbLogger, TtlPropertiesCollectorFactory,
-    DEFAULT_PROP_KEYS_INDEX_DISTANCE, DEFAULT_PROP_SIZE_INDEX_DISTANCE,
+    CreationReasonPropertiesCollectorFactory, RaftDbLogger, RangePropertiesCollectorFactory,
+    RangeTombstonePropertiesCollectorFactory, RawMvccPropertiesCollectorFactory, RocksCfOptions,
+    RocksDbOptions, RocksEngine, SstCreationReason,
+    RocksEventListener, RocksStatistics, RocksTitanDbOptions, RocksdbLogger,
+    TtlPropertiesCollectorFactory, DEFAULT_PROP_KEYS_INDEX_DISTANCE,
+    DEFAULT_PROP_SIZE_INDEX_DISTANCE,
 };
 use engine_traits::{
     CfOptions as _, DbOptions as _, MiscExt, TitanCfOptions as _, CF_DEFAULT, CF_LOCK, CF_RAFT,
@@ -807,6 +809,14 @@ impl DefaultCfConfig {
             prop_keys_index_distance: self.prop_keys_index_distance,
         };
         cf_opts.add_table_properties_collector_factory("tikv.range-properties-collector", f);
+        cf_opts.add_table_properties_collector_factory(
+            "tikv.range-tombstone-properties-collector",
+            RangeTombstonePropertiesCollectorFactory::default(),
+        );
+        cf_opts.add_table_properties_collector_factory(
+            "tikv.creation-reason-collector",
+            CreationReasonPropertiesCollectorFactory::new(SstCreationReason::Write),
+        );
         if let Some(factory) = filter_factory {
             match api_version {
                 ApiVersion::V1 => {
@@ -989,6 +999,14 @@ impl WriteCfConfig {
             prop_keys_index_distance: self.prop_keys_index_distance,
         };
         cf_opts.add_table_properties_collector_factory("tikv.range-properties-collector", f);
+        cf_opts.add_table_properties_collector_factory(
+            "tikv.range-tombstone-properties-collector",
+            RangeTombstonePropertiesCollectorFactory::default(),
+        );
+        cf_opts.add_table_properties_collector_factory(
+            "tikv.creation-reason-collector",
+            CreationReasonPropertiesCollectorFactory::new(SstCreationReason::Write),
+        );
         if let Some(factory) = filter_factory {
             let factory =
                 StackingCompactionFilterFactory::new(factory.clone(), WriteCompactionFilterFactory);
@@ -1098,6 +1116,14 @@ impl LockCfConfig {
             prop_keys_index_distance: self.prop_keys_index_distance,
         };
         cf_opts.add_table_properties_collector_factory("tikv.range-properties-collector", f);
+        cf_opts.add_table_properties_collector_factory(
+            "tikv.range-tombstone-properties-collector",
+            RangeTombstonePropertiesCollectorFactory::default(),
+        );
+        cf_opts.add_table_properties_collector_factory(
+            "tikv.creation-reason-collector",
+            CreationReasonPropertiesCollectorFactory::new(SstCreationReason::Write),
+        );
         cf_opts.set_memtable_prefix_bloom_size_ratio(bloom_filter_ratio(for_engine));
         if let Some(factory) = filter_factory {
             cf_opts
@@ -2907,6 +2933,25 @@ impl Default for BackupConfig {
     }
 }
 
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug, OnlineConfig)]
+#[serde(default)]
+#[serde(rename_all = "kebab-case")]
+pub struct KafkaSinkConfig {
+    /// Names of the log backup tasks that should publish their flushed,
+    /// region-partitioned data to `brokers`/`topic` instead of (in addition
+    /// to meta and checkpoint writes, which are unaffected) the task's
+    /// configured external storage.
+    ///
+    /// There is no real Kafka client wired up yet, only a logging stub (see
+    /// `backup_stream::sink::LoggingKafkaProducer`), so actually publishing
+    /// to Kafka requires building with the off-by-default
+    /// `kafka-sink-experimental` feature; without it, listing a task here
+    /// is accepted but has no effect beyond a startup warning.
+    pub tasks: Vec<String>,
+    pub brokers: Vec<String>,
+    pub topic: String,
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Debug, OnlineConfig)]
 #[serde(default)]
 #[serde(rename_all = "kebab-case")]
@@ -2924,6 +2969,13 @@ pub struct BackupStreamConfig {
 
     pub file_size_limit: ReadableSize,
 
+    /// The quota of local disk space that may be used for temporary files
+    /// before they are flushed to the external storage. `0` means no limit.
+    ///
+    /// Once this quota is reached, new events for observed ranges will be
+    /// rejected instead of being written to an already-full disk.
+    pub temp_file_disk_quota: ReadableSize,
+
     #[doc(hidden)]
     #[serde(skip_serializing)]
     #[online_config(skip)]
@@ -2935,6 +2987,9 @@ pub struct BackupStreamConfig {
     #[online_config(skip)]
     pub initial_scan_rate_limit: ReadableSize,
     pub initial_scan_concurrency: usize,
+
+    #[online_config(submodule)]
+    pub kafka_sink: KafkaSinkConfig,
 }
 
 impl BackupStreamConfig {
@@ -2968,6 +3023,30 @@ impl BackupStreamConfig {
         if self.initial_scan_rate_limit.0 < 1024 {
             return Err("the `initial_scan_rate_limit` should be at least 1024 bytes".into());
         }
+        if !self.kafka_sink.tasks.is_empty()
+            && (self.kafka_sink.brokers.is_empty() || self.kafka_sink.topic.is_empty())
+        {
+            return Err(
+                "log-backup.kafka-sink.brokers and log-backup.kafka-sink.topic must be set \
+                 when log-backup.kafka-sink.tasks is non-empty"
+                    .into(),
+            );
+        }
+        #[cfg(not(feature = "kafka-sink-experimental"))]
+        if !self.kafka_sink.tasks.is_empty() {
+            // No real Kafka client is wired up yet (see
+            // `backup_stream::sink::LoggingKafkaProducer`): this build's
+            // `Sink` for a task only logs and drops what it's asked to
+            // publish, so `backup_stream::router::RouterInner` refuses to
+            // construct one unless compiled with the `kafka-sink-experimental`
+            // feature. Warn here too, since otherwise this reads as a
+            // production-ready knob that's silently a no-op.
+            warn!(
+                "log-backup.kafka-sink.tasks is set, but this build was not compiled with the \
+                 `kafka-sink-experimental` feature, so those tasks' flushed data files will \
+                 continue to go to external storage only, not Kafka"
+            );
+        }
         Ok(())
     }
 }
@@ -2993,10 +3072,15 @@ impl Default for BackupStreamConfig {
             // TODO: may be use raft store directory
             temp_path: String::new(),
             file_size_limit,
+            // Unlimited by default, to keep the behavior of existing deployments
+            // unchanged; operators should set this based on the size of the disk
+            // backing `temp_path`.
+            temp_file_disk_quota: ReadableSize(0),
             initial_scan_pending_memory_quota: ReadableSize(quota_size as _),
             initial_scan_rate_limit: ReadableSize::mb(60),
             initial_scan_concurrency: 6,
             temp_file_memory_quota: cache_size,
+            kafka_sink: KafkaSinkConfig::default(),
         }
     }
 }
@@ -3007,15 +3091,39 @@ impl Default for BackupStreamConfig {
 pub struct CdcConfig {
     pub min_ts_interval: ReadableDuration,
     pub hibernate_regions_compatible: bool,
-    // TODO(hi-rustin): Consider resizing the thread pool based on `incremental_scan_threads`.
-    #[online_config(skip)]
+    // `Endpoint::on_change_cfg` rebuilds the incremental-scan worker pool with the new
+    // thread count when this changes; already-running scans keep running on the old pool
+    // until they finish, see its handling of this field.
     pub incremental_scan_threads: usize,
+    /// CPU ids the incremental scan worker pool's threads are confined to,
+    /// e.g. the CPUs of a single NUMA node, to avoid the pool's threads
+    /// bouncing across sockets. Empty (the default) leaves scheduling to the
+    /// OS. Only takes effect on Linux.
+    #[online_config(skip)]
+    pub incremental_scan_worker_cpus: Vec<usize>,
     // The number of scan tasks that is allowed to run concurrently.
     pub incremental_scan_concurrency: usize,
     // The number of scan tasks that is allowed to be created. In other words,
     // there will be at most `incremental_scan_concurrency_limit - incremental_scan_concurrency`
     // number of scan tasks that is waitting to run.
     pub incremental_scan_concurrency_limit: usize,
+    /// Caps how many pending incremental scan tasks a single connection may
+    /// have registered, so one connection piling up scans gets its own
+    /// registrations rejected instead of `incremental_scan_concurrency_limit`
+    /// tripping for every connection once the store-wide count is exhausted.
+    /// 0 disables the per-connection cap; only the store-wide limit applies.
+    pub incremental_scan_concurrency_limit_per_conn: usize,
+    /// Caps how many regions this store's CDC endpoint will capture at
+    /// once, to protect small stores from an unbounded subscription
+    /// backlog. New registrations for a region not already captured are
+    /// rejected once the cap is hit -- existing captured regions keep
+    /// being observed, and registering an additional downstream on an
+    /// already-captured region is unaffected. The cap and current usage
+    /// are exported (`tikv_cdc_captured_region_limit`/
+    /// `tikv_cdc_captured_region_count`) so TiCDC can steer new
+    /// subscriptions toward a replica on a less-loaded store. 0 (the
+    /// default) disables the cap.
+    pub max_capture_regions: usize,
     /// Limit scan speed based on disk I/O traffic.
     pub incremental_scan_speed_limit: ReadableSize,
     /// Limit scan speed based on memory accesing traffic.
@@ -3031,14 +3139,83 @@ pub struct CdcConfig {
     /// Set `incremental_scan_ts_filter_ratio` to 0 will disable it.
     pub incremental_scan_ts_filter_ratio: f64,
 
+    /// Whether the incremental scan is allowed to fill the block cache.
+    ///
+    /// Incremental scans read a region's whole key range once and are
+    /// usually not repeated, so by default they skip the block cache to
+    /// avoid evicting hot data that other readers rely on. Enable this if
+    /// the scanned range is expected to be read again shortly afterwards
+    /// (e.g. small regions, or downstreams that resubscribe often) and
+    /// warming the cache is worth the eviction cost.
+    pub incremental_scan_fill_cache: bool,
+
     /// Count of threads to confirm Region leadership in TiKV instances, 1 by
     /// default. Please consider to increase it if count of regions on one
     /// TiKV instance is greater than 20k.
     #[online_config(skip)]
     pub tso_worker_threads: usize,
+    /// CPU ids the tso worker pool's threads are confined to. Empty (the
+    /// default) leaves scheduling to the OS. Only takes effect on Linux.
+    #[online_config(skip)]
+    pub tso_worker_cpus: Vec<usize>,
 
     pub sink_memory_quota: ReadableSize,
+    /// Caps how much of `sink_memory_quota` a single CDC connection may use,
+    /// so one busy changefeed can't starve the others sharing the store-wide
+    /// quota. New connections pick up config changes; existing ones keep
+    /// their quota until reconnected.
+    #[online_config(skip)]
+    pub conn_memory_quota: ReadableSize,
     pub old_value_cache_memory_quota: ReadableSize,
+    /// Rows whose encoded key and value together exceed this size are
+    /// truncated before being sent to downstreams during the incremental
+    /// scan phase, so that a single oversized row can't break a client
+    /// whose gRPC channel has a smaller max receive message size than
+    /// this store's. Delivering such rows losslessly instead of truncating
+    /// them would require downstream-negotiated chunked encoding, which
+    /// isn't implemented yet.
+    pub max_row_size: ReadableSize,
+
+    /// How long the sink is willing to wait, after the first pending event
+    /// of a batch arrives, for more events to coalesce with it into a
+    /// single gRPC message. 0 (the default) disables the wait: events are
+    /// still opportunistically batched with whatever else is already
+    /// queued, but no latency is added to wait for more. New connections
+    /// pick up config changes; existing ones keep their prior behavior
+    /// until reconnected.
+    #[online_config(skip)]
+    pub sink_batch_wait_duration: ReadableDuration,
+
+    /// Ceiling on how many bytes can be sent to a single CDC connection
+    /// without its downstream acking them as consumed (see `Task::Ack` in
+    /// `cdc::endpoint`). Once a connection's unacked window crosses this,
+    /// its downstreams are paused until enough acks bring the window back
+    /// under the limit. This catches a downstream that is alive and
+    /// draining its gRPC channel, but processing events slower than TiKV
+    /// produces them -- something `sink_memory_quota` alone can't see,
+    /// since that only tracks events still buffered locally. 0 disables
+    /// this check.
+    pub unacked_bytes_limit: ReadableSize,
+
+    /// How long a region may keep blocking the store-wide resolved ts --
+    /// i.e. stay the slowest region any downstream is waiting on -- before
+    /// `Endpoint::on_min_ts` quarantines it: the region stops participating
+    /// in resolved-ts advancement (so every other, healthy region keeps
+    /// advancing normally) and its downstreams are sent a dedicated notice.
+    /// A quarantined region keeps being observed and can be brought back
+    /// with `Task::ReleaseQuarantine`. 0 (the default) disables quarantine;
+    /// regions are only ever allowed to lag.
+    pub resolved_ts_quarantine_timeout: ReadableDuration,
+
+    /// How long `Endpoint::on_register` remembers a `(request_id, region)`
+    /// that it just rejected. A re-registration of the same pair within
+    /// this window is rejected immediately, with a backoff hint in the
+    /// error event telling the downstream how much longer to wait, instead
+    /// of repeating the full rejection path (region lookup, scan-slot
+    /// bookkeeping, etc.) for a downstream that is retrying faster than
+    /// whatever made it fail the first time can resolve. 0 (the default)
+    /// disables this: every registration is handled in full, as before.
+    pub register_backoff_interval: ReadableDuration,
 
     // Deprecated! preserved for compatibility check.
     #[online_config(hidden)]
@@ -3056,20 +3233,42 @@ impl Default for CdcConfig {
             hibernate_regions_compatible: true,
             // 4 threads for incremental scan.
             incremental_scan_threads: 4,
+            // No affinity by default.
+            incremental_scan_worker_cpus: vec![],
             // At most 6 concurrent running tasks.
             incremental_scan_concurrency: 6,
             // At most 10000 tasks can exist simultaneously.
             incremental_scan_concurrency_limit: 10000,
+            // Disabled: only the store-wide limit applies by default.
+            incremental_scan_concurrency_limit_per_conn: 0,
+            // Disabled: no cap on the number of captured regions by default.
+            max_capture_regions: 0,
             // TiCDC requires a SSD, the typical write speed of SSD
             // is more than 500MB/s, so 128MB/s is enough.
             incremental_scan_speed_limit: ReadableSize::mb(128),
             incremental_fetch_speed_limit: ReadableSize::mb(512),
             incremental_scan_ts_filter_ratio: 0.2,
+            // Keep incremental scans out of the block cache by default.
+            incremental_scan_fill_cache: false,
             tso_worker_threads: 1,
+            // No affinity by default.
+            tso_worker_cpus: vec![],
             // 512MB memory for CDC sink.
             sink_memory_quota: ReadableSize::mb(512),
+            // 64MB memory for a single CDC connection.
+            conn_memory_quota: ReadableSize::mb(64),
             // 512MB memory for old value cache.
             old_value_cache_memory_quota: ReadableSize::mb(512),
+            // 6MB, matching the response size threshold CDC already batches events by.
+            max_row_size: ReadableSize::mb(6),
+            // Disabled: don't add latency to wait for a bigger batch.
+            sink_batch_wait_duration: ReadableDuration::ZERO,
+            // 128MB unacked per connection before its downstreams are paused.
+            unacked_bytes_limit: ReadableSize::mb(128),
+            // Disabled: a slow region is only ever allowed to lag, never quarantined.
+            resolved_ts_quarantine_timeout: ReadableDuration::ZERO,
+            // Disabled: every registration attempt is handled in full by default.
+            register_backoff_interval: ReadableDuration::ZERO,
             // Deprecated! preserved for compatibility check.
             old_value_cache_size: 0,
         }
@@ -3119,6 +3318,21 @@ impl CdcConfig {
             );
             self.incremental_scan_ts_filter_ratio = default_cfg.incremental_scan_ts_filter_ratio;
         }
+        if self.conn_memory_quota.0 == 0 || self.conn_memory_quota > self.sink_memory_quota {
+            warn!(
+                "cdc.conn-memory-quota should be larger than 0 and no larger than
+                cdc.sink-memory-quota, change it to {}",
+                default_cfg.conn_memory_quota
+            );
+            self.conn_memory_quota = default_cfg.conn_memory_quota;
+        }
+        if self.max_row_size.0 == 0 {
+            warn!(
+                "cdc.max-row-size can't be 0, change it to {}",
+                default_cfg.max_row_size
+            );
+            self.max_row_size = default_cfg.max_row_size;
+        }
         if raftstore_v2 && self.hibernate_regions_compatible {
             warn!(
                 "cdc.hibernate_regions_compatible is overwritten to false for partitioned-raft-kv"
@@ -3126,6 +3340,23 @@ impl CdcConfig {
             self.hibernate_regions_compatible = false;
         }
 
+        let cpu_count = num_cpus::get();
+        for (name, cpus) in [
+            (
+                "cdc.incremental-scan-worker-cpus",
+                &self.incremental_scan_worker_cpus,
+            ),
+            ("cdc.tso-worker-cpus", &self.tso_worker_cpus),
+        ] {
+            if let Some(&cpu) = cpus.iter().find(|&&cpu| cpu >= cpu_count) {
+                return Err(format!(
+                    "{} contains cpu id {}, but this machine only has {} cpus",
+                    name, cpu, cpu_count
+                )
+                .into());
+            }
+        }
+
         Ok(())
     }
 }
@@ -5408,6 +5639,15 @@ mod tests {
         backup_stream_cfg.validate().unwrap();
         backup_stream_cfg.initial_scan_rate_limit.0 = 2048;
         backup_stream_cfg.validate().unwrap();
+        // kafka_sink.tasks requires brokers/topic to also be set, regardless
+        // of whether this build was compiled with `kafka-sink-experimental`.
+        backup_stream_cfg.kafka_sink.tasks = vec!["my-task".to_owned()];
+        backup_stream_cfg.validate().unwrap_err();
+        backup_stream_cfg.kafka_sink.brokers = vec!["localhost:9092".to_owned()];
+        backup_stream_cfg.kafka_sink.topic = "log-backup".to_owned();
+        backup_stream_cfg.validate().unwrap();
+        backup_stream_cfg.kafka_sink.tasks.clear();
+        backup_stream_cfg.validate().unwrap();
     }
 
     #[test]
@@ -7268,6 +7508,14 @@ mod tests {
         let mut cfg: TikvConfig = toml::from_str(content).unwrap();
         cfg.validate().unwrap();
         assert!(!cfg.cdc.hibernate_regions_compatible);
+
+        let content = r#"
+            [cdc]
+            max-row-size = "0B"
+        "#;
+        let mut cfg: TikvConfig = toml::from_str(content).unwrap();
+        cfg.validate().unwrap();
+        assert_eq!(cfg.cdc.max_row_size, CdcConfig::default().max_row_size);
     }
 
     #[test]
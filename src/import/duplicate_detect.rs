@@ -292,6 +292,7 @@ mod tests {
         let cmd = commands::Rollback::new(
             data.into_iter().map(|key| Key::from_raw(&key)).collect(),
             start_ts.into(),
+            None,
             Context::default(),
         );
         let (tx, rx) = channel();
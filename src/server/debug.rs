@@ -18,8 +18,9 @@ use engine_rocks::{
 };
 use engine_traits::{
     Engines, Error as EngineTraitError, IterOptions, Iterable, Iterator as EngineIterator, MiscExt,
-    Mutable, MvccProperties, Peekable, RaftEngine, RaftLogBatch, Range, RangePropertiesExt,
-    SyncMutable, WriteBatch, WriteBatchExt, WriteOptions, CF_DEFAULT, CF_LOCK, CF_RAFT, CF_WRITE,
+    Mutable, MvccProperties, MvccPropertiesExt, Peekable, RaftEngine, RaftLogBatch, Range,
+    RangePropertiesExt, SyncMutable, WriteBatch, WriteBatchExt, WriteOptions, CF_DEFAULT, CF_LOCK,
+    CF_RAFT, CF_WRITE,
 };
 use futures::future::Future;
 use kvproto::{
@@ -198,6 +199,11 @@ pub trait Debugger {
     fn set_raft_statistics(&mut self, s: Option<Arc<RocksStatistics>>);
 
     fn get_range_properties(&self, start: &[u8], end: &[u8]) -> Result<Vec<(String, String)>>;
+
+    /// Ranks every region in this store by how much reclaimable garbage
+    /// (old MVCC versions) it's estimated to hold as of `safe_point`, most
+    /// garbage first. See [`crate::server::gc_worker::garbage_ratio`].
+    fn get_region_garbage_ranking(&self, safe_point: u64) -> Result<Vec<(u64, f64)>>;
 }
 
 #[derive(Clone)]
@@ -1067,6 +1073,26 @@ where
         props.append(&mut props1);
         Ok(props)
     }
+
+    fn get_region_garbage_ranking(&self, safe_point: u64) -> Result<Vec<(u64, f64)>> {
+        let safe_point = TimeStamp::from(safe_point);
+        let mut ranking = Vec::new();
+        for region_id in self.get_all_regions_in_store()? {
+            let region_state = self.get_region_state(region_id)?;
+            let region = region_state.get_region();
+            let start = keys::enc_start_key(region);
+            let end = keys::enc_end_key(region);
+            let ratio = self
+                .engines
+                .kv
+                .get_mvcc_properties_cf(CF_WRITE, safe_point, &start, &end)
+                .map(|props| crate::server::gc_worker::garbage_ratio(&props))
+                .unwrap_or(0.0);
+            ranking.push((region_id, ratio));
+        }
+        ranking.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(ranking)
+    }
 }
 
 async fn async_key_range_flashback_to_version<E: Engine, L: LockManager, F: KvFormat>(
@@ -1233,6 +1259,27 @@ pub fn dump_write_cf_properties(
     res.push(("writecf.num_files".to_owned(), num_files.to_string()));
     res.push(("writecf.sst_files".to_owned(), sst_files));
 
+    // Per-level breakdown, to help diagnose why stale data isn't being
+    // compacted away (e.g. it is stuck in the bottom levels due to range
+    // deletions).
+    let props_by_level = db
+        .get_mvcc_properties_cf_by_level(CF_WRITE, TimeStamp::max(), start, end)
+        .unwrap_or_default();
+    for (level, props) in props_by_level.iter().enumerate() {
+        res.push((
+            format!("mvcc.per_level.{}.num_rows", level),
+            props.num_rows.to_string(),
+        ));
+        res.push((
+            format!("mvcc.per_level.{}.num_versions", level),
+            props.num_versions.to_string(),
+        ));
+        res.push((
+            format!("mvcc.per_level.{}.num_deletes", level),
+            props.num_deletes.to_string(),
+        ));
+    }
+
     Ok(res)
 }
 
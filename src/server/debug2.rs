@@ -896,6 +896,36 @@ impl<ER: RaftEngine> Debugger for DebuggerImplV2<ER> {
         Ok(res)
     }
 
+    fn get_region_garbage_ranking(&self, safe_point: u64) -> Result<Vec<(u64, f64)>> {
+        let safe_point = txn_types::TimeStamp::from(safe_point);
+        let mut ranking = Vec::new();
+        for region_id in self.get_all_regions_in_store()? {
+            let region_state = match self.raft_engine.get_region_state(region_id, u64::MAX) {
+                Ok(Some(region_state)) => region_state,
+                _ => continue,
+            };
+            let region = region_state.get_region().clone();
+            let start = keys::enc_start_key(&region);
+            let end = keys::enc_end_key(&region);
+            let mut tablet_cache =
+                match get_tablet_cache(&self.tablet_reg, region.id, Some(region_state)) {
+                    Ok(cache) => cache,
+                    Err(_) => continue,
+                };
+            let Some(tablet) = tablet_cache.latest() else {
+                continue;
+            };
+            let ratio = engine_traits::MvccPropertiesExt::get_mvcc_properties_cf(
+                tablet, CF_WRITE, safe_point, &start, &end,
+            )
+            .map(|props| crate::server::gc_worker::garbage_ratio(&props))
+            .unwrap_or(0.0);
+            ranking.push((region_id, ratio));
+        }
+        ranking.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(ranking)
+    }
+
     fn reset_to_version(&self, _version: u64) {
         unimplemented!()
     }
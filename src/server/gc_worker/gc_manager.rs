@@ -695,6 +695,7 @@ mod tests {
             GcTask::GcKeys { .. } => unreachable!(),
             GcTask::RawGcKeys { .. } => unreachable!(),
             GcTask::OrphanVersions { .. } => unreachable!(),
+            GcTask::RankRangesByGarbage { .. } => unreachable!(),
             GcTask::Validate(_) => unreachable!(),
         };
         mem::replace(callback, Box::new(|_| {}))
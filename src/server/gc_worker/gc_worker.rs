@@ -17,8 +17,8 @@ use collections::HashMap;
 use concurrency_manager::ConcurrencyManager;
 use engine_rocks::{FlowInfo, RocksEngine};
 use engine_traits::{
-    raw_ttl::ttl_current_ts, DeleteStrategy, Error as EngineError, KvEngine, MiscExt, Range,
-    WriteBatch, WriteOptions, CF_DEFAULT, CF_LOCK, CF_WRITE,
+    raw_ttl::ttl_current_ts, CompactExt, DeleteStrategy, Error as EngineError, KvEngine, MiscExt,
+    Range, WriteBatch, WriteOptions, CF_DEFAULT, CF_LOCK, CF_WRITE,
 };
 use file_system::{IoType, WithIoType};
 use futures::executor::block_on;
@@ -37,7 +37,7 @@ use txn_types::{Key, TimeStamp};
 use yatp::{task::future::TaskCell, Remote};
 
 use super::{
-    check_need_gc,
+    check_need_gc, garbage_ratio,
     compaction_filter::{
         CompactionFilterInitializer, DeleteBatch, GC_COMPACTION_FILTER_MVCC_DELETION_HANDLED,
         GC_COMPACTION_FILTER_MVCC_DELETION_WASTED, GC_COMPACTION_FILTER_ORPHAN_VERSIONS,
@@ -108,6 +108,30 @@ where
         callback: Callback<()>,
         region_info_provider: Arc<dyn RegionInfoProvider>,
     },
+    /// Forces recomputation of table properties over `[start_key, end_key)`
+    /// by running a targeted, bottommost compaction there. Table
+    /// properties (e.g. MVCC counts) are only refreshed when the SST files
+    /// backing a range are rewritten, so after a large batch of deletes
+    /// they can stay stale — and any property-derived decision (GC,
+    /// split-check, ...) stays stale with them — until compaction
+    /// naturally revisits the range. This lets callers force that
+    /// recomputation on demand instead of waiting on it.
+    RecomputeRangeProperties {
+        start_key: Key,
+        end_key: Key,
+        callback: Callback<()>,
+    },
+    /// Ranks `ranges` by how much reclaimable garbage each holds (see
+    /// [`garbage_ratio`]) and, for the `recompute_top` worst ones, forces a
+    /// targeted compaction via [`GcTask::RecomputeRangeProperties`] so they
+    /// get to invoke the GC compaction filter ahead of everything else,
+    /// instead of waiting on `GcManager`'s uniform, fixed-order scan.
+    RankRangesByGarbage {
+        ranges: Vec<(Key, Key)>,
+        safe_point: TimeStamp,
+        recompute_top: usize,
+        callback: Callback<Vec<(Key, Key, f64)>>,
+    },
     /// If GC in compaction filter is enabled, versions on default CF will be
     /// handled with `DB::delete` in write CF's compaction filter. However if
     /// the compaction filter finds the DB is stalled, it will send the task
@@ -139,6 +163,8 @@ where
             GcTask::RawGcKeys { .. } => GcCommandKind::raw_gc_keys,
             GcTask::UnsafeDestroyRange { .. } => GcCommandKind::unsafe_destroy_range,
             GcTask::OrphanVersions { .. } => GcCommandKind::orphan_versions,
+            GcTask::RecomputeRangeProperties { .. } => GcCommandKind::recompute_range_properties,
+            GcTask::RankRangesByGarbage { .. } => GcCommandKind::rank_ranges_by_garbage,
             #[cfg(any(test, feature = "testexport"))]
             GcTask::Validate(_) => GcCommandKind::validate_config,
         }
@@ -172,6 +198,17 @@ where
                 .field("id", id)
                 .field("count", &wb.count())
                 .finish(),
+            GcTask::RecomputeRangeProperties {
+                start_key, end_key, ..
+            } => f
+                .debug_struct("RecomputeRangeProperties")
+                .field("start_key", &format!("{}", start_key))
+                .field("end_key", &format!("{}", end_key))
+                .finish(),
+            GcTask::RankRangesByGarbage { ranges, .. } => f
+                .debug_struct("RankRangesByGarbage")
+                .field("ranges", &ranges.len())
+                .finish(),
             #[cfg(any(test, feature = "testexport"))]
             GcTask::Validate(_) => write!(f, "Validate gc worker config"),
         }
@@ -858,6 +895,77 @@ impl<E: Engine> GcRunnerCore<E> {
         Ok(())
     }
 
+    /// Forces recomputation of table properties over `[start_key, end_key)`
+    /// by running a targeted, bottommost compaction there. See
+    /// [`GcTask::RecomputeRangeProperties`].
+    fn recompute_range_properties(&self, start_key: &Key, end_key: &Key) -> Result<()> {
+        let local_storage = match self.engine.kv_engine() {
+            Some(local_storage) => local_storage,
+            // The multi-rocksdb (tablet-per-region) version has no single engine to compact;
+            // there's no equivalent of this maintenance op there yet.
+            None => return Ok(()),
+        };
+
+        let start_data_key = keys::data_key(start_key.as_encoded());
+        let end_data_key = keys::data_end_key(end_key.as_encoded());
+        for cf in &[CF_LOCK, CF_DEFAULT, CF_WRITE] {
+            local_storage
+                .recompute_properties_in_range(cf, Some(&start_data_key), Some(&end_data_key))
+                .map_err(|e| {
+                    let e: Error = box_err!(e);
+                    warn!("recompute range properties failed"; "cf" => cf, "err" => ?e);
+                    e
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Ranks `ranges` by [`garbage_ratio`], most garbage first, and forces a
+    /// targeted compaction (see [`Self::recompute_range_properties`]) on the
+    /// `recompute_top` worst ones. Ranges the engine can't report properties
+    /// for (e.g. no data yet) rank last, at a ratio of 0 and are never
+    /// selected for recompaction.
+    ///
+    /// This is the GC worker's priority feed into the compaction filter:
+    /// RocksDB's own background compaction picker decides when compactions
+    /// actually run and isn't directly controllable from here, but forcing
+    /// one on the worst ranges makes sure those are the next ones to invoke
+    /// the filter, rather than waiting for `GcManager`'s uniform scan to
+    /// reach them in lexicographical order.
+    fn rank_ranges_by_garbage(
+        &self,
+        ranges: Vec<(Key, Key)>,
+        safe_point: TimeStamp,
+        recompute_top: usize,
+    ) -> Result<Vec<(Key, Key, f64)>> {
+        let mut ranked: Vec<(Key, Key, f64)> = ranges
+            .into_iter()
+            .map(|(start, end)| {
+                let ratio = self
+                    .engine
+                    .get_mvcc_properties_cf(
+                        CF_WRITE,
+                        safe_point,
+                        start.as_encoded(),
+                        end.as_encoded(),
+                    )
+                    .map(|props| garbage_ratio(&props))
+                    .unwrap_or(0.0);
+                (start, end, ratio)
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+
+        for (start, end, ratio) in ranked.iter().take(recompute_top) {
+            if *ratio <= 0.0 {
+                break;
+            }
+            self.recompute_range_properties(start, end)?;
+        }
+
+        Ok(ranked)
+    }
+
     fn update_statistics_metrics(&mut self, key_mode: GcKeyMode) {
         if let Some(mut_stats) = self.stats_map.get_mut(&key_mode) {
             let stats = mem::take(mut_stats);
@@ -1038,6 +1146,31 @@ impl<E: Engine> GcRunnerCore<E> {
                     end_key
                 );
             }
+            GcTask::RecomputeRangeProperties {
+                start_key,
+                end_key,
+                callback,
+            } => {
+                let res = self.recompute_range_properties(&start_key, &end_key);
+                update_metrics(res.is_err());
+                callback(res);
+                slow_log!(
+                    T timer,
+                    "RecomputeRangeProperties start_key {:?}, end_key {:?}",
+                    start_key,
+                    end_key
+                );
+            }
+            GcTask::RankRangesByGarbage {
+                ranges,
+                safe_point,
+                recompute_top,
+                callback,
+            } => {
+                let res = self.rank_ranges_by_garbage(ranges, safe_point, recompute_top);
+                update_metrics(res.is_err());
+                callback(res);
+            }
             GcTask::OrphanVersions {
                 wb,
                 id,
@@ -1108,7 +1241,12 @@ fn handle_gc_task_schedule_error(e: ScheduleError<GcTask<impl KvEngine>>) -> Res
     error!("failed to schedule gc task"; "err" => %e);
     let res = Err(box_err!("failed to schedule gc task: {:?}", e));
     match e.into_inner() {
-        GcTask::Gc { callback, .. } | GcTask::UnsafeDestroyRange { callback, .. } => {
+        GcTask::Gc { callback, .. }
+        | GcTask::UnsafeDestroyRange { callback, .. }
+        | GcTask::RecomputeRangeProperties { callback, .. } => {
+            callback(Err(Error::from(ErrorInner::GcWorkerTooBusy)))
+        }
+        GcTask::RankRangesByGarbage { callback, .. } => {
             callback(Err(Error::from(ErrorInner::GcWorkerTooBusy)))
         }
         // Attention: If you are adding a new GcTask, do not forget to call the callback if it has a
@@ -1351,6 +1489,55 @@ impl<E: Engine> GcWorker<E> {
             .or_else(handle_gc_task_schedule_error)
     }
 
+    /// Forces recomputation of table properties (e.g. MVCC counts) over
+    /// `[start_key, end_key)`, by running a targeted, bottommost compaction
+    /// there. Useful after a large batch of deletes (e.g. `unsafe_destroy_range`
+    /// or a bulk raw delete) whose property-derived follow-on decisions, such
+    /// as further GC or split-check, would otherwise keep relying on stale
+    /// properties until compaction naturally revisits the range.
+    pub fn recompute_range_properties(
+        &self,
+        start_key: Key,
+        end_key: Key,
+        callback: Callback<()>,
+    ) -> Result<()> {
+        GC_COMMAND_COUNTER_VEC_STATIC
+            .recompute_range_properties
+            .inc();
+
+        self.worker_scheduler
+            .schedule(GcTask::RecomputeRangeProperties {
+                start_key,
+                end_key,
+                callback,
+            })
+            .or_else(handle_gc_task_schedule_error)
+    }
+
+    /// Ranks `ranges` by how much reclaimable garbage they hold (see
+    /// [`garbage_ratio`]) and forces a targeted compaction on the
+    /// `recompute_top` worst ones, so forced compactions get scheduled by
+    /// priority instead of a fixed visiting order. The full ranking, most
+    /// garbage first, is returned via `callback`.
+    pub fn rank_ranges_by_garbage(
+        &self,
+        ranges: Vec<(Key, Key)>,
+        safe_point: TimeStamp,
+        recompute_top: usize,
+        callback: Callback<Vec<(Key, Key, f64)>>,
+    ) -> Result<()> {
+        GC_COMMAND_COUNTER_VEC_STATIC.rank_ranges_by_garbage.inc();
+
+        self.worker_scheduler
+            .schedule(GcTask::RankRangesByGarbage {
+                ranges,
+                safe_point,
+                recompute_top,
+                callback,
+            })
+            .or_else(handle_gc_task_schedule_error)
+    }
+
     pub fn get_config_manager(&self) -> GcWorkerConfigManager {
         self.config_manager.clone()
     }
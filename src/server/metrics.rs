@@ -75,6 +75,8 @@ make_auto_flush_static_metric! {
         unsafe_destroy_range,
         validate_config,
         orphan_versions,
+        recompute_range_properties,
+        rank_ranges_by_garbage,
     }
 
     pub label_enum SnapTask {
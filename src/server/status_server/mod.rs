@@ -55,6 +55,7 @@ use tikv_util::{
     logger::set_log_level,
     metrics::{dump, dump_to},
     timer::GLOBAL_TIMER_HANDLE,
+    worker::Scheduler,
 };
 use tokio::{
     io::{AsyncRead, AsyncWrite},
@@ -95,6 +96,7 @@ pub struct StatusServer<R> {
     security_config: Arc<SecurityConfig>,
     resource_manager: Option<Arc<ResourceGroupManager>>,
     grpc_service_mgr: GrpcServiceManager,
+    cdc_scheduler: Option<Scheduler<cdc::Task>>,
 }
 
 impl<R> StatusServer<R>
@@ -108,6 +110,7 @@ where
         router: R,
         resource_manager: Option<Arc<ResourceGroupManager>>,
         grpc_service_mgr: GrpcServiceManager,
+        cdc_scheduler: Option<Scheduler<cdc::Task>>,
     ) -> Result<Self> {
         let thread_pool = Builder::new_multi_thread()
             .enable_all()
@@ -130,6 +133,7 @@ where
             security_config,
             resource_manager,
             grpc_service_mgr,
+            cdc_scheduler,
         })
     }
 
@@ -500,6 +504,175 @@ where
         ))
     }
 
+    async fn dump_old_value_cache_stats(
+        req: Request<Body>,
+        cdc_scheduler: Option<Scheduler<cdc::Task>>,
+    ) -> hyper::Result<Response<Body>> {
+        let scheduler = match cdc_scheduler {
+            Some(scheduler) => scheduler,
+            None => {
+                return Ok(make_response(StatusCode::NOT_FOUND, "CDC is not enabled"));
+            }
+        };
+
+        let top_n = req
+            .uri()
+            .query()
+            .and_then(|query| {
+                url::form_urlencoded::parse(query.as_bytes())
+                    .find(|(k, _)| k == "top")
+                    .and_then(|(_, v)| v.parse::<usize>().ok())
+            })
+            .unwrap_or(10);
+
+        let (tx, rx) = oneshot::channel();
+        let res = scheduler.schedule(cdc::Task::Validate(cdc::Validate::OldValueCacheStats(
+            top_n,
+            Box::new(move |stats| {
+                let _ = tx.send(stats);
+            }),
+        )));
+        if let Err(err) = res {
+            return Ok(make_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to query cdc old value cache: {}", err),
+            ));
+        }
+        let stats = match rx.await {
+            Ok(stats) => stats,
+            Err(_) => {
+                return Ok(make_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "cdc worker dropped the old value cache query",
+                ));
+            }
+        };
+
+        #[derive(Serialize)]
+        struct OldValueCacheStatsResponse {
+            access_count: usize,
+            miss_count: usize,
+            miss_none_count: usize,
+            update_count: usize,
+            len: usize,
+            bytes: usize,
+            capacity: usize,
+            hit_ratio: Option<f64>,
+            top_keys: Vec<(String, usize)>,
+        }
+
+        let resp = OldValueCacheStatsResponse {
+            access_count: stats.access_count,
+            miss_count: stats.miss_count,
+            miss_none_count: stats.miss_none_count,
+            update_count: stats.update_count,
+            len: stats.len,
+            bytes: stats.bytes,
+            capacity: stats.capacity,
+            hit_ratio: stats.hit_ratio(),
+            top_keys: stats
+                .top_keys
+                .iter()
+                .map(|(key, size)| (log_wrappers::Value::key(key.as_encoded()).to_string(), *size))
+                .collect(),
+        };
+        match serde_json::to_vec(&resp) {
+            Ok(body) => Ok(Response::builder()
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(body.into())
+                .unwrap()),
+            Err(err) => Ok(make_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("fails to json: {}", err),
+            )),
+        }
+    }
+
+    async fn dump_cdc_status(
+        cdc_scheduler: Option<Scheduler<cdc::Task>>,
+    ) -> hyper::Result<Response<Body>> {
+        let scheduler = match cdc_scheduler {
+            Some(scheduler) => scheduler,
+            None => {
+                return Ok(make_response(StatusCode::NOT_FOUND, "CDC is not enabled"));
+            }
+        };
+
+        let (tx, rx) = oneshot::channel();
+        let res = scheduler.schedule(cdc::Task::Validate(cdc::Validate::EndpointStats(
+            Box::new(move |stats| {
+                let _ = tx.send(stats);
+            }),
+        )));
+        if let Err(err) = res {
+            return Ok(make_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to query cdc status: {}", err),
+            ));
+        }
+        let stats = match rx.await {
+            Ok(stats) => stats,
+            Err(_) => {
+                return Ok(make_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "cdc worker dropped the status query",
+                ));
+            }
+        };
+
+        #[derive(Serialize)]
+        struct ConnStatsResponse {
+            conn_id: usize,
+            peer: String,
+            subscription_count: usize,
+            unacked_bytes: usize,
+            scan_task_count: isize,
+        }
+
+        #[derive(Serialize)]
+        struct EndpointStatsResponse {
+            connections: Vec<ConnStatsResponse>,
+            capture_region_count: usize,
+            max_capture_regions: usize,
+            sink_memory_quota_in_use: usize,
+            sink_memory_quota_capacity: usize,
+            scan_task_count: isize,
+            min_resolved_ts: u64,
+            min_resolved_ts_region_id: u64,
+        }
+
+        let resp = EndpointStatsResponse {
+            connections: stats
+                .connections
+                .iter()
+                .map(|c| ConnStatsResponse {
+                    conn_id: c.conn_id.id(),
+                    peer: c.peer.clone(),
+                    subscription_count: c.subscription_count,
+                    unacked_bytes: c.unacked_bytes,
+                    scan_task_count: c.scan_task_count,
+                })
+                .collect(),
+            capture_region_count: stats.capture_region_count,
+            max_capture_regions: stats.max_capture_regions,
+            sink_memory_quota_in_use: stats.sink_memory_quota_in_use,
+            sink_memory_quota_capacity: stats.sink_memory_quota_capacity,
+            scan_task_count: stats.scan_task_count,
+            min_resolved_ts: stats.min_resolved_ts.into_inner(),
+            min_resolved_ts_region_id: stats.min_resolved_ts_region_id,
+        };
+        match serde_json::to_vec(&resp) {
+            Ok(body) => Ok(Response::builder()
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(body.into())
+                .unwrap()),
+            Err(err) => Ok(make_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("fails to json: {}", err),
+            )),
+        }
+    }
+
     pub async fn dump_region_meta(req: Request<Body>, router: R) -> hyper::Result<Response<Body>> {
         lazy_static! {
             static ref REGION: Regex = Regex::new(r"/region/(?P<id>\d+)").unwrap();
@@ -613,6 +786,7 @@ where
         let router = self.router.clone();
         let resource_manager = self.resource_manager.clone();
         let grpc_service_mgr = self.grpc_service_mgr.clone();
+        let cdc_scheduler = self.cdc_scheduler.clone();
         // Start to serve.
         let server = builder.serve(make_service_fn(move |conn: &C| {
             let x509 = conn.get_x509();
@@ -621,6 +795,7 @@ where
             let router = router.clone();
             let resource_manager = resource_manager.clone();
             let grpc_service_mgr = grpc_service_mgr.clone();
+            let cdc_scheduler = cdc_scheduler.clone();
             async move {
                 // Create a status service.
                 Ok::<_, hyper::Error>(service_fn(move |req: Request<Body>| {
@@ -630,6 +805,7 @@ where
                     let router = router.clone();
                     let resource_manager = resource_manager.clone();
                     let grpc_service_mgr = grpc_service_mgr.clone();
+                    let cdc_scheduler = cdc_scheduler.clone();
                     async move {
                         let path = req.uri().path().to_owned();
                         let method = req.method().to_owned();
@@ -734,6 +910,12 @@ where
                                 Self::handle_resume_grpc(grpc_service_mgr)
                             }
                             (Method::GET, "/async_tasks") => Self::dump_async_trace(),
+                            (Method::GET, "/debug/old_value_cache") => {
+                                Self::dump_old_value_cache_stats(req, cdc_scheduler.clone()).await
+                            }
+                            (Method::GET, "/debug/cdc_status") => {
+                                Self::dump_cdc_status(cdc_scheduler).await
+                            }
                             _ => {
                                 is_unknown_path = true;
                                 Ok(make_response(StatusCode::NOT_FOUND, "path not found"))
@@ -1170,6 +1352,7 @@ mod tests {
             MockRouter,
             None,
             GrpcServiceManager::dummy(),
+            None,
         )
         .unwrap();
         let addr = "127.0.0.1:0".to_owned();
@@ -1218,6 +1401,7 @@ mod tests {
             MockRouter,
             None,
             GrpcServiceManager::dummy(),
+            None,
         )
         .unwrap();
         let addr = "127.0.0.1:0".to_owned();
@@ -1270,6 +1454,7 @@ mod tests {
                 MockRouter,
                 None,
                 GrpcServiceManager::dummy(),
+                None,
             )
             .unwrap();
             let addr = "127.0.0.1:0".to_owned();
@@ -1332,6 +1517,7 @@ mod tests {
             MockRouter,
             None,
             GrpcServiceManager::dummy(),
+            None,
         )
         .unwrap();
         let addr = "127.0.0.1:0".to_owned();
@@ -1448,6 +1634,7 @@ mod tests {
             MockRouter,
             None,
             GrpcServiceManager::dummy(),
+            None,
         )
         .unwrap();
         let addr = "127.0.0.1:0".to_owned();
@@ -1492,6 +1679,7 @@ mod tests {
             MockRouter,
             None,
             GrpcServiceManager::dummy(),
+            None,
         )
         .unwrap();
         let addr = "127.0.0.1:0".to_owned();
@@ -1528,6 +1716,7 @@ mod tests {
             MockRouter,
             None,
             GrpcServiceManager::dummy(),
+            None,
         )
         .unwrap();
         let addr = "127.0.0.1:0".to_owned();
@@ -1600,6 +1789,7 @@ mod tests {
             MockRouter,
             None,
             GrpcServiceManager::dummy(),
+            None,
         )
         .unwrap();
         let addr = "127.0.0.1:0".to_owned();
@@ -1630,6 +1820,7 @@ mod tests {
             MockRouter,
             None,
             GrpcServiceManager::dummy(),
+            None,
         )
         .unwrap();
         let addr = "127.0.0.1:0".to_owned();
@@ -1663,6 +1854,7 @@ mod tests {
             MockRouter,
             None,
             GrpcServiceManager::dummy(),
+            None,
         )
         .unwrap();
         let addr = "127.0.0.1:0".to_owned();
@@ -1714,6 +1906,7 @@ mod tests {
             MockRouter,
             None,
             GrpcServiceManager::dummy(),
+            None,
         )
         .unwrap();
         let addr = "127.0.0.1:0".to_owned();
@@ -1769,6 +1962,7 @@ mod tests {
             MockRouter,
             None,
             GrpcServiceManager::dummy(),
+            None,
         )
         .unwrap();
         let addr = "127.0.0.1:0".to_owned();
@@ -1823,6 +2017,7 @@ mod tests {
                 MockRouter,
                 None,
                 GrpcServiceManager::dummy(),
+                None,
             )
             .unwrap();
             let addr = "127.0.0.1:0".to_owned();
@@ -1860,6 +2055,7 @@ mod tests {
                 MockRouter,
                 None,
                 GrpcServiceManager::dummy(),
+                None,
             )
             .unwrap();
             let addr = "127.0.0.1:0".to_owned();
@@ -87,6 +87,19 @@ pub struct Config {
     pub scheduler_worker_pool_size: usize,
     #[online_config(skip)]
     pub scheduler_pending_write_threshold: ReadableSize,
+    // Caps how many rollback/resolve-lock family commands (`rollback`,
+    // `pessimistic_rollback`, `pessimistic_rollback_read_phase`, `resolve_lock`,
+    // `resolve_lock_lite`) may be in flight
+    // in the scheduler at once, so an abort storm of pessimistic rollbacks can't
+    // monopolize the scheduler worker pool and starve other commands. 0 disables
+    // the limit.
+    pub scheduler_rollback_concurrency_limit: usize,
+    // Caps how many keys a single `pessimistic_rollback` command rolls back.
+    // A client-supplied key list longer than this is split, rolling back only
+    // the first batch and chaining the rest into a follow-up command, so
+    // that one oversized request can't occupy a scheduler worker for too
+    // long or produce an outsized raft entry. 0 disables the limit.
+    pub pessimistic_rollback_batch_keys_limit: usize,
     #[online_config(skip)]
     // Reserve disk space to make tikv would have enough space to compact when disk is full.
     pub reserve_space: ReadableSize,
@@ -128,6 +141,8 @@ impl Default for Config {
                 cpu_num.clamp(1., 4.) as usize
             },
             scheduler_pending_write_threshold: ReadableSize::mb(DEFAULT_SCHED_PENDING_WRITE_MB),
+            scheduler_rollback_concurrency_limit: 0,
+            pessimistic_rollback_batch_keys_limit: 0,
             reserve_space: ReadableSize::gb(DEFAULT_RESERVED_SPACE_GB),
             reserve_raft_space: ReadableSize::gb(DEFAULT_RESERVED_RAFT_SPACE_GB),
             enable_async_apply_prewrite: false,
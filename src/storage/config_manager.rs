@@ -84,6 +84,13 @@ impl<EK: Engine, K: ConfigurableDb, L: LockManager> ConfigManager
         } else if let Some(v) = change.remove("memory_quota") {
             let cap: ReadableSize = v.into();
             self.scheduler.set_memory_quota_capacity(cap.0 as usize);
+        } else if let Some(v) = change.remove("scheduler_rollback_concurrency_limit") {
+            let limit: usize = v.into();
+            self.scheduler.set_rollback_concurrency_limit(limit);
+        } else if let Some(v) = change.remove("pessimistic_rollback_batch_keys_limit") {
+            let limit: usize = v.into();
+            self.scheduler
+                .set_pessimistic_rollback_batch_keys_limit(limit);
         }
         if let Some(ConfigValue::Module(mut io_rate_limit)) = change.remove("io_rate_limit") {
             let limiter = match get_io_rate_limiter() {
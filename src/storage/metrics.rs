@@ -133,8 +133,10 @@ make_auto_flush_static_metric! {
         rollback,
         pessimistic_rollback,
         pessimistic_rollback_read_phase,
+        force_unlock_pessimistic_lock,
         txn_heart_beat,
         check_txn_status,
+        check_txn_status_and_rollback,
         check_secondary_locks,
         scan_lock,
         resolve_lock,
@@ -262,6 +264,10 @@ make_auto_flush_static_metric! {
         "type" => CommandKind,
     }
 
+    pub struct SchedCommandConcurrencyThrottledVec: LocalIntCounter {
+        "type" => CommandKind,
+    }
+
     pub struct SchedCommandPriCounterVec: LocalIntCounter {
         "priority" => CommandPriority,
     }
@@ -540,6 +546,17 @@ lazy_static! {
     .unwrap();
     pub static ref SCHED_TOO_BUSY_COUNTER_VEC: SchedTooBusyVec =
         auto_flush_from!(SCHED_TOO_BUSY_COUNTER, SchedTooBusyVec);
+    pub static ref SCHED_COMMAND_CONCURRENCY_THROTTLED_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "tikv_scheduler_command_concurrency_throttled_total",
+        "Total count of commands rejected by the per-command-type concurrency limit",
+        &["type"]
+    )
+    .unwrap();
+    pub static ref SCHED_COMMAND_CONCURRENCY_THROTTLED_COUNTER_VEC: SchedCommandConcurrencyThrottledVec =
+        auto_flush_from!(
+            SCHED_COMMAND_CONCURRENCY_THROTTLED_COUNTER,
+            SchedCommandConcurrencyThrottledVec
+        );
     pub static ref SCHED_COMMANDS_PRI_COUNTER_VEC: IntCounterVec = register_int_counter_vec!(
         "tikv_scheduler_commands_pri_total",
         "Total count of different priority commands",
@@ -644,4 +661,28 @@ lazy_static! {
         "The count of running scheduler commands"
     )
     .unwrap();
+
+    // The generic `SCHED_PROCESSING_READ_HISTOGRAM_VEC`/`_WRITE_HISTOGRAM_VEC`
+    // cover every command's end-to-end processing time, but transaction abort
+    // SLOs need the pessimistic rollback family (`PessimisticRollback` and
+    // its read-phase scan, `PessimisticRollbackReadPhase`) broken out on its
+    // own, plus how often a rollback had to chain into a follow-up command.
+    pub static ref PESSIMISTIC_ROLLBACK_SCAN_DURATION_HISTOGRAM: Histogram = register_histogram!(
+        "tikv_storage_pessimistic_rollback_scan_duration_seconds",
+        "Bucketed histogram of the lock scan duration of the pessimistic rollback read phase",
+        exponential_buckets(0.00001, 2.0, 26).unwrap()
+    )
+    .unwrap();
+    pub static ref PESSIMISTIC_ROLLBACK_APPLY_DURATION_HISTOGRAM: Histogram = register_histogram!(
+        "tikv_storage_pessimistic_rollback_apply_duration_seconds",
+        "Bucketed histogram of the lock release duration of the pessimistic rollback write phase",
+        exponential_buckets(0.00001, 2.0, 26).unwrap()
+    )
+    .unwrap();
+    pub static ref PESSIMISTIC_ROLLBACK_CONTINUATION_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "tikv_storage_pessimistic_rollback_continuation_total",
+        "Total count of pessimistic rollback commands chained into a follow-up command, by reason",
+        &["reason"]
+    )
+    .unwrap();
 }
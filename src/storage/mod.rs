@@ -4015,6 +4015,7 @@ pub mod test_util {
                         start_ts.into(),
                         for_update_ts.into(),
                         None,
+                        0,
                         Context::default(),
                     ),
                     expect_ok_callback(tx, 0),
@@ -9361,6 +9362,7 @@ mod tests {
                     commands::Rollback::new(
                         vec![Key::from_raw(&key(5))],
                         30.into(),
+                        None,
                         Context::default(),
                     ),
                     expect_ok_callback(tx.clone(), 0),
@@ -9858,7 +9860,7 @@ mod tests {
             let h = lock_blocked(&keys, 45, ts.into_inner(), 0);
             storage
                 .sched_txn_command(
-                    commands::Rollback::new(keys.clone(), ts, Context::default()),
+                    commands::Rollback::new(keys.clone(), ts, None, Context::default()),
                     expect_ok_callback(tx.clone(), 0),
                 )
                 .unwrap();
@@ -9877,6 +9879,7 @@ mod tests {
                     50.into(),
                     50.into(),
                     None,
+                    0,
                     Context::default(),
                 ),
                 expect_ok_callback(tx.clone(), 0),
@@ -12,8 +12,8 @@ use raftstore::store::{LocksStatus, PeerPessimisticLocks};
 use tikv_kv::{SnapshotExt, SEEK_BOUND};
 use tikv_util::time::Instant;
 use txn_types::{
-    Key, LastChange, Lock, OldValue, PessimisticLock, TimeStamp, TxnLockRef, Value, Write,
-    WriteRef, WriteType,
+    Key, LastChange, Lock, OldValue, PessimisticLock, RollbackReason, TimeStamp, TxnLockRef,
+    Value, Write, WriteRef, WriteType,
 };
 
 use crate::storage::{
@@ -68,6 +68,11 @@ impl<S: EngineSnapshot> SnapshotReader<S> {
         self.reader.load_lock(key)
     }
 
+    #[inline(always)]
+    pub fn load_locks(&mut self, keys: &[Key]) -> Result<Vec<Option<Lock>>> {
+        self.reader.load_locks(keys)
+    }
+
     #[inline(always)]
     pub fn key_exist(&mut self, key: &Key, ts: TimeStamp) -> Result<bool> {
         Ok(self
@@ -256,6 +261,45 @@ impl<S: EngineSnapshot> MvccReader<S> {
         Ok(res)
     }
 
+    /// Load locks for a batch of keys at once.
+    ///
+    /// This exists for callers that need to look up many unrelated keys'
+    /// locks up front (e.g. pessimistic rollback of a large batch of keys),
+    /// where doing so one key at a time means a separate point lookup (and
+    /// block-cache probe) per key. Results are returned in the same order as
+    /// `keys`.
+    pub fn load_locks(&mut self, keys: &[Key]) -> Result<Vec<Option<Lock>>> {
+        if self.scan_mode.is_some() {
+            // The cursor-based path already amortizes seeks across nearby keys, so
+            // there's nothing to gain from batching here.
+            return keys.iter().map(|key| self.load_lock(key)).collect();
+        }
+
+        let mut result = vec![None; keys.len()];
+        let mut storage_indices = Vec::new();
+        let mut storage_keys = Vec::new();
+        for (i, key) in keys.iter().enumerate() {
+            if let Some(lock) = self.load_in_memory_pessimistic_lock(key)? {
+                result[i] = Some(lock);
+            } else {
+                storage_indices.push(i);
+                storage_keys.push(key.clone());
+            }
+        }
+
+        if !storage_keys.is_empty() {
+            self.statistics.lock.get += storage_keys.len();
+            let values = self.snapshot.multi_get_cf(CF_LOCK, &storage_keys)?;
+            for (i, value) in storage_indices.into_iter().zip(values) {
+                if let Some(v) = value {
+                    result[i] = Some(Lock::parse(&v)?);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
     fn check_term_version_status(&self, locks: &PeerPessimisticLocks) -> Result<()> {
         // If the term or region version has changed, do not read the lock table.
         // Instead, just return a StaleCommand or EpochNotMatch error, so the
@@ -1157,6 +1201,7 @@ pub mod tests {
                 Key::from_raw(pk),
                 TimeStamp::zero(),
                 true,
+                RollbackReason::ClientInitiated,
             )
             .unwrap();
             self.write(txn.into_modifies());
@@ -6,7 +6,7 @@ use std::fmt;
 use concurrency_manager::{ConcurrencyManager, KeyHandleGuard};
 use engine_traits::{CF_DEFAULT, CF_LOCK, CF_WRITE};
 use kvproto::kvrpcpb::LockInfo;
-use txn_types::{Key, Lock, PessimisticLock, TimeStamp, Value};
+use txn_types::{Key, Lock, PessimisticLock, RollbackReason, TimeStamp, Value};
 
 use super::metrics::{GC_DELETE_VERSIONS_HISTOGRAM, MVCC_VERSIONS_HISTOGRAM};
 use crate::storage::kv::Modify;
@@ -80,6 +80,9 @@ pub struct MvccTxn {
     // reading requests should be able to read the locks from the engine.
     // So these guards can be released after finishing writing.
     pub(crate) guards: Vec<KeyHandleGuard>,
+    // Records why each rolled-back key's lock was released, so it can be
+    // surfaced to CDC via `TxnExtra::rollback_reasons`.
+    pub(crate) rollback_reasons: Vec<(Key, RollbackReason)>,
 }
 
 impl MvccTxn {
@@ -94,6 +97,7 @@ impl MvccTxn {
             new_locks: vec![],
             concurrency_manager,
             guards: vec![],
+            rollback_reasons: vec![],
         }
     }
 
@@ -110,6 +114,10 @@ impl MvccTxn {
         std::mem::take(&mut self.new_locks)
     }
 
+    pub fn take_rollback_reasons(&mut self) -> Vec<(Key, RollbackReason)> {
+        std::mem::take(&mut self.rollback_reasons)
+    }
+
     pub fn write_size(&self) -> usize {
         self.write_size
     }
@@ -160,6 +168,18 @@ impl MvccTxn {
         Some(released)
     }
 
+    /// Like [`unlock_key`](Self::unlock_key), but for the rollback case,
+    /// additionally recording why the rollback happened.
+    pub(crate) fn unlock_key_for_rollback(
+        &mut self,
+        key: Key,
+        pessimistic: bool,
+        reason: RollbackReason,
+    ) -> Option<ReleasedLock> {
+        self.rollback_reasons.push((key.clone(), reason));
+        self.unlock_key(key, pessimistic, TimeStamp::zero())
+    }
+
     pub(crate) fn put_value(&mut self, key: Key, ts: TimeStamp, value: Value) {
         let write = Modify::Put(CF_DEFAULT, key.append_ts(ts), value);
         self.write_size += write.size();
@@ -229,6 +249,7 @@ impl MvccTxn {
         self.new_locks.clear();
         self.locks_for_1pc.clear();
         self.guards.clear();
+        self.rollback_reasons.clear();
     }
 }
 
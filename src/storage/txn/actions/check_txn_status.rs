@@ -2,7 +2,7 @@
 
 use tikv_kv::SnapshotExt;
 // #[PerformanceCriticalPath]
-use txn_types::{Key, Lock, TimeStamp, Write, WriteType};
+use txn_types::{Key, Lock, RollbackReason, TimeStamp, Write, WriteType};
 
 use crate::storage::{
     mvcc::{
@@ -37,7 +37,12 @@ fn check_txn_status_from_pessimistic_primary_lock(
                 "current_ts" => current_ts,
                 "resolving_pessimistic_lock" => ?resolving_pessimistic_lock,
             );
-            let released = txn.unlock_key(primary_key, true, TimeStamp::zero());
+            let reason = if resolving_pessimistic_lock {
+                RollbackReason::DeadlockVictim
+            } else {
+                RollbackReason::LockTtlExpired
+            };
+            let released = txn.unlock_key_for_rollback(primary_key, true, reason);
             MVCC_CHECK_TXN_STATUS_COUNTER_VEC.pessimistic_rollback.inc();
             return Ok((Some(txn_status), released));
         }
@@ -54,11 +59,20 @@ fn check_txn_status_from_pessimistic_primary_lock(
     // rollback record.
     if lock.ts.physical() + lock.ttl < current_ts.physical() {
         return if resolving_pessimistic_lock {
-            let released = txn.unlock_key(primary_key, true, TimeStamp::zero());
+            let released =
+                txn.unlock_key_for_rollback(primary_key, true, RollbackReason::DeadlockVictim);
             MVCC_CHECK_TXN_STATUS_COUNTER_VEC.pessimistic_rollback.inc();
             Ok((Some(TxnStatus::PessimisticRollBack), released))
         } else {
-            let released = rollback_lock(txn, reader, primary_key, lock, true, true)?;
+            let released = rollback_lock(
+                txn,
+                reader,
+                primary_key,
+                lock,
+                true,
+                true,
+                RollbackReason::LockTtlExpired,
+            )?;
             MVCC_CHECK_TXN_STATUS_COUNTER_VEC.rollback.inc();
             Ok((Some(TxnStatus::TtlExpire), released))
         };
@@ -124,7 +138,11 @@ pub fn check_txn_status_lock_exists(
                     MissingLockAction::rollback(rollback_if_not_exist),
                     resolving_pessimistic_lock,
                 )?;
-                let released = txn.unlock_key(primary_key, true, TimeStamp::zero());
+                let released = txn.unlock_key_for_rollback(
+                    primary_key,
+                    true,
+                    RollbackReason::LockTtlExpired,
+                );
                 MVCC_CHECK_TXN_STATUS_COUNTER_VEC.pessimistic_rollback.inc();
                 Ok((txn_status, released))
             }
@@ -183,7 +201,15 @@ pub fn check_txn_status_lock_exists(
                 "caller_start_ts" => caller_start_ts,
             );
         }
-        let released = rollback_lock(txn, reader, primary_key, &lock, is_pessimistic_txn, true)?;
+        let released = rollback_lock(
+            txn,
+            reader,
+            primary_key,
+            &lock,
+            is_pessimistic_txn,
+            true,
+            RollbackReason::LockTtlExpired,
+        )?;
         MVCC_CHECK_TXN_STATUS_COUNTER_VEC.rollback.inc();
         return Ok((TxnStatus::TtlExpire, released));
     }
@@ -305,6 +331,7 @@ pub fn rollback_lock(
     lock: &Lock,
     is_pessimistic_txn: bool,
     collapse_rollback: bool,
+    reason: RollbackReason,
 ) -> Result<Option<ReleasedLock>> {
     let overlapped_write = match reader.get_txn_commit_record(&key)? {
         TxnCommitRecord::None { overlapped_write } => overlapped_write,
@@ -320,7 +347,7 @@ pub fn rollback_lock(
                 reader.reader.snapshot_ext().get_region_id().unwrap_or(0)
             )
         }
-        _ => return Ok(txn.unlock_key(key, is_pessimistic_txn, TimeStamp::zero())),
+        _ => return Ok(txn.unlock_key_for_rollback(key, is_pessimistic_txn, reason)),
     };
 
     // If prewrite type is DEL or LOCK or PESSIMISTIC, it is no need to delete
@@ -353,7 +380,7 @@ pub fn rollback_lock(
         collapse_prev_rollback(txn, reader, &key)?;
     }
 
-    Ok(txn.unlock_key(key, is_pessimistic_txn, TimeStamp::zero()))
+    Ok(txn.unlock_key_for_rollback(key, is_pessimistic_txn, reason))
 }
 
 pub fn collapse_prev_rollback(
@@ -1,6 +1,8 @@
 // Copyright 2021 TiKV Project Authors. Licensed under Apache-2.0.
 
 // #[PerformanceCriticalPath]
+use txn_types::RollbackReason;
+
 use crate::storage::{
     mvcc::{
         metrics::{MVCC_CONFLICT_COUNTER, MVCC_DUPLICATE_CMD_COUNTER_VEC},
@@ -17,6 +19,10 @@ use crate::storage::{
 /// primary lock of a pessimistic transaction, the rollback record is protected
 /// from being collapsed.
 ///
+/// `reason` records why the caller wants this rollback to happen (e.g. a
+/// client-initiated `Rollback` vs. a TTL-driven `Cleanup`), and is forwarded
+/// all the way to `TxnExtra::rollback_reasons` for CDC to consume.
+///
 /// Returns the released lock. Returns error if the key is locked or has already
 /// been committed.
 pub fn cleanup<S: Snapshot>(
@@ -25,6 +31,7 @@ pub fn cleanup<S: Snapshot>(
     key: Key,
     current_ts: TimeStamp,
     protect_rollback: bool,
+    reason: RollbackReason,
 ) -> MvccResult<Option<ReleasedLock>> {
     fail_point!("cleanup", |err| Err(
         crate::storage::mvcc::txn::make_txn_error(err, &key, reader.start_ts).into()
@@ -46,6 +53,7 @@ pub fn cleanup<S: Snapshot>(
                 lock,
                 lock.is_pessimistic_txn(),
                 !protect_rollback,
+                reason,
             )
         }
         l => match check_txn_status_missing_lock(
@@ -117,7 +125,15 @@ pub mod tests {
         let start_ts = start_ts.into();
         let mut txn = MvccTxn::new(start_ts, cm);
         let mut reader = SnapshotReader::new(start_ts, snapshot, true);
-        cleanup(&mut txn, &mut reader, Key::from_raw(key), current_ts, true).unwrap();
+        cleanup(
+            &mut txn,
+            &mut reader,
+            Key::from_raw(key),
+            current_ts,
+            true,
+            RollbackReason::LockTtlExpired,
+        )
+        .unwrap();
         write(engine, &ctx, txn.into_modifies());
     }
 
@@ -133,7 +149,15 @@ pub mod tests {
         let start_ts = start_ts.into();
         let mut txn = MvccTxn::new(start_ts, cm);
         let mut reader = SnapshotReader::new(start_ts, snapshot, true);
-        cleanup(&mut txn, &mut reader, Key::from_raw(key), current_ts, true).unwrap_err()
+        cleanup(
+            &mut txn,
+            &mut reader,
+            Key::from_raw(key),
+            current_ts,
+            true,
+            RollbackReason::LockTtlExpired,
+        )
+        .unwrap_err()
     }
 
     pub fn must_cleanup_with_gc_fence<E: Engine>(
@@ -160,7 +184,15 @@ pub mod tests {
         let snapshot = engine.snapshot(Default::default()).unwrap();
         let mut txn = MvccTxn::new(start_ts, cm);
         let mut reader = SnapshotReader::new(start_ts, snapshot, true);
-        cleanup(&mut txn, &mut reader, Key::from_raw(key), current_ts, true).unwrap();
+        cleanup(
+            &mut txn,
+            &mut reader,
+            Key::from_raw(key),
+            current_ts,
+            true,
+            RollbackReason::LockTtlExpired,
+        )
+        .unwrap();
 
         write(engine, &ctx, txn.into_modifies());
 
@@ -1,6 +1,6 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
-use txn_types::{Key, Lock, LockType, TimeStamp, Write, WriteType};
+use txn_types::{Key, Lock, LockType, RollbackReason, TimeStamp, Write, WriteType};
 
 use crate::storage::{
     mvcc::{self, MvccReader, MvccTxn, SnapshotReader, MAX_TXN_WRITE_SIZE},
@@ -79,6 +79,7 @@ pub fn rollback_locks(
             &lock,
             lock.is_pessimistic_txn(),
             true,
+            RollbackReason::LockTtlExpired,
         )?;
     }
     Ok(None)
@@ -9,6 +9,7 @@ use kvproto::kvrpcpb::{
 };
 use prewrite::{prewrite, CommitKind, TransactionKind, TransactionProperties};
 use tikv_kv::SnapContext;
+use txn_types::RollbackReason;
 
 use super::*;
 use crate::storage::{
@@ -928,6 +929,7 @@ pub fn must_rollback<E: Engine>(
         Key::from_raw(key),
         TimeStamp::zero(),
         protect_rollback,
+        RollbackReason::ClientInitiated,
     )
     .unwrap();
     write(engine, &ctx, txn.into_modifies());
@@ -945,6 +947,7 @@ pub fn must_rollback_err<E: Engine>(engine: &mut E, key: &[u8], start_ts: impl I
         Key::from_raw(key),
         TimeStamp::zero(),
         false,
+        RollbackReason::ClientInitiated,
     )
     .unwrap_err();
 }
@@ -202,6 +202,7 @@ pub(super) fn make_write_data(modifies: Vec<Modify>, old_values: OldValues) -> W
             // One pc status is unknown in AcquirePessimisticLock stage.
             one_pc: false,
             allowed_in_flashback: false,
+            rollback_reasons: Default::default(),
         };
         WriteData::new(modifies, extra)
     } else {
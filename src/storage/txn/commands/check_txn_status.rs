@@ -1,7 +1,7 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
 // #[PerformanceCriticalPath]
-use txn_types::{Key, TimeStamp};
+use txn_types::{Key, TimeStamp, TxnExtra};
 
 use crate::storage::{
     kv::WriteData,
@@ -144,7 +144,11 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for CheckTxnStatus {
         };
         let pr = ProcessResult::TxnStatus { txn_status };
         let new_acquired_locks = txn.take_new_locks();
-        let mut write_data = WriteData::from_modifies(txn.into_modifies());
+        let extra = TxnExtra {
+            rollback_reasons: txn.take_rollback_reasons().into_iter().collect(),
+            ..Default::default()
+        };
+        let mut write_data = WriteData::new(txn.into_modifies(), extra);
         write_data.set_allowed_on_disk_almost_full();
         Ok(WriteResult {
             ctx: self.ctx,
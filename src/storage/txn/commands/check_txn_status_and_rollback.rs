@@ -0,0 +1,333 @@
+// Copyright 2026 TiKV Project Authors. Licensed under Apache-2.0.
+
+// #[PerformanceCriticalPath]
+use txn_types::{Key, TimeStamp, TxnExtra};
+
+use crate::storage::{
+    kv::WriteData,
+    lock_manager::LockManager,
+    mvcc::{MvccTxn, SnapshotReader},
+    txn::{
+        actions::check_txn_status::*,
+        commands::{
+            Command, CommandExt, ReaderWithStats, ReleasedLocks, ResponsePolicy, TypedCommand,
+            WriteCommand, WriteContext, WriteResult,
+        },
+        latch::Lock,
+        Result,
+    },
+    ProcessResult, Result as StorageResult, Snapshot, TxnStatus,
+};
+
+command! {
+    /// Check the status of a transaction's primary lock and, if it turns out
+    /// the transaction is not going to commit, pessimistically roll back a
+    /// batch of its secondary locks in the same scheduler pass.
+    ///
+    /// This is [`CheckTxnStatus`](Command::CheckTxnStatus) followed by a
+    /// conditional [`PessimisticRollback`](Command::PessimisticRollback),
+    /// for the common case where a client that already met one of this
+    /// transaction's pessimistic locks knows a batch of other keys locked by
+    /// the same transaction that live in this command's region: instead of
+    /// checking the primary, waiting for the response, and only then issuing
+    /// a separate rollback, both steps happen in one scheduler pass.
+    ///
+    /// `secondary_keys` is only rolled back when the primary's status comes
+    /// back as [`TxnStatus::is_rolled_back`]; an uncommitted-but-still-live
+    /// or committed primary leaves the secondaries untouched, same as if
+    /// only `CheckTxnStatus` had been called.
+    CheckTxnStatusAndRollback:
+        cmd_ty => (TxnStatus, Vec<StorageResult<()>>),
+        display => {
+            "kv::command::check_txn_status_and_rollback {} @ {} curr({}, {}, {}) rollback {} keys | {:?}",
+            (primary_key, lock_ts, caller_start_ts, current_ts, rollback_if_not_exist,
+                secondary_keys.len(), ctx),
+        }
+        content => {
+            /// The primary key of the transaction.
+            primary_key: Key,
+            /// The lock's ts, namely the transaction's start_ts.
+            lock_ts: TimeStamp,
+            /// The start_ts of the transaction that invokes this command.
+            caller_start_ts: TimeStamp,
+            /// The approximate current_ts when the command is invoked.
+            current_ts: TimeStamp,
+            /// Specifies the behavior when neither commit/rollback record nor lock is found for
+            /// the primary. If true, rollbacks that transaction; otherwise returns an error.
+            rollback_if_not_exist: bool,
+            /// Secondary keys locked pessimistically by this transaction, residing in this
+            /// command's region, to roll back if the primary turns out to not be committing.
+            secondary_keys: Vec<Key>,
+            /// The `for_update_ts` to match against `secondary_keys`' pessimistic locks, with
+            /// the same semantics as `PessimisticRollback::for_update_ts`.
+            for_update_ts: TimeStamp,
+        }
+        in_heap => {
+            primary_key,
+            secondary_keys,
+        }
+}
+
+impl CommandExt for CheckTxnStatusAndRollback {
+    ctx!();
+    tag!(check_txn_status_and_rollback);
+    request_type!(KvCheckTxnStatusAndRollback);
+    ts!(lock_ts);
+
+    fn write_bytes(&self) -> usize {
+        self.primary_key.as_encoded().len()
+            + self
+                .secondary_keys
+                .iter()
+                .map(|k| k.as_encoded().len())
+                .sum::<usize>()
+    }
+
+    fn gen_lock(&self) -> Lock {
+        Lock::new(std::iter::once(&self.primary_key).chain(self.secondary_keys.iter()))
+    }
+}
+
+impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for CheckTxnStatusAndRollback {
+    fn process_write(self, snapshot: S, context: WriteContext<'_, L>) -> Result<WriteResult> {
+        let mut new_max_ts = self.lock_ts;
+        if !self.current_ts.is_max() && self.current_ts > new_max_ts {
+            new_max_ts = self.current_ts;
+        }
+        if !self.caller_start_ts.is_max() && self.caller_start_ts > new_max_ts {
+            new_max_ts = self.caller_start_ts;
+        }
+        context.concurrency_manager.update_max_ts(new_max_ts);
+
+        let mut txn = MvccTxn::new(self.lock_ts, context.concurrency_manager);
+        let mut reader = ReaderWithStats::new(
+            SnapshotReader::new_with_ctx(self.lock_ts, snapshot, &self.ctx),
+            context.statistics,
+        );
+
+        fail_point!("check_txn_status_and_rollback", |err| Err(
+            crate::storage::mvcc::Error::from(crate::storage::mvcc::txn::make_txn_error(
+                err,
+                &self.primary_key,
+                self.lock_ts
+            ))
+            .into()
+        ));
+
+        let (txn_status, primary_released) = match reader.load_lock(&self.primary_key)? {
+            Some(lock) if lock.ts == self.lock_ts => check_txn_status_lock_exists(
+                &mut txn,
+                &mut reader,
+                self.primary_key,
+                lock,
+                self.current_ts,
+                self.caller_start_ts,
+                // This command is only meant for the pessimistic-lock resolution path: it
+                // always resolves by rolling the primary back, never falls back from async
+                // commit.
+                false,
+                true,
+                true,
+                self.rollback_if_not_exist,
+            )?,
+            l => (
+                check_txn_status_missing_lock(
+                    &mut txn,
+                    &mut reader,
+                    self.primary_key,
+                    l,
+                    MissingLockAction::rollback(self.rollback_if_not_exist),
+                    true,
+                )?,
+                None,
+            ),
+        };
+
+        let mut released_locks = ReleasedLocks::new();
+        released_locks.push(primary_released);
+
+        // Only roll back the secondaries once the primary has come back as not
+        // committing; an in-progress or already-committed primary must leave them
+        // alone, exactly as a standalone `CheckTxnStatus` would.
+        let mut rollback_results = Vec::with_capacity(self.secondary_keys.len());
+        let mut rows = 1;
+        if txn_status.is_rolled_back() {
+            let locks = reader.load_locks(&self.secondary_keys)?;
+            for (key, lock) in self.secondary_keys.into_iter().zip(locks) {
+                let released_lock = if let Some(lock) = lock {
+                    if lock.is_pessimistic_lock()
+                        && lock.ts == self.lock_ts
+                        && lock.for_update_ts <= self.for_update_ts
+                    {
+                        txn.unlock_key(key, true, TimeStamp::zero())
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                };
+                rows += 1;
+                released_locks.push(released_lock);
+                rollback_results.push(Ok(()));
+            }
+        }
+
+        let write_result_known_txn_status = if let TxnStatus::Committed { commit_ts } = &txn_status
+        {
+            vec![(self.lock_ts, *commit_ts)]
+        } else {
+            vec![]
+        };
+        let pr = ProcessResult::TxnStatusAndRollback {
+            txn_status,
+            rollback_results,
+        };
+        let new_acquired_locks = txn.take_new_locks();
+        let extra = TxnExtra {
+            rollback_reasons: txn.take_rollback_reasons().into_iter().collect(),
+            ..Default::default()
+        };
+        let mut write_data = WriteData::new(txn.into_modifies(), extra);
+        write_data.set_allowed_on_disk_almost_full();
+        Ok(WriteResult {
+            ctx: self.ctx,
+            to_be_write: write_data,
+            rows,
+            pr,
+            lock_info: vec![],
+            released_locks,
+            new_acquired_locks,
+            lock_guards: vec![],
+            response_policy: ResponsePolicy::OnApplied,
+            known_txn_status: write_result_known_txn_status,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use concurrency_manager::ConcurrencyManager;
+    use kvproto::kvrpcpb::Context;
+    use tikv_util::deadline::Deadline;
+
+    use super::*;
+    use crate::storage::{
+        kv::Engine,
+        lock_manager::MockLockManager,
+        mvcc::tests::*,
+        txn::{
+            commands::WriteContext, scheduler::DEFAULT_EXECUTION_DURATION_LIMIT, tests::*,
+            txn_status_cache::TxnStatusCache,
+        },
+        TestEngineBuilder,
+    };
+
+    fn must_run<E: Engine>(
+        engine: &mut E,
+        primary_key: &[u8],
+        lock_ts: impl Into<TimeStamp>,
+        current_ts: impl Into<TimeStamp>,
+        secondary_keys: Vec<&[u8]>,
+        for_update_ts: impl Into<TimeStamp>,
+    ) -> (TxnStatus, Vec<StorageResult<()>>) {
+        let ctx = Context::default();
+        let snapshot = engine.snapshot(Default::default()).unwrap();
+        let lock_ts = lock_ts.into();
+        let current_ts = current_ts.into();
+        let cm = ConcurrencyManager::new(current_ts);
+        let command = CheckTxnStatusAndRollback {
+            ctx: ctx.clone(),
+            primary_key: Key::from_raw(primary_key),
+            lock_ts,
+            caller_start_ts: current_ts,
+            current_ts,
+            rollback_if_not_exist: true,
+            secondary_keys: secondary_keys.into_iter().map(Key::from_raw).collect(),
+            for_update_ts: for_update_ts.into(),
+            deadline: Deadline::from_now(DEFAULT_EXECUTION_DURATION_LIMIT),
+        };
+        let result = command
+            .process_write(
+                snapshot,
+                WriteContext {
+                    lock_mgr: &MockLockManager::new(),
+                    concurrency_manager: cm,
+                    extra_op: Default::default(),
+                    statistics: &mut Default::default(),
+                    async_apply_prewrite: false,
+                    raw_ext: None,
+                    txn_status_cache: &TxnStatusCache::new_for_test(),
+                },
+            )
+            .unwrap();
+        write(engine, &ctx, result.to_be_write.modifies);
+        match result.pr {
+            ProcessResult::TxnStatusAndRollback {
+                txn_status,
+                rollback_results,
+            } => (txn_status, rollback_results),
+            other => panic!("unexpected process result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rollback_secondaries_when_primary_expires() {
+        let ts = TimeStamp::compose;
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        must_acquire_pessimistic_lock(&mut engine, b"primary", b"primary", ts(10, 0), ts(10, 0));
+        must_acquire_pessimistic_lock(&mut engine, b"k1", b"primary", ts(10, 0), ts(10, 0));
+        must_acquire_pessimistic_lock(&mut engine, b"k2", b"primary", ts(10, 0), ts(10, 0));
+
+        // The primary lock's default TTL is 0, so any later physical time is
+        // already past its deadline.
+        let (status, rollback_results) = must_run(
+            &mut engine,
+            b"primary",
+            ts(10, 0),
+            ts(11, 0),
+            vec![b"k1", b"k2"],
+            ts(11, 0),
+        );
+        assert!(status.is_rolled_back());
+        assert_eq!(rollback_results.len(), 2);
+        must_unlocked(&mut engine, b"primary");
+        must_unlocked(&mut engine, b"k1");
+        must_unlocked(&mut engine, b"k2");
+    }
+
+    #[test]
+    fn test_secondaries_untouched_when_primary_still_live() {
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        must_acquire_pessimistic_lock_with_ttl(&mut engine, b"primary", b"primary", 10, 10, 10_000);
+        must_acquire_pessimistic_lock(&mut engine, b"k1", b"primary", 10, 10);
+
+        let (status, rollback_results) =
+            must_run(&mut engine, b"primary", 10, 10, vec![b"k1"], 10);
+        assert!(!status.is_rolled_back());
+        assert!(rollback_results.is_empty());
+        must_pessimistic_locked(&mut engine, b"k1", 10, 10);
+    }
+
+    #[test]
+    fn test_secondaries_untouched_when_primary_committed() {
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        must_acquire_pessimistic_lock(&mut engine, b"primary", b"primary", 10, 10);
+        must_acquire_pessimistic_lock(&mut engine, b"k1", b"primary", 10, 10);
+        must_pessimistic_prewrite_put(
+            &mut engine,
+            b"primary",
+            b"v",
+            b"primary",
+            10,
+            10,
+            kvproto::kvrpcpb::PrewriteRequestPessimisticAction::DoPessimisticCheck,
+        );
+        must_commit(&mut engine, b"primary", 10, 20);
+
+        let (status, rollback_results) =
+            must_run(&mut engine, b"primary", 10, 10, vec![b"k1"], 10);
+        assert_eq!(status, TxnStatus::committed(20.into()));
+        assert!(rollback_results.is_empty());
+        must_pessimistic_locked(&mut engine, b"k1", 10, 10);
+    }
+}
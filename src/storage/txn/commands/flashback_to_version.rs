@@ -4,7 +4,7 @@
 use std::mem;
 
 use tikv_kv::ScanMode;
-use txn_types::{Key, TimeStamp};
+use txn_types::{Key, TimeStamp, TxnExtra};
 
 use crate::storage::{
     kv::WriteData,
@@ -146,7 +146,11 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for FlashbackToVersion {
             )?,
         }
         let rows = txn.modifies.len();
-        let mut write_data = WriteData::from_modifies(txn.into_modifies());
+        let extra = TxnExtra {
+            rollback_reasons: txn.take_rollback_reasons().into_iter().collect(),
+            ..Default::default()
+        };
+        let mut write_data = WriteData::new(txn.into_modifies(), extra);
         // To let the flashback modification could be proposed and applied successfully.
         write_data.extra.allowed_in_flashback = true;
         // To let the CDC treat the flashback modification as an 1PC transaction.
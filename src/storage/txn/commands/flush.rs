@@ -88,6 +88,7 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for Flush {
             old_values,
             one_pc: false,
             allowed_in_flashback: false,
+            rollback_reasons: txn.take_rollback_reasons().into_iter().collect(),
         };
         let new_locks = txn.take_new_locks();
         let guards = txn.take_guards();
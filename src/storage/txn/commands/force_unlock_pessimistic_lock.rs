@@ -0,0 +1,255 @@
+// Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
+
+// #[PerformanceCriticalPath]
+use txn_types::{Key, TimeStamp};
+
+use crate::storage::{
+    kv::WriteData,
+    lock_manager::LockManager,
+    mvcc::{ErrorInner as MvccErrorInner, MvccTxn, PessimisticLockNotFoundReason, SnapshotReader},
+    txn::{
+        commands::{
+            Command, CommandExt, ReaderWithStats, ReleasedLocks, ResponsePolicy, TypedCommand,
+            WriteCommand, WriteContext, WriteResult,
+        },
+        Error, ErrorInner, Result,
+    },
+    ProcessResult, Snapshot,
+};
+
+command! {
+    /// Forcibly release a single pessimistic lock identified by `key`,
+    /// `start_ts` and `for_update_ts`.
+    ///
+    /// Unlike [`PessimisticRollback`](Command::PessimisticRollback), this is
+    /// not part of the normal transaction protocol: it exists for operators
+    /// to clean up an orphan lock (e.g. one left behind by a crashed client)
+    /// that is blocking the resolved ts from advancing, without resorting to
+    /// unsafe, unaudited tools. The caller must set `force` to acknowledge
+    /// this, and the lock is only removed if `start_ts` and `for_update_ts`
+    /// match it exactly.
+    ForceUnlockPessimisticLock:
+        cmd_ty => (),
+        display => {
+            "kv::command::force_unlock_pessimistic_lock key({:?}) @ {} {} force({}) | {:?}",
+            (key, start_ts, for_update_ts, force, ctx),
+        }
+        content => {
+            key: Key,
+            /// The transaction timestamp of the lock to be removed.
+            start_ts: TimeStamp,
+            /// The for_update_ts of the lock to be removed.
+            for_update_ts: TimeStamp,
+            /// Must be explicitly set to `true` for the command to take
+            /// effect. Exists so that this destructive operation can never
+            /// be issued by accident.
+            force: bool,
+        }
+        in_heap => {
+            key,
+        }
+}
+
+impl CommandExt for ForceUnlockPessimisticLock {
+    ctx!();
+    tag!(force_unlock_pessimistic_lock);
+    request_type!(KvForceUnlockPessimisticLock);
+    ts!(start_ts);
+    write_bytes!(key);
+    gen_lock!(key);
+}
+
+impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for ForceUnlockPessimisticLock {
+    fn process_write(self, snapshot: S, context: WriteContext<'_, L>) -> Result<WriteResult> {
+        if !self.force {
+            return Err(Error::from(ErrorInner::ForceUnlockWithoutForceFlag {
+                key: self.key.into_raw()?,
+            }));
+        }
+
+        let mut txn = MvccTxn::new(self.start_ts, context.concurrency_manager);
+        let mut reader = ReaderWithStats::new(
+            SnapshotReader::new_with_ctx(self.start_ts, snapshot, &self.ctx),
+            context.statistics,
+        );
+
+        let lock = reader.load_lock(&self.key)?;
+        let matched = lock.as_ref().is_some_and(|lock| {
+            lock.is_pessimistic_lock()
+                && lock.ts == self.start_ts
+                && lock.for_update_ts == self.for_update_ts
+        });
+
+        if !matched {
+            warn!(
+                "failed to force-unlock pessimistic lock, lock does not match";
+                "key" => %self.key,
+                "start_ts" => self.start_ts,
+                "for_update_ts" => self.for_update_ts,
+                "found_lock" => ?lock,
+            );
+            let reason = match &lock {
+                None => PessimisticLockNotFoundReason::NonLockKeyConflict,
+                Some(lock) if lock.ts != self.start_ts => {
+                    PessimisticLockNotFoundReason::LockTsMismatch
+                }
+                Some(lock) if !lock.is_pessimistic_lock() => {
+                    PessimisticLockNotFoundReason::NonLockKeyConflict
+                }
+                Some(_) => PessimisticLockNotFoundReason::LockForUpdateTsMismatch,
+            };
+            return Err(Error::from_mvcc(MvccErrorInner::PessimisticLockNotFound {
+                start_ts: self.start_ts,
+                key: self.key.into_raw()?,
+                reason,
+            }));
+        }
+
+        let mut released_locks = ReleasedLocks::new();
+        released_locks.push(txn.unlock_key(self.key.clone(), true, TimeStamp::zero()));
+        warn!(
+            "force-unlocked pessimistic lock";
+            "key" => %self.key,
+            "start_ts" => self.start_ts,
+            "for_update_ts" => self.for_update_ts,
+            "ctx" => ?self.ctx,
+        );
+
+        let new_acquired_locks = txn.take_new_locks();
+        let mut write_data = WriteData::from_modifies(txn.into_modifies());
+        write_data.set_allowed_on_disk_almost_full();
+        Ok(WriteResult {
+            ctx: self.ctx,
+            to_be_write: write_data,
+            rows: 1,
+            pr: ProcessResult::Res,
+            lock_info: vec![],
+            released_locks,
+            new_acquired_locks,
+            lock_guards: vec![],
+            response_policy: ResponsePolicy::OnApplied,
+            known_txn_status: vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use concurrency_manager::ConcurrencyManager;
+    use error_code::ErrorCodeExt;
+    use kvproto::kvrpcpb::Context;
+    use tikv_util::deadline::Deadline;
+    use txn_types::Key;
+
+    use super::*;
+    use crate::storage::{
+        kv::Engine,
+        lock_manager::MockLockManager,
+        mvcc::tests::*,
+        txn::{
+            commands::{WriteCommand, WriteContext},
+            scheduler::DEFAULT_EXECUTION_DURATION_LIMIT,
+            tests::*,
+            txn_status_cache::TxnStatusCache,
+        },
+        TestEngineBuilder,
+    };
+
+    fn new_command(
+        key: &[u8],
+        start_ts: impl Into<TimeStamp>,
+        for_update_ts: impl Into<TimeStamp>,
+        force: bool,
+    ) -> ForceUnlockPessimisticLock {
+        ForceUnlockPessimisticLock {
+            ctx: Context::default(),
+            deadline: Deadline::from_now(DEFAULT_EXECUTION_DURATION_LIMIT),
+            key: Key::from_raw(key),
+            start_ts: start_ts.into(),
+            for_update_ts: for_update_ts.into(),
+            force,
+        }
+    }
+
+    fn run<E: Engine>(engine: &mut E, command: ForceUnlockPessimisticLock) -> Result<()> {
+        let snapshot = engine.snapshot(Default::default()).unwrap();
+        let cm = ConcurrencyManager::new(1.into());
+        let lock_mgr = MockLockManager::new();
+        let write_context = WriteContext {
+            lock_mgr: &lock_mgr,
+            concurrency_manager: cm,
+            extra_op: Default::default(),
+            statistics: &mut Default::default(),
+            async_apply_prewrite: false,
+            raw_ext: None,
+            txn_status_cache: &TxnStatusCache::new_for_test(),
+        };
+        let result = command.process_write(snapshot, write_context)?;
+        write(engine, &Context::default(), result.to_be_write.modifies);
+        Ok(())
+    }
+
+    #[test]
+    fn test_force_unlock_requires_force_flag() {
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let key = b"k1";
+        must_acquire_pessimistic_lock(&mut engine, key, key, 1, 1);
+
+        let err = run(&mut engine, new_command(key, 1, 1, false)).unwrap_err();
+        assert_eq!(
+            err.error_code(),
+            error_code::storage::FORCE_UNLOCK_WITHOUT_FORCE_FLAG
+        );
+        must_pessimistic_locked(&mut engine, key, 1, 1);
+    }
+
+    #[test]
+    fn test_force_unlock_mismatched_start_ts() {
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let key = b"k1";
+        must_acquire_pessimistic_lock(&mut engine, key, key, 1, 1);
+
+        run(&mut engine, new_command(key, 2, 1, true)).unwrap_err();
+        must_pessimistic_locked(&mut engine, key, 1, 1);
+    }
+
+    #[test]
+    fn test_force_unlock_mismatched_for_update_ts() {
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let key = b"k1";
+        must_acquire_pessimistic_lock(&mut engine, key, key, 1, 2);
+
+        run(&mut engine, new_command(key, 1, 1, true)).unwrap_err();
+        must_pessimistic_locked(&mut engine, key, 1, 2);
+    }
+
+    #[test]
+    fn test_force_unlock_missing_lock() {
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let key = b"k1";
+
+        run(&mut engine, new_command(key, 1, 1, true)).unwrap_err();
+        must_unlocked(&mut engine, key);
+    }
+
+    #[test]
+    fn test_force_unlock_succeeds_on_exact_match() {
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let key = b"k1";
+        must_acquire_pessimistic_lock(&mut engine, key, key, 1, 1);
+
+        run(&mut engine, new_command(key, 1, 1, true)).unwrap();
+        must_unlocked(&mut engine, key);
+    }
+
+    #[test]
+    fn test_force_unlock_does_not_touch_optimistic_lock() {
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let key = b"k1";
+        let value = b"v1";
+        must_prewrite_put(&mut engine, key, value, key, 1);
+
+        run(&mut engine, new_command(key, 1, 1, true)).unwrap_err();
+        must_locked(&mut engine, key, 1);
+    }
+}
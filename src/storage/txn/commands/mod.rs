@@ -9,12 +9,14 @@ pub(crate) mod acquire_pessimistic_lock_resumed;
 pub(crate) mod atomic_store;
 pub(crate) mod check_secondary_locks;
 pub(crate) mod check_txn_status;
+pub(crate) mod check_txn_status_and_rollback;
 pub(crate) mod cleanup;
 pub(crate) mod commit;
 pub(crate) mod compare_and_swap;
 pub(crate) mod flashback_to_version;
 pub(crate) mod flashback_to_version_read_phase;
 pub(crate) mod flush;
+pub(crate) mod force_unlock_pessimistic_lock;
 pub(crate) mod mvcc_by_key;
 pub(crate) mod mvcc_by_start_ts;
 pub(crate) mod pause;
@@ -40,6 +42,7 @@ pub use acquire_pessimistic_lock_resumed::AcquirePessimisticLockResumed;
 pub use atomic_store::RawAtomicStore;
 pub use check_secondary_locks::CheckSecondaryLocks;
 pub use check_txn_status::CheckTxnStatus;
+pub use check_txn_status_and_rollback::CheckTxnStatusAndRollback;
 pub use cleanup::Cleanup;
 pub use commit::Commit;
 pub use compare_and_swap::RawCompareAndSwap;
@@ -50,6 +53,7 @@ pub use flashback_to_version_read_phase::{
     FlashbackToVersionState,
 };
 pub use flush::Flush;
+pub use force_unlock_pessimistic_lock::ForceUnlockPessimisticLock;
 use kvproto::kvrpcpb::*;
 pub use mvcc_by_key::MvccByKey;
 pub use mvcc_by_start_ts::MvccByStartTs;
@@ -102,6 +106,7 @@ pub enum Command {
     PessimisticRollbackReadPhase(PessimisticRollbackReadPhase),
     TxnHeartBeat(TxnHeartBeat),
     CheckTxnStatus(CheckTxnStatus),
+    CheckTxnStatusAndRollback(CheckTxnStatusAndRollback),
     CheckSecondaryLocks(CheckSecondaryLocks),
     ResolveLockReadPhase(ResolveLockReadPhase),
     ResolveLock(ResolveLock),
@@ -114,6 +119,7 @@ pub enum Command {
     FlashbackToVersionReadPhase(FlashbackToVersionReadPhase),
     FlashbackToVersion(FlashbackToVersion),
     Flush(Flush),
+    ForceUnlockPessimisticLock(ForceUnlockPessimisticLock),
 }
 
 /// A `Command` with its return type, reified as the generic parameter `T`.
@@ -275,7 +281,15 @@ impl From<CleanupRequest> for TypedCommand<()> {
 impl From<BatchRollbackRequest> for TypedCommand<()> {
     fn from(mut req: BatchRollbackRequest) -> Self {
         let keys = req.get_keys().iter().map(|x| Key::from_raw(x)).collect();
-        Rollback::new(keys, req.get_start_version().into(), req.take_context())
+        // `BatchRollbackRequest` has no field for a client-supplied idempotency
+        // token yet, so duplicate-retry detection via `TxnStatusCache` is not
+        // reachable from the wire protocol today; see `Rollback::idempotency_token`.
+        Rollback::new(
+            keys,
+            req.get_start_version().into(),
+            None,
+            req.take_context(),
+        )
     }
 }
 
@@ -298,6 +312,9 @@ impl From<PessimisticRollbackRequest> for TypedCommand<Vec<StorageResult<()>>> {
                 req.get_start_version().into(),
                 req.get_for_update_ts().into(),
                 None,
+                // Overwritten by the scheduler with the live config value
+                // right before this command is dispatched.
+                0,
                 req.take_context(),
             )
         }
@@ -663,6 +680,7 @@ impl Command {
             Command::PessimisticRollbackReadPhase(t) => t,
             Command::TxnHeartBeat(t) => t,
             Command::CheckTxnStatus(t) => t,
+            Command::CheckTxnStatusAndRollback(t) => t,
             Command::CheckSecondaryLocks(t) => t,
             Command::ResolveLockReadPhase(t) => t,
             Command::ResolveLock(t) => t,
@@ -675,6 +693,7 @@ impl Command {
             Command::FlashbackToVersionReadPhase(t) => t,
             Command::FlashbackToVersion(t) => t,
             Command::Flush(t) => t,
+            Command::ForceUnlockPessimisticLock(t) => t,
         }
     }
 
@@ -691,6 +710,7 @@ impl Command {
             Command::PessimisticRollbackReadPhase(t) => t,
             Command::TxnHeartBeat(t) => t,
             Command::CheckTxnStatus(t) => t,
+            Command::CheckTxnStatusAndRollback(t) => t,
             Command::CheckSecondaryLocks(t) => t,
             Command::ResolveLockReadPhase(t) => t,
             Command::ResolveLock(t) => t,
@@ -703,6 +723,7 @@ impl Command {
             Command::FlashbackToVersionReadPhase(t) => t,
             Command::FlashbackToVersion(t) => t,
             Command::Flush(t) => t,
+            Command::ForceUnlockPessimisticLock(t) => t,
         }
     }
 
@@ -739,12 +760,14 @@ impl Command {
             Command::ResolveLockLite(t) => t.process_write(snapshot, context),
             Command::TxnHeartBeat(t) => t.process_write(snapshot, context),
             Command::CheckTxnStatus(t) => t.process_write(snapshot, context),
+            Command::CheckTxnStatusAndRollback(t) => t.process_write(snapshot, context),
             Command::CheckSecondaryLocks(t) => t.process_write(snapshot, context),
             Command::Pause(t) => t.process_write(snapshot, context),
             Command::RawCompareAndSwap(t) => t.process_write(snapshot, context),
             Command::RawAtomicStore(t) => t.process_write(snapshot, context),
             Command::FlashbackToVersion(t) => t.process_write(snapshot, context),
             Command::Flush(t) => t.process_write(snapshot, context),
+            Command::ForceUnlockPessimisticLock(t) => t.process_write(snapshot, context),
             _ => panic!("unsupported write command"),
         }
     }
@@ -842,6 +865,7 @@ impl HeapSize for Command {
                 Command::PessimisticRollbackReadPhase(t) => t.approximate_heap_size(),
                 Command::TxnHeartBeat(t) => t.approximate_heap_size(),
                 Command::CheckTxnStatus(t) => t.approximate_heap_size(),
+                Command::CheckTxnStatusAndRollback(t) => t.approximate_heap_size(),
                 Command::CheckSecondaryLocks(t) => t.approximate_heap_size(),
                 Command::ResolveLockReadPhase(t) => t.approximate_heap_size(),
                 Command::ResolveLock(t) => t.approximate_heap_size(),
@@ -854,6 +878,7 @@ impl HeapSize for Command {
                 Command::FlashbackToVersionReadPhase(t) => t.approximate_heap_size(),
                 Command::FlashbackToVersion(t) => t.approximate_heap_size(),
                 Command::Flush(t) => t.approximate_heap_size(),
+                Command::ForceUnlockPessimisticLock(t) => t.approximate_heap_size(),
             }
     }
 }
@@ -1080,7 +1105,7 @@ pub mod test_util {
         let ctx = Context::default();
         let snap = engine.snapshot(Default::default())?;
         let concurrency_manager = ConcurrencyManager::new(start_ts.into());
-        let cmd = Rollback::new(keys, TimeStamp::from(start_ts), ctx);
+        let cmd = Rollback::new(keys, TimeStamp::from(start_ts), None, ctx);
         let context = WriteContext {
             lock_mgr: &MockLockManager::new(),
             concurrency_manager,
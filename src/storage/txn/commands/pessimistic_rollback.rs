@@ -3,11 +3,13 @@
 // #[PerformanceCriticalPath]
 use std::mem;
 
+use tikv_util::time::Instant;
 use txn_types::{Key, TimeStamp};
 
 use crate::storage::{
     kv::WriteData,
     lock_manager::LockManager,
+    metrics::{PESSIMISTIC_ROLLBACK_APPLY_DURATION_HISTOGRAM, PESSIMISTIC_ROLLBACK_CONTINUATION_COUNTER},
     mvcc::{MvccTxn, Result as MvccResult, SnapshotReader},
     txn::{
         commands::{
@@ -37,6 +39,16 @@ command! {
             for_update_ts: TimeStamp,
             /// The next key to scan using pessimistic rollback read phase.
             scan_key: Option<Key>,
+            /// Caps how many of `keys` a single execution of this command
+            /// rolls back; the scheduler fills this in from
+            /// `storage.pessimistic-rollback-batch-keys-limit` right before
+            /// dispatch, so changes apply to the next command without a
+            /// restart. 0 means unlimited. Any remaining keys are chained
+            /// into a follow-up `PessimisticRollback`, the same way
+            /// `scan_key` chains into a follow-up command, so that one
+            /// oversized client-supplied key list can't block a scheduler
+            /// worker or produce an outsized raft entry.
+            batch_keys_limit: usize,
         }
         in_heap => {
             keys,
@@ -64,11 +76,34 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for PessimisticRollback {
         );
 
         let ctx = mem::take(&mut self.ctx);
-        let keys = mem::take(&mut self.keys);
+        let mut keys = mem::take(&mut self.keys);
+
+        // Roll back at most `batch_keys_limit` keys in this pass, chaining
+        // the rest into a follow-up command below, so that an oversized
+        // client-supplied key list can't block a scheduler worker for too
+        // long or produce an outsized raft entry.
+        let remaining_keys = if self.batch_keys_limit > 0 && keys.len() > self.batch_keys_limit {
+            PESSIMISTIC_ROLLBACK_CONTINUATION_COUNTER
+                .with_label_values(&["batch_limit"])
+                .inc();
+            Some(keys.split_off(self.batch_keys_limit))
+        } else {
+            None
+        };
 
         let rows = keys.len();
+        let begin_instant = Instant::now();
+        // Every lock released here is reported back to the scheduler through
+        // `WriteResult::released_locks`, which feeds `on_release_locks`: if any of
+        // these keys has a request parked in the lock waiting queues, it's popped
+        // and granted in the same scheduler pass that applies this rollback,
+        // instead of waiting for the next round of conflict detection.
         let mut released_locks = ReleasedLocks::new();
-        for key in keys {
+        // Look up all the locks for this batch up front in one `load_locks` call.
+        // It goes through `Snapshot::multi_get_cf`, whose default implementation
+        // still does one point lookup per key; no backend here overrides it.
+        let locks = reader.load_locks(&keys)?;
+        for (key, lock) in keys.into_iter().zip(locks) {
             fail_point!("pessimistic_rollback", |err| Err(
                 crate::storage::mvcc::Error::from(crate::storage::mvcc::txn::make_txn_error(
                     err,
@@ -77,7 +112,7 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for PessimisticRollback {
                 ))
                 .into()
             ));
-            let released_lock: MvccResult<_> = if let Some(lock) = reader.load_lock(&key)? {
+            let released_lock: MvccResult<_> = if let Some(lock) = lock {
                 if lock.is_pessimistic_lock()
                     && lock.ts == self.start_ts
                     && lock.for_update_ts <= self.for_update_ts
@@ -91,8 +126,24 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for PessimisticRollback {
             };
             released_locks.push(released_lock?);
         }
+        PESSIMISTIC_ROLLBACK_APPLY_DURATION_HISTOGRAM.observe(begin_instant.saturating_elapsed_secs());
 
-        let pr = if self.scan_key.is_none() {
+        let pr = if let Some(remaining_keys) = remaining_keys {
+            // Still over the batch limit: keep chaining rather than finish
+            // the scan_key continuation (if any) early.
+            let next_cmd = PessimisticRollback {
+                ctx: ctx.clone(),
+                deadline: self.deadline,
+                keys: remaining_keys,
+                start_ts: self.start_ts,
+                for_update_ts: self.for_update_ts,
+                scan_key: self.scan_key.take(),
+                batch_keys_limit: self.batch_keys_limit,
+            };
+            ProcessResult::NextCommand {
+                cmd: Command::PessimisticRollback(next_cmd),
+            }
+        } else if self.scan_key.is_none() {
             ProcessResult::MultiRes { results: vec![] }
         } else {
             let next_cmd = PessimisticRollbackReadPhase {
@@ -164,6 +215,7 @@ pub mod tests {
             for_update_ts,
             deadline: Deadline::from_now(DEFAULT_EXECUTION_DURATION_LIMIT),
             scan_key: None,
+            batch_keys_limit: 0,
         };
         let lock_mgr = MockLockManager::new();
         let write_context = WriteContext {
@@ -179,6 +231,110 @@ pub mod tests {
         write(engine, &ctx, result.to_be_write.modifies);
     }
 
+    #[test]
+    fn test_pessimistic_rollback_batch() {
+        // A single command rolling back several keys at once should behave the
+        // same as rolling each one back individually: it exercises the batched
+        // `SnapshotReader::load_locks` path used to look up all of the keys'
+        // locks up front.
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let keys: &[&[u8]] = &[b"k1", b"k2", b"k3"];
+
+        for k in keys {
+            must_acquire_pessimistic_lock(&mut engine, k, k, 1, 1);
+            must_pessimistic_locked(&mut engine, k, 1, 1);
+        }
+        // Leave one key unlocked, so the batch mixes hits and misses.
+        must_success(&mut engine, b"k4", 1, 1);
+
+        let ctx = Context::default();
+        let snapshot = engine.snapshot(Default::default()).unwrap();
+        let cm = ConcurrencyManager::new(1.into());
+        let command = crate::storage::txn::commands::PessimisticRollback {
+            ctx: ctx.clone(),
+            keys: keys
+                .iter()
+                .chain([&b"k4"[..]].iter())
+                .map(|k| Key::from_raw(k))
+                .collect(),
+            start_ts: 1.into(),
+            for_update_ts: 1.into(),
+            deadline: Deadline::from_now(DEFAULT_EXECUTION_DURATION_LIMIT),
+            scan_key: None,
+            batch_keys_limit: 0,
+        };
+        let lock_mgr = MockLockManager::new();
+        let write_context = WriteContext {
+            lock_mgr: &lock_mgr,
+            concurrency_manager: cm,
+            extra_op: Default::default(),
+            statistics: &mut Default::default(),
+            async_apply_prewrite: false,
+            raw_ext: None,
+            txn_status_cache: &TxnStatusCache::new_for_test(),
+        };
+        let result = command.process_write(snapshot, write_context).unwrap();
+        write(&mut engine, &ctx, result.to_be_write.modifies);
+
+        for k in keys {
+            must_unlocked(&mut engine, k);
+        }
+    }
+
+    #[test]
+    fn test_pessimistic_rollback_batch_keys_limit() {
+        // With a limit smaller than the key list, only the first
+        // `batch_keys_limit` keys should be rolled back in this pass, and the
+        // rest should come back as a chained follow-up command rather than
+        // being dropped or processed all at once.
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let keys: &[&[u8]] = &[b"k1", b"k2", b"k3"];
+
+        for k in keys {
+            must_acquire_pessimistic_lock(&mut engine, k, k, 1, 1);
+        }
+
+        let ctx = Context::default();
+        let snapshot = engine.snapshot(Default::default()).unwrap();
+        let cm = ConcurrencyManager::new(1.into());
+        let command = crate::storage::txn::commands::PessimisticRollback {
+            ctx: ctx.clone(),
+            keys: keys.iter().map(|k| Key::from_raw(k)).collect(),
+            start_ts: 1.into(),
+            for_update_ts: 1.into(),
+            deadline: Deadline::from_now(DEFAULT_EXECUTION_DURATION_LIMIT),
+            scan_key: None,
+            batch_keys_limit: 2,
+        };
+        let lock_mgr = MockLockManager::new();
+        let write_context = WriteContext {
+            lock_mgr: &lock_mgr,
+            concurrency_manager: cm,
+            extra_op: Default::default(),
+            statistics: &mut Default::default(),
+            async_apply_prewrite: false,
+            raw_ext: None,
+            txn_status_cache: &TxnStatusCache::new_for_test(),
+        };
+        let result = command.process_write(snapshot, write_context).unwrap();
+        assert_eq!(result.rows, 2);
+        write(&mut engine, &ctx, result.to_be_write.modifies);
+
+        must_unlocked(&mut engine, keys[0]);
+        must_unlocked(&mut engine, keys[1]);
+        must_pessimistic_locked(&mut engine, keys[2], 1, 1);
+
+        match result.pr {
+            ProcessResult::NextCommand {
+                cmd: Command::PessimisticRollback(next),
+            } => {
+                assert_eq!(next.keys, vec![Key::from_raw(keys[2])]);
+                assert_eq!(next.batch_keys_limit, 2);
+            }
+            other => panic!("expected a chained PessimisticRollback, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_pessimistic_rollback() {
         let mut engine = TestEngineBuilder::new().build().unwrap();
@@ -1,9 +1,11 @@
 // Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
 
 // #[PerformanceCriticalPath]
+use tikv_util::time::Instant;
 use txn_types::{Key, TimeStamp};
 
 use crate::storage::{
+    metrics::{PESSIMISTIC_ROLLBACK_CONTINUATION_COUNTER, PESSIMISTIC_ROLLBACK_SCAN_DURATION_HISTOGRAM},
     mvcc::{metrics::ScanLockReadTimeSource::pessimistic_rollback, MvccReader},
     txn,
     txn::{
@@ -44,6 +46,7 @@ impl<S: Snapshot> ReadCommand<S> for PessimisticRollbackReadPhase {
     fn process_read(self, snapshot: S, statistics: &mut Statistics) -> Result<ProcessResult> {
         let tag = self.tag();
         let mut reader = MvccReader::new_with_ctx(snapshot, Some(ScanMode::Forward), &self.ctx);
+        let begin_instant = Instant::now();
         let res = reader
             .scan_locks(
                 self.scan_key.as_ref(),
@@ -57,6 +60,7 @@ impl<S: Snapshot> ReadCommand<S> for PessimisticRollbackReadPhase {
                 pessimistic_rollback,
             )
             .map_err(txn::Error::from);
+        PESSIMISTIC_ROLLBACK_SCAN_DURATION_HISTOGRAM.observe(begin_instant.saturating_elapsed_secs());
         statistics.add(&reader.statistics);
         let (locks, has_remain) = res?;
         tls_collect_keyread_histogram_vec(tag.get_str(), locks.len() as f64);
@@ -66,6 +70,9 @@ impl<S: Snapshot> ReadCommand<S> for PessimisticRollbackReadPhase {
         } else {
             let next_scan_key = if has_remain {
                 // There might be more locks.
+                PESSIMISTIC_ROLLBACK_CONTINUATION_COUNTER
+                    .with_label_values(&["scan_key"])
+                    .inc();
                 locks.last().map(|(k, _lock)| k.clone())
             } else {
                 // All locks are scanned
@@ -78,6 +85,9 @@ impl<S: Snapshot> ReadCommand<S> for PessimisticRollbackReadPhase {
                 start_ts: self.start_ts,
                 for_update_ts: self.for_update_ts,
                 scan_key: next_scan_key,
+                // Overwritten by the scheduler with the live config value
+                // right before this command is dispatched.
+                batch_keys_limit: 0,
             };
             Ok(ProcessResult::NextCommand {
                 cmd: Command::PessimisticRollback(next_cmd),
@@ -742,6 +742,7 @@ impl<K: PrewriteKind> Prewriter<K> {
                 // Set one_pc flag in TxnExtra to let CDC skip handling the resolver.
                 one_pc: self.try_one_pc,
                 allowed_in_flashback: false,
+                rollback_reasons: Default::default(),
             };
             // Here the lock guards are taken and will be released after the write finishes.
             // If an error (KeyIsLocked or WriteConflict) occurs before, these lock guards
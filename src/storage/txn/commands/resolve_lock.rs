@@ -2,7 +2,7 @@
 
 // #[PerformanceCriticalPath]
 use collections::HashMap;
-use txn_types::{Key, Lock, TimeStamp};
+use txn_types::{Key, Lock, RollbackReason, TimeStamp, TxnExtra};
 
 use crate::storage::{
     kv::WriteData,
@@ -106,6 +106,7 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for ResolveLock {
                     current_key.clone(),
                     TimeStamp::zero(),
                     false,
+                    RollbackReason::LockTtlExpired,
                 )?
             } else if commit_ts > current_lock.ts {
                 // Continue to resolve locks if the not found committed locks are pessimistic
@@ -154,7 +155,11 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for ResolveLock {
             }
         };
         let new_acquired_locks = txn.take_new_locks();
-        let mut write_data = WriteData::from_modifies(txn.into_modifies());
+        let extra = TxnExtra {
+            rollback_reasons: txn.take_rollback_reasons().into_iter().collect(),
+            ..Default::default()
+        };
+        let mut write_data = WriteData::new(txn.into_modifies(), extra);
         write_data.set_allowed_on_disk_almost_full();
         Ok(WriteResult {
             ctx,
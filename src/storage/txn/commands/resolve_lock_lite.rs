@@ -1,7 +1,7 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
 // #[PerformanceCriticalPath]
-use txn_types::{Key, TimeStamp};
+use txn_types::{Key, RollbackReason, TimeStamp, TxnExtra};
 
 use crate::storage::{
     kv::WriteData,
@@ -65,7 +65,14 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for ResolveLockLite {
             released_locks.push(if !self.commit_ts.is_zero() {
                 commit(&mut txn, &mut reader, key, self.commit_ts)?
             } else {
-                cleanup(&mut txn, &mut reader, key, TimeStamp::zero(), false)?
+                cleanup(
+                    &mut txn,
+                    &mut reader,
+                    key,
+                    TimeStamp::zero(),
+                    false,
+                    RollbackReason::ClientInitiated,
+                )?
             });
         }
 
@@ -75,7 +82,11 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for ResolveLockLite {
             vec![]
         };
         let new_acquired_locks = txn.take_new_locks();
-        let mut write_data = WriteData::from_modifies(txn.into_modifies());
+        let extra = TxnExtra {
+            rollback_reasons: txn.take_rollback_reasons().into_iter().collect(),
+            ..Default::default()
+        };
+        let mut write_data = WriteData::new(txn.into_modifies(), extra);
         write_data.set_allowed_on_disk_almost_full();
         Ok(WriteResult {
             ctx: self.ctx,
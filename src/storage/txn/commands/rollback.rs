@@ -1,7 +1,9 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
 // #[PerformanceCriticalPath]
-use txn_types::{Key, TimeStamp};
+use std::time::SystemTime;
+
+use txn_types::{Key, RollbackReason, TimeStamp, TxnExtra};
 
 use crate::storage::{
     kv::WriteData,
@@ -32,6 +34,17 @@ command! {
             keys: Vec<Key>,
             /// The transaction timestamp.
             start_ts: TimeStamp,
+            /// An optional client-supplied idempotency token. If the same
+            /// token was already recorded in the [`TxnStatusCache`] by an
+            /// earlier execution of this rollback, the request is treated
+            /// as a duplicate retry and the writes are skipped.
+            ///
+            /// Note: no caller sets this yet, since `BatchRollbackRequest`
+            /// has no field for it; wiring it up to the wire protocol
+            /// requires a `kvproto` change.
+            ///
+            /// [`TxnStatusCache`]: crate::storage::txn::txn_status_cache::TxnStatusCache
+            idempotency_token: Option<u64>,
         }
         in_heap => {
             keys,
@@ -49,6 +62,28 @@ impl CommandExt for Rollback {
 
 impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for Rollback {
     fn process_write(self, snapshot: S, context: WriteContext<'_, L>) -> Result<WriteResult> {
+        if let Some(token) = self.idempotency_token {
+            if context
+                .txn_status_cache
+                .get_rollback_record_no_promote(token)
+            {
+                // This exact rollback was already executed for a previous, duplicate
+                // delivery of the same request. Skip re-applying the writes.
+                return Ok(WriteResult {
+                    ctx: self.ctx,
+                    to_be_write: WriteData::default(),
+                    rows: 0,
+                    pr: ProcessResult::Res,
+                    lock_info: vec![],
+                    released_locks: ReleasedLocks::new(),
+                    new_acquired_locks: vec![],
+                    lock_guards: vec![],
+                    response_policy: ResponsePolicy::OnApplied,
+                    known_txn_status: vec![],
+                });
+            }
+        }
+
         let mut txn = MvccTxn::new(self.start_ts, context.concurrency_manager);
         let mut reader = ReaderWithStats::new(
             SnapshotReader::new_with_ctx(self.start_ts, snapshot, &self.ctx),
@@ -60,13 +95,29 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for Rollback {
         for k in self.keys {
             // Rollback is called only if the transaction is known to fail. Under the
             // circumstances, the rollback record needn't be protected.
-            let released_lock = cleanup(&mut txn, &mut reader, k, TimeStamp::zero(), false)?;
+            let released_lock = cleanup(
+                &mut txn,
+                &mut reader,
+                k,
+                TimeStamp::zero(),
+                false,
+                RollbackReason::ClientInitiated,
+            )?;
             released_locks.push(released_lock);
         }
 
         let new_acquired_locks = txn.take_new_locks();
-        let mut write_data = WriteData::from_modifies(txn.into_modifies());
+        let extra = TxnExtra {
+            rollback_reasons: txn.take_rollback_reasons().into_iter().collect(),
+            ..Default::default()
+        };
+        let mut write_data = WriteData::new(txn.into_modifies(), extra);
         write_data.set_allowed_on_disk_almost_full();
+        if let Some(token) = self.idempotency_token {
+            context
+                .txn_status_cache
+                .insert_rollback_record(token, SystemTime::now());
+        }
         Ok(WriteResult {
             ctx: self.ctx,
             to_be_write: write_data,
@@ -84,9 +135,20 @@ impl<S: Snapshot, L: LockManager> WriteCommand<S, L> for Rollback {
 
 #[cfg(test)]
 mod tests {
-    use kvproto::kvrpcpb::PrewriteRequestPessimisticAction::*;
+    use concurrency_manager::ConcurrencyManager;
+    use kvproto::kvrpcpb::{Context, PrewriteRequestPessimisticAction::*};
+    use tikv_util::deadline::Deadline;
 
-    use crate::storage::{txn::tests::*, TestEngineBuilder};
+    use super::*;
+    use crate::storage::{
+        kv::Engine,
+        lock_manager::MockLockManager,
+        txn::{
+            scheduler::DEFAULT_EXECUTION_DURATION_LIMIT, tests::*,
+            txn_status_cache::TxnStatusCache,
+        },
+        TestEngineBuilder,
+    };
 
     #[test]
     fn rollback_lock_with_existing_rollback() {
@@ -101,4 +163,47 @@ mod tests {
         must_pessimistic_prewrite_put(&mut engine, k2, v, k1, 10, 10, SkipPessimisticCheck);
         must_rollback(&mut engine, k2, 10, false);
     }
+
+    #[test]
+    fn rollback_with_idempotency_token_skips_duplicate_retry() {
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let k = b"k";
+
+        must_acquire_pessimistic_lock(&mut engine, k, k, 10, 10);
+
+        let txn_status_cache = TxnStatusCache::new_for_test();
+        let run_rollback = |engine: &mut _| {
+            let ctx = Context::default();
+            let snapshot = engine.snapshot(Default::default()).unwrap();
+            let cm = ConcurrencyManager::new(10.into());
+            let command = Rollback {
+                ctx: ctx.clone(),
+                keys: vec![Key::from_raw(k)],
+                start_ts: 10.into(),
+                idempotency_token: Some(1),
+                deadline: Deadline::from_now(DEFAULT_EXECUTION_DURATION_LIMIT),
+            };
+            let write_context = WriteContext {
+                lock_mgr: &MockLockManager::new(),
+                concurrency_manager: cm,
+                extra_op: Default::default(),
+                statistics: &mut Default::default(),
+                async_apply_prewrite: false,
+                raw_ext: None,
+                txn_status_cache: &txn_status_cache,
+            };
+            command.process_write(snapshot, write_context).unwrap()
+        };
+
+        // The first execution actually rolls back the lock.
+        let result = run_rollback(&mut engine);
+        assert_eq!(result.rows, 1);
+        assert!(!result.to_be_write.modifies.is_empty());
+
+        // A duplicate retry carrying the same token is recognized and doesn't
+        // attempt to roll back the (already resolved) lock again.
+        let result = run_rollback(&mut engine);
+        assert_eq!(result.rows, 0);
+        assert!(result.to_be_write.modifies.is_empty());
+    }
 }
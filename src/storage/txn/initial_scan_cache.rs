@@ -0,0 +1,189 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! A short-lived cache of completed "initial scan" results.
+//!
+//! Both CDC changefeeds and log-backup (`backup-stream`) tasks start a
+//! subscription over a region by doing a full incremental scan (everything
+//! committed since the subscription's start ts) before switching over to
+//! consuming live raft apply events. When a changefeed and a log-backup task
+//! subscribe the same region at a close-enough start ts, both end up
+//! redundantly scanning (and reading from disk) the same data.
+//!
+//! [`InitialScanCache`] lets the consumer that finishes its scan first save
+//! the scanned [`TxnEntry`] batch here, keyed by the exact `(region_id,
+//! start_ts)` pair. A second consumer that requests the same key shortly
+//! after can reuse it instead of re-scanning. Entries are kept only for
+//! [`CACHE_ITEM_KEEP_TIME`]; there's no point keeping them longer, since a
+//! cache miss just falls back to a normal scan.
+//!
+//! As of now, only `backup-stream` populates and consults this cache (see
+//! `InitialDataLoader` in the `backup-stream` crate); wiring CDC's
+//! `Initializer` to do the same is left as a follow-up, since CDC's scan
+//! loop interleaves batch delivery with resolver bookkeeping in a way that
+//! needs more careful handling of partial-batch reuse.
+
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use parking_lot::Mutex;
+use tikv_util::lru::{self, GetTailEntry, LruCache};
+use txn_types::TimeStamp;
+
+use super::TxnEntry;
+
+/// A cached result should not be kept for longer than this, so that a
+/// consumer can never reuse a scan result that's stale enough to likely be
+/// a different "initial scan" attempt entirely.
+const CACHE_ITEM_KEEP_TIME: Duration = Duration::from_secs(10);
+
+const CACHE_SLOTS: usize = 16;
+
+struct CacheEntry {
+    entries: Arc<Vec<TxnEntry>>,
+    insert_time: u64,
+}
+
+struct InitialScanCacheEvictPolicy {
+    keep_time_millis: u64,
+}
+
+impl InitialScanCacheEvictPolicy {
+    fn now_millis() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+    }
+}
+
+impl lru::EvictPolicy<(u64, TimeStamp), CacheEntry> for InitialScanCacheEvictPolicy {
+    fn should_evict(
+        &self,
+        current_size: usize,
+        capacity: usize,
+        get_tail_entry: &impl GetTailEntry<(u64, TimeStamp), CacheEntry>,
+    ) -> bool {
+        if let Some((_, v)) = get_tail_entry.get_tail_entry() {
+            if Self::now_millis() > self.keep_time_millis + v.insert_time {
+                return true;
+            }
+        }
+        current_size > capacity
+    }
+}
+
+type CacheSlot =
+    LruCache<(u64, TimeStamp), CacheEntry, lru::CountTracker, InitialScanCacheEvictPolicy>;
+
+/// A short-lived, capacity-bounded cache of initial-scan results, shared by
+/// whichever initial-scan consumers (currently only `backup-stream`) choose
+/// to use it.
+///
+/// Sharded by region id for the same reason as
+/// [`TxnStatusCache`](super::txn_status_cache::TxnStatusCache): to keep the
+/// lock fine-grained.
+pub struct InitialScanCache {
+    slots: Vec<Mutex<CacheSlot>>,
+}
+
+impl InitialScanCache {
+    pub fn new(capacity: usize) -> Self {
+        Self::with_slots(CACHE_SLOTS, capacity)
+    }
+
+    fn with_slots(slots: usize, capacity: usize) -> Self {
+        let per_slot_capacity = (capacity / slots).max(1);
+        Self {
+            slots: (0..slots)
+                .map(|_| {
+                    Mutex::new(LruCache::new(
+                        per_slot_capacity,
+                        0,
+                        lru::CountTracker::default(),
+                        InitialScanCacheEvictPolicy {
+                            keep_time_millis: CACHE_ITEM_KEEP_TIME.as_millis() as u64,
+                        },
+                    ))
+                })
+                .collect(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn new_for_test() -> Self {
+        Self::with_slots(1, 1024)
+    }
+
+    fn slot_index(&self, region_id: u64) -> usize {
+        fxhash::hash(&region_id) % self.slots.len()
+    }
+
+    /// Save the result of a completed initial scan, so that a second
+    /// consumer scanning the same region at the same start ts can reuse it.
+    ///
+    /// If an entry already exists for this key, it's left untouched: the
+    /// first writer wins, and whichever entries it saved are just as valid
+    /// for a later consumer as a fresh scan would be.
+    pub fn insert(&self, region_id: u64, start_ts: TimeStamp, entries: Arc<Vec<TxnEntry>>) {
+        let insert_time = InitialScanCacheEvictPolicy::now_millis();
+        let mut slot = self.slots[self.slot_index(region_id)].lock();
+        slot.insert_if_not_exist(
+            (region_id, start_ts),
+            CacheEntry {
+                entries,
+                insert_time,
+            },
+        );
+    }
+
+    /// Look up a cached initial-scan result, without promoting it in the
+    /// LRU order: a cache hit here doesn't make the entry any less stale,
+    /// so it shouldn't be kept around any longer just because it was read.
+    pub fn get(&self, region_id: u64, start_ts: TimeStamp) -> Option<Arc<Vec<TxnEntry>>> {
+        let now = InitialScanCacheEvictPolicy::now_millis();
+        let keep_time_millis = CACHE_ITEM_KEEP_TIME.as_millis() as u64;
+        let slot = self.slots[self.slot_index(region_id)].lock();
+        slot.get_no_promote(&(region_id, start_ts))
+            .filter(|e| now <= keep_time_millis + e.insert_time)
+            .map(|e| e.entries.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_get() {
+        let c = InitialScanCache::new_for_test();
+        assert!(c.get(1, 10.into()).is_none());
+
+        let entries = Arc::new(vec![]);
+        c.insert(1, 10.into(), entries.clone());
+        assert!(Arc::ptr_eq(&c.get(1, 10.into()).unwrap(), &entries));
+
+        // A different start_ts on the same region, or the same start_ts on a
+        // different region, is a different key.
+        assert!(c.get(1, 11.into()).is_none());
+        assert!(c.get(2, 10.into()).is_none());
+
+        // The first writer wins: a later insert for the same key is ignored.
+        let other_entries = Arc::new(vec![]);
+        c.insert(1, 10.into(), other_entries.clone());
+        assert!(Arc::ptr_eq(&c.get(1, 10.into()).unwrap(), &entries));
+        assert!(!Arc::ptr_eq(&c.get(1, 10.into()).unwrap(), &other_entries));
+    }
+
+    #[test]
+    fn test_evicting_by_capacity() {
+        let c = InitialScanCache::with_slots(1, 2);
+        c.insert(1, 1.into(), Arc::new(vec![]));
+        c.insert(2, 1.into(), Arc::new(vec![]));
+        c.insert(3, 1.into(), Arc::new(vec![]));
+        assert!(c.get(1, 1.into()).is_none());
+        assert!(c.get(2, 1.into()).is_some());
+        assert!(c.get(3, 1.into()).is_some());
+    }
+}
@@ -4,6 +4,7 @@
 
 pub mod commands;
 pub mod flow_controller;
+pub mod initial_scan_cache;
 pub mod sched_pool;
 pub mod scheduler;
 pub mod txn_status_cache;
@@ -69,6 +70,10 @@ pub enum ProcessResult {
     TxnStatus {
         txn_status: TxnStatus,
     },
+    TxnStatusAndRollback {
+        txn_status: TxnStatus,
+        rollback_results: Vec<StorageResult<()>>,
+    },
     NextCommand {
         cmd: Command,
     },
@@ -149,6 +154,12 @@ pub enum ErrorInner {
 
     #[error("region {0} not prepared the flashback")]
     FlashbackNotPrepared(u64),
+
+    #[error(
+        "force-unlocking pessimistic lock on key {} requires the force flag to be set",
+        log_wrappers::Value::key(.key)
+    )]
+    ForceUnlockWithoutForceFlag { key: Vec<u8> },
 }
 
 impl ErrorInner {
@@ -188,6 +199,9 @@ impl ErrorInner {
             ErrorInner::FlashbackNotPrepared(region_id) => {
                 Some(ErrorInner::FlashbackNotPrepared(region_id))
             }
+            ErrorInner::ForceUnlockWithoutForceFlag { ref key } => {
+                Some(ErrorInner::ForceUnlockWithoutForceFlag { key: key.clone() })
+            }
             ErrorInner::Other(_) | ErrorInner::ProtoBuf(_) | ErrorInner::Io(_) => None,
         }
     }
@@ -242,6 +256,9 @@ impl ErrorCodeExt for Error {
                 error_code::storage::MAX_TIMESTAMP_NOT_SYNCED
             }
             ErrorInner::FlashbackNotPrepared(_) => error_code::storage::FLASHBACK_NOT_PREPARED,
+            ErrorInner::ForceUnlockWithoutForceFlag { .. } => {
+                error_code::storage::FORCE_UNLOCK_WITHOUT_FORCE_FLAG
+            }
         }
     }
 }
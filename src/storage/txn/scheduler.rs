@@ -281,6 +281,32 @@ struct TxnSchedulerInner<L: LockManager> {
     txn_status_cache: TxnStatusCache,
 
     memory_quota: Arc<MemoryQuota>,
+
+    // Caps how many rollback/resolve-lock family commands may be in flight at
+    // once; see `is_rollback_family`. 0 means unlimited.
+    rollback_concurrency_limit: CachePadded<AtomicUsize>,
+    rollback_concurrency: CachePadded<AtomicUsize>,
+
+    // Live value of `pessimistic_rollback_batch_keys_limit`, applied to each
+    // `PessimisticRollback` command right before it's dispatched. 0 means
+    // unlimited.
+    pessimistic_rollback_batch_keys_limit: CachePadded<AtomicUsize>,
+}
+
+/// Whether `tag` belongs to the rollback/resolve-lock family that
+/// `scheduler_rollback_concurrency_limit` throttles. An abort storm (e.g. a
+/// burst of conflicting pessimistic transactions all rolling back at once)
+/// mostly consists of these commands, so limiting just this family is enough
+/// to stop it from monopolizing the scheduler worker pool.
+fn is_rollback_family(tag: CommandKind) -> bool {
+    matches!(
+        tag,
+        CommandKind::rollback
+            | CommandKind::pessimistic_rollback
+            | CommandKind::pessimistic_rollback_read_phase
+            | CommandKind::resolve_lock
+            | CommandKind::resolve_lock_lite
+    )
 }
 
 #[inline]
@@ -325,6 +351,8 @@ impl<L: LockManager> TxnSchedulerInner<L> {
         SCHED_WRITING_BYTES_GAUGE.set(running_write_bytes - tctx.write_bytes as i64);
         SCHED_CONTEX_GAUGE.dec();
 
+        self.release_rollback_concurrency(tctx.tag);
+
         tctx
     }
 
@@ -366,6 +394,48 @@ impl<L: LockManager> TxnSchedulerInner<L> {
             || self.flow_controller.should_drop(region_id)
     }
 
+    /// Tries to admit a command of the rollback/resolve-lock family under
+    /// `scheduler_rollback_concurrency_limit`. Returns `false` if the limit is
+    /// exceeded and the command should be rejected with `SchedTooBusy`.
+    ///
+    /// Every call that returns `true` for a rollback-family `tag` must be
+    /// matched by exactly one later call into `dequeue_task_context`, which is
+    /// where the count gets released.
+    fn try_acquire_rollback_concurrency(&self, tag: CommandKind) -> bool {
+        if !is_rollback_family(tag) {
+            return true;
+        }
+        let limit = self.rollback_concurrency_limit.load(Ordering::Relaxed);
+        let current = self.rollback_concurrency.fetch_add(1, Ordering::AcqRel) + 1;
+        if limit > 0 && current > limit {
+            self.rollback_concurrency.fetch_sub(1, Ordering::AcqRel);
+            return false;
+        }
+        true
+    }
+
+    fn set_rollback_concurrency_limit(&self, limit: usize) {
+        self.rollback_concurrency_limit.store(limit, Ordering::Relaxed);
+    }
+
+    fn pessimistic_rollback_batch_keys_limit(&self) -> usize {
+        self.pessimistic_rollback_batch_keys_limit
+            .load(Ordering::Relaxed)
+    }
+
+    fn set_pessimistic_rollback_batch_keys_limit(&self, limit: usize) {
+        self.pessimistic_rollback_batch_keys_limit
+            .store(limit, Ordering::Relaxed);
+    }
+
+    /// Releases the slot acquired by a successful `try_acquire_rollback_concurrency`
+    /// call for `tag`. No-op for tags outside the rollback/resolve-lock family.
+    fn release_rollback_concurrency(&self, tag: CommandKind) {
+        if is_rollback_family(tag) {
+            self.rollback_concurrency.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+
     /// Tries to acquire all the required latches for a command when waken up by
     /// another finished command.
     ///
@@ -475,6 +545,15 @@ impl<E: Engine, L: LockManager> TxnScheduler<E, L> {
             feature_gate,
             txn_status_cache: TxnStatusCache::new(config.txn_status_cache_capacity),
             memory_quota: Arc::new(MemoryQuota::new(config.memory_quota.0 as _)),
+            rollback_concurrency_limit: AtomicUsize::new(
+                config.scheduler_rollback_concurrency_limit,
+            )
+            .into(),
+            rollback_concurrency: AtomicUsize::new(0).into(),
+            pessimistic_rollback_batch_keys_limit: AtomicUsize::new(
+                config.pessimistic_rollback_batch_keys_limit,
+            )
+            .into(),
         });
 
         SCHED_TXN_MEMORY_QUOTA
@@ -508,6 +587,14 @@ impl<E: Engine, L: LockManager> TxnScheduler<E, L> {
         self.inner.memory_quota.set_capacity(cap)
     }
 
+    pub(in crate::storage) fn set_rollback_concurrency_limit(&self, limit: usize) {
+        self.inner.set_rollback_concurrency_limit(limit);
+    }
+
+    pub(in crate::storage) fn set_pessimistic_rollback_batch_keys_limit(&self, limit: usize) {
+        self.inner.set_pessimistic_rollback_batch_keys_limit(limit);
+    }
+
     pub(in crate::storage) fn run_cmd(&self, cmd: Command, callback: StorageCallback) {
         let tag = cmd.tag();
         let fail_with_busy = |callback: StorageCallback| {
@@ -525,12 +612,18 @@ impl<E: Engine, L: LockManager> TxnScheduler<E, L> {
             fail_with_busy(callback);
             return;
         }
+        if !self.inner.try_acquire_rollback_concurrency(tag) {
+            SCHED_COMMAND_CONCURRENCY_THROTTLED_COUNTER_VEC.get(tag).inc();
+            fail_with_busy(callback);
+            return;
+        }
         let cid = self.inner.gen_id();
         let mut task = Task::new(cid, cmd);
         if task
             .alloc_memory_quota(self.inner.memory_quota.clone())
             .is_err()
         {
+            self.inner.release_rollback_concurrency(tag);
             fail_with_busy(callback);
             return;
         }
@@ -756,6 +849,11 @@ impl<E: Engine, L: LockManager> TxnScheduler<E, L> {
                     }
                     task.set_extra_op(extra_op);
 
+                    if let Command::PessimisticRollback(cmd) = task.cmd_mut() {
+                        cmd.batch_keys_limit =
+                            sched.inner.pessimistic_rollback_batch_keys_limit();
+                    }
+
                     debug!(
                         "process cmd with snapshot";
                         "cid" => task.cid(), "term" => ?term, "extra_op" => ?extra_op,
@@ -2147,13 +2245,14 @@ mod tests {
                 Context::default(),
             )
             .into(),
-            commands::Rollback::new(vec![Key::from_raw(b"k")], 10.into(), Context::default())
+            commands::Rollback::new(vec![Key::from_raw(b"k")], 10.into(), None, Context::default())
                 .into(),
             commands::PessimisticRollback::new(
                 vec![Key::from_raw(b"k")],
                 10.into(),
                 20.into(),
                 None,
+                0,
                 Context::default(),
             )
             .into(),
@@ -2302,6 +2401,44 @@ mod tests {
         assert!(tctx.cb.is_none());
     }
 
+    #[test]
+    fn test_scheduler_rollback_concurrency_limit() {
+        let config = Config {
+            scheduler_concurrency: 1024,
+            scheduler_worker_pool_size: 1,
+            scheduler_pending_write_threshold: ReadableSize(100 * 1024 * 1024),
+            enable_async_apply_prewrite: false,
+            scheduler_rollback_concurrency_limit: 1,
+            ..Default::default()
+        };
+        let (scheduler, _engine) = new_test_scheduler_with_config(config);
+
+        // Hold the latch for key "a" so the first rollback command can never
+        // acquire its latches, keeping it (and its concurrency slot) in flight.
+        let mut lock = Lock::new(&[Key::from_raw(b"a")]);
+        let holder_cid = scheduler.inner.gen_id();
+        assert!(scheduler.inner.latches.acquire(&mut lock, holder_cid));
+
+        let new_rollback = || -> Command {
+            commands::Rollback::new(vec![Key::from_raw(b"a")], 10.into(), None, Context::default())
+                .into()
+        };
+
+        let (cb1, _f1) = paired_future_callback();
+        scheduler.run_cmd(new_rollback(), StorageCallback::Boolean(cb1));
+
+        // A second rollback command should be rejected: the first one is still
+        // occupying the only available concurrency slot.
+        let (cb2, f2) = paired_future_callback();
+        scheduler.run_cmd(new_rollback(), StorageCallback::Boolean(cb2));
+        assert!(matches!(
+            block_on(f2).unwrap(),
+            Err(StorageError(box StorageErrorInner::SchedTooBusy))
+        ));
+
+        scheduler.inner.latches.release(&lock, holder_cid, None);
+    }
+
     #[test]
     fn test_pool_available_deadline() {
         let (scheduler, _) = new_test_scheduler();
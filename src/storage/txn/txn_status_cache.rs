@@ -7,12 +7,20 @@
 //! to quickly find out the transaction status in some cases.
 //!
 //! > **Note:**
-//! > * Currently, only committed transactions are cached. We may also cache
-//! > rolled-back transactions in the future.
+//! > * Currently, only committed transactions are cached by `start_ts`. We
+//! > may also cache rolled-back transactions in the future.
 //! > * Currently, the cache is only used to filter unnecessary stale prewrite
 //! > requests. We will also consider use the cache for other purposes in the
 //! > future.
 //!
+//! In addition to the `start_ts -> commit_ts` cache described above,
+//! `TxnStatusCache` also keeps a second, independent cache that records
+//! rollback requests by an idempotency token supplied by the caller (see
+//! [`TxnStatusCache::insert_rollback_record`]). This lets a rollback request
+//! that's retried externally (e.g. by a client after a timeout) recognize
+//! that it has already been executed, instead of re-applying the rollback
+//! writes.
+//!
 //! ## Why we need this?
 //!
 //! ### For filtering out unwanted late-arrived stale prewrite requests
@@ -129,6 +137,15 @@ const TXN_STATUS_CACHE_SLOTS: usize = 128;
 /// about why this is needed.
 const CACHE_ITEMS_REQUIRED_KEEP_TIME: Duration = Duration::from_secs(30);
 
+/// Implemented by the value types stored in the caches of [`TxnStatusCache`],
+/// so that [`TxnStatusCacheEvictPolicy`] can be shared between them instead of
+/// being hard-coded to a single value type.
+trait HasInsertTime {
+    /// The system timestamp in milliseconds when the entry was inserted to
+    /// the cache.
+    fn insert_time(&self) -> u64;
+}
+
 struct CacheEntry {
     commit_ts: TimeStamp,
     /// The system timestamp in milliseconds when the entry is inserted to the
@@ -136,6 +153,25 @@ struct CacheEntry {
     insert_time: u64,
 }
 
+impl HasInsertTime for CacheEntry {
+    fn insert_time(&self) -> u64 {
+        self.insert_time
+    }
+}
+
+/// A record of a rollback request that has already been executed, indexed by
+/// the caller-supplied idempotency token. See
+/// [`TxnStatusCache::insert_rollback_record`].
+struct RollbackRecord {
+    insert_time: u64,
+}
+
+impl HasInsertTime for RollbackRecord {
+    fn insert_time(&self) -> u64 {
+        self.insert_time
+    }
+}
+
 /// Defines the policy to evict expired entries from the cache.
 /// [`TxnStatusCache`] needs to keep entries for a while, so the common
 /// policy that only limiting capacity is not proper to be used here.
@@ -181,18 +217,18 @@ impl TxnStatusCacheEvictPolicy {
     }
 }
 
-impl lru::EvictPolicy<TimeStamp, CacheEntry> for TxnStatusCacheEvictPolicy {
+impl<K, V: HasInsertTime> lru::EvictPolicy<K, V> for TxnStatusCacheEvictPolicy {
     fn should_evict(
         &self,
         current_size: usize,
         capacity: usize,
-        get_tail_entry: &impl GetTailEntry<TimeStamp, CacheEntry>,
+        get_tail_entry: &impl GetTailEntry<K, V>,
     ) -> bool {
         // See how much time has been elapsed since the tail entry is inserted.
         // If it's long enough, remove it.
         if let Some((_, v)) = get_tail_entry.get_tail_entry() {
             if self.now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
-                > self.required_keep_time_millis + v.insert_time
+                > self.required_keep_time_millis + v.insert_time()
             {
                 return true;
             }
@@ -205,6 +241,8 @@ impl lru::EvictPolicy<TimeStamp, CacheEntry> for TxnStatusCacheEvictPolicy {
 
 type TxnStatusCacheSlot =
     LruCache<TimeStamp, CacheEntry, lru::CountTracker, TxnStatusCacheEvictPolicy>;
+type RollbackRecordCacheSlot =
+    LruCache<u64, RollbackRecord, lru::CountTracker, TxnStatusCacheEvictPolicy>;
 
 /// The cache for storing transaction status. It holds recent
 /// `start_ts` -> `commit_ts` pairs for a while, which can be useful for quickly
@@ -220,6 +258,11 @@ type TxnStatusCacheSlot =
 /// there's at most one instance of `TxnStatusCache` in a process.
 pub struct TxnStatusCache {
     slots: Vec<CachePadded<Mutex<TxnStatusCacheSlot>>>,
+    /// Sharded the same way as `slots`, but keyed by a caller-supplied
+    /// idempotency token rather than `start_ts`, and used to recognize
+    /// retried rollback requests. See
+    /// [`TxnStatusCache::insert_rollback_record`].
+    rollback_token_slots: Vec<CachePadded<Mutex<RollbackRecordCacheSlot>>>,
     is_enabled: bool,
 }
 
@@ -235,6 +278,7 @@ impl TxnStatusCache {
         if capacity == 0 {
             return Self {
                 slots: vec![],
+                rollback_token_slots: vec![],
                 is_enabled: false,
             };
         }
@@ -262,6 +306,29 @@ impl TxnStatusCache {
                     Mutex::new(cache).into()
                 })
                 .collect(),
+            // The rollback-token cache is given the same number of slots and
+            // per-slot capacity as the `start_ts` cache above. This is a
+            // simple, independent budget rather than splitting the existing
+            // one, so enabling idempotency tokens roughly doubles the
+            // worst-case memory this struct can use; that's acceptable given
+            // `capacity` is already documented as "very large but
+            // configurable".
+            rollback_token_slots: (0..slots)
+                .map(|_| {
+                    let cache = LruCache::new(
+                        allowed_capacity_per_slot,
+                        0,
+                        lru::CountTracker::default(),
+                        TxnStatusCacheEvictPolicy::new(
+                            required_keep_time,
+                            simulated_system_time.clone(),
+                        ),
+                    );
+                    let allocated_capacity = cache.internal_allocated_capacity();
+                    initial_allocated_capacity_total += allocated_capacity;
+                    Mutex::new(cache).into()
+                })
+                .collect(),
             is_enabled: true,
         };
         SCHED_TXN_STATUS_CACHE_SIZE
@@ -388,6 +455,45 @@ impl TxnStatusCache {
         debug_assert!(self.get_no_promote(start_ts).is_none());
         res
     }
+
+    fn rollback_token_slot_index(&self, token: u64) -> usize {
+        fxhash::hash(&token) % self.rollback_token_slots.len()
+    }
+
+    /// Record that a rollback request carrying the given client-supplied
+    /// idempotency token has been executed. A later call to
+    /// [`get_rollback_record_no_promote`](Self::get_rollback_record_no_promote)
+    /// with the same token can then recognize the request as a duplicate
+    /// retry, instead of re-applying the rollback writes.
+    ///
+    /// Like [`insert`](Self::insert), the current system time should be
+    /// passed from outside to avoid repeated clock reads, and an existing
+    /// entry is never overwritten.
+    ///
+    /// Note: as of now, no request carries such a token yet (it requires a
+    /// `kvproto` change to add the field to the rollback requests), so this
+    /// is only reachable by callers that set one explicitly.
+    pub fn insert_rollback_record(&self, token: u64, now: SystemTime) {
+        if !self.is_enabled {
+            return;
+        }
+
+        let insert_time = now.duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+        let mut slot = self.rollback_token_slots[self.rollback_token_slot_index(token)].lock();
+        slot.insert_if_not_exist(token, RollbackRecord { insert_time });
+    }
+
+    /// Check whether a rollback request with the given idempotency token has
+    /// already been executed, without promoting the entry (if it exists) to
+    /// the most recent place.
+    pub fn get_rollback_record_no_promote(&self, token: u64) -> bool {
+        if !self.is_enabled {
+            return false;
+        }
+
+        let slot = self.rollback_token_slots[self.rollback_token_slot_index(token)].lock();
+        slot.get_no_promote(&token).is_some()
+    }
 }
 
 #[cfg(test)]
@@ -724,6 +830,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_rollback_record_insert_and_get() {
+        let c = TxnStatusCache::new_for_test();
+        assert!(!c.get_rollback_record_no_promote(1));
+
+        let now = SystemTime::now();
+        c.insert_rollback_record(1, now);
+        assert!(c.get_rollback_record_no_promote(1));
+        assert!(!c.get_rollback_record_no_promote(2));
+
+        // Inserting the same token again doesn't panic and the record is still
+        // recognized.
+        c.insert_rollback_record(1, now);
+        assert!(c.get_rollback_record_no_promote(1));
+
+        // The rollback-record cache and the commit-info cache are independent: a
+        // `start_ts` that happens to equal a rollback token is not found in the
+        // other cache.
+        assert!(c.get_no_promote(1.into()).is_none());
+    }
+
     #[test]
     fn test_evicting_expired() {
         let (c, time) =
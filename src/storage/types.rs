@@ -138,6 +138,18 @@ impl TxnStatus {
             TxnStatus::RolledBack | TxnStatus::TtlExpire | TxnStatus::Committed { .. }
         )
     }
+
+    // Returns if the transaction is known to not be committing, i.e. its
+    // secondary locks are safe to pessimistically roll back.
+    pub fn is_rolled_back(&self) -> bool {
+        matches!(
+            self,
+            TxnStatus::RolledBack
+                | TxnStatus::TtlExpire
+                | TxnStatus::LockNotExist
+                | TxnStatus::PessimisticRollBack
+        )
+    }
 }
 
 #[derive(Debug)]
@@ -449,6 +461,7 @@ storage_callback! {
     MvccInfoByStartTs(Option<(Key, MvccInfo)>) ProcessResult::MvccStartTs { mvcc } => mvcc,
     Locks(Vec<kvrpcpb::LockInfo>) ProcessResult::Locks { locks } => locks,
     TxnStatus(TxnStatus) ProcessResult::TxnStatus { txn_status } => txn_status,
+    TxnStatusAndRollback((TxnStatus, Vec<Result<()>>)) ProcessResult::TxnStatusAndRollback { txn_status, rollback_results } => (txn_status, rollback_results),
     Prewrite(PrewriteResult) ProcessResult::PrewriteResult { result } => result,
     PessimisticLock(Result<PessimisticLockResults>) ProcessResult::PessimisticLockRes { res } => res,
     SecondaryLocksStatus(SecondaryLocksStatus) ProcessResult::SecondaryLocksStatus { status } => status,
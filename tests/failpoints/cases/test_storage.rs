@@ -1530,7 +1530,7 @@ fn test_before_async_write_deadline() {
     fail::cfg("cleanup", "sleep(500)").unwrap();
     storage
         .sched_txn_command(
-            commands::Rollback::new(vec![Key::from_raw(b"k")], 10.into(), ctx),
+            commands::Rollback::new(vec![Key::from_raw(b"k")], 10.into(), None, ctx),
             Box::new(move |res: storage::Result<_>| {
                 tx.send(res).unwrap();
             }),
@@ -1623,7 +1623,7 @@ fn test_before_propose_deadline() {
     fail::cfg("pause_on_peer_collect_message", "sleep(500)").unwrap();
     storage
         .sched_txn_command(
-            commands::Rollback::new(vec![Key::from_raw(b"k")], 10.into(), ctx),
+            commands::Rollback::new(vec![Key::from_raw(b"k")], 10.into(), None, ctx),
             Box::new(move |res: storage::Result<_>| {
                 tx.send(res).unwrap();
             }),